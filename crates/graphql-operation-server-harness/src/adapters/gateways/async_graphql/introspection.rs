@@ -0,0 +1,354 @@
+use async_graphql::dynamic::{
+    Enum, EnumItem, Field, FieldFuture, InputObject, InputValue, Interface, InterfaceField,
+    Object, Scalar, Schema, Subscription, SubscriptionField, SubscriptionFieldFuture, Type,
+    TypeRef,
+};
+use async_graphql::futures_util::stream;
+use async_graphql::parser::types::{
+    BaseType, ConstDirective, FieldDefinition, InputValueDefinition, Type as ParsedType,
+    TypeDefinition, TypeKind, TypeSystemDefinition,
+};
+use async_graphql::parser::{parse_schema, Positioned};
+use async_graphql::{Value as ConstValue, Variables};
+use serde_json::Value;
+
+use super::selection::SelectedOperation;
+use crate::entities::OperationType;
+
+/// Build a schema from `sdl` that only answers introspection queries.
+///
+/// Root types come from the `schema` definition, or default to `Query`,
+/// `Mutation` and `Subscription` when types with those names exist.
+pub(super) fn schema_from_sdl(sdl: &str) -> Result<Schema, String> {
+    let document = parse_schema(sdl).map_err(|e| e.to_string())?;
+
+    let mut roots = [None, None, None];
+    let mut types = Vec::new();
+    for definition in document.definitions {
+        match definition {
+            TypeSystemDefinition::Schema(schema) => {
+                let schema = schema.node;
+                roots = [schema.query, schema.mutation, schema.subscription]
+                    .map(|root| root.map(|name| name.node.to_string()));
+            }
+            TypeSystemDefinition::Type(ty) if ty.node.extend => {
+                return Err(format!(
+                    "Type extensions are not supported, in \"{}\"",
+                    ty.node.name.node
+                ));
+            }
+            TypeSystemDefinition::Type(ty) => types.push(ty.node),
+            // Custom directives are not part of the introspected types
+            TypeSystemDefinition::Directive(_) => {}
+        }
+    }
+
+    let defined = |name: &str| types.iter().any(|ty| ty.name.node == name);
+    let [query, mutation, subscription] = roots;
+    let query = query.unwrap_or_else(|| "Query".to_string());
+    let mutation = mutation.or_else(|| defined("Mutation").then(|| "Mutation".to_string()));
+    let subscription =
+        subscription.or_else(|| defined("Subscription").then(|| "Subscription".to_string()));
+
+    let mut builder = Schema::build(&query, mutation.as_deref(), subscription.as_deref())
+        .introspection_only();
+    for ty in types {
+        let is_subscription = subscription.as_deref() == Some(ty.name.node.as_str());
+        builder = builder.register(dynamic_type(ty, is_subscription));
+    }
+    builder.finish().map_err(|e| e.to_string())
+}
+
+/// Whether every field the operation selects is a meta field such as
+/// `__schema` or `__type`
+pub(super) fn is_introspection(selected: &SelectedOperation) -> bool {
+    selected.operation_type == OperationType::Query
+        && !selected.fields.is_empty()
+        && selected.fields.iter().all(|field| field.name.starts_with("__"))
+}
+
+/// Run an introspection query, returning the GraphQL response as JSON
+pub(super) async fn execute(
+    schema: &Schema,
+    query: &str,
+    operation_name: Option<&str>,
+    variables: Option<&Value>,
+) -> Value {
+    let mut request = async_graphql::Request::new(query);
+    if let Some(operation_name) = operation_name {
+        request = request.operation_name(operation_name);
+    }
+    if let Some(variables) = variables {
+        request = request.variables(Variables::from_json(variables.clone()));
+    }
+    serde_json::to_value(schema.execute(request).await).unwrap_or_default()
+}
+
+/// Convert a parsed type definition; fields never resolve as only
+/// introspection is served
+fn dynamic_type(ty: TypeDefinition, is_subscription: bool) -> Type {
+    let name = ty.name.node.to_string();
+    let description = ty.description.map(|d| d.node);
+    match ty.kind {
+        TypeKind::Scalar => {
+            let mut scalar = Scalar::new(name);
+            if let Some(description) = description {
+                scalar = scalar.description(description);
+            }
+            scalar.into()
+        }
+        TypeKind::Object(object) if is_subscription => {
+            let mut subscription = Subscription::new(name);
+            if let Some(description) = description {
+                subscription = subscription.description(description);
+            }
+            for field in object.fields {
+                subscription = subscription.field(subscription_field(field.node));
+            }
+            subscription.into()
+        }
+        TypeKind::Object(object) => {
+            let mut dynamic = Object::new(name);
+            if let Some(description) = description {
+                dynamic = dynamic.description(description);
+            }
+            for interface in object.implements {
+                dynamic = dynamic.implement(interface.node.to_string());
+            }
+            for field in object.fields {
+                dynamic = dynamic.field(object_field(field.node));
+            }
+            dynamic.into()
+        }
+        TypeKind::Interface(interface) => {
+            let mut dynamic = Interface::new(name);
+            if let Some(description) = description {
+                dynamic = dynamic.description(description);
+            }
+            for parent in interface.implements {
+                dynamic = dynamic.implement(parent.node.to_string());
+            }
+            for field in interface.fields {
+                dynamic = dynamic.field(interface_field(field.node));
+            }
+            dynamic.into()
+        }
+        TypeKind::Union(union) => {
+            let mut dynamic = async_graphql::dynamic::Union::new(name);
+            if let Some(description) = description {
+                dynamic = dynamic.description(description);
+            }
+            for member in union.members {
+                dynamic = dynamic.possible_type(member.node.to_string());
+            }
+            dynamic.into()
+        }
+        TypeKind::Enum(enum_type) => {
+            let mut dynamic = Enum::new(name);
+            if let Some(description) = description {
+                dynamic = dynamic.description(description);
+            }
+            for value in enum_type.values {
+                let value = value.node;
+                let mut item = EnumItem::new(value.value.node.to_string());
+                if let Some(description) = value.description {
+                    item = item.description(description.node);
+                }
+                if let Some(reason) = deprecation(&value.directives) {
+                    item = item.deprecation(reason.as_deref());
+                }
+                dynamic = dynamic.item(item);
+            }
+            dynamic.into()
+        }
+        TypeKind::InputObject(input) => {
+            let mut dynamic = InputObject::new(name);
+            if let Some(description) = description {
+                dynamic = dynamic.description(description);
+            }
+            for field in input.fields {
+                dynamic = dynamic.field(input_value(field.node));
+            }
+            dynamic.into()
+        }
+    }
+}
+
+fn object_field(definition: FieldDefinition) -> Field {
+    let ty = type_ref(&definition.ty.node);
+    let mut field = Field::new(definition.name.node.to_string(), ty, |_| {
+        FieldFuture::from_value(None)
+    });
+    if let Some(description) = definition.description {
+        field = field.description(description.node);
+    }
+    if let Some(reason) = deprecation(&definition.directives) {
+        field = field.deprecation(reason.as_deref());
+    }
+    for argument in definition.arguments {
+        field = field.argument(input_value(argument.node));
+    }
+    field
+}
+
+fn interface_field(definition: FieldDefinition) -> InterfaceField {
+    let ty = type_ref(&definition.ty.node);
+    let mut field = InterfaceField::new(definition.name.node.to_string(), ty);
+    if let Some(description) = definition.description {
+        field = field.description(description.node);
+    }
+    if let Some(reason) = deprecation(&definition.directives) {
+        field = field.deprecation(reason.as_deref());
+    }
+    for argument in definition.arguments {
+        field = field.argument(input_value(argument.node));
+    }
+    field
+}
+
+fn subscription_field(definition: FieldDefinition) -> SubscriptionField {
+    let ty = type_ref(&definition.ty.node);
+    let mut field = SubscriptionField::new(definition.name.node.to_string(), ty, |_| {
+        SubscriptionFieldFuture::new(async {
+            Ok(stream::empty::<async_graphql::Result<ConstValue>>())
+        })
+    });
+    if let Some(description) = definition.description {
+        field = field.description(description.node);
+    }
+    if let Some(reason) = deprecation(&definition.directives) {
+        field = field.deprecation(reason.as_deref());
+    }
+    for argument in definition.arguments {
+        field = field.argument(input_value(argument.node));
+    }
+    field
+}
+
+fn input_value(definition: InputValueDefinition) -> InputValue {
+    let ty = type_ref(&definition.ty.node);
+    let mut value = InputValue::new(definition.name.node.to_string(), ty);
+    if let Some(description) = definition.description {
+        value = value.description(description.node);
+    }
+    if let Some(default) = definition.default_value {
+        value = value.default_value(default.node);
+    }
+    value
+}
+
+fn type_ref(ty: &ParsedType) -> TypeRef {
+    let base = match &ty.base {
+        BaseType::Named(name) => TypeRef::Named(name.to_string().into()),
+        BaseType::List(item) => TypeRef::List(Box::new(type_ref(item))),
+    };
+    match ty.nullable {
+        true => base,
+        false => TypeRef::NonNull(Box::new(base)),
+    }
+}
+
+/// The `@deprecated` reason of a definition, `Some(None)` when deprecated without one
+fn deprecation(directives: &[Positioned<ConstDirective>]) -> Option<Option<String>> {
+    let directive = directives
+        .iter()
+        .find(|directive| directive.node.name.node == "deprecated")?;
+    let reason = directive
+        .node
+        .get_argument("reason")
+        .and_then(|reason| match &reason.node {
+            ConstValue::String(reason) => Some(reason.clone()),
+            _ => None,
+        });
+    Some(reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const SDL: &str = r#"
+        "A registered user"
+        type User implements Node {
+            id: ID!
+            name: String @deprecated(reason: "use fullName")
+            fullName: String!
+            role: Role
+        }
+
+        interface Node {
+            id: ID!
+        }
+
+        enum Role {
+            ADMIN
+            MEMBER
+        }
+
+        input UserFilter {
+            role: Role = MEMBER
+        }
+
+        type Query {
+            users(filter: UserFilter, first: Int = 10): [User!]!
+            node(id: ID!): Node
+        }
+    "#;
+
+    #[tokio::test]
+    async fn test_introspects_sdl_types() {
+        let schema = schema_from_sdl(SDL).unwrap();
+        let query = r#"{
+            __schema { queryType { name } mutationType { name } }
+            __type(name: "User") {
+                kind
+                description
+                interfaces { name }
+                fields(includeDeprecated: true) {
+                    name
+                    isDeprecated
+                    deprecationReason
+                    type { kind name ofType { name } }
+                }
+            }
+        }"#;
+        let response = execute(&schema, query, None, None).await;
+        assert_eq!(response["errors"], Value::Null, "{}", response);
+
+        let data = &response["data"];
+        assert_eq!(data["__schema"]["queryType"]["name"], "Query");
+        assert_eq!(data["__schema"]["mutationType"], Value::Null);
+
+        let user = &data["__type"];
+        assert_eq!(user["kind"], "OBJECT");
+        assert_eq!(user["description"], "A registered user");
+        assert_eq!(user["interfaces"], json!([{"name": "Node"}]));
+        assert_eq!(
+            user["fields"][0],
+            json!({
+                "name": "id",
+                "isDeprecated": false,
+                "deprecationReason": null,
+                "type": {"kind": "NON_NULL", "name": null, "ofType": {"name": "ID"}}
+            })
+        );
+        assert_eq!(user["fields"][1]["deprecationReason"], "use fullName");
+    }
+
+    #[tokio::test]
+    async fn test_explicit_schema_roots() {
+        let sdl = "schema { query: Root } type Root { ping: Boolean }";
+        let schema = schema_from_sdl(sdl).unwrap();
+        let response = execute(&schema, "{ __schema { queryType { name } } }", None, None).await;
+        assert_eq!(response["data"]["__schema"]["queryType"]["name"], "Root");
+    }
+
+    #[test]
+    fn test_invalid_sdl() {
+        assert!(schema_from_sdl("type Query {").is_err());
+        assert!(schema_from_sdl("type Other { id: ID }").is_err());
+        let error = schema_from_sdl("type Query { a: Int } extend type Query { b: Int }");
+        assert!(error.unwrap_err().contains("Type extensions are not supported"));
+    }
+}
@@ -1,3 +1,5 @@
+mod introspection;
+mod selection;
 mod server;
 
-pub use server::AsyncGraphQL;
\ No newline at end of file
+pub use server::AsyncGraphQL;
@@ -0,0 +1,234 @@
+use async_graphql::parser::{
+    parse_query,
+    types::{
+        ExecutableDocument, OperationDefinition, OperationType as ParsedOperationType, Selection,
+        SelectionSet,
+    },
+};
+use async_graphql::{Name, Value as ConstValue};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+use crate::entities::OperationType;
+
+/// A top-level field selected by an operation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct SelectedField {
+    /// Key the field appears under in the response (the alias, if any)
+    pub response_key: String,
+    /// Underlying schema field name, used to route to a handler
+    pub name: String,
+    /// Field arguments with variable references resolved
+    pub arguments: HashMap<String, Value>,
+}
+
+/// The operation a request will execute and its top-level fields
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct SelectedOperation {
+    pub operation_type: OperationType,
+    pub fields: Vec<SelectedField>,
+}
+
+/// Parse `query` and extract the top-level fields of the operation to execute.
+///
+/// With several operations in the document, `operation_name` picks one.
+/// Fragment spreads and inline fragments are flattened into their fields.
+/// Variable references in arguments are resolved against `variables`, falling
+/// back to the variable's default and then to `null`.
+pub(super) fn select(
+    query: &str,
+    operation_name: Option<&str>,
+    variables: Option<&Value>,
+) -> Result<SelectedOperation, String> {
+    let document = parse_query(query).map_err(|e| e.to_string())?;
+
+    let mut operations = document.operations.iter();
+    let operation = match operation_name {
+        Some(name) => operations
+            .find(|(op_name, _)| op_name.map(|n| n.as_str()) == Some(name))
+            .map(|(_, op)| op)
+            .ok_or_else(|| format!("Unknown operation named \"{}\"", name))?,
+        None => match (operations.next(), operations.next()) {
+            (Some((_, op)), None) => op,
+            (Some(_), Some(_)) => {
+                let message = "Must provide operation name if query contains multiple operations.";
+                return Err(message.to_string());
+            }
+            _ => return Err("Must provide an operation.".to_string()),
+        },
+    };
+
+    let operation_type = match operation.node.ty {
+        ParsedOperationType::Query => OperationType::Query,
+        ParsedOperationType::Mutation => OperationType::Mutation,
+        ParsedOperationType::Subscription => OperationType::Subscription,
+    };
+
+    let variables = resolve_variables(&operation.node, variables);
+    let mut fields = Vec::new();
+    collect_fields(
+        &document,
+        &operation.node.selection_set.node,
+        &variables,
+        &mut HashSet::new(),
+        &mut fields,
+    );
+
+    Ok(SelectedOperation {
+        operation_type,
+        fields,
+    })
+}
+
+/// Values of the operation's declared variables, provided or defaulted
+fn resolve_variables(
+    operation: &OperationDefinition,
+    variables: Option<&Value>,
+) -> HashMap<Name, ConstValue> {
+    operation
+        .variable_definitions
+        .iter()
+        .filter_map(|definition| {
+            let name = definition.node.name.node.clone();
+            let value = variables
+                .and_then(|vars| vars.get(name.as_str()))
+                .and_then(|value| ConstValue::from_json(value.clone()).ok())
+                .or_else(|| definition.node.default_value().cloned())?;
+            Some((name, value))
+        })
+        .collect()
+}
+
+fn collect_fields<'a>(
+    document: &'a ExecutableDocument,
+    selection_set: &'a SelectionSet,
+    variables: &HashMap<Name, ConstValue>,
+    visited_fragments: &mut HashSet<&'a str>,
+    fields: &mut Vec<SelectedField>,
+) {
+    for selection in &selection_set.items {
+        match &selection.node {
+            Selection::Field(field) => fields.push(SelectedField {
+                response_key: field.node.response_key().node.to_string(),
+                name: field.node.name.node.to_string(),
+                arguments: field
+                    .node
+                    .arguments
+                    .iter()
+                    .map(|(name, value)| {
+                        let value = value
+                            .node
+                            .clone()
+                            .into_const_with(|var| {
+                                Ok::<_, ()>(variables.get(&var).cloned().unwrap_or_default())
+                            })
+                            .ok()
+                            .and_then(|value| value.into_json().ok())
+                            .unwrap_or_default();
+                        (name.node.to_string(), value)
+                    })
+                    .collect(),
+            }),
+            Selection::InlineFragment(fragment) => collect_fields(
+                document,
+                &fragment.node.selection_set.node,
+                variables,
+                visited_fragments,
+                fields,
+            ),
+            Selection::FragmentSpread(spread) => {
+                let name = spread.node.fragment_name.node.as_str();
+                if !visited_fragments.insert(name) {
+                    continue;
+                }
+                if let Some(fragment) = document.fragments.get(&spread.node.fragment_name.node) {
+                    collect_fields(
+                        document,
+                        &fragment.node.selection_set.node,
+                        variables,
+                        visited_fragments,
+                        fields,
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(operation: &SelectedOperation) -> Vec<&str> {
+        operation.fields.iter().map(|f| f.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_select_exact_top_level_fields() {
+        let operation = select("{ users { id user } }", None, None).unwrap();
+        assert_eq!(operation.operation_type, OperationType::Query);
+        assert_eq!(names(&operation), vec!["users"]);
+    }
+
+    #[test]
+    fn test_select_ignores_comments() {
+        let operation = select("# user\n{ users { id } }", None, None).unwrap();
+        assert_eq!(names(&operation), vec!["users"]);
+    }
+
+    #[test]
+    fn test_select_alias_maps_to_field_name() {
+        let operation = select("{ me: user { id } }", None, None).unwrap();
+        assert_eq!(
+            operation.fields,
+            vec![SelectedField {
+                response_key: "me".to_string(),
+                name: "user".to_string(),
+                arguments: HashMap::new(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_select_named_operation() {
+        let query = "query A { a } mutation B { b }";
+        let operation = select(query, Some("B"), None).unwrap();
+        assert_eq!(operation.operation_type, OperationType::Mutation);
+        assert_eq!(names(&operation), vec!["b"]);
+        assert_eq!(
+            select(query, None, None).unwrap_err(),
+            "Must provide operation name if query contains multiple operations."
+        );
+        assert_eq!(
+            select(query, Some("C"), None).unwrap_err(),
+            "Unknown operation named \"C\""
+        );
+    }
+
+    #[test]
+    fn test_select_flattens_fragments() {
+        let query = "query { ...Root ... on Query { c } } fragment Root on Query { a b }";
+        let operation = select(query, None, None).unwrap();
+        assert_eq!(names(&operation), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_select_resolves_arguments() {
+        let query = "query ($id: ID, $limit: Int = 10) { user(id: $id, role: ADMIN) { posts(first: $limit) } }";
+        let operation = select(query, None, Some(&serde_json::json!({"id": "5"}))).unwrap();
+        let arguments = &operation.fields[0].arguments;
+        assert_eq!(arguments["id"], serde_json::json!("5"));
+        assert_eq!(arguments["role"], serde_json::json!("ADMIN"));
+        assert!(!arguments.contains_key("first"));
+
+        let query = "query ($limit: Int = 10) { posts(first: $limit, after: $missing) }";
+        let operation = select(query, None, None).unwrap();
+        assert_eq!(operation.fields[0].arguments["first"], serde_json::json!(10));
+        assert_eq!(operation.fields[0].arguments["after"], Value::Null);
+    }
+
+    #[test]
+    fn test_select_rejects_invalid_query() {
+        assert!(select("{ user", None, None).is_err());
+    }
+}
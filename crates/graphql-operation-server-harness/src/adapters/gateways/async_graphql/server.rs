@@ -1,37 +1,62 @@
+use std::collections::HashMap;
+use std::future::{Future, IntoFuture};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
 use async_trait::async_trait;
 use axum::{
-    extract::State,
-    http::StatusCode,
+    extract::{
+        ws::{rejection::WebSocketUpgradeRejection, Message as WsMessage, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    http::{header, HeaderMap, StatusCode},
     response::IntoResponse,
     routing::post,
     Router,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
-use std::net::SocketAddr;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
-use tokio::sync::{oneshot, Mutex as TokioMutex};
+use server_harness_core::CompletionTracker;
+use tokio::sync::oneshot;
 
-use crate::entities::{CollectedRequest, Handler, Operation, OperationType, RequestContext};
+use super::introspection;
+use super::selection::{select, SelectedField, SelectedOperation};
+use crate::entities::{
+    CollectedRequest, Field, Handler, HandlerResponse, Operation, OperationType, RequestContext,
+};
 use crate::error::HarnessError;
-use crate::use_cases::ports::{Collector, Server};
+use crate::use_cases::ports::{Collector, RunOptions, Server};
 
 /// AsyncGraphQL-compatible server implementation
 #[derive(Clone)]
 pub struct AsyncGraphQL {
     addr: SocketAddr,
+    path: String,
 }
 
 impl AsyncGraphQL {
     pub fn new(addr: SocketAddr) -> Self {
-        Self { addr }
+        Self {
+            addr,
+            path: "/graphql".to_string(),
+        }
     }
 
     pub fn bind(addr: impl Into<SocketAddr>) -> Self {
         Self::new(addr.into())
     }
+
+    /// Serve the GraphQL endpoint, including subscriptions, at `path` instead
+    /// of `/graphql`, e.g. `/query` or `/api/graphql`.
+    ///
+    /// Running fails with [`HarnessError::ConfigurationError`] unless `path`
+    /// starts with `/`.
+    pub fn at_path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into();
+        self
+    }
 }
 
 impl Default for AsyncGraphQL {
@@ -42,10 +67,95 @@ impl Default for AsyncGraphQL {
 
 #[derive(Debug, Deserialize)]
 struct GraphQLRequest {
+    /// Empty when an automatic persisted query sends only its hash
+    #[serde(default)]
     query: String,
     #[serde(rename = "operationName")]
     operation_name: Option<String>,
     variables: Option<Value>,
+    #[serde(default)]
+    extensions: Option<Value>,
+    /// The `POST` body the request was read from
+    #[serde(skip)]
+    raw_body: String,
+}
+
+/// A `graphql-transport-ws` message sent by the client
+#[derive(Debug, Deserialize)]
+struct WsClientMessage {
+    #[serde(rename = "type")]
+    kind: String,
+    id: Option<String>,
+    payload: Option<Value>,
+}
+
+/// Subprotocol spoken on the websocket endpoint
+const GRAPHQL_TRANSPORT_WS: &str = "graphql-transport-ws";
+
+/// Media type of the GraphQL-over-HTTP spec
+const GRAPHQL_RESPONSE_JSON: &str = "application/graphql-response+json";
+
+/// Media type a response is sent as, negotiated from the `Accept` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ResponseFormat {
+    /// Legacy `application/json`: every executed operation is answered with 200
+    Json,
+    /// `application/graphql-response+json`: responses without `data`, such as
+    /// request and validation errors, are answered with 400
+    GraphQLResponseJson,
+}
+
+impl ResponseFormat {
+    fn from_headers(headers: &HeaderMap) -> Self {
+        let accepted = headers
+            .get_all(header::ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|media_type| media_type.split(';').next())
+            .any(|media_type| media_type.trim().eq_ignore_ascii_case(GRAPHQL_RESPONSE_JSON));
+        match accepted {
+            true => ResponseFormat::GraphQLResponseJson,
+            false => ResponseFormat::Json,
+        }
+    }
+
+    /// Answer with `response`, using `status` unless it is 200 and the
+    /// format derives the status from the response
+    fn reply(self, status: StatusCode, response: GraphQLResponse) -> axum::response::Response {
+        match self {
+            ResponseFormat::Json => (status, axum::Json(response)).into_response(),
+            ResponseFormat::GraphQLResponseJson => {
+                let status = match (status, &response.data) {
+                    (StatusCode::OK, None) => StatusCode::BAD_REQUEST,
+                    (status, _) => status,
+                };
+                let mut body = serde_json::to_value(&response).unwrap_or_default();
+                if response.data.is_none() {
+                    if let Some(body) = body.as_object_mut() {
+                        body.remove("data");
+                    }
+                }
+                graphql_response_json(status, &body)
+            }
+        }
+    }
+
+    /// Answer a batch with one response per operation, always with 200
+    fn reply_batch(self, responses: Vec<GraphQLResponse>) -> axum::response::Response {
+        match self {
+            ResponseFormat::Json => (StatusCode::OK, axum::Json(responses)).into_response(),
+            ResponseFormat::GraphQLResponseJson => {
+                graphql_response_json(StatusCode::OK, &responses)
+            }
+        }
+    }
+}
+
+/// Serialize `body` as `application/graphql-response+json`
+fn graphql_response_json(status: StatusCode, body: &impl Serialize) -> axum::response::Response {
+    let body = serde_json::to_vec(body).unwrap_or_default();
+    (status, [(header::CONTENT_TYPE, GRAPHQL_RESPONSE_JSON)], body).into_response()
 }
 
 #[derive(Debug, Serialize)]
@@ -55,41 +165,190 @@ struct GraphQLResponse {
     errors: Option<Vec<Value>>,
 }
 
-/// Shared state for tracking completion
-#[derive(Clone)]
-struct CompletionTracker {
-    total_handlers: usize,
-    handlers_called: Arc<AtomicUsize>,
-    shutdown_tx: Arc<TokioMutex<Option<oneshot::Sender<()>>>>,
-}
+impl GraphQLRequest {
+    /// Read a request from `GET` query string parameters; `variables` and
+    /// `extensions` are JSON
+    fn from_params(mut params: HashMap<String, String>) -> Result<Self, String> {
+        let query = params.remove("query");
+        let mut json_param = |name: &str| match params.remove(name) {
+            Some(value) => serde_json::from_str(&value)
+                .map(Some)
+                .map_err(|e| format!("Invalid {} parameter: {}", name, e)),
+            None => Ok(None),
+        };
+        let variables = json_param("variables")?;
+        let extensions = json_param("extensions")?;
+        let request = Self {
+            query: query.clone().unwrap_or_default(),
+            operation_name: params.remove("operationName"),
+            variables,
+            extensions,
+            raw_body: String::new(),
+        };
+        match query.is_some() || request.persisted_query_hash().is_some() {
+            true => Ok(request),
+            false => Err("Missing query parameter".to_string()),
+        }
+    }
 
-impl CompletionTracker {
-    fn new(total_handlers: usize, shutdown_tx: oneshot::Sender<()>) -> Self {
-        Self {
-            total_handlers,
-            handlers_called: Arc::new(AtomicUsize::new(0)),
-            shutdown_tx: Arc::new(TokioMutex::new(Some(shutdown_tx))),
+    /// The `sha256Hash` of an Apollo persisted query sent in `extensions`
+    fn persisted_query_hash(&self) -> Option<&str> {
+        self.extensions
+            .as_ref()?
+            .get("persistedQuery")?
+            .get("sha256Hash")?
+            .as_str()
+    }
+
+    /// Parse one operation of a batch, keeping the whole batch body as `raw_body`
+    fn from_batch_entry(value: Value, raw_body: &str) -> serde_json::Result<Self> {
+        let mut request: Self = serde_json::from_value(value)?;
+        request.raw_body = raw_body.to_string();
+        Ok(request)
+    }
+
+    fn collected(&self) -> CollectedRequest {
+        let mut collected = CollectedRequest::new(&self.query);
+        if let Some(op_name) = &self.operation_name {
+            collected = collected.with_operation_name(op_name);
         }
+        if let Some(vars) = &self.variables {
+            collected = collected.with_variables(vars.clone());
+        }
+        if let Some(extensions) = &self.extensions {
+            collected = collected.with_extensions(extensions.clone());
+        }
+        collected.raw_body = self.raw_body.clone();
+        collected
+    }
+
+    fn context(&self, field: &SelectedField) -> RequestContext {
+        let mut ctx = RequestContext::new(&field.name).with_query(&self.query);
+        for (name, value) in &field.arguments {
+            ctx = ctx.with_argument(name, value.clone());
+        }
+        if let Some(op_name) = &self.operation_name {
+            ctx = ctx.with_operation_name(op_name);
+        }
+        if let Some(vars) = &self.variables {
+            ctx = ctx.with_variables(vars.clone());
+        }
+        ctx
+    }
+}
+
+/// Serialize a handler response's errors into GraphQL error objects.
+///
+/// Field errors are pathed at the field's response key.
+fn error_values(response: &HandlerResponse, field: &SelectedField) -> Vec<Value> {
+    response
+        .errors
+        .iter()
+        .flatten()
+        .map(|err| err.to_value(err.at_field.then(|| serde_json::json!([field.response_key]))))
+        .collect()
+}
+
+/// A response carrying a single request-level error and no data
+fn error_response(message: String) -> GraphQLResponse {
+    GraphQLResponse {
+        data: None,
+        errors: Some(vec![serde_json::json!({ "message": message })]),
     }
+}
+
+/// Whether every selected field is mocked under the operation's type
+fn all_fields_mocked(state: &ServerState, selected: &SelectedOperation) -> bool {
+    let handlers = match selected.operation_type {
+        OperationType::Query => &state.query_handlers,
+        OperationType::Mutation => &state.mutation_handlers,
+        OperationType::Subscription => &state.subscription_handlers,
+    };
+    selected
+        .fields
+        .iter()
+        .all(|field| handlers.contains_key(&field.name))
+}
+
+/// Errors for selected fields that are not mocked under the operation's type
+/// but are under another one, e.g. a mutation field requested in a query
+fn misplaced_fields(state: &ServerState, selected: &SelectedOperation) -> Vec<Value> {
+    let roots = [
+        (OperationType::Query, &state.query_handlers),
+        (OperationType::Mutation, &state.mutation_handlers),
+        (OperationType::Subscription, &state.subscription_handlers),
+    ];
+
+    selected
+        .fields
+        .iter()
+        .filter(|field| {
+            !roots.iter().any(|(kind, handlers)| {
+                *kind == selected.operation_type && handlers.contains_key(&field.name)
+            })
+        })
+        .filter_map(|field| {
+            let (kind, _) = roots
+                .iter()
+                .find(|(_, handlers)| handlers.contains_key(&field.name))?;
+            Some(serde_json::json!({
+                "message": format!(
+                    "Field \"{}\" is a {} field and cannot be used in a {}",
+                    field.name, kind, selected.operation_type
+                ),
+                "path": [field.response_key],
+            }))
+        })
+        .collect()
+}
 
-    async fn handler_called(&self) {
-        let called = self.handlers_called.fetch_add(1, Ordering::SeqCst) + 1;
-        if called >= self.total_handlers {
-            if let Some(tx) = self.shutdown_tx.lock().await.take() {
-                let _ = tx.send(());
+/// Place a handler's data in the response under the field's response key.
+///
+/// Object data is merged as-is, with the field name renamed to its alias.
+fn insert_field_data(
+    response_data: &mut serde_json::Map<String, Value>,
+    field: &SelectedField,
+    data: Value,
+) {
+    match data {
+        Value::Object(obj) => {
+            for (key, value) in obj {
+                let key = if key == field.name {
+                    field.response_key.clone()
+                } else {
+                    key
+                };
+                response_data.insert(key, value);
             }
         }
+        data => {
+            response_data.insert(field.response_key.clone(), data);
+        }
     }
 }
 
-/// Internal trait for collecting requests (without Output type)
-trait InternalCollector: Send + Sync {
+/// Type-erased collector trait for internal use
+trait ErasedCollector: Send + Sync {
     fn collect(&self, request: CollectedRequest);
 }
 
-impl<C: Collector> InternalCollector for C {
-    fn collect(&self, request: CollectedRequest) {
-        Collector::collect(self, request);
+/// The scenario's collector and the sequence number of the next collected request
+struct CollectorSlot<C> {
+    collector: Option<C>,
+    next_sequence: u64,
+}
+
+impl<C: Collector> ErasedCollector for std::sync::Mutex<CollectorSlot<C>> {
+    fn collect(&self, mut request: CollectedRequest) {
+        if let Ok(mut slot) = self.lock() {
+            // Stamped under the lock so sequence numbers follow collection order
+            request.received_at = std::time::Instant::now();
+            request.sequence = slot.next_sequence;
+            slot.next_sequence += 1;
+            if let Some(ref collector) = slot.collector {
+                collector.collect(request);
+            }
+        }
     }
 }
 
@@ -99,100 +358,497 @@ struct ServerState {
     /// Map from field name to handlers
     query_handlers: Arc<HashMap<String, FieldState>>,
     mutation_handlers: Arc<HashMap<String, FieldState>>,
-    collector: Arc<dyn InternalCollector>,
+    subscription_handlers: Arc<HashMap<String, FieldState>>,
+    collector: Arc<dyn ErasedCollector>,
     completion_tracker: CompletionTracker,
+    /// Schema answering introspection queries, if an operation set one
+    introspection: Option<async_graphql::dynamic::Schema>,
+    /// Queries registered by automatic persisted query clients, by hash
+    persisted_queries: Arc<std::sync::Mutex<HashMap<String, String>>>,
 }
 
 #[derive(Clone)]
 struct FieldState {
     handlers: Vec<Handler>,
+    cycle: bool,
     call_count: Arc<AtomicUsize>,
+    /// Whether each handler index counting towards completion has been used
+    called: Arc<Vec<AtomicBool>>,
+    /// Handlers picked by `operationName`, with whether each has been used
+    operation_handlers: Arc<HashMap<String, (Handler, AtomicBool)>>,
+}
+
+impl FieldState {
+    fn new(field: Field, operation_type: OperationType) -> Self {
+        // Subscriptions stream the sequential handlers only
+        let operation_handlers: HashMap<_, _> = match operation_type {
+            OperationType::Subscription => HashMap::new(),
+            _ => field
+                .operation_handlers
+                .into_iter()
+                .map(|(name, handler)| (name, (handler, AtomicBool::new(false))))
+                .collect(),
+        };
+        // A field answered by operation handlers alone expects no unnamed call
+        let expected_calls = match operation_handlers.is_empty() {
+            true => CompletionTracker::expected_calls(field.handlers.len()),
+            false => field.handlers.len(),
+        };
+        Self {
+            handlers: field.handlers,
+            cycle: field.cycle,
+            call_count: Arc::new(AtomicUsize::new(0)),
+            called: Arc::new((0..expected_calls).map(|_| AtomicBool::new(false)).collect()),
+            operation_handlers: Arc::new(operation_handlers),
+        }
+    }
+
+    /// Number of first calls this field contributes to the shutdown total
+    fn completion_calls(&self) -> usize {
+        self.called.len() + self.operation_handlers.len()
+    }
+
+    /// Mark the handler at `handler_index` as used, returning whether this is
+    /// its first call. Concurrent requests see `true` at most once per index.
+    fn first_call(&self, handler_index: usize) -> bool {
+        self.called
+            .get(handler_index)
+            .is_some_and(|called| !called.swap(true, Ordering::SeqCst))
+    }
 }
 
 async fn handle_graphql(
     State(state): State<ServerState>,
+    headers: HeaderMap,
     body: String,
-) -> impl IntoResponse {
-    let request: GraphQLRequest = match serde_json::from_str(&body) {
-        Ok(req) => req,
+) -> axum::response::Response {
+    let format = ResponseFormat::from_headers(&headers);
+    // A JSON array is a batch: each operation runs in order and gets its own response
+    if body.trim_start().starts_with('[') {
+        let operations: Vec<Value> = match serde_json::from_str(&body) {
+            Ok(operations) => operations,
+            Err(e) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(error = %e, "failed to parse batched request body");
+                return format.reply(StatusCode::OK, error_response(e.to_string()));
+            }
+        };
+        let mut responses = Vec::with_capacity(operations.len());
+        for operation in operations {
+            responses.push(match GraphQLRequest::from_batch_entry(operation, &body) {
+                Ok(request) => execute(&state, request).await,
+                Err(e) => error_response(e.to_string()),
+            });
+        }
+        return format.reply_batch(responses);
+    }
+
+    let response = match serde_json::from_str::<GraphQLRequest>(&body) {
+        Ok(mut request) => {
+            request.raw_body = body;
+            execute(&state, request).await
+        }
         Err(e) => {
-            let response = GraphQLResponse {
-                data: None,
-                errors: Some(vec![serde_json::json!({"message": e.to_string()})]),
-            };
-            return (StatusCode::OK, axum::Json(response));
+            #[cfg(feature = "tracing")]
+            tracing::warn!(error = %e, "failed to parse request body");
+            error_response(e.to_string())
         }
     };
+    format.reply(StatusCode::OK, response)
+}
 
-    // Collect the request
-    let mut collected = CollectedRequest::new(&request.query);
-    if let Some(op_name) = &request.operation_name {
-        collected = collected.with_operation_name(op_name);
+/// Resolve an Apollo automatic persisted query.
+///
+/// A request sending both a query and its hash registers the query; one
+/// sending only a known hash gets the registered query. An unknown hash fails
+/// with `PersistedQueryNotFound` so the client retries with the full query.
+/// Hashes are not checked against the query.
+fn resolve_persisted_query(
+    state: &ServerState,
+    request: &mut GraphQLRequest,
+) -> Result<(), GraphQLResponse> {
+    let Some(hash) = request.persisted_query_hash() else {
+        return match request.query.is_empty() {
+            true => Err(error_response("Missing query".to_string())),
+            false => Ok(()),
+        };
+    };
+    let mut persisted = state
+        .persisted_queries
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if !request.query.is_empty() {
+        persisted.insert(hash.to_string(), request.query.clone());
+        return Ok(());
     }
-    if let Some(vars) = &request.variables {
-        collected = collected.with_variables(vars.clone());
+    match persisted.get(hash) {
+        Some(query) => {
+            request.query = query.clone();
+            Ok(())
+        }
+        None => Err(GraphQLResponse {
+            data: None,
+            errors: Some(vec![serde_json::json!({
+                "message": "PersistedQueryNotFound",
+                "extensions": {"code": "PERSISTED_QUERY_NOT_FOUND"},
+            })]),
+        }),
+    }
+}
+
+/// Collect and run one operation against the mocked fields.
+///
+/// Requests for an unknown persisted query are answered without being collected.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "operation",
+        skip_all,
+        fields(operation_name = ?request.operation_name)
+    )
+)]
+async fn execute(state: &ServerState, mut request: GraphQLRequest) -> GraphQLResponse {
+    #[cfg(feature = "tracing")]
+    tracing::debug!("request received");
+
+    if let Err(response) = resolve_persisted_query(state, &mut request) {
+        return response;
     }
+
+    let selected = select(
+        &request.query,
+        request.operation_name.as_deref(),
+        request.variables.as_ref(),
+    );
+
+    // Introspection queries are answered from the SDL schema, without
+    // counting towards completion
+    let introspected = state.introspection.as_ref().filter(|_| {
+        selected
+            .as_ref()
+            .is_ok_and(introspection::is_introspection)
+    });
+
+    // Collect the request, unmatched unless every field it selects is mocked
+    let mut collected = request.collected();
+    collected.matched = introspected.is_some()
+        || selected
+            .as_ref()
+            .is_ok_and(|selected| all_fields_mocked(state, selected));
     state.collector.collect(collected);
 
-    // Parse the query to find the operation type and field
-    let query = request.query.trim();
-    let (handlers_map, _op_type) = if query.starts_with("mutation") {
-        (&state.mutation_handlers, "mutation")
-    } else {
-        (&state.query_handlers, "query")
+    if let Some(schema) = introspected {
+        #[cfg(feature = "tracing")]
+        tracing::debug!("introspection query answered");
+        let mut response = introspection::execute(
+            schema,
+            &request.query,
+            request.operation_name.as_deref(),
+            request.variables.as_ref(),
+        )
+        .await;
+        return GraphQLResponse {
+            data: Some(response["data"].take()),
+            errors: response["errors"].as_array().cloned(),
+        };
+    }
+
+    let mut first_calls = 0;
+    let response = resolve(state, &request, selected, &mut first_calls).await;
+    // Every operation counts once towards `expected_requests`, rejected or
+    // not and however many fields it selects
+    state.completion_tracker.operation_handled(first_calls).await;
+    response
+}
+
+/// Answer the selected operation from the mocked fields, counting the handlers
+/// used for the first time in `first_calls`
+async fn resolve(
+    state: &ServerState,
+    request: &GraphQLRequest,
+    selected: Result<SelectedOperation, String>,
+    first_calls: &mut usize,
+) -> GraphQLResponse {
+    let selected = match selected {
+        Ok(selected) => selected,
+        Err(message) => {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(%message, "failed to parse operation");
+            return error_response(message);
+        }
+    };
+    let handlers_map = match selected.operation_type {
+        OperationType::Query => &state.query_handlers,
+        OperationType::Mutation => &state.mutation_handlers,
+        OperationType::Subscription => {
+            let message = "Subscriptions are only served over websocket".to_string();
+            return error_response(message);
+        }
     };
 
-    // Simple field extraction - find field names in the query
+    // Fields only mocked under another operation type fail the whole request
+    let misplaced = misplaced_fields(state, &selected);
+    if !misplaced.is_empty() {
+        return GraphQLResponse {
+            data: None,
+            errors: Some(misplaced),
+        };
+    }
+
     let mut response_data = serde_json::Map::new();
     let mut errors: Vec<Value> = Vec::new();
 
-    for (field_name, field_state) in handlers_map.iter() {
-        if query.contains(field_name) {
-            let call_index = field_state.call_count.fetch_add(1, Ordering::SeqCst);
-            let handler_count = field_state.handlers.len();
-            let handler_index = call_index.min(handler_count.saturating_sub(1));
-
-            // Check if this is a new handler being called for the first time
-            if call_index < handler_count {
-                state.completion_tracker.handler_called().await;
+    for field in &selected.fields {
+        let Some(field_state) = handlers_map.get(&field.name) else {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(field = %field.name, "no handler matched");
+            continue;
+        };
+        let operation_handler = request
+            .operation_name
+            .as_deref()
+            .and_then(|name| field_state.operation_handlers.get(name));
+        let (handler, first_call) = match operation_handler {
+            Some((handler, called)) => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(field = %field.name, "handler selected");
+                (Some(handler), !called.swap(true, Ordering::SeqCst))
             }
-
-            if let Some(handler) = field_state.handlers.get(handler_index) {
-                let mut ctx = RequestContext::new(field_name).with_query(&request.query);
-                if let Some(op_name) = &request.operation_name {
-                    ctx = ctx.with_operation_name(op_name);
-                }
-                if let Some(vars) = &request.variables {
-                    ctx = ctx.with_variables(vars.clone());
+            None => {
+                let call_index = field_state.call_count.fetch_add(1, Ordering::SeqCst);
+                let handler_count = field_state.handlers.len();
+                let handler_index = match handler_count {
+                    0 => 0,
+                    _ if field_state.cycle => call_index % handler_count,
+                    _ => call_index.min(handler_count - 1),
+                };
+                #[cfg(feature = "tracing")]
+                if handler_count > 0 {
+                    tracing::debug!(
+                        field = %field.name,
+                        index = handler_index,
+                        count = handler_count,
+                        "handler selected"
+                    );
+                } else {
+                    tracing::warn!(field = %field.name, "no handler matched, the field has no handlers");
                 }
+                (
+                    field_state.handlers.get(handler_index),
+                    field_state.first_call(handler_index),
+                )
+            }
+        };
+
+        // First calls of a handler count towards completion
+        if first_call {
+            *first_calls += 1;
+        }
+
+        if let Some(handler) = handler {
+            let ctx = request.context(field);
+            let handler_response = match handler {
+                Handler::AsyncDynamic(f) => f(&ctx).await,
+                handler => handler.respond(&ctx),
+            };
+            insert_field_data(&mut response_data, field, handler_response.data.clone());
+            errors.extend(error_values(&handler_response, field));
+        }
+    }
+
+    GraphQLResponse {
+        data: Some(Value::Object(response_data)),
+        errors: if errors.is_empty() { None } else { Some(errors) },
+    }
+}
+
+/// Serve `GET /graphql`: websocket upgrades for subscriptions, otherwise an
+/// operation read from the `query`, `operationName` and `variables` parameters
+async fn handle_graphql_get(
+    State(state): State<ServerState>,
+    ws: Result<WebSocketUpgrade, WebSocketUpgradeRejection>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> axum::response::Response {
+    if let Ok(ws) = ws {
+        return ws
+            .protocols([GRAPHQL_TRANSPORT_WS])
+            .on_upgrade(move |socket| handle_socket(socket, state))
+            .into_response();
+    }
+
+    let format = ResponseFormat::from_headers(&headers);
+    let mut request = match GraphQLRequest::from_params(params) {
+        Ok(request) => request,
+        Err(message) => return format.reply(StatusCode::BAD_REQUEST, error_response(message)),
+    };
+    if let Err(response) = resolve_persisted_query(&state, &mut request) {
+        return format.reply(StatusCode::OK, response);
+    }
+
+    // Mutations must not be sent over GET
+    let selected = select(
+        &request.query,
+        request.operation_name.as_deref(),
+        request.variables.as_ref(),
+    );
+    if selected.is_ok_and(|selected| selected.operation_type == OperationType::Mutation) {
+        state.collector.collect(request.collected());
+        state.completion_tracker.request_handled(false).await;
+        let message = "Mutations are not allowed over GET".to_string();
+        return format.reply(StatusCode::METHOD_NOT_ALLOWED, error_response(message));
+    }
+
+    format.reply(StatusCode::OK, execute(&state, request).await)
+}
+
+/// Serve one `graphql-transport-ws` connection
+async fn handle_socket(mut socket: WebSocket, state: ServerState) {
+    while let Some(Ok(message)) = socket.recv().await {
+        let text = match message {
+            WsMessage::Text(text) => text,
+            WsMessage::Close(_) => break,
+            _ => continue,
+        };
+        let Ok(message) = serde_json::from_str::<WsClientMessage>(&text) else {
+            break;
+        };
 
-                let handler_response = handler.respond(&ctx);
-                if let Some(obj) = handler_response.data.as_object() {
-                    for (k, v) in obj {
-                        response_data.insert(k.clone(), v.clone());
+        let reply = match message.kind.as_str() {
+            "connection_init" => serde_json::json!({"type": "connection_ack"}),
+            "ping" => serde_json::json!({"type": "pong"}),
+            "subscribe" => {
+                let id = message.id.unwrap_or_default();
+                let request = message
+                    .payload
+                    .and_then(|payload| serde_json::from_value::<GraphQLRequest>(payload).ok())
+                    .ok_or_else(|| "Invalid subscribe payload".to_string());
+                let selected = request.and_then(|request| {
+                    let selected = select(
+                        &request.query,
+                        request.operation_name.as_deref(),
+                        request.variables.as_ref(),
+                    )?;
+                    if selected.operation_type != OperationType::Subscription {
+                        return Err("Only subscriptions are served over websocket".to_string());
                     }
-                } else {
-                    response_data.insert(field_name.clone(), handler_response.data.clone());
-                }
-                if let Some(errs) = &handler_response.errors {
-                    for err in errs {
-                        let mut err_val = serde_json::json!({"message": err.message});
-                        if let Some(path) = &err.path {
-                            err_val["path"] = serde_json::json!(path);
+                    Ok((request, selected))
+                });
+                match selected {
+                    Ok((request, selected)) => {
+                        if !stream_subscription(&mut socket, &state, &id, &request, &selected)
+                            .await
+                        {
+                            break;
                         }
-                        errors.push(err_val);
+                        serde_json::json!({"id": id, "type": "complete"})
                     }
+                    Err(message) => serde_json::json!({
+                        "id": id,
+                        "type": "error",
+                        "payload": [{ "message": message }]
+                    }),
                 }
             }
+            _ => continue,
+        };
+
+        if send_json(&mut socket, &reply).await.is_err() {
+            break;
         }
     }
+}
 
-    let response = GraphQLResponse {
-        data: Some(Value::Object(response_data)),
-        errors: if errors.is_empty() { None } else { Some(errors) },
+/// Stream every configured event of the subscribed fields as `next` messages.
+///
+/// Each event emitted for the first time counts as a handler call. Events left
+/// when the client goes away mid-stream count as well, so a disconnected client
+/// does not keep the scenario running. Returns `false` in that case.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "subscription",
+        skip_all,
+        fields(%id, operation_name = ?request.operation_name)
+    )
+)]
+async fn stream_subscription(
+    socket: &mut WebSocket,
+    state: &ServerState,
+    id: &str,
+    request: &GraphQLRequest,
+    selected: &SelectedOperation,
+) -> bool {
+    let mut collected = request.collected();
+    collected.matched = all_fields_mocked(state, selected);
+    state.collector.collect(collected);
+
+    let mut connected = true;
+    for field in &selected.fields {
+        let Some(field_state) = state.subscription_handlers.get(&field.name) else {
+            continue;
+        };
+
+        for (index, handler) in field_state.handlers.iter().enumerate() {
+            if !connected {
+                state
+                    .completion_tracker
+                    .request_handled(field_state.first_call(index))
+                    .await;
+                continue;
+            }
+
+            let ctx = request.context(field);
+            let handler_response = match handler {
+                Handler::AsyncDynamic(f) => f(&ctx).await,
+                handler => handler.respond(&ctx),
+            };
+            let mut data = serde_json::Map::new();
+            insert_field_data(&mut data, field, handler_response.data.clone());
+            let errors = error_values(&handler_response, field);
+            let payload = GraphQLResponse {
+                data: Some(Value::Object(data)),
+                errors: if errors.is_empty() { None } else { Some(errors) },
+            };
+            let next = serde_json::json!({"id": id, "type": "next", "payload": payload});
+            if send_json(socket, &next).await.is_err() {
+                connected = false;
+            }
+
+            state
+                .completion_tracker
+                .request_handled(field_state.first_call(index))
+                .await;
+        }
+    }
+
+    connected
+}
+
+/// Run `serve` until it has drained its connections, giving up `grace` after
+/// `draining` fires when a grace period is set
+async fn drain_within<F>(
+    serve: F,
+    draining: oneshot::Receiver<()>,
+    grace: Option<Duration>,
+) -> std::io::Result<()>
+where
+    F: Future<Output = std::io::Result<()>>,
+{
+    let Some(grace) = grace else {
+        return serve.await;
     };
+    tokio::select! {
+        served = serve => served,
+        _ = async {
+            let _ = draining.await;
+            tokio::time::sleep(grace).await;
+        } => Ok(()),
+    }
+}
 
-    (StatusCode::OK, axum::Json(response))
+async fn send_json(socket: &mut WebSocket, value: &Value) -> Result<(), axum::Error> {
+    socket.send(WsMessage::Text(value.to_string().into())).await
 }
 
 #[async_trait]
@@ -201,64 +857,83 @@ impl Server for AsyncGraphQL {
         &self,
         operations: Vec<Operation>,
         collector: C,
+        options: RunOptions,
         on_ready: Option<F>,
     ) -> Result<C::Output, HarnessError>
     where
         C: Collector + 'static,
         F: FnOnce(SocketAddr) + Send + 'static,
     {
-        let collector_arc: Arc<C> = Arc::new(collector);
-
-        // Count total handlers
-        let total_handlers: usize = operations
-            .iter()
-            .map(|op| {
-                op.fields
-                    .iter()
-                    .map(|f| f.handlers.len().max(1))
-                    .sum::<usize>()
-            })
-            .sum();
+        if !self.path.starts_with('/') {
+            return Err(HarnessError::ConfigurationError(format!(
+                "GraphQL path {:?} must start with '/'",
+                self.path
+            )));
+        }
 
-        // Create shutdown channel for auto-shutdown
-        let (auto_shutdown_tx, auto_shutdown_rx) = oneshot::channel();
-        let completion_tracker = CompletionTracker::new(total_handlers, auto_shutdown_tx);
+        // Wrap collector in a Mutex so we can take it out at the end,
+        // even while upgraded websocket connections still hold the state
+        let collector_holder = Arc::new(std::sync::Mutex::new(CollectorSlot {
+            collector: Some(collector),
+            next_sequence: 0,
+        }));
+        let erased_collector: Arc<dyn ErasedCollector> = collector_holder.clone();
 
         let mut query_handlers = HashMap::new();
         let mut mutation_handlers = HashMap::new();
+        let mut subscription_handlers = HashMap::new();
+
+        let introspection = operations
+            .iter()
+            .find_map(|operation| operation.introspection.as_deref())
+            .map(introspection::schema_from_sdl)
+            .transpose()
+            .map_err(|e| HarnessError::ConfigurationError(format!("Invalid SDL: {}", e)))?;
 
         for operation in operations {
             let handlers_map = match operation.operation_type {
                 OperationType::Query => &mut query_handlers,
                 OperationType::Mutation => &mut mutation_handlers,
-                OperationType::Subscription => continue, // Skip subscriptions for now
+                OperationType::Subscription => &mut subscription_handlers,
             };
 
             for field in operation.fields {
-                handlers_map.insert(
-                    field.name,
-                    FieldState {
-                        handlers: field.handlers,
-                        call_count: Arc::new(AtomicUsize::new(0)),
-                    },
-                );
+                let name = field.name.clone();
+                handlers_map.insert(name, FieldState::new(field, operation.operation_type));
             }
         }
 
+        // Count total handlers
+        let total_handlers: usize = [&query_handlers, &mutation_handlers, &subscription_handlers]
+            .iter()
+            .flat_map(|handlers| handlers.values())
+            .map(FieldState::completion_calls)
+            .sum();
+
+        // Create shutdown channel for auto-shutdown
+        let (auto_shutdown_tx, auto_shutdown_rx) = oneshot::channel();
+        let mut completion_tracker = CompletionTracker::new(total_handlers, auto_shutdown_tx);
+        if let Some(expected) = options.expected_requests {
+            completion_tracker = completion_tracker.counting_requests(expected);
+        }
+
         let state = ServerState {
             query_handlers: Arc::new(query_handlers),
             mutation_handlers: Arc::new(mutation_handlers),
-            collector: collector_arc.clone(),
+            subscription_handlers: Arc::new(subscription_handlers),
+            collector: erased_collector,
             completion_tracker,
+            introspection,
+            persisted_queries: Arc::default(),
         };
 
         let router = Router::new()
-            .route("/graphql", post(handle_graphql))
+            .route(&self.path, post(handle_graphql).get(handle_graphql_get))
             .with_state(state);
 
         let listener = tokio::net::TcpListener::bind(self.addr)
             .await
-            .map_err(|e| HarnessError::ServerError(e.to_string()))?;
+            .map_err(HarnessError::Bind)?;
 
         let addr = listener
             .local_addr()
@@ -269,17 +944,30 @@ impl Server for AsyncGraphQL {
             callback(addr);
         }
 
-        // Run the server until auto-shutdown
-        axum::serve(listener, router)
+        // Run the server until auto-shutdown, then let connections left open
+        // drain until the grace period ends
+        let (draining_tx, draining_rx) = oneshot::channel();
+        let serve = axum::serve(listener, router)
             .with_graceful_shutdown(async {
-                let _ = auto_shutdown_rx.await;
+                if auto_shutdown_rx.await.is_ok() {
+                    #[cfg(feature = "tracing")]
+                    tracing::info!("scenario complete, shutting down");
+                }
+                let _ = draining_tx.send(());
             })
+            .into_future();
+        drain_within(serve, draining_rx, options.shutdown_grace)
             .await
-            .map_err(|e| HarnessError::ServerError(e.to_string()))?;
+            .map_err(|e| HarnessError::Serve(Box::new(e)))?;
+
+        // Extract the collector and return its output
+        let collector = collector_holder
+            .lock()
+            .map_err(|e| HarnessError::ServerError(e.to_string()))?
+            .collector
+            .take()
+            .ok_or_else(|| HarnessError::ServerError("Collector already taken".to_string()))?;
 
-        // Extract the collector from the Arc and return its output
-        let collector = Arc::try_unwrap(collector_arc)
-            .map_err(|_| HarnessError::ServerError("Failed to unwrap collector".to_string()))?;
         Ok(collector.into_output())
     }
 }
@@ -0,0 +1,127 @@
+//! Assertions over the requests collected by a scenario
+
+use serde_json::Value;
+
+pub use server_harness_core::RequestLog;
+use server_harness_core::LogEntry;
+
+use crate::entities::CollectedRequest;
+
+impl LogEntry for CollectedRequest {
+    fn summary(&self) -> String {
+        self.query.clone()
+    }
+}
+
+/// GraphQL assertions on a [`RequestLog`], panicking with readable messages
+///
+/// ```
+/// use graphql_operation_server_harness::prelude::*;
+///
+/// let log = RequestLog::from(vec![
+///     CollectedRequest::new("query ($id: ID!) { user(id: $id) { name } }")
+///         .with_variables(json!({"id": "1", "locale": "en"})),
+/// ]);
+/// log.assert_count(1).assert_variable(0, "id", &json!("1"));
+/// ```
+pub trait RequestAssertions {
+    /// Assert that the request at `index` sent variable `name` equal to `expected`.
+    ///
+    /// Other variables are ignored; compare [`CollectedRequest::variables`]
+    /// directly for an exact match.
+    #[track_caller]
+    fn assert_variable(&self, index: usize, name: &str, expected: &Value) -> &Self;
+}
+
+impl RequestAssertions for RequestLog<CollectedRequest> {
+    #[track_caller]
+    fn assert_variable(&self, index: usize, name: &str, expected: &Value) -> &Self {
+        let request = self.request(index);
+        match request.variable(name) {
+            Some(actual) if actual == expected => {}
+            Some(actual) => panic!(
+                "request {} variable {} mismatch\n  expected: {}\n  actual:   {}",
+                index, name, expected, actual
+            ),
+            None => panic!(
+                "request {} has no variable {}\n  variables: {}",
+                index,
+                name,
+                request.variables.as_ref().unwrap_or(&Value::Null)
+            ),
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    fn log() -> RequestLog<CollectedRequest> {
+        RequestLog::from(vec![
+            CollectedRequest::new("query ($limit: Int) { users(limit: $limit) { id } }")
+                .with_variables(json!({"limit": 10, "offset": 0})),
+            CollectedRequest::new("{ health }"),
+        ])
+    }
+
+    fn panic_message(f: impl FnOnce()) -> String {
+        let error = catch_unwind(AssertUnwindSafe(f)).expect_err("assertion should fail");
+        error.downcast_ref::<String>().cloned().unwrap_or_default()
+    }
+
+    #[test]
+    fn test_assertions_pass() {
+        let log = log();
+        log.assert_count(2).assert_variable(0, "limit", &json!(10));
+        assert_eq!(log.len(), 2);
+        assert_eq!(log.into_inner()[1].query, "{ health }");
+    }
+
+    #[test]
+    fn test_assert_count_lists_received_requests() {
+        let message = panic_message(|| {
+            log().assert_count(1);
+        });
+        assert!(message.contains("expected 1 requests, got 2"));
+        assert!(message.contains("  { health }"));
+    }
+
+    #[test]
+    fn test_assert_variable_mismatch() {
+        let message = panic_message(|| {
+            log().assert_variable(0, "limit", &json!(20));
+        });
+        assert_eq!(
+            message,
+            "request 0 variable limit mismatch\n  expected: 20\n  actual:   10"
+        );
+    }
+
+    #[test]
+    fn test_assert_variable_absent() {
+        let message = panic_message(|| {
+            log().assert_variable(0, "cursor", &json!("abc"));
+        });
+        assert_eq!(
+            message,
+            "request 0 has no variable cursor\n  variables: {\"limit\":10,\"offset\":0}"
+        );
+
+        let message = panic_message(|| {
+            log().assert_variable(1, "limit", &json!(10));
+        });
+        assert_eq!(message, "request 1 has no variable limit\n  variables: null");
+    }
+
+    #[test]
+    fn test_assert_out_of_range() {
+        let message = panic_message(|| {
+            log().assert_variable(5, "limit", &json!(10));
+        });
+        assert_eq!(message, "no request at index 5, only 2 collected");
+    }
+}
@@ -1,11 +1,28 @@
+use serde::Serialize;
 use serde_json::Value;
+use std::time::Instant;
 
 /// A collected GraphQL request
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct CollectedRequest {
     pub query: String,
     pub operation_name: Option<String>,
     pub variables: Option<Value>,
+    /// The request's `extensions` object, such as an Apollo persisted query hash
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Value>,
+    /// The raw `POST` body, shared by every operation of a batch; empty for
+    /// `GET` and websocket requests
+    #[serde(skip)]
+    pub raw_body: String,
+    /// When the server collected the request
+    #[serde(skip)]
+    pub received_at: Instant,
+    /// Position of the request in collection order, starting at 0 for each server run
+    pub sequence: u64,
+    /// Whether a configured handler served the request, rather than a default
+    /// handler or the server's "not found" fallback
+    pub matched: bool,
 }
 
 impl CollectedRequest {
@@ -14,6 +31,11 @@ impl CollectedRequest {
             query: query.into(),
             operation_name: None,
             variables: None,
+            extensions: None,
+            raw_body: String::new(),
+            received_at: Instant::now(),
+            sequence: 0,
+            matched: true,
         }
     }
 
@@ -26,11 +48,62 @@ impl CollectedRequest {
         self.variables = Some(variables);
         self
     }
+
+    pub fn with_extensions(mut self, extensions: Value) -> Self {
+        self.extensions = Some(extensions);
+        self
+    }
+
+    /// Get a single variable by name, ignoring any other variables sent
+    pub fn variable(&self, name: &str) -> Option<&Value> {
+        self.variables.as_ref()?.get(name)
+    }
+}
+
+impl server_harness_core::Matched for CollectedRequest {
+    fn matched(&self) -> bool {
+        self.matched
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::use_cases::ports::Collector;
+    use crate::RecordingCollector;
+
+    #[test]
+    fn test_recording_collector_output() {
+        let collector = RecordingCollector::new();
+        collector.collect(CollectedRequest::new("{ users { id } }"));
+        collector.collect(
+            CollectedRequest::new("query GetUser($id: ID!) { user(id: $id) { id } }")
+                .with_operation_name("GetUser")
+                .with_variables(serde_json::json!({"id": "1"}))
+                .with_extensions(serde_json::json!({"persistedQuery": {"version": 1}})),
+        );
+
+        assert_eq!(
+            collector.into_output(),
+            serde_json::json!([
+                {
+                    "query": "{ users { id } }",
+                    "operation_name": null,
+                    "variables": null,
+                    "sequence": 0,
+                    "matched": true
+                },
+                {
+                    "query": "query GetUser($id: ID!) { user(id: $id) { id } }",
+                    "operation_name": "GetUser",
+                    "variables": {"id": "1"},
+                    "extensions": {"persistedQuery": {"version": 1}},
+                    "sequence": 0,
+                    "matched": true
+                }
+            ])
+        );
+    }
 
     #[test]
     fn test_collected_request_new() {
@@ -53,5 +126,16 @@ mod tests {
             .with_variables(serde_json::json!({"id": "123"}));
         assert_eq!(req.variables, Some(serde_json::json!({"id": "123"})));
     }
+
+    #[test]
+    fn test_collected_request_variable() {
+        let req = CollectedRequest::new("query ($id: ID!) { user(id: $id) { name } }")
+            .with_variables(serde_json::json!({"id": "123", "locale": "en"}));
+        assert_eq!(req.variable("id"), Some(&serde_json::json!("123")));
+        assert_eq!(req.variable("limit"), None);
+
+        let req = CollectedRequest::new("{ users { id } }");
+        assert_eq!(req.variable("id"), None);
+    }
 }
 
@@ -1,10 +1,16 @@
 use super::Handler;
+use serde_json::Value;
+use std::collections::HashMap;
 
 /// Represents a GraphQL field (query or mutation field)
 #[derive(Debug, Clone)]
 pub struct Field {
     pub name: String,
     pub handlers: Vec<Handler>,
+    /// Whether handlers restart from the first once the last has been used
+    pub cycle: bool,
+    /// Handlers picked by the request's `operationName` before the sequential ones
+    pub operation_handlers: HashMap<String, Handler>,
 }
 
 impl Field {
@@ -12,6 +18,8 @@ impl Field {
         Self {
             name: name.into(),
             handlers: Vec::new(),
+            cycle: false,
+            operation_handlers: HashMap::new(),
         }
     }
 
@@ -24,6 +32,33 @@ impl Field {
         self.handlers.extend(handlers);
         self
     }
+
+    /// Answer requests whose `operationName` is `operation_name` with `handler`,
+    /// e.g. when named operations share a field but expect different data.
+    ///
+    /// Other requests use the sequential handlers. Each operation handler counts
+    /// once towards auto-shutdown. Not used for subscriptions.
+    pub fn with_handler_for_operation(
+        mut self,
+        operation_name: impl Into<String>,
+        handler: Handler,
+    ) -> Self {
+        self.operation_handlers.insert(operation_name.into(), handler);
+        self
+    }
+
+    /// Cycle through the handlers instead of repeating the last one.
+    /// Subscriptions always stream every handler once per subscribe.
+    pub fn cycle(mut self) -> Self {
+        self.cycle = true;
+        self
+    }
+
+    /// Add a sequence of payloads for a subscription field.
+    /// Each payload is emitted to subscribers as its own `next` message, in order.
+    pub fn with_stream(self, payloads: Vec<Value>) -> Self {
+        self.with_handlers(payloads.into_iter().map(Handler::new))
+    }
 }
 
 #[cfg(test)]
@@ -35,6 +70,8 @@ mod tests {
         let field = Field::new("users");
         assert_eq!(field.name, "users");
         assert!(field.handlers.is_empty());
+        assert!(!field.cycle);
+        assert!(field.operation_handlers.is_empty());
     }
 
     #[test]
@@ -51,4 +88,20 @@ mod tests {
             .with_handler(Handler::new(serde_json::json!({"id": 2})));
         assert_eq!(field.handlers.len(), 2);
     }
+
+    #[test]
+    fn test_field_with_handler_for_operation() {
+        let field = Field::new("user")
+            .with_handler_for_operation("GetAdmin", Handler::new(serde_json::json!({"id": 1})))
+            .with_handler(Handler::new(serde_json::json!({"id": 2})));
+        assert_eq!(field.handlers.len(), 1);
+        assert!(field.operation_handlers.contains_key("GetAdmin"));
+    }
+
+    #[test]
+    fn test_field_with_stream() {
+        let field = Field::new("ticks")
+            .with_stream(vec![serde_json::json!(1), serde_json::json!(2), serde_json::json!(3)]);
+        assert_eq!(field.handlers.len(), 3);
+    }
 }
@@ -1,4 +1,7 @@
 use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
 
 /// Context passed to dynamic handlers
@@ -8,6 +11,8 @@ pub struct RequestContext {
     pub operation_name: Option<String>,
     pub variables: Option<Value>,
     pub field_name: String,
+    /// Arguments of the matched field, with variable references resolved
+    pub arguments: HashMap<String, Value>,
 }
 
 impl RequestContext {
@@ -17,6 +22,7 @@ impl RequestContext {
             operation_name: None,
             variables: None,
             field_name: field_name.into(),
+            arguments: HashMap::new(),
         }
     }
 
@@ -35,10 +41,20 @@ impl RequestContext {
         self
     }
 
+    pub fn with_argument(mut self, name: impl Into<String>, value: Value) -> Self {
+        self.arguments.insert(name.into(), value);
+        self
+    }
+
     /// Get a variable by name
     pub fn get_variable(&self, name: &str) -> Option<&Value> {
         self.variables.as_ref().and_then(|v| v.get(name))
     }
+
+    /// Get an argument of the matched field by name
+    pub fn get_argument(&self, name: &str) -> Option<&Value> {
+        self.arguments.get(name)
+    }
 }
 
 /// A GraphQL error
@@ -46,11 +62,36 @@ impl RequestContext {
 pub struct GraphQLError {
     pub message: String,
     pub path: Option<Vec<String>>,
+    /// Position the error at the responding field; the server fills in `path`
+    pub at_field: bool,
+    /// Machine-readable details, e.g. `{"code": "UNAUTHENTICATED"}`
+    pub extensions: Option<Value>,
+}
+
+impl GraphQLError {
+    /// Serialize into a GraphQL error object, using `path` unless the error sets one
+    pub(crate) fn to_value(&self, path: Option<Value>) -> Value {
+        let mut error = serde_json::json!({"message": self.message});
+        if let Some(path) = &self.path {
+            error["path"] = serde_json::json!(path);
+        } else if let Some(path) = path {
+            error["path"] = path;
+        }
+        if let Some(extensions) = &self.extensions {
+            error["extensions"] = extensions.clone();
+        }
+        error
+    }
 }
 
 /// Type alias for dynamic handler functions
 pub type HandlerFn = Arc<dyn Fn(&RequestContext) -> HandlerResponse + Send + Sync>;
 
+/// Type alias for async dynamic handler functions
+pub type AsyncHandlerFn = Arc<
+    dyn Fn(&RequestContext) -> Pin<Box<dyn Future<Output = HandlerResponse> + Send>> + Send + Sync,
+>;
+
 /// Response from a handler
 #[derive(Debug, Clone)]
 pub struct HandlerResponse {
@@ -67,6 +108,32 @@ impl HandlerResponse {
         let error = GraphQLError {
             message: message.into(),
             path: None,
+            at_field: false,
+            extensions: None,
+        };
+        self.errors.get_or_insert_with(Vec::new).push(error);
+        self
+    }
+
+    /// Add an error whose `path` points at the field this response is for
+    pub fn with_field_error(mut self, message: impl Into<String>) -> Self {
+        let error = GraphQLError {
+            message: message.into(),
+            path: None,
+            at_field: true,
+            extensions: None,
+        };
+        self.errors.get_or_insert_with(Vec::new).push(error);
+        self
+    }
+
+    /// Add an error carrying `extensions`, e.g. `json!({"code": "UNAUTHENTICATED"})`
+    pub fn with_error_extensions(mut self, message: impl Into<String>, extensions: Value) -> Self {
+        let error = GraphQLError {
+            message: message.into(),
+            path: None,
+            at_field: false,
+            extensions: Some(extensions),
         };
         self.errors.get_or_insert_with(Vec::new).push(error);
         self
@@ -77,16 +144,7 @@ impl HandlerResponse {
             "data": self.data
         });
         if let Some(errors) = &self.errors {
-            let error_values: Vec<Value> = errors
-                .iter()
-                .map(|e| {
-                    let mut err = serde_json::json!({"message": e.message});
-                    if let Some(path) = &e.path {
-                        err["path"] = serde_json::json!(path);
-                    }
-                    err
-                })
-                .collect();
+            let error_values: Vec<Value> = errors.iter().map(|e| e.to_value(None)).collect();
             response["errors"] = serde_json::json!(error_values);
         }
         response
@@ -100,6 +158,8 @@ pub enum Handler {
     Static(HandlerResponse),
     /// Dynamic response - builds data based on the request context
     Dynamic(HandlerFn),
+    /// Async dynamic response - awaited before responding
+    AsyncDynamic(AsyncHandlerFn),
 }
 
 impl std::fmt::Debug for Handler {
@@ -107,6 +167,7 @@ impl std::fmt::Debug for Handler {
         match self {
             Handler::Static(response) => f.debug_tuple("Static").field(response).finish(),
             Handler::Dynamic(_) => f.debug_tuple("Dynamic").field(&"<fn>").finish(),
+            Handler::AsyncDynamic(_) => f.debug_tuple("AsyncDynamic").field(&"<fn>").finish(),
         }
     }
 }
@@ -125,11 +186,95 @@ impl Handler {
         Handler::Dynamic(Arc::new(f))
     }
 
+    /// Create a dynamic handler whose response is computed asynchronously,
+    /// e.g. to simulate latency or read shared async state
+    pub fn dynamic_async<F, Fut>(f: F) -> Self
+    where
+        F: Fn(&RequestContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = HandlerResponse> + Send + 'static,
+    {
+        Handler::AsyncDynamic(Arc::new(move |ctx| Box::pin(f(ctx))))
+    }
+
+    /// Create a handler serving `items` as a Relay-style connection, paged by
+    /// the `page_size_var` and `cursor_var` arguments or variables, e.g.
+    /// `first` and `after`.
+    ///
+    /// The field resolves to `edges` of `{node, cursor}` and a `pageInfo` with
+    /// `hasNextPage`, `hasPreviousPage`, `startCursor` and `endCursor`. Cursors
+    /// are item offsets; a page starts after the cursor given, and without a
+    /// page size it holds every remaining item. An unknown cursor fails the
+    /// field.
+    pub fn paginated(
+        items: Vec<Value>,
+        page_size_var: impl Into<String>,
+        cursor_var: impl Into<String>,
+    ) -> Self {
+        let page_size_var = page_size_var.into();
+        let cursor_var = cursor_var.into();
+        Handler::dynamic(move |ctx| {
+            let lookup = |name: &str| {
+                ctx.get_argument(name)
+                    .or_else(|| ctx.get_variable(name))
+                    .filter(|value| !value.is_null())
+            };
+            let start = match lookup(&cursor_var) {
+                None => 0,
+                Some(cursor) => match cursor_offset(cursor, items.len()) {
+                    Some(offset) => offset + 1,
+                    None => {
+                        return HandlerResponse::new(Value::Null)
+                            .with_field_error(format!("Invalid cursor: {}", cursor))
+                    }
+                },
+            };
+            let end = match lookup(&page_size_var).and_then(Value::as_u64) {
+                Some(size) => items.len().min(start.saturating_add(size as usize)),
+                None => items.len(),
+            };
+
+            let edges: Vec<Value> = (start..end)
+                .map(|index| {
+                    serde_json::json!({"node": items[index], "cursor": index.to_string()})
+                })
+                .collect();
+            let cursor = |index: usize| (start < end).then(|| index.to_string());
+            let connection = serde_json::json!({
+                "edges": edges,
+                "pageInfo": {
+                    "hasNextPage": end < items.len(),
+                    "hasPreviousPage": start > 0,
+                    "startCursor": cursor(start),
+                    "endCursor": cursor(end.saturating_sub(1)),
+                },
+            });
+            HandlerResponse::new(serde_json::json!({ ctx.field_name.clone(): connection }))
+        })
+    }
+
     /// Add an error to a static handler
     pub fn with_error(self, message: impl Into<String>) -> Self {
         match self {
             Handler::Static(response) => Handler::Static(response.with_error(message)),
-            Handler::Dynamic(_) => self,
+            Handler::Dynamic(_) | Handler::AsyncDynamic(_) => self,
+        }
+    }
+
+    /// Add an error positioned at the responding field to a static handler
+    pub fn with_field_error(self, message: impl Into<String>) -> Self {
+        match self {
+            Handler::Static(response) => Handler::Static(response.with_field_error(message)),
+            Handler::Dynamic(_) | Handler::AsyncDynamic(_) => self,
+        }
+    }
+
+    /// Add an error carrying `extensions` to a static handler
+    pub fn with_error_extensions(self, message: impl Into<String>, extensions: Value) -> Self {
+        match self {
+            Handler::Static(response) => {
+                Handler::Static(response.with_error_extensions(message, extensions))
+            }
+            Handler::Dynamic(_) | Handler::AsyncDynamic(_) => self,
         }
     }
 
@@ -140,19 +285,25 @@ impl Handler {
                 let error = GraphQLError {
                     message: message.into(),
                     path: Some(path),
+                    at_field: false,
+                    extensions: None,
                 };
                 response.errors.get_or_insert_with(Vec::new).push(error);
                 Handler::Static(response)
             }
-            Handler::Dynamic(_) => self,
+            Handler::Dynamic(_) | Handler::AsyncDynamic(_) => self,
         }
     }
 
-    /// Get the response for a given request context
+    /// Get the response for a given request context.
+    /// Async handlers must be awaited; they are driven by the server, so this
+    /// returns an error response for them.
     pub fn respond(&self, ctx: &RequestContext) -> HandlerResponse {
         match self {
             Handler::Static(response) => response.clone(),
             Handler::Dynamic(f) => f(ctx),
+            Handler::AsyncDynamic(_) => HandlerResponse::new(Value::Null)
+                .with_error("Async handlers are awaited by the server"),
         }
     }
 
@@ -160,7 +311,7 @@ impl Handler {
     pub fn data(&self) -> &Value {
         match self {
             Handler::Static(response) => &response.data,
-            Handler::Dynamic(_) => &Value::Null,
+            Handler::Dynamic(_) | Handler::AsyncDynamic(_) => &Value::Null,
         }
     }
 
@@ -168,7 +319,7 @@ impl Handler {
     pub fn errors(&self) -> Option<&Vec<GraphQLError>> {
         match self {
             Handler::Static(response) => response.errors.as_ref(),
-            Handler::Dynamic(_) => None,
+            Handler::Dynamic(_) | Handler::AsyncDynamic(_) => None,
         }
     }
 
@@ -176,11 +327,21 @@ impl Handler {
     pub fn to_response(&self) -> Value {
         match self {
             Handler::Static(response) => response.to_response_value(),
-            Handler::Dynamic(_) => serde_json::json!({"data": null}),
+            Handler::Dynamic(_) | Handler::AsyncDynamic(_) => serde_json::json!({"data": null}),
         }
     }
 }
 
+/// The item offset a pagination cursor points at, `None` unless it is one of
+/// the `len` items
+fn cursor_offset(cursor: &Value, len: usize) -> Option<usize> {
+    let offset = match cursor {
+        Value::String(cursor) => cursor.parse().ok()?,
+        cursor => cursor.as_u64()? as usize,
+    };
+    (offset < len).then_some(offset)
+}
+
 impl From<Value> for Handler {
     fn from(data: Value) -> Self {
         Handler::new(data)
@@ -198,6 +359,13 @@ mod tests {
         assert_eq!(handler.respond(&ctx).data, serde_json::json!({"users": []}));
     }
 
+    #[test]
+    fn test_request_context_get_argument() {
+        let ctx = RequestContext::new("user").with_argument("id", serde_json::json!(5));
+        assert_eq!(ctx.get_argument("id"), Some(&serde_json::json!(5)));
+        assert_eq!(ctx.get_argument("name"), None);
+    }
+
     #[test]
     fn test_handler_with_error() {
         let handler = Handler::new(serde_json::json!(null)).with_error("Something went wrong");
@@ -208,6 +376,15 @@ mod tests {
         assert_eq!(errors[0].message, "Something went wrong");
     }
 
+    #[test]
+    fn test_handler_response_with_field_error() {
+        let response = HandlerResponse::new(Value::Null).with_field_error("Not found");
+        let errors = response.errors.unwrap();
+        assert_eq!(errors[0].message, "Not found");
+        assert!(errors[0].at_field);
+        assert_eq!(errors[0].path, None);
+    }
+
     #[test]
     fn test_handler_with_error_at_path() {
         let handler = Handler::new(serde_json::json!(null))
@@ -236,6 +413,21 @@ mod tests {
         assert_eq!(errors.len(), 2);
     }
 
+    #[test]
+    fn test_handler_to_response_with_error_extensions() {
+        let handler = Handler::new(serde_json::json!(null)).with_error_extensions(
+            "Not signed in",
+            serde_json::json!({"code": "UNAUTHENTICATED"}),
+        );
+        assert_eq!(
+            handler.to_response()["errors"],
+            serde_json::json!([{
+                "message": "Not signed in",
+                "extensions": {"code": "UNAUTHENTICATED"}
+            }])
+        );
+    }
+
     #[test]
     fn test_handler_from_value() {
         let value = serde_json::json!({"test": true});
@@ -276,4 +468,58 @@ mod tests {
         assert_eq!(response.data["user"]["id"], "123");
         assert_eq!(response.data["user"]["name"], "User 123");
     }
+
+    #[test]
+    fn test_paginated_handler() {
+        use serde_json::json;
+
+        let items = vec![json!("a"), json!("b"), json!("c")];
+        let handler = Handler::paginated(items, "first", "after");
+
+        let ctx = RequestContext::new("letters");
+        let data = handler.respond(&ctx).data;
+        assert_eq!(data["letters"]["edges"].as_array().unwrap().len(), 3);
+        assert_eq!(data["letters"]["pageInfo"]["hasNextPage"], false);
+
+        let ctx = RequestContext::new("letters")
+            .with_argument("first", json!(1))
+            .with_argument("after", json!("0"));
+        let data = handler.respond(&ctx).data;
+        assert_eq!(
+            data,
+            json!({"letters": {
+                "edges": [{"node": "b", "cursor": "1"}],
+                "pageInfo": {
+                    "hasNextPage": true,
+                    "hasPreviousPage": true,
+                    "startCursor": "1",
+                    "endCursor": "1",
+                },
+            }})
+        );
+
+        let ctx = RequestContext::new("letters").with_variables(json!({"after": "7"}));
+        let response = handler.respond(&ctx);
+        assert_eq!(response.data, Value::Null);
+        assert_eq!(response.errors.unwrap()[0].message, "Invalid cursor: \"7\"");
+    }
+
+    #[tokio::test]
+    async fn test_async_dynamic_handler() {
+        let handler = Handler::dynamic_async(|ctx: &RequestContext| {
+            let field = ctx.field_name.clone();
+            async move {
+                tokio::task::yield_now().await;
+                HandlerResponse::new(serde_json::json!({ field: "done" }))
+            }
+        });
+
+        let ctx = RequestContext::new("job");
+        let Handler::AsyncDynamic(f) = &handler else {
+            panic!("expected an async handler");
+        };
+        assert_eq!(f(&ctx).await.data, serde_json::json!({"job": "done"}));
+        assert!(handler.respond(&ctx).errors.is_some());
+        assert_eq!(format!("{:?}", handler), "AsyncDynamic(\"<fn>\")");
+    }
 }
@@ -23,6 +23,8 @@ impl std::fmt::Display for OperationType {
 pub struct Operation {
     pub operation_type: OperationType,
     pub fields: Vec<Field>,
+    /// Schema SDL answering introspection queries, see [`Operation::with_introspection`]
+    pub introspection: Option<String>,
 }
 
 impl Operation {
@@ -30,6 +32,7 @@ impl Operation {
         Self {
             operation_type: OperationType::Query,
             fields: Vec::new(),
+            introspection: None,
         }
     }
 
@@ -37,6 +40,7 @@ impl Operation {
         Self {
             operation_type: OperationType::Mutation,
             fields: Vec::new(),
+            introspection: None,
         }
     }
 
@@ -44,6 +48,7 @@ impl Operation {
         Self {
             operation_type: OperationType::Subscription,
             fields: Vec::new(),
+            introspection: None,
         }
     }
 
@@ -56,6 +61,18 @@ impl Operation {
         self.fields.extend(fields);
         self
     }
+
+    /// Answer `__schema` and `__type` introspection queries from the schema
+    /// described by `sdl`, e.g. for clients that introspect on startup.
+    ///
+    /// Introspection queries never count towards auto-shutdown. The SDL is
+    /// parsed when the server starts, failing the run with a
+    /// [`HarnessError::ConfigurationError`](crate::error::HarnessError::ConfigurationError)
+    /// if it is invalid. Only one SDL is used per scenario.
+    pub fn with_introspection(mut self, sdl: &str) -> Self {
+        self.introspection = Some(sdl.to_string());
+        self
+    }
 }
 
 #[cfg(test)]
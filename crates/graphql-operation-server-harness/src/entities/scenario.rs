@@ -1,8 +1,10 @@
 use crate::entities::Operation;
+use crate::use_cases::ports::RunOptions;
 
 /// A test scenario containing a server configuration, collector, and operations
 pub struct Scenario<S, C> {
     pub(crate) server: S,
     pub(crate) collector: C,
     pub(crate) operations: Vec<Operation>,
+    pub(crate) options: RunOptions,
 }
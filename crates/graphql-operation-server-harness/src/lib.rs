@@ -30,6 +30,7 @@
 //! ```
 
 mod adapters;
+pub mod assertions;
 pub mod entities;
 pub mod error;
 pub mod use_cases;
@@ -39,41 +40,32 @@ pub use error::HarnessError;
 #[cfg(feature = "async-graphql")]
 pub use adapters::gateways::AsyncGraphQL;
 
-/// Default collector implementation using a thread-safe vector
-pub struct DefaultCollector {
-    requests: std::sync::Mutex<Vec<entities::CollectedRequest>>,
-}
-
-impl DefaultCollector {
-    pub fn new() -> Self {
-        Self {
-            requests: std::sync::Mutex::new(Vec::new()),
-        }
-    }
-}
+/// Default collector implementation that collects requests into a Vec
+pub type DefaultCollector = server_harness_core::DefaultCollector<entities::CollectedRequest>;
 
-impl Default for DefaultCollector {
-    fn default() -> Self {
-        Self::new()
-    }
-}
+/// Collector that records requests as JSON, e.g. for snapshot tests
+///
+/// `into_output` returns an array with one object per request.
+pub type RecordingCollector = server_harness_core::RecordingCollector<entities::CollectedRequest>;
 
-impl use_cases::ports::Collector for DefaultCollector {
-    type Output = Vec<entities::CollectedRequest>;
+/// Collector that fails when a request matched no configured handler
+///
+/// `into_output` returns every request as `Ok` when all of them matched, and the
+/// unmatched ones as `Err` otherwise. See [`CollectedRequest::matched`](entities::CollectedRequest::matched).
+pub type StrictCollector = server_harness_core::StrictCollector<entities::CollectedRequest>;
 
-    fn collect(&self, request: entities::CollectedRequest) {
-        if let Ok(mut requests) = self.requests.lock() {
-            requests.push(request);
-        }
-    }
+/// Collector storing only the requests a predicate accepts, e.g. to ignore
+/// health checks, and delegating them to an inner collector
+///
+/// Filtered requests are still answered normally.
+pub use server_harness_core::FilteringCollector;
 
-    fn into_output(self) -> Self::Output {
-        self.requests.into_inner().unwrap_or_default()
-    }
-}
+/// Collectors that can be read mid-scenario through a shared `Arc` handle
+pub use server_harness_core::Snapshot;
 
 /// Prelude module for convenient imports
 pub mod prelude {
+    pub use crate::assertions::{RequestAssertions, RequestLog};
     pub use crate::entities::{
         CollectedRequest, Field, GraphQLError, Handler, HandlerResponse, Operation, OperationType,
         RequestContext, Scenario,
@@ -81,7 +73,9 @@ pub mod prelude {
     pub use crate::error::HarnessError;
     pub use crate::use_cases::ports::{Collector, Server};
     pub use crate::use_cases::ScenarioBuilder;
-    pub use crate::DefaultCollector;
+    pub use crate::{
+        DefaultCollector, FilteringCollector, RecordingCollector, Snapshot, StrictCollector,
+    };
     pub use serde_json::json;
 
     #[cfg(feature = "async-graphql")]
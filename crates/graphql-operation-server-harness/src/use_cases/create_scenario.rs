@@ -1,14 +1,16 @@
 use std::net::SocketAddr;
+use std::time::Duration;
 
 use crate::entities::{Operation, Scenario};
 use crate::error::HarnessError;
-use crate::use_cases::ports::{Collector, Server};
+use crate::use_cases::ports::{Collector, RunOptions, Server};
 
 /// Builder for creating scenarios with a fluent API
 pub struct ScenarioBuilder<S, C> {
     server: Option<S>,
     collector: Option<C>,
     operations: Vec<Operation>,
+    options: RunOptions,
 }
 
 impl ScenarioBuilder<(), ()> {
@@ -17,6 +19,7 @@ impl ScenarioBuilder<(), ()> {
             server: None,
             collector: None,
             operations: Vec::new(),
+            options: RunOptions::default(),
         }
     }
 }
@@ -33,6 +36,7 @@ impl<S, C> ScenarioBuilder<S, C> {
             server: Some(server),
             collector: self.collector,
             operations: self.operations,
+            options: self.options,
         }
     }
 
@@ -41,6 +45,7 @@ impl<S, C> ScenarioBuilder<S, C> {
             server: self.server,
             collector: Some(collector),
             operations: self.operations,
+            options: self.options,
         }
     }
 
@@ -48,6 +53,27 @@ impl<S, C> ScenarioBuilder<S, C> {
         self.operations.push(operation);
         self
     }
+
+    /// Shut down after `n` handled requests rather than once every handler has been called.
+    ///
+    /// Each HTTP request counts once, however many fields it selects, including
+    /// requests rejected after parsing, e.g. for an unknown field or operation.
+    /// Combine with cyclic fields ([`Field::cycle`](crate::entities::Field::cycle))
+    /// for load-style tests.
+    pub fn expected_requests(mut self, n: usize) -> Self {
+        self.options.expected_requests = Some(n);
+        self
+    }
+
+    /// Keep serving in-flight requests for up to `grace` once the scenario completes.
+    ///
+    /// The server stops accepting connections when every handler has been called,
+    /// then waits for slow responses still being written. With a grace period it
+    /// stops waiting after `grace` instead of waiting for every in-flight request.
+    pub fn shutdown_grace(mut self, grace: Duration) -> Self {
+        self.options.shutdown_grace = Some(grace);
+        self
+    }
 }
 
 impl<S: Server + 'static, C: Collector + 'static> ScenarioBuilder<S, C> {
@@ -56,6 +82,7 @@ impl<S: Server + 'static, C: Collector + 'static> ScenarioBuilder<S, C> {
             server: self.server.expect("server is required"),
             collector: self.collector.expect("collector is required"),
             operations: self.operations,
+            options: self.options,
         }
     }
 
@@ -64,7 +91,12 @@ impl<S: Server + 'static, C: Collector + 'static> ScenarioBuilder<S, C> {
         let scenario = self.build();
         scenario
             .server
-            .run(scenario.operations, scenario.collector, None::<fn(SocketAddr)>)
+            .run(
+                scenario.operations,
+                scenario.collector,
+                scenario.options,
+                None::<fn(SocketAddr)>,
+            )
             .await
     }
 }
@@ -73,7 +105,12 @@ impl<S: Server + 'static, C: Collector + 'static> Scenario<S, C> {
     /// Execute the scenario
     pub async fn execute(self) -> Result<C::Output, HarnessError> {
         self.server
-            .run(self.operations, self.collector, None::<fn(SocketAddr)>)
+            .run(
+                self.operations,
+                self.collector,
+                self.options,
+                None::<fn(SocketAddr)>,
+            )
             .await
     }
 }
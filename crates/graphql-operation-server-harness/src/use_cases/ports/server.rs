@@ -1,10 +1,22 @@
 use async_trait::async_trait;
 use std::net::SocketAddr;
+use std::time::Duration;
 
 use super::Collector;
 use crate::entities::Operation;
 use crate::error::HarnessError;
 
+/// Options controlling how a server runs a scenario
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    /// If set, the server shuts down after this many requests have been handled
+    /// instead of once every handler has been called.
+    pub expected_requests: Option<usize>,
+    /// If set, connections still in flight when the scenario completes are
+    /// drained for at most this long before the server stops.
+    pub shutdown_grace: Option<Duration>,
+}
+
 /// Trait for GraphQL server implementations
 #[async_trait]
 pub trait Server: Send + Sync + Clone {
@@ -13,6 +25,7 @@ pub trait Server: Send + Sync + Clone {
         &self,
         operations: Vec<Operation>,
         collector: C,
+        options: RunOptions,
         on_ready: Option<F>,
     ) -> Result<C::Output, HarnessError>
     where
@@ -1,7 +1,9 @@
 //! Integration tests for graphql-operation-server-harness
 
 use graphql_operation_server_harness::prelude::*;
+use graphql_operation_server_harness::use_cases::ports::RunOptions;
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use tokio::sync::Notify;
 
@@ -14,6 +16,34 @@ where
     F: FnOnce(SocketAddr) -> Fut + Send + 'static,
     Fut: std::future::Future<Output = ()> + Send,
 {
+    run_with_options(operations, RunOptions::default(), make_requests).await
+}
+
+/// Helper to run a server with options and execute requests against it
+async fn run_with_options<F, Fut>(
+    operations: Vec<Operation>,
+    options: RunOptions,
+    make_requests: F,
+) -> Vec<CollectedRequest>
+where
+    F: FnOnce(SocketAddr) -> Fut + Send + 'static,
+    Fut: std::future::Future<Output = ()> + Send,
+{
+    let (addr, server_task) = start_server(operations, options).await;
+
+    make_requests(addr).await;
+
+    server_task.await.unwrap().unwrap()
+}
+
+/// Helper to start a server in the background, returning its address and run task
+async fn start_server(
+    operations: Vec<Operation>,
+    options: RunOptions,
+) -> (
+    SocketAddr,
+    tokio::task::JoinHandle<Result<Vec<CollectedRequest>, HarnessError>>,
+) {
     let ready = Arc::new(Notify::new());
     let ready_clone = ready.clone();
     let addr_holder = Arc::new(std::sync::Mutex::new(None));
@@ -27,6 +57,7 @@ where
             .run(
                 operations,
                 collector,
+                options,
                 Some(move |addr| {
                     *addr_holder_clone.lock().unwrap() = Some(addr);
                     ready_clone.notify_one();
@@ -37,10 +68,7 @@ where
 
     ready.notified().await;
     let addr = addr_holder.lock().unwrap().unwrap();
-
-    make_requests(addr).await;
-
-    server_task.await.unwrap().unwrap()
+    (addr, server_task)
 }
 
 #[tokio::test]
@@ -103,6 +131,236 @@ async fn test_mutation() {
     assert_eq!(result.len(), 1);
 }
 
+#[tokio::test]
+async fn test_routes_by_parsed_operation_type() {
+    let result = run_with_requests(
+        vec![
+            Operation::query().with_field(
+                Field::new("users").with_handler(Handler::new(json!({"users": []}))),
+            ),
+            Operation::mutation().with_field(
+                Field::new("createUser")
+                    .with_handler(Handler::new(json!({"createUser": {"id": 1}}))),
+            ),
+        ],
+        |addr| async move {
+            let client = reqwest::Client::new();
+            let post = |query: &'static str| {
+                client
+                    .post(format!("http://{}/graphql", addr))
+                    .json(&json!({ "query": query }))
+                    .send()
+            };
+
+            // Mutation field requested in a query: rejected without calling a handler
+            let body: serde_json::Value = post("query { createUser { id } }")
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            assert!(body["data"].is_null());
+            assert_eq!(
+                body["errors"][0]["message"],
+                "Field \"createUser\" is a mutation field and cannot be used in a query"
+            );
+            assert_eq!(body["errors"][0]["path"], json!(["createUser"]));
+
+            // Shorthand query
+            let body: serde_json::Value = post("{ users { id } }")
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            assert_eq!(body["data"]["users"], json!([]));
+
+            // Comment before the mutation keyword
+            let body: serde_json::Value = post("# create\n  mutation { createUser { id } }")
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            assert_eq!(body["data"]["createUser"]["id"], 1);
+        },
+    )
+    .await;
+
+    assert_eq!(result.len(), 3);
+}
+
+#[tokio::test]
+async fn test_batched_operations() {
+    let result = run_with_requests(
+        vec![
+            Operation::query().with_field(
+                Field::new("users").with_handler(Handler::new(json!({"users": [{"id": 1}]}))),
+            ),
+            Operation::mutation().with_field(
+                Field::new("createUser")
+                    .with_handler(Handler::new(json!({"createUser": {"id": 2}}))),
+            ),
+        ],
+        |addr| async move {
+            let client = reqwest::Client::new();
+            let response = client
+                .post(format!("http://{}/graphql", addr))
+                .json(&json!([
+                    {"query": "mutation { createUser { id } }"},
+                    {"query": "{ users { id } }"}
+                ]))
+                .send()
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), 200);
+            let body: serde_json::Value = response.json().await.unwrap();
+            assert_eq!(
+                body,
+                json!([
+                    {"data": {"createUser": {"id": 2}}},
+                    {"data": {"users": [{"id": 1}]}}
+                ])
+            );
+        },
+    )
+    .await;
+
+    assert_eq!(result.len(), 2);
+    assert!(result[0].query.starts_with("mutation"));
+    assert_eq!(result[1].query, "{ users { id } }");
+}
+
+#[tokio::test]
+async fn test_collects_raw_body_and_extensions() {
+    let hash = "ecf4edb46db40b5132295c0291d62fb65d6759a9eedfa4d5d612dd5ec54a6b38";
+    let body = json!({
+        "query": "{ users { id } }",
+        "extensions": {"persistedQuery": {"version": 1, "sha256Hash": hash}}
+    })
+    .to_string();
+    let sent = body.clone();
+    let result = run_with_requests(
+        vec![Operation::query()
+            .with_field(Field::new("users").with_handler(Handler::new(json!({"users": []}))))],
+        |addr| async move {
+            reqwest::Client::new()
+                .post(format!("http://{}/graphql", addr))
+                .header("content-type", "application/json")
+                .body(sent)
+                .send()
+                .await
+                .unwrap();
+        },
+    )
+    .await;
+
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].raw_body, body);
+    let extensions = result[0].extensions.as_ref().unwrap();
+    assert_eq!(extensions["persistedQuery"]["sha256Hash"], hash);
+}
+
+#[tokio::test]
+async fn test_automatic_persisted_query_handshake() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let handler_calls = calls.clone();
+    let result = run_with_requests(
+        vec![Operation::query().with_field(Field::new("users").with_handler(
+            Handler::dynamic(move |_| {
+                handler_calls.fetch_add(1, Ordering::SeqCst);
+                HandlerResponse::new(json!({"users": []}))
+            }),
+        ))],
+        |addr| async move {
+            let client = reqwest::Client::new();
+            let url = format!("http://{}/graphql", addr);
+            let extensions = json!({"persistedQuery": {"version": 1, "sha256Hash": "abc123"}});
+
+            let response: serde_json::Value = client
+                .post(&url)
+                .json(&json!({"extensions": extensions}))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            assert_eq!(response["errors"][0]["message"], "PersistedQueryNotFound");
+            assert_eq!(
+                response["errors"][0]["extensions"]["code"],
+                "PERSISTED_QUERY_NOT_FOUND"
+            );
+
+            let response: serde_json::Value = client
+                .post(&url)
+                .json(&json!({"query": "{ users { id } }", "extensions": extensions}))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            assert_eq!(response, json!({"data": {"users": []}}));
+        },
+    )
+    .await;
+
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].query, "{ users { id } }");
+}
+
+#[tokio::test]
+async fn test_get_query() {
+    let result = run_with_options(
+        vec![
+            Operation::query().with_field(Field::new("user").with_handler(Handler::dynamic(
+                |ctx| HandlerResponse::new(json!({"user": {"id": ctx.get_argument("id")}})),
+            ))),
+            Operation::mutation().with_field(
+                Field::new("deleteUser").with_handler(Handler::new(json!({"deleteUser": true}))),
+            ),
+        ],
+        RunOptions {
+            expected_requests: Some(2),
+            ..Default::default()
+        },
+        |addr| async move {
+            let client = reqwest::Client::new();
+
+            // Mutations are rejected over GET, without calling the handler
+            let response = client
+                .get(format!("http://{}/graphql", addr))
+                .query(&[("query", "mutation { deleteUser }")])
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), 405);
+
+            let response = client
+                .get(format!("http://{}/graphql", addr))
+                .query(&[
+                    ("query", "query User($id: ID) { user(id: $id) { id } }"),
+                    ("operationName", "User"),
+                    ("variables", r#"{"id": "7"}"#),
+                ])
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), 200);
+            let body: serde_json::Value = response.json().await.unwrap();
+            assert_eq!(body["data"]["user"]["id"], "7");
+        },
+    )
+    .await;
+
+    assert_eq!(result.len(), 2);
+    assert_eq!(result[1].operation_name.as_deref(), Some("User"));
+    assert_eq!(result[1].variables, Some(json!({"id": "7"})));
+}
+
 #[tokio::test]
 async fn test_multiple_fields() {
     let result = run_with_requests(
@@ -142,6 +400,45 @@ async fn test_multiple_fields() {
     assert_eq!(result.len(), 2);
 }
 
+#[tokio::test]
+async fn test_field_names_match_exactly_and_follow_aliases() {
+    let result = run_with_requests(
+        vec![Operation::query()
+            .with_field(Field::new("user").with_handler(Handler::new(json!({
+                "user": {"id": 1}
+            }))))
+            .with_field(Field::new("users").with_handler(Handler::new(json!({
+                "users": [{"id": 2}]
+            }))))],
+        |addr| async move {
+            let client = reqwest::Client::new();
+
+            // `users` must not also fire the `user` handler
+            let response = client
+                .post(format!("http://{}/graphql", addr))
+                .json(&json!({"query": "# user\nquery { users { id } }"}))
+                .send()
+                .await
+                .unwrap();
+            let body: serde_json::Value = response.json().await.unwrap();
+            assert_eq!(body["data"], json!({"users": [{"id": 2}]}));
+
+            // An alias routes to the underlying field
+            let response = client
+                .post(format!("http://{}/graphql", addr))
+                .json(&json!({"query": "query { me: user { id } }"}))
+                .send()
+                .await
+                .unwrap();
+            let body: serde_json::Value = response.json().await.unwrap();
+            assert_eq!(body["data"], json!({"me": {"id": 1}}));
+        },
+    )
+    .await;
+
+    assert_eq!(result.len(), 2);
+}
+
 #[tokio::test]
 async fn test_sequential_handlers() {
     let result = run_with_requests(
@@ -172,6 +469,111 @@ async fn test_sequential_handlers() {
     assert_eq!(result.len(), 3);
 }
 
+#[tokio::test]
+async fn test_collected_requests_are_sequenced() {
+    let result = run_with_requests(
+        vec![Operation::query().with_field(
+            Field::new("counter")
+                .with_handler(Handler::new(json!({"counter": 1})))
+                .with_handler(Handler::new(json!({"counter": 2}))),
+        )],
+        |addr| async move {
+            let client = reqwest::Client::new();
+            for name in ["First", "Second"] {
+                client
+                    .post(format!("http://{}/graphql", addr))
+                    .json(&json!({
+                        "query": format!("query {} {{ counter }}", name),
+                        "operationName": name
+                    }))
+                    .send()
+                    .await
+                    .unwrap();
+            }
+        },
+    )
+    .await;
+
+    assert_eq!(result[0].operation_name.as_deref(), Some("First"));
+    assert_eq!(result[0].sequence, 0);
+    assert_eq!(result[1].operation_name.as_deref(), Some("Second"));
+    assert_eq!(result[1].sequence, 1);
+    assert!(result[0].received_at <= result[1].received_at);
+}
+
+#[tokio::test]
+async fn test_cyclic_field_until_expected_requests() {
+    let result = run_with_options(
+        vec![Operation::query().with_field(
+            Field::new("status")
+                .cycle()
+                .with_handler(Handler::new(json!({"status": "up"})))
+                .with_handler(Handler::new(json!({"status": "down"}))),
+        )],
+        RunOptions {
+            expected_requests: Some(3),
+            ..Default::default()
+        },
+        |addr| async move {
+            let client = reqwest::Client::new();
+            for expected in ["up", "down", "up"] {
+                let body: serde_json::Value = client
+                    .post(format!("http://{}/graphql", addr))
+                    .json(&json!({"query": "{ status }"}))
+                    .send()
+                    .await
+                    .unwrap()
+                    .json()
+                    .await
+                    .unwrap();
+                assert_eq!(body["data"]["status"], expected);
+            }
+        },
+    )
+    .await;
+
+    assert_eq!(result.len(), 3);
+}
+
+#[tokio::test]
+async fn test_expected_requests_counts_each_request_once() {
+    let result = run_with_options(
+        vec![Operation::query()
+            .with_field(Field::new("users").with_handler(Handler::new(json!({"users": []}))))
+            .with_field(Field::new("posts").with_handler(Handler::new(json!({"posts": []}))))],
+        RunOptions {
+            expected_requests: Some(5),
+            ..Default::default()
+        },
+        |addr| async move {
+            let client = reqwest::Client::new();
+            let requests = [
+                // Several fields in one request
+                json!({"query": "{ users { id } posts { id } }"}),
+                // Rejected requests count too
+                json!({"query": "{ users { id "}),
+                json!({"query": "query A { users { id } }", "operationName": "B"}),
+                json!({"query": "{ comments { id } }"}),
+                json!({"query": "subscription { users { id } }"}),
+            ];
+            for request in requests {
+                let response = client
+                    .post(format!("http://{}/graphql", addr))
+                    .json(&request)
+                    .send()
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), 200);
+            }
+        },
+    )
+    .await;
+
+    assert_eq!(result.len(), 5);
+    assert!(result[0].matched);
+    assert!(result[1..].iter().all(|request| !request.matched));
+}
+
 #[tokio::test]
 async fn test_operation_name_captured() {
     let result = run_with_requests(
@@ -197,36 +599,144 @@ async fn test_operation_name_captured() {
 }
 
 #[tokio::test]
-async fn test_variables_captured() {
+async fn test_handlers_picked_by_operation_name() {
     let result = run_with_requests(
         vec![Operation::query().with_field(
-            Field::new("user").with_handler(Handler::new(json!({"user": {"id": 1}}))),
+            Field::new("user")
+                .with_handler_for_operation(
+                    "GetAdmin",
+                    Handler::new(json!({"user": {"id": 1, "role": "admin"}})),
+                )
+                .with_handler_for_operation(
+                    "GetGuest",
+                    Handler::new(json!({"user": {"id": 2, "role": "guest"}})),
+                ),
         )],
         |addr| async move {
             let client = reqwest::Client::new();
-            client
-                .post(format!("http://{}/graphql", addr))
-                .json(&json!({
-                    "query": "query GetUser($id: ID!) { user(id: $id) { id } }",
-                    "variables": {"id": "123"}
-                }))
-                .send()
-                .await
-                .unwrap();
+            for (operation_name, role) in [("GetGuest", "guest"), ("GetAdmin", "admin")] {
+                let response: serde_json::Value = client
+                    .post(format!("http://{}/graphql", addr))
+                    .json(&json!({
+                        "query": format!("query {} {{ user {{ id role }} }}", operation_name),
+                        "operationName": operation_name
+                    }))
+                    .send()
+                    .await
+                    .unwrap()
+                    .json()
+                    .await
+                    .unwrap();
+                assert_eq!(response["data"]["user"]["role"], role);
+            }
         },
     )
     .await;
 
-    assert_eq!(result[0].variables, Some(json!({"id": "123"})));
+    // Both operation handlers were called, so the server shut down
+    assert_eq!(result.len(), 2);
 }
 
 #[tokio::test]
-async fn test_invalid_json_request() {
-    // For invalid JSON, we need a custom test since run_with_requests expects the handler to be called
-    let ready = Arc::new(Notify::new());
-    let ready_clone = ready.clone();
-    let addr_holder = Arc::new(std::sync::Mutex::new(None));
-    let addr_holder_clone = addr_holder.clone();
+async fn test_operation_name_selects_one_of_several_operations() {
+    let result = run_with_requests(
+        vec![Operation::query()
+            .with_field(Field::new("users").with_handler(Handler::new(json!([{"id": 1}]))))
+            .with_field(Field::new("posts").with_handler(Handler::new(json!([{"id": 2}]))))],
+        |addr| async move {
+            let client = reqwest::Client::new();
+            let query = "query Users { users { id } } query Posts { posts { id } }";
+            for (operation_name, data) in [
+                ("Posts", json!({"posts": [{"id": 2}]})),
+                ("Users", json!({"users": [{"id": 1}]})),
+            ] {
+                let response: serde_json::Value = client
+                    .post(format!("http://{}/graphql", addr))
+                    .json(&json!({"query": query, "operationName": operation_name}))
+                    .send()
+                    .await
+                    .unwrap()
+                    .json()
+                    .await
+                    .unwrap();
+                assert_eq!(response["data"], data);
+            }
+        },
+    )
+    .await;
+
+    assert_eq!(result.len(), 2);
+    assert!(result.iter().all(|request| request.matched));
+}
+
+#[tokio::test]
+async fn test_several_operations_without_operation_name_are_rejected() {
+    let result = run_with_requests(
+        vec![Operation::query()
+            .with_field(Field::new("users").with_handler(Handler::new(json!([{"id": 1}]))))],
+        |addr| async move {
+            let client = reqwest::Client::new();
+            let response: serde_json::Value = client
+                .post(format!("http://{}/graphql", addr))
+                .json(&json!({"query": "query Users { users { id } } query Count { count }"}))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            assert!(response.get("data").is_none() || response["data"].is_null());
+            assert_eq!(
+                response["errors"][0]["message"],
+                "Must provide operation name if query contains multiple operations."
+            );
+
+            // The rejected request does not count, so call the handler to finish
+            client
+                .post(format!("http://{}/graphql", addr))
+                .json(&json!({"query": "{ users { id } }"}))
+                .send()
+                .await
+                .unwrap();
+        },
+    )
+    .await;
+
+    assert_eq!(result.len(), 2);
+    assert!(!result[0].matched);
+}
+
+#[tokio::test]
+async fn test_variables_captured() {
+    let result = run_with_requests(
+        vec![Operation::query().with_field(
+            Field::new("user").with_handler(Handler::new(json!({"user": {"id": 1}}))),
+        )],
+        |addr| async move {
+            let client = reqwest::Client::new();
+            client
+                .post(format!("http://{}/graphql", addr))
+                .json(&json!({
+                    "query": "query GetUser($id: ID!) { user(id: $id) { id } }",
+                    "variables": {"id": "123"}
+                }))
+                .send()
+                .await
+                .unwrap();
+        },
+    )
+    .await;
+
+    assert_eq!(result[0].variables, Some(json!({"id": "123"})));
+}
+
+#[tokio::test]
+async fn test_invalid_json_request() {
+    // For invalid JSON, we need a custom test since run_with_requests expects the handler to be called
+    let ready = Arc::new(Notify::new());
+    let ready_clone = ready.clone();
+    let addr_holder = Arc::new(std::sync::Mutex::new(None));
+    let addr_holder_clone = addr_holder.clone();
 
     let server = AsyncGraphQL::default();
     let collector = DefaultCollector::new();
@@ -238,6 +748,7 @@ async fn test_invalid_json_request() {
                     Field::new("test").with_handler(Handler::new(json!({"test": true}))),
                 )],
                 collector,
+                RunOptions::default(),
                 Some(move |addr| {
                     *addr_holder_clone.lock().unwrap() = Some(addr);
                     ready_clone.notify_one();
@@ -265,6 +776,53 @@ async fn test_invalid_json_request() {
     // Note: Server will keep running since the handler wasn't called, but that's ok for this test
 }
 
+#[tokio::test]
+async fn test_graphql_response_json_content_negotiation() {
+    let result = run_with_requests(
+        vec![Operation::query()
+            .with_field(Field::new("users").with_handler(Handler::new(json!({"users": []}))))],
+        |addr| async move {
+            let client = reqwest::Client::new();
+            let url = format!("http://{}/graphql", addr);
+            let send = |accept: &'static str, query: &'static str| {
+                client
+                    .post(&url)
+                    .header("accept", accept)
+                    .json(&json!({"query": query}))
+                    .send()
+            };
+            let content_type = |response: &reqwest::Response| {
+                response.headers()["content-type"].to_str().unwrap().to_string()
+            };
+
+            // Legacy clients get 200 even for request errors
+            let response = send("application/json", "{ users { id }").await.unwrap();
+            assert_eq!(response.status(), 200);
+            assert_eq!(content_type(&response), "application/json");
+            let body: serde_json::Value = response.json().await.unwrap();
+            assert_eq!(body["data"], serde_json::Value::Null);
+            assert!(body["errors"].is_array());
+
+            let accept = "application/graphql-response+json, application/json;q=0.9";
+            let response = send(accept, "{ users { id }").await.unwrap();
+            assert_eq!(response.status(), 400);
+            assert_eq!(content_type(&response), "application/graphql-response+json");
+            let body: serde_json::Value = response.json().await.unwrap();
+            assert!(body.get("data").is_none());
+            assert!(body["errors"].is_array());
+
+            let response = send(accept, "{ users { id } }").await.unwrap();
+            assert_eq!(response.status(), 200);
+            assert_eq!(content_type(&response), "application/graphql-response+json");
+            let body: serde_json::Value = response.json().await.unwrap();
+            assert_eq!(body, json!({"data": {"users": []}}));
+        },
+    )
+    .await;
+
+    assert_eq!(result.len(), 3);
+}
+
 #[tokio::test]
 async fn test_dynamic_handler_echo_variables() {
     run_with_requests(
@@ -301,6 +859,113 @@ async fn test_dynamic_handler_echo_variables() {
     .await;
 }
 
+#[tokio::test]
+async fn test_dynamic_handler_reads_arguments() {
+    run_with_requests(
+        vec![Operation::query().with_field(
+            Field::new("user")
+                .with_handler(Handler::dynamic(|ctx| {
+                    HandlerResponse::new(json!({"user": {"id": ctx.get_argument("id")}}))
+                }))
+                .with_handler(Handler::dynamic(|ctx| {
+                    HandlerResponse::new(json!({"user": {"id": ctx.get_argument("id")}}))
+                })),
+        )],
+        |addr| async move {
+            let client = reqwest::Client::new();
+
+            // Inline literal
+            let response: serde_json::Value = client
+                .post(format!("http://{}/graphql", addr))
+                .json(&json!({"query": "query { user(id: 5) { id } }"}))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            assert_eq!(response["data"]["user"]["id"], 5);
+
+            // Variable reference
+            let response: serde_json::Value = client
+                .post(format!("http://{}/graphql", addr))
+                .json(&json!({
+                    "query": "query ($id: ID!) { user(id: $id) { id } }",
+                    "variables": {"id": "7"}
+                }))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            assert_eq!(response["data"]["user"]["id"], "7");
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_async_dynamic_handler_awaits_before_responding() {
+    run_with_requests(
+        vec![Operation::query().with_field(Field::new("total").with_handler(
+            Handler::dynamic_async(|ctx| {
+                let count = ctx.get_argument("count").and_then(|v| v.as_u64()).unwrap_or(0);
+                async move {
+                    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                    HandlerResponse::new(json!({"total": count * 10}))
+                }
+            }),
+        ))],
+        |addr| async move {
+            let started = std::time::Instant::now();
+            let response: serde_json::Value = reqwest::Client::new()
+                .post(format!("http://{}/graphql", addr))
+                .json(&json!({"query": "query { total(count: 4) }"}))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+
+            assert_eq!(response["data"]["total"], 40);
+            assert!(started.elapsed() >= std::time::Duration::from_millis(20));
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_shutdown_grace_lets_slow_final_response_complete() {
+    run_with_options(
+        vec![Operation::query().with_field(Field::new("report").with_handler(
+            Handler::dynamic_async(|_| async {
+                tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+                HandlerResponse::new(json!({"report": "done"}))
+            }),
+        ))],
+        RunOptions {
+            shutdown_grace: Some(std::time::Duration::from_secs(5)),
+            ..Default::default()
+        },
+        |addr| async move {
+            let response: serde_json::Value = reqwest::Client::new()
+                .post(format!("http://{}/graphql", addr))
+                .json(&json!({"query": "query { report }"}))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+
+            assert_eq!(response["data"]["report"], "done");
+        },
+    )
+    .await;
+}
+
 #[tokio::test]
 async fn test_dynamic_handler_conditional_response() {
     run_with_requests(
@@ -401,6 +1066,66 @@ async fn test_dynamic_handler_with_error() {
     .await;
 }
 
+#[tokio::test]
+async fn test_partial_data_with_field_error() {
+    run_with_requests(
+        vec![Operation::query()
+            .with_field(Field::new("user").with_handler(Handler::new(json!({
+                "user": {"id": 1}
+            }))))
+            .with_field(Field::new("posts").with_handler(Handler::dynamic(|_| {
+                HandlerResponse::new(json!(null)).with_field_error("Posts unavailable")
+            })))],
+        |addr| async move {
+            let client = reqwest::Client::new();
+            let response: serde_json::Value = client
+                .post(format!("http://{}/graphql", addr))
+                .json(&json!({"query": "query { user { id } feed: posts { id } }"}))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+
+            assert_eq!(response["data"], json!({"user": {"id": 1}, "feed": null}));
+            assert_eq!(
+                response["errors"],
+                json!([{"message": "Posts unavailable", "path": ["feed"]}])
+            );
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_error_extensions() {
+    run_with_requests(
+        vec![Operation::query().with_field(Field::new("me").with_handler(
+            Handler::new(json!({"me": null})).with_error_extensions(
+                "Not signed in",
+                json!({"code": "UNAUTHENTICATED"}),
+            ),
+        ))],
+        |addr| async move {
+            let client = reqwest::Client::new();
+            let response: serde_json::Value = client
+                .post(format!("http://{}/graphql", addr))
+                .json(&json!({"query": "query { me { id } }"}))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+
+            assert_eq!(response["errors"][0]["message"], "Not signed in");
+            assert_eq!(response["errors"][0]["extensions"]["code"], "UNAUTHENTICATED");
+        },
+    )
+    .await;
+}
+
 #[tokio::test]
 async fn test_auto_shutdown_after_all_handlers() {
     // Test that the server automatically shuts down after all handlers are called
@@ -423,6 +1148,7 @@ async fn test_auto_shutdown_after_all_handlers() {
                         Field::new("posts").with_handler(Handler::new(json!({"posts": []}))),
                     )],
                 collector,
+                RunOptions::default(),
                 Some(move |addr| {
                     *addr_holder_clone.lock().unwrap() = Some(addr);
                     ready_clone.notify_one();
@@ -470,6 +1196,35 @@ async fn test_auto_shutdown_after_all_handlers() {
     );
 }
 
+#[tokio::test]
+async fn test_field_without_handlers_counts_once_toward_shutdown() {
+    // A handler-less field must be queried once, then no longer holds the server open
+    let result = tokio::time::timeout(
+        std::time::Duration::from_secs(2),
+        run_with_requests(
+            vec![Operation::query()
+                .with_field(Field::new("empty"))
+                .with_field(Field::new("users").with_handler(Handler::new(json!({"users": []}))))],
+            |addr| async move {
+                let client = reqwest::Client::new();
+                for query in ["query { empty }", "query { users { id } }"] {
+                    let response = client
+                        .post(format!("http://{}/graphql", addr))
+                        .json(&json!({"query": query}))
+                        .send()
+                        .await
+                        .unwrap();
+                    assert_eq!(response.status(), 200);
+                }
+            },
+        ),
+    )
+    .await;
+
+    let collected = result.expect("server should shut down once every field was queried");
+    assert_eq!(collected.len(), 2);
+}
+
 #[tokio::test]
 async fn test_auto_shutdown_with_sequential_handlers() {
     let ready = Arc::new(Notify::new());
@@ -489,6 +1244,7 @@ async fn test_auto_shutdown_with_sequential_handlers() {
                         .with_handler(Handler::new(json!({"counter": 2}))),
                 )],
                 collector,
+                RunOptions::default(),
                 Some(move |addr| {
                     *addr_holder_clone.lock().unwrap() = Some(addr);
                     ready_clone.notify_one();
@@ -529,3 +1285,528 @@ async fn test_auto_shutdown_with_sequential_handlers() {
         "Server should have shut down after all handlers were called"
     );
 }
+
+/// Minimal `graphql-transport-ws` client for subscription tests
+struct WsClient(
+    tokio_tungstenite::WebSocketStream<
+        tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
+    >,
+);
+
+impl WsClient {
+    /// Connect and complete the `connection_init` handshake
+    async fn connect(addr: SocketAddr) -> Self {
+        use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+
+        let mut request = format!("ws://{}/graphql", addr).into_client_request().unwrap();
+        request
+            .headers_mut()
+            .insert("sec-websocket-protocol", "graphql-transport-ws".parse().unwrap());
+        let (ws, _) = tokio_tungstenite::connect_async(request).await.unwrap();
+        let mut client = Self(ws);
+        client.send(json!({"type": "connection_init"})).await;
+        assert_eq!(client.recv().await["type"], "connection_ack");
+        client
+    }
+
+    async fn subscribe(&mut self, id: &str, query: &str) {
+        let subscribe = json!({"id": id, "type": "subscribe", "payload": {"query": query}});
+        self.send(subscribe).await;
+    }
+
+    async fn send(&mut self, message: serde_json::Value) {
+        use futures::SinkExt;
+        use tokio_tungstenite::tungstenite::Message;
+
+        self.0.send(Message::text(message.to_string())).await.unwrap();
+    }
+
+    async fn recv(&mut self) -> serde_json::Value {
+        use futures::StreamExt;
+        use tokio_tungstenite::tungstenite::Message;
+
+        loop {
+            if let Message::Text(text) = self.0.next().await.unwrap().unwrap() {
+                return serde_json::from_str(&text).unwrap();
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_concurrent_queries_count_each_handler_once() {
+    const REQUESTS: usize = 64;
+
+    // `done` keeps the server up until the concurrent queries have finished;
+    // counting a sequential handler twice would shut it down early
+    let collected = run_with_requests(
+        vec![Operation::query()
+            .with_field(
+                Field::new("counter")
+                    .with_handler(Handler::new(json!(1)))
+                    .with_handler(Handler::new(json!(2)))
+                    .with_handler(Handler::new(json!(3))),
+            )
+            .with_field(Field::new("done").with_handler(Handler::new(json!(true))))],
+        |addr| async move {
+            let client = reqwest::Client::new();
+            let responses = futures::future::join_all((0..REQUESTS).map(|_| {
+                let client = client.clone();
+                async move {
+                    let response: serde_json::Value = client
+                        .post(format!("http://{}/graphql", addr))
+                        .json(&json!({"query": "query { counter }"}))
+                        .send()
+                        .await
+                        .unwrap()
+                        .json()
+                        .await
+                        .unwrap();
+                    response["data"]["counter"].as_u64().unwrap()
+                }
+            }))
+            .await;
+
+            let count = |value| responses.iter().filter(|&&r| r == value).count();
+            assert_eq!(count(1), 1);
+            assert_eq!(count(2), 1);
+            assert_eq!(count(3), REQUESTS - 2);
+
+            client
+                .post(format!("http://{}/graphql", addr))
+                .json(&json!({"query": "query { done }"}))
+                .send()
+                .await
+                .unwrap();
+        },
+    )
+    .await;
+
+    assert_eq!(collected.len(), REQUESTS + 1);
+}
+
+#[tokio::test]
+async fn test_subscription_streams_configured_sequence() {
+    let result = run_with_requests(
+        vec![Operation::subscription().with_field(
+            Field::new("ticks").with_stream(vec![json!(1), json!(2), json!(3)]),
+        )],
+        |addr| async move {
+            let mut client = WsClient::connect(addr).await;
+            client.subscribe("1", "subscription { ticks }").await;
+
+            for expected in 1..=3 {
+                let message = client.recv().await;
+                assert_eq!(message["type"], "next");
+                assert_eq!(message["id"], "1");
+                assert_eq!(message["payload"]["data"]["ticks"], expected);
+            }
+
+            let message = client.recv().await;
+            assert_eq!(message["type"], "complete");
+            assert_eq!(message["id"], "1");
+        },
+    )
+    .await;
+
+    assert_eq!(result.len(), 1);
+    assert!(result[0].query.contains("ticks"));
+}
+
+#[tokio::test]
+async fn test_subscription_shuts_down_after_last_event() {
+    use std::time::Duration;
+
+    let (addr, server_task) = start_server(
+        vec![Operation::subscription()
+            .with_field(Field::new("ticks").with_stream(vec![json!(1), json!(2)]))
+            .with_field(Field::new("tocks").with_stream(vec![json!(3)]))],
+        RunOptions::default(),
+    )
+    .await;
+
+    let mut client = WsClient::connect(addr).await;
+    client.subscribe("1", "subscription { ticks }").await;
+    for expected in 1..=2 {
+        assert_eq!(client.recv().await["payload"]["data"]["ticks"], expected);
+    }
+    assert_eq!(client.recv().await["type"], "complete");
+
+    // The scenario keeps running until every event has been sent
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(!server_task.is_finished());
+
+    client.subscribe("2", "subscription { tocks }").await;
+    assert_eq!(client.recv().await["payload"]["data"]["tocks"], 3);
+    assert_eq!(client.recv().await["type"], "complete");
+
+    // ... and then stops, even though the client stays connected
+    let result = tokio::time::timeout(Duration::from_secs(5), server_task)
+        .await
+        .expect("scenario should shut down after the last event")
+        .unwrap()
+        .unwrap();
+    assert_eq!(result.len(), 2);
+    drop(client);
+}
+
+#[tokio::test]
+async fn test_subscription_error_mid_stream() {
+    let result = run_with_requests(
+        vec![Operation::subscription().with_field(
+            Field::new("ticks")
+                .with_handler(Handler::new(json!(1)))
+                .with_handler(Handler::new(serde_json::Value::Null).with_error("tick failed"))
+                .with_handler(Handler::new(json!(3))),
+        )],
+        |addr| async move {
+            let mut client = WsClient::connect(addr).await;
+            client.subscribe("1", "subscription { ticks }").await;
+
+            assert_eq!(client.recv().await["payload"]["data"]["ticks"], 1);
+
+            // An event carrying errors does not end the stream
+            let message = client.recv().await;
+            assert_eq!(message["type"], "next");
+            assert_eq!(message["payload"]["errors"][0]["message"], "tick failed");
+
+            assert_eq!(client.recv().await["payload"]["data"]["ticks"], 3);
+            assert_eq!(client.recv().await["type"], "complete");
+        },
+    )
+    .await;
+
+    assert_eq!(result.len(), 1);
+}
+
+#[tokio::test]
+async fn test_subscription_client_disconnect_still_shuts_down() {
+    use std::time::Duration;
+
+    // Events large enough that the stream cannot be buffered before the
+    // client goes away
+    let payload = "x".repeat(64 * 1024);
+    let events = (0..64).map(|_| json!(payload)).collect();
+    let (addr, server_task) = start_server(
+        vec![Operation::subscription().with_field(Field::new("ticks").with_stream(events))],
+        RunOptions::default(),
+    )
+    .await;
+
+    let mut client = WsClient::connect(addr).await;
+    client.subscribe("1", "subscription { ticks }").await;
+    assert_eq!(client.recv().await["type"], "next");
+
+    // The client goes away before the stream completes
+    drop(client);
+
+    let result = tokio::time::timeout(Duration::from_secs(5), server_task)
+        .await
+        .expect("scenario should shut down after the client disconnects")
+        .unwrap()
+        .unwrap();
+    assert_eq!(result.len(), 1);
+}
+
+#[tokio::test]
+async fn test_concurrent_subscriptions() {
+    let result = run_with_requests(
+        vec![Operation::subscription()
+            .with_field(Field::new("ticks").with_stream(vec![json!(1), json!(2), json!(3)]))
+            .with_field(Field::new("tocks").with_stream(vec![json!(1), json!(2), json!(3)]))],
+        |addr| async move {
+            let subscriptions = [("1", "ticks"), ("2", "tocks")].map(|(id, field)| async move {
+                let mut client = WsClient::connect(addr).await;
+                client
+                    .subscribe(id, &format!("subscription {{ {} }}", field))
+                    .await;
+                for expected in 1..=3 {
+                    let message = client.recv().await;
+                    assert_eq!(message["id"], id);
+                    assert_eq!(message["payload"]["data"][field], expected);
+                }
+                assert_eq!(client.recv().await["type"], "complete");
+            });
+            futures::future::join_all(subscriptions).await;
+        },
+    )
+    .await;
+
+    assert_eq!(result.len(), 2);
+}
+
+#[tokio::test]
+async fn test_introspection_from_sdl() {
+    let sdl = r#"
+        type User {
+            id: ID!
+            name: String
+        }
+
+        type Query {
+            users: [User!]!
+        }
+    "#;
+
+    let result = run_with_requests(
+        vec![Operation::query()
+            .with_introspection(sdl)
+            .with_field(Field::new("users").with_handler(Handler::new(json!({"users": []}))))],
+        |addr| async move {
+            let client = reqwest::Client::new();
+            let body: serde_json::Value = client
+                .post(format!("http://{}/graphql", addr))
+                .json(&json!({
+                    "query": "query IntrospectionQuery { __schema { queryType { name } \
+                              types { kind name fields { name type { kind name } } } } }",
+                    "operationName": "IntrospectionQuery"
+                }))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+
+            assert!(body.get("errors").is_none(), "{}", body);
+            let schema = &body["data"]["__schema"];
+            assert_eq!(schema["queryType"]["name"], "Query");
+            let types = schema["types"].as_array().unwrap();
+            let user = types.iter().find(|ty| ty["name"] == "User").unwrap();
+            assert_eq!(user["kind"], "OBJECT");
+            assert_eq!(
+                user["fields"][0],
+                json!({"name": "id", "type": {"kind": "NON_NULL", "name": null}})
+            );
+            assert!(types.iter().any(|ty| ty["name"] == "String"));
+
+            // Introspection does not count towards shutdown, the server still
+            // waits for the mocked field
+            client
+                .post(format!("http://{}/graphql", addr))
+                .json(&json!({"query": "{ users { id } }"}))
+                .send()
+                .await
+                .unwrap();
+        },
+    )
+    .await;
+
+    assert_eq!(result.len(), 2);
+    assert!(result.iter().all(|request| request.matched));
+}
+
+#[tokio::test]
+async fn test_serves_at_custom_path() {
+    let ready = Arc::new(Notify::new());
+    let ready_clone = ready.clone();
+    let addr_holder = Arc::new(std::sync::Mutex::new(None));
+    let addr_holder_clone = addr_holder.clone();
+
+    let server_task = tokio::spawn(async move {
+        AsyncGraphQL::default()
+            .at_path("/query")
+            .run(
+                vec![Operation::query().with_field(
+                    Field::new("users").with_handler(Handler::new(json!([{"id": 1}]))),
+                )],
+                DefaultCollector::new(),
+                RunOptions::default(),
+                Some(move |addr| {
+                    *addr_holder_clone.lock().unwrap() = Some(addr);
+                    ready_clone.notify_one();
+                }),
+            )
+            .await
+    });
+
+    ready.notified().await;
+    let addr = addr_holder.lock().unwrap().unwrap();
+
+    let client = reqwest::Client::new();
+    let query = json!({"query": "{ users { id } }"});
+    let response = client
+        .post(format!("http://{}/graphql", addr))
+        .json(&query)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    let response: serde_json::Value = client
+        .post(format!("http://{}/query", addr))
+        .json(&query)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(response["data"]["users"], json!([{"id": 1}]));
+
+    let result = server_task.await.unwrap().unwrap();
+    assert_eq!(result.len(), 1);
+}
+
+#[tokio::test]
+async fn test_relative_path_fails_run() {
+    let result = AsyncGraphQL::default()
+        .at_path("graphql")
+        .run(
+            vec![Operation::query().with_field(Field::new("users"))],
+            DefaultCollector::new(),
+            RunOptions::default(),
+            None::<fn(SocketAddr)>,
+        )
+        .await;
+
+    match result {
+        Err(HarnessError::ConfigurationError(message)) => {
+            assert_eq!(message, "GraphQL path \"graphql\" must start with '/'")
+        }
+        other => panic!("expected a configuration error, got {:?}", other.map(|r| r.len())),
+    }
+}
+
+#[tokio::test]
+async fn test_invalid_introspection_sdl_fails_run() {
+    let result = AsyncGraphQL::default()
+        .run(
+            vec![Operation::query().with_introspection("type Query {")],
+            DefaultCollector::new(),
+            RunOptions::default(),
+            None::<fn(SocketAddr)>,
+        )
+        .await;
+
+    match result {
+        Err(HarnessError::ConfigurationError(message)) => {
+            assert!(message.starts_with("Invalid SDL"), "{}", message)
+        }
+        other => panic!("expected a configuration error, got {:?}", other.map(|r| r.len())),
+    }
+}
+
+#[tokio::test]
+async fn test_strict_collector_reports_unmatched_requests() {
+    let ready = Arc::new(Notify::new());
+    let ready_clone = ready.clone();
+    let addr_holder = Arc::new(std::sync::Mutex::new(None));
+    let addr_holder_clone = addr_holder.clone();
+
+    let server_task = tokio::spawn(async move {
+        AsyncGraphQL::default()
+            .run(
+                vec![Operation::query().with_field(
+                    Field::new("users").with_handler(Handler::new(json!({"users": []}))),
+                )],
+                StrictCollector::new(),
+                RunOptions::default(),
+                Some(move |addr| {
+                    *addr_holder_clone.lock().unwrap() = Some(addr);
+                    ready_clone.notify_one();
+                }),
+            )
+            .await
+    });
+
+    ready.notified().await;
+    let addr = addr_holder.lock().unwrap().unwrap();
+
+    let client = reqwest::Client::new();
+    for query in ["query { posts { id } }", "query { users { id } }"] {
+        client
+            .post(format!("http://{}/graphql", addr))
+            .json(&json!({"query": query}))
+            .send()
+            .await
+            .unwrap();
+    }
+
+    let unmatched = server_task.await.unwrap().unwrap().unwrap_err();
+    assert_eq!(unmatched.len(), 1);
+    assert!(unmatched[0].query.contains("posts"));
+    assert!(!unmatched[0].matched);
+}
+
+#[cfg(feature = "tracing")]
+#[tokio::test]
+async fn test_tracing_events() {
+    let logs = server_harness_test_support::CapturedLogs::start();
+
+    run_with_requests(
+        vec![Operation::query()
+            .with_field(Field::new("users").with_handler(Handler::new(json!({"users": []}))))],
+        |addr| async move {
+            let client = reqwest::Client::new();
+            for query in ["query { posts { id } }", "query Users { users { id } }"] {
+                client
+                    .post(format!("http://{}/graphql", addr))
+                    .json(&json!({"query": query}))
+                    .send()
+                    .await
+                    .unwrap();
+            }
+        },
+    )
+    .await;
+
+    let logs = logs.contents();
+    assert!(logs.contains("request received"), "{}", logs);
+    assert!(logs.contains("operation{operation_name=None}: "), "{}", logs);
+    assert!(logs.contains("no handler matched field=posts"), "{}", logs);
+    assert!(logs.contains("handler selected field=users index=0 count=1"), "{}", logs);
+    assert!(logs.contains("scenario complete, shutting down"), "{}", logs);
+}
+
+#[tokio::test]
+async fn test_paginated_field_pages_through_items() {
+    let items = (1..=5).map(|id| json!({"id": id})).collect();
+    let result = run_with_options(
+        vec![Operation::query().with_field(
+            Field::new("users").with_handler(Handler::paginated(items, "first", "after")),
+        )],
+        RunOptions {
+            expected_requests: Some(3),
+            ..Default::default()
+        },
+        |addr| async move {
+            let client = reqwest::Client::new();
+            let query = "query Users($first: Int, $after: String) {
+                users(first: $first, after: $after) {
+                    edges { node { id } cursor }
+                    pageInfo { hasNextPage endCursor }
+                }
+            }";
+            let mut after = serde_json::Value::Null;
+            let mut pages = Vec::new();
+            for _ in 0..3 {
+                let body: serde_json::Value = client
+                    .post(format!("http://{}/graphql", addr))
+                    .json(&json!({"query": query, "variables": {"first": 2, "after": after}}))
+                    .send()
+                    .await
+                    .unwrap()
+                    .json()
+                    .await
+                    .unwrap();
+                let users = &body["data"]["users"];
+                let ids: Vec<_> = users["edges"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|edge| edge["node"]["id"].as_i64().unwrap())
+                    .collect();
+                pages.push((ids, users["pageInfo"]["hasNextPage"].as_bool().unwrap()));
+                after = users["pageInfo"]["endCursor"].clone();
+            }
+            assert_eq!(
+                pages,
+                [(vec![1, 2], true), (vec![3, 4], true), (vec![5], false)]
+            );
+        },
+    )
+    .await;
+
+    assert_eq!(result.len(), 3);
+}
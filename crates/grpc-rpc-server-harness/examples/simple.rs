@@ -8,8 +8,9 @@
 //!
 //! The server automatically shuts down once all handlers have been called.
 
+use grpc_rpc_server_harness::framing::{decode_frame, encode_frame};
 use grpc_rpc_server_harness::prelude::*;
-use grpc_rpc_server_harness::use_cases::ports::Server;
+use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
 use http_body_util::{BodyExt, Full};
 use hyper::body::Bytes;
 use hyper_util::client::legacy::Client;
@@ -18,24 +19,6 @@ use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::sync::Notify;
 
-/// Helper to create a gRPC request body with length prefix
-fn grpc_request_body(data: &[u8]) -> Vec<u8> {
-    let mut body = Vec::with_capacity(5 + data.len());
-    body.push(0); // No compression
-    body.extend_from_slice(&(data.len() as u32).to_be_bytes());
-    body.extend_from_slice(data);
-    body
-}
-
-/// Helper to parse gRPC response body (skip 5-byte header)
-fn parse_grpc_response(data: &[u8]) -> &[u8] {
-    if data.len() > 5 {
-        &data[5..]
-    } else {
-        &[]
-    }
-}
-
 #[tokio::main]
 async fn main() -> Result<(), HarnessError> {
     println!("Starting gRPC scenario...");
@@ -69,12 +52,12 @@ async fn main() -> Result<(), HarnessError> {
             .method("POST")
             .uri(format!("http://{}/example.UserService/GetUser", server_addr))
             .header("content-type", "application/grpc")
-            .body(Full::new(Bytes::from(grpc_request_body(&[1, 2, 3]))))
+            .body(Full::new(Bytes::from(encode_frame(&[1, 2, 3]))))
             .unwrap();
 
         let response1 = client.request(request1).await.expect("Request failed");
         let body1 = response1.into_body().collect().await.unwrap().to_bytes();
-        println!("Response: {:?}\n", parse_grpc_response(&body1));
+        println!("Response: {:?}\n", decode_frame(&body1).unwrap());
 
         // Request 2: Echo (dynamic handler)
         println!("Making gRPC request to EchoService/Echo...");
@@ -82,12 +65,12 @@ async fn main() -> Result<(), HarnessError> {
             .method("POST")
             .uri(format!("http://{}/example.EchoService/Echo", server_addr))
             .header("content-type", "application/grpc")
-            .body(Full::new(Bytes::from(grpc_request_body(&[10, 20, 30]))))
+            .body(Full::new(Bytes::from(encode_frame(&[10, 20, 30]))))
             .unwrap();
 
         let response2 = client.request(request2).await.expect("Request failed");
         let body2 = response2.into_body().collect().await.unwrap().to_bytes();
-        println!("Response (echoed with prefix): {:?}\n", parse_grpc_response(&body2));
+        println!("Response (echoed with prefix): {:?}\n", decode_frame(&body2).unwrap());
 
         // Request 3: CreateUser (static handler)
         println!("Making gRPC request to UserService/CreateUser...");
@@ -95,12 +78,12 @@ async fn main() -> Result<(), HarnessError> {
             .method("POST")
             .uri(format!("http://{}/example.UserService/CreateUser", server_addr))
             .header("content-type", "application/grpc")
-            .body(Full::new(Bytes::from(grpc_request_body(&[4, 5, 6]))))
+            .body(Full::new(Bytes::from(encode_frame(&[4, 5, 6]))))
             .unwrap();
 
         let response3 = client.request(request3).await.expect("Request failed");
         let body3 = response3.into_body().collect().await.unwrap().to_bytes();
-        println!("Response: {:?}\n", parse_grpc_response(&body3));
+        println!("Response: {:?}\n", decode_frame(&body3).unwrap());
     });
 
     // Build and execute the scenario using the Server::run API directly
@@ -129,6 +112,7 @@ async fn main() -> Result<(), HarnessError> {
                 ),
             ],
             DefaultCollector::new(),
+            RunOptions::default(),
             Some(move |actual_addr: SocketAddr| {
                 *addr_holder.lock().unwrap() = Some(actual_addr);
                 addr_notify.notify_one();
@@ -2,4 +2,7 @@
 mod tonic;
 
 #[cfg(feature = "tonic")]
-pub use self::tonic::Tonic;
\ No newline at end of file
+pub use self::tonic::Tonic;
+
+#[cfg(feature = "tls")]
+pub use self::tonic::{rustls_test_config, TEST_CERT_PEM};
@@ -0,0 +1,63 @@
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::{Read, Write};
+
+/// Why a request message could not be decoded, as a gRPC status and message
+pub(super) type FrameError = (u32, String);
+
+/// Decompress the message of a frame with the compression flag set,
+/// according to `grpc_encoding`; only `gzip` is supported.
+pub(super) fn decompress(data: &[u8], grpc_encoding: Option<&str>) -> Result<Vec<u8>, FrameError> {
+    match grpc_encoding.map(str::trim) {
+        Some(encoding) if encoding.eq_ignore_ascii_case("gzip") => {
+            let mut decoded = Vec::new();
+            GzDecoder::new(data)
+                .read_to_end(&mut decoded)
+                .map_err(|_| (13, "Failed to decompress gzip message".to_string()))?; // INTERNAL
+            Ok(decoded)
+        }
+        // UNIMPLEMENTED
+        _ => Err((12, "Compressed message with unsupported grpc-encoding".to_string())),
+    }
+}
+
+/// Whether a `grpc-accept-encoding` header lists gzip
+pub(super) fn accepts_gzip(grpc_accept_encoding: Option<&str>) -> bool {
+    grpc_accept_encoding.is_some_and(|header| {
+        header
+            .split(',')
+            .any(|encoding| encoding.trim().eq_ignore_ascii_case("gzip"))
+    })
+}
+
+pub(super) fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    let _ = encoder.write_all(data);
+    encoder.finish().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decompress_gzip() {
+        assert_eq!(decompress(&gzip(b"hello"), Some("gzip")), Ok(b"hello".to_vec()));
+    }
+
+    #[test]
+    fn test_decompress_unsupported_encoding() {
+        let data = gzip(b"hello");
+        assert_eq!(decompress(&data, None).unwrap_err().0, 12);
+        assert_eq!(decompress(&data, Some("snappy")).unwrap_err().0, 12);
+        assert_eq!(decompress(b"junk", Some("gzip")).unwrap_err().0, 13);
+    }
+
+    #[test]
+    fn test_accepts_gzip() {
+        assert!(accepts_gzip(Some("identity, gzip")));
+        assert!(!accepts_gzip(Some("deflate")));
+        assert!(!accepts_gzip(None));
+    }
+}
@@ -1,3 +1,8 @@
+mod compression;
 mod server;
+#[cfg(feature = "tls")]
+mod tls;
 
-pub use server::Tonic;
\ No newline at end of file
+pub use server::Tonic;
+#[cfg(feature = "tls")]
+pub use tls::{rustls_test_config, TEST_CERT_PEM};
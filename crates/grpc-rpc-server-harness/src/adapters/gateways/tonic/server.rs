@@ -1,66 +1,151 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::net::{Ipv6Addr, SocketAddr};
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::Poll;
+
 use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use http_body_util::{BodyExt, Full};
 use hyper::body::{Bytes, Incoming};
 use hyper::server::conn::http2;
 use hyper::service::service_fn;
 use hyper::{Request, Response};
 use hyper_util::rt::{TokioExecutor, TokioIo};
-use std::collections::HashMap;
-use std::net::SocketAddr;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
-use tokio::net::TcpListener;
-use tokio::sync::{oneshot, Mutex};
-
-use crate::entities::{CollectedRequest, Handler, Message, RequestContext, Service};
+use hyper_util::server::graceful::GracefulConnection;
+use server_harness_core::CompletionTracker;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, oneshot, watch, Semaphore};
+use tokio::task::JoinSet;
+
+use super::compression;
+use crate::entities::{
+    CollectedRequest, Handler, HandlerResponse, Message, MessageValidator, RequestContext, Service,
+};
 use crate::error::HarnessError;
-use crate::use_cases::ports::{Collector, Server};
+use crate::framing::{push_frame, split_frame};
+use crate::use_cases::ports::{Collector, RunOptions, Server};
 
 /// Tonic-compatible gRPC server implementation
 #[derive(Clone)]
 pub struct Tonic {
     addr: SocketAddr,
+    strict_http2: bool,
+    #[cfg(feature = "tls")]
+    tls: Option<Arc<crate::RustlsConfig>>,
+    /// Listener bound by the caller, served instead of binding `addr`
+    listener: Option<Arc<std::net::TcpListener>>,
 }
 
 impl Tonic {
     pub fn new(addr: SocketAddr) -> Self {
-        Self { addr }
+        Self {
+            addr,
+            strict_http2: false,
+            #[cfg(feature = "tls")]
+            tls: None,
+            listener: None,
+        }
     }
 
     pub fn bind(addr: impl Into<SocketAddr>) -> Self {
         Self::new(addr.into())
     }
+
+    /// Bind every IPv6 interface on an ephemeral port.
+    ///
+    /// Dual-stack systems, such as Linux by default, accept IPv4 clients on
+    /// the same socket. The reported address is `[::]:port`; clients reach
+    /// it at `[::1]:port`.
+    pub fn bind_any_v6() -> Self {
+        Self::new((Ipv6Addr::UNSPECIFIED, 0).into())
+    }
+
+    /// Serve on a `std` listener bound by the caller, for instance to set
+    /// `SO_REUSEADDR` or to know the port before the run starts.
+    ///
+    /// The listener is duplicated on every run rather than consumed.
+    pub fn from_listener(listener: std::net::TcpListener) -> Self {
+        let addr = listener
+            .local_addr()
+            .unwrap_or_else(|_| ([127, 0, 0, 1], 0).into());
+        Self {
+            listener: Some(Arc::new(listener)),
+            ..Self::new(addr)
+        }
+    }
+
+    /// Bind a server terminating TLS with `config` on every connection.
+    ///
+    /// The config should advertise `h2` through ALPN so clients negotiate HTTP/2.
+    #[cfg(feature = "tls")]
+    pub fn bind_tls(
+        addr: impl Into<SocketAddr>,
+        config: impl Into<Arc<crate::RustlsConfig>>,
+    ) -> Self {
+        Self {
+            tls: Some(config.into()),
+            ..Self::bind(addr)
+        }
+    }
+
+    /// Fail the run when a client that does not speak HTTP/2 connects
+    /// before any HTTP/2 client has.
+    ///
+    /// Without this the client only gets a `505` explaining the problem, and a
+    /// warning is emitted with the `tracing` feature.
+    pub fn strict_http2(mut self) -> Self {
+        self.strict_http2 = true;
+        self
+    }
 }
 
-impl Default for Tonic {
-    fn default() -> Self {
-        Self::new(([127, 0, 0, 1], 0).into())
+/// Connection preface every HTTP/2 client sends first
+const HTTP2_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+/// Check whether the first bytes received on `stream` match the HTTP/2 preface.
+///
+/// Connections that close before sending anything are left to hyper.
+async fn starts_with_http2_preface(stream: &TcpStream) -> bool {
+    let mut buf = [0u8; HTTP2_PREFACE.len()];
+    match stream.peek(&mut buf).await {
+        Ok(n) => buf[..n] == HTTP2_PREFACE[..n],
+        Err(_) => true,
     }
 }
 
-/// Shared state for tracking completion
-#[derive(Clone)]
-struct CompletionTracker {
-    total_handlers: usize,
-    handlers_called: Arc<AtomicUsize>,
-    shutdown_tx: Arc<Mutex<Option<oneshot::Sender<()>>>>,
+/// Answer a non-HTTP/2 client with a plain HTTP/1.1 505 explaining the problem
+async fn reject_non_http2(mut stream: TcpStream, message: &str) {
+    let response = format!(
+        "HTTP/1.1 505 HTTP Version Not Supported\r\ncontent-type: text/plain\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+        message.len(),
+        message
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
 }
 
-impl CompletionTracker {
-    fn new(total_handlers: usize, shutdown_tx: oneshot::Sender<()>) -> Self {
-        Self {
-            total_handlers,
-            handlers_called: Arc::new(AtomicUsize::new(0)),
-            shutdown_tx: Arc::new(Mutex::new(Some(shutdown_tx))),
-        }
+impl Default for Tonic {
+    fn default() -> Self {
+        Self::new(([127, 0, 0, 1], 0).into())
     }
+}
 
-    async fn handler_called(&self) {
-        let called = self.handlers_called.fetch_add(1, Ordering::SeqCst) + 1;
-        if called >= self.total_handlers {
-            if let Some(tx) = self.shutdown_tx.lock().await.take() {
-                let _ = tx.send(());
-            }
+/// Serve `connection` until it closes, shutting it down gracefully once
+/// `draining` fires so in-flight calls can finish
+async fn serve_until_drained<C: GracefulConnection>(
+    connection: C,
+    mut draining: watch::Receiver<()>,
+) {
+    tokio::pin!(connection);
+    tokio::select! {
+        _ = connection.as_mut() => {}
+        Ok(()) = draining.changed() => {
+            connection.as_mut().graceful_shutdown();
+            let _ = connection.await;
         }
     }
 }
@@ -68,28 +153,56 @@ impl CompletionTracker {
 /// Type-erased collector trait for internal use
 trait ErasedCollector: Send + Sync {
     fn collect(&self, request: CollectedRequest);
+    fn collects_unmatched(&self) -> bool;
 }
 
-impl<C: Collector> ErasedCollector for std::sync::Mutex<Option<C>> {
-    fn collect(&self, request: CollectedRequest) {
-        if let Ok(guard) = self.lock() {
-            if let Some(ref collector) = *guard {
+/// The scenario's collector and the sequence number of the next collected request
+struct CollectorSlot<C> {
+    collector: Option<C>,
+    next_sequence: u64,
+}
+
+impl<C: Collector> ErasedCollector for std::sync::Mutex<CollectorSlot<C>> {
+    fn collect(&self, mut request: CollectedRequest) {
+        if let Ok(mut slot) = self.lock() {
+            // Stamped under the lock so sequence numbers follow collection order
+            request.received_at = std::time::Instant::now();
+            request.sequence = slot.next_sequence;
+            slot.next_sequence += 1;
+            if let Some(ref collector) = slot.collector {
                 collector.collect(request);
             }
         }
     }
+
+    fn collects_unmatched(&self) -> bool {
+        self.lock()
+            .ok()
+            .and_then(|slot| slot.collector.as_ref().map(Collector::collects_unmatched))
+            .unwrap_or(false)
+    }
 }
 
 /// State shared with handlers
 struct ServerState {
     /// Map from "/package.Service/Method" to handlers
     routes: HashMap<String, RouteState>,
+    /// Serves calls to methods without a route
+    default_route: Option<RouteState>,
     collector: Arc<dyn ErasedCollector>,
     completion_tracker: CompletionTracker,
+    /// Whether completion counts requests, so streams count once per call
+    counts_requests: bool,
+    /// Request messages that failed to decode into their method's expected type
+    invalid_messages: std::sync::Mutex<Vec<String>>,
+    /// Reports handler panics to the server loop when they fail the run
+    panics: Option<mpsc::UnboundedSender<String>>,
 }
 
 struct RouteState {
     handlers: Vec<Handler>,
+    cycle: bool,
+    validator: Option<MessageValidator>,
     call_count: AtomicUsize,
     service_name: String,
     method_name: String,
@@ -98,8 +211,11 @@ struct RouteState {
 impl ServerState {
     fn new(
         services: Vec<Service>,
+        default_handler: Option<Handler>,
         collector: Arc<dyn ErasedCollector>,
         completion_tracker: CompletionTracker,
+        counts_requests: bool,
+        panics: Option<mpsc::UnboundedSender<String>>,
     ) -> Self {
         let mut routes = HashMap::new();
 
@@ -110,6 +226,8 @@ impl ServerState {
                     path,
                     RouteState {
                         handlers: method.handlers,
+                        cycle: method.cycle,
+                        validator: method.validator,
                         call_count: AtomicUsize::new(0),
                         service_name: service.name.clone(),
                         method_name: method.name.clone(),
@@ -118,100 +236,408 @@ impl ServerState {
             }
         }
 
+        let default_route = default_handler.map(|handler| RouteState {
+            handlers: vec![handler],
+            cycle: false,
+            validator: None,
+            call_count: AtomicUsize::new(0),
+            service_name: String::new(),
+            method_name: String::new(),
+        });
+
         Self {
             routes,
+            default_route,
             collector,
             completion_tracker,
+            counts_requests,
+            invalid_messages: std::sync::Mutex::new(Vec::new()),
+            panics,
         }
     }
 }
 
+/// Wire framing used by a request, detected from its `content-type`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Framing {
+    /// Standard gRPC over HTTP/2
+    Grpc,
+    /// gRPC-Web with binary frames (`application/grpc-web`, `application/grpc-web+proto`)
+    GrpcWeb,
+    /// gRPC-Web with base64-encoded frames (`application/grpc-web-text`)
+    GrpcWebText,
+}
+
+impl Framing {
+    fn from_content_type(content_type: &str) -> Self {
+        if content_type.starts_with("application/grpc-web-text") {
+            Framing::GrpcWebText
+        } else if content_type.starts_with("application/grpc-web") {
+            Framing::GrpcWeb
+        } else {
+            Framing::Grpc
+        }
+    }
+
+    /// Decode the raw request body into length-prefixed frames
+    fn decode_body(self, body: &[u8]) -> Vec<u8> {
+        match self {
+            Framing::GrpcWebText => BASE64.decode(body).unwrap_or_default(),
+            Framing::Grpc | Framing::GrpcWeb => body.to_vec(),
+        }
+    }
+
+    /// Build a response carrying one frame per message and the given status.
+    ///
+    /// Standard gRPC sends the status as headers. gRPC-Web appends it as a
+    /// trailers frame in the body, base64-encoding everything for the text variant.
+    /// With `gzip`, the message frames are compressed and `grpc-encoding` set.
+    fn respond(
+        self,
+        content_type: &str,
+        messages: &[&[u8]],
+        gzip: bool,
+        grpc_status: u32,
+        grpc_message: Option<&str>,
+    ) -> Response<Full<Bytes>> {
+        let mut body = Vec::new();
+        let mut builder = Response::builder().status(200);
+        if gzip && !messages.is_empty() {
+            builder = builder.header("grpc-encoding", "gzip");
+        }
+        for data in messages {
+            match gzip {
+                true => push_frame(&mut body, 0x01, &compression::gzip(data)),
+                false => push_frame(&mut body, 0x00, data),
+            }
+        }
+
+        match self {
+            Framing::Grpc => {
+                builder = builder
+                    .header("content-type", "application/grpc")
+                    .header("grpc-status", grpc_status.to_string());
+                if let Some(msg) = grpc_message {
+                    builder = builder.header("grpc-message", msg);
+                }
+            }
+            Framing::GrpcWeb | Framing::GrpcWebText => {
+                let mut trailers = format!("grpc-status:{}\r\n", grpc_status);
+                if let Some(msg) = grpc_message {
+                    trailers.push_str(&format!("grpc-message:{}\r\n", msg));
+                }
+                push_frame(&mut body, 0x80, trailers.as_bytes());
+                if self == Framing::GrpcWebText {
+                    body = BASE64.encode(&body).into_bytes();
+                }
+                builder = builder.header("content-type", content_type);
+            }
+        }
+
+        builder.body(Full::new(Bytes::from(body))).unwrap()
+    }
+
+    /// Build the response to a handler's reply; errors are sent
+    /// trailers-only, without a message
+    fn respond_handler(
+        self,
+        content_type: &str,
+        response: &HandlerResponse,
+        gzip: bool,
+    ) -> Response<Full<Bytes>> {
+        match response.is_ok() {
+            true => self.respond(content_type, &[&response.message.data], gzip, 0, None),
+            false => self.respond(
+                content_type,
+                &[],
+                false,
+                response.status,
+                response.status_message.as_deref(),
+            ),
+        }
+    }
+
+    /// Build a "Trailers-Only" response: the status in the headers, no body
+    fn respond_trailers_only(
+        self,
+        content_type: &str,
+        response: &HandlerResponse,
+    ) -> Response<Full<Bytes>> {
+        let content_type = match self {
+            Framing::Grpc => "application/grpc",
+            Framing::GrpcWeb | Framing::GrpcWebText => content_type,
+        };
+        let mut builder = Response::builder()
+            .status(200)
+            .header("content-type", content_type)
+            .header("grpc-status", response.status.to_string());
+        if let Some(msg) = &response.status_message {
+            builder = builder.header("grpc-message", msg);
+        }
+        builder.body(Full::new(Bytes::new())).unwrap()
+    }
+
+    /// Build a response whose only frame is truncated: its length prefix
+    /// declares more bytes than the body carries. No status is sent, so
+    /// clients read the body and fail to decode it.
+    fn respond_malformed(self, content_type: &str) -> Response<Full<Bytes>> {
+        let content_type = match self {
+            Framing::Grpc => "application/grpc",
+            Framing::GrpcWeb | Framing::GrpcWebText => content_type,
+        };
+        let mut body = vec![0x00];
+        body.extend_from_slice(&u32::MAX.to_be_bytes());
+        if self == Framing::GrpcWebText {
+            body = BASE64.encode(&body).into_bytes();
+        }
+        Response::builder()
+            .status(200)
+            .header("content-type", content_type)
+            .body(Full::new(Bytes::from(body)))
+            .unwrap()
+    }
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "call", skip_all, fields(path = %req.uri().path()))
+)]
 async fn handle_grpc_request(
     state: Arc<ServerState>,
     req: Request<Incoming>,
-) -> Result<Response<Full<Bytes>>, hyper::Error> {
+) -> Result<Response<Full<Bytes>>, HarnessError> {
     let path = req.uri().path().to_string();
+    let content_type = req
+        .headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("application/grpc")
+        .to_string();
+    let framing = Framing::from_content_type(&content_type);
+    let metadata: HashMap<String, String> = req
+        .headers()
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
+        .collect();
+    let gzip = compression::accepts_gzip(metadata.get("grpc-accept-encoding").map(String::as_str));
+    #[cfg(feature = "tracing")]
+    tracing::debug!(%content_type, "request received");
 
     // Collect the request body
-    let body_bytes = req.into_body().collect().await?.to_bytes();
+    let body_bytes = req
+        .into_body()
+        .collect()
+        .await
+        .map_err(|e| HarnessError::TransportError(e.to_string()))?
+        .to_bytes();
+    let body_bytes = framing.decode_body(&body_bytes);
 
     // gRPC messages are prefixed with 5 bytes: 1 byte compression flag + 4 bytes length
-    let message_data = if body_bytes.len() > 5 {
-        body_bytes[5..].to_vec()
-    } else {
-        Vec::new()
+    let message_data = match split_frame(&body_bytes) {
+        Ok((flag, data, [])) if flag & 0x01 == 0 => Ok(data.to_vec()),
+        Ok((_, data, [])) => {
+            compression::decompress(data, metadata.get("grpc-encoding").map(String::as_str))
+        }
+        // INTERNAL
+        Ok(_) => Err((13, "Expected a single request message".to_string())),
+        Err(e) => Err((13, e.to_string())),
+    };
+    let message_data = match message_data {
+        Ok(data) => data,
+        Err((status, message)) => {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(status, message, "failed to decode request message");
+            return Ok(framing.respond(&content_type, &[], false, status, Some(&message)))
+        }
     };
 
-    if let Some(route) = state.routes.get(&path) {
-        // Collect the request
-        let collected = CollectedRequest::new(
-            route.service_name.clone(),
-            route.method_name.clone(),
-            Message::new(message_data.clone()),
-        );
-        state.collector.collect(collected);
-
-        // Get the response from the handler
-        let call_index = route.call_count.fetch_add(1, Ordering::SeqCst);
-        let handler_count = route.handlers.len();
-        let handler_index = call_index.min(handler_count.saturating_sub(1));
-
-        // Check if this is a new handler being called for the first time
-        if call_index < handler_count.max(1) {
-            state.completion_tracker.handler_called().await;
-        }
-
-        let response_data = if let Some(handler) = route.handlers.get(handler_index) {
-            let ctx = RequestContext::new(
-                route.service_name.clone(),
-                route.method_name.clone(),
-                Message::new(message_data),
-            );
-            handler.respond(&ctx).data
-        } else {
-            Vec::new()
-        };
+    // Unmatched calls go to the default route, if any
+    let (route, is_default) = match (state.routes.get(&path), &state.default_route) {
+        (Some(route), _) => (route, false),
+        (None, Some(route)) => (route, true),
+        // Service/method not found
+        (None, None) => {
+            #[cfg(feature = "tracing")]
+            tracing::warn!("no handler matched");
+            if state.collector.collects_unmatched() {
+                let (service_name, method_name) = split_path(&path);
+                let mut collected =
+                    CollectedRequest::new(service_name, method_name, Message::new(message_data));
+                collected.metadata = metadata;
+                collected.matched = false;
+                state.collector.collect(collected);
+            }
+            return Ok(framing.respond(&content_type, &[], false, 12, Some("Method not found"))) // UNIMPLEMENTED
+        }
+    };
+    let (service_name, method_name) = if is_default {
+        split_path(&path)
+    } else {
+        (route.service_name.clone(), route.method_name.clone())
+    };
 
-        // Build gRPC response with length prefix
-        let mut grpc_response = Vec::with_capacity(5 + response_data.len());
-        grpc_response.push(0); // No compression
-        let len = response_data.len() as u32;
-        grpc_response.extend_from_slice(&len.to_be_bytes());
-        grpc_response.extend_from_slice(&response_data);
+    // Collect the request
+    let mut collected = CollectedRequest::new(
+        service_name.clone(),
+        method_name.clone(),
+        Message::new(message_data.clone()),
+    );
+    collected.metadata = metadata.clone();
+    collected.matched = !is_default;
+    state.collector.collect(collected);
+
+    if let Some(failure) = route.validator.as_ref().and_then(|v| v.failure(&message_data)) {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(%failure, "request message failed validation");
+        if let Ok(mut invalid) = state.invalid_messages.lock() {
+            invalid.push(format!("{}: {}", path, failure));
+        }
+    }
 
-        Ok(Response::builder()
-            .status(200)
-            .header("content-type", "application/grpc")
-            .header("grpc-status", "0")
-            .body(Full::new(Bytes::from(grpc_response)))
-            .unwrap())
+    // Get the response from the handler
+    let call_index = route.call_count.fetch_add(1, Ordering::SeqCst);
+    let handler_count = route.handlers.len();
+    let handler_index = match handler_count {
+        0 => 0,
+        _ if route.cycle => call_index % handler_count,
+        _ => call_index.min(handler_count - 1),
+    };
+    #[cfg(feature = "tracing")]
+    if handler_count > 0 {
+        tracing::debug!(index = handler_index, count = handler_count, "handler selected");
     } else {
-        // Service/method not found
-        Ok(Response::builder()
-            .status(200)
-            .header("content-type", "application/grpc")
-            .header("grpc-status", "12") // UNIMPLEMENTED
-            .header("grpc-message", "Method not found")
-            .body(Full::new(Bytes::new()))
-            .unwrap())
+        tracing::warn!("no handler matched, the method has no handlers");
+    }
+
+    // First calls of a handler count towards completion, once per message
+    // for streams; default route calls only count towards `expected_requests`
+    let first_call = !is_default && CompletionTracker::is_first_call(call_index, handler_count);
+    let completion_calls = match route.handlers.get(handler_index) {
+        Some(handler) if first_call && !state.counts_requests => handler.completion_calls(),
+        _ => 1,
+    };
+    // When panics fail the run, calls count once their handler returns, so a
+    // panic in the scenario's last call is reported before shutdown
+    let count_after_handler = state.panics.is_some();
+    if !count_after_handler {
+        for _ in 0..completion_calls {
+            state.completion_tracker.request_handled(first_call).await;
+        }
+    }
+
+    let reply = match route.handlers.get(handler_index) {
+        // A failed service makes hyper reset the stream with `INTERNAL_ERROR`
+        Some(Handler::ResetStream) => {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("resetting stream");
+            Err(HarnessError::TransportError("stream reset by handler".to_string()))
+        }
+        Some(Handler::Malformed) => Ok(framing.respond_malformed(&content_type)),
+        Some(Handler::TrailersOnly(response)) => {
+            Ok(framing.respond_trailers_only(&content_type, response))
+        }
+        Some(Handler::Stream(messages)) => {
+            let messages: Vec<&[u8]> = messages.iter().map(|m| m.data.as_slice()).collect();
+            Ok(framing.respond(&content_type, &messages, gzip, 0, None))
+        }
+        Some(handler) => {
+            let mut ctx =
+                RequestContext::new(service_name, method_name, Message::new(message_data));
+            ctx.metadata = metadata;
+            let response = match call_handler(handler, &ctx).await {
+                Ok(response) => response,
+                Err(payload) => {
+                    let Some(panics) = &state.panics else {
+                        resume_unwind(payload);
+                    };
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!("handler panicked");
+                    let _ = panics.send(panic_message(payload.as_ref()));
+                    HandlerResponse::error(13, "handler panicked")
+                }
+            };
+            Ok(framing.respond_handler(&content_type, &response, gzip))
+        }
+        None => Ok(framing.respond_handler(
+            &content_type,
+            &HandlerResponse::new(Message::empty()),
+            gzip,
+        )),
+    };
+
+    if count_after_handler {
+        for _ in 0..completion_calls {
+            state.completion_tracker.request_handled(first_call).await;
+        }
+    }
+    reply
+}
+
+/// Run a dynamic or static handler, catching any panic it raises
+async fn call_handler(
+    handler: &Handler,
+    ctx: &RequestContext,
+) -> Result<HandlerResponse, Box<dyn Any + Send>> {
+    match handler {
+        Handler::AsyncDynamic(f) => {
+            let mut future = catch_unwind(AssertUnwindSafe(|| f(ctx)))?;
+            std::future::poll_fn(|cx| {
+                match catch_unwind(AssertUnwindSafe(|| future.as_mut().poll(cx))) {
+                    Ok(Poll::Ready(response)) => Poll::Ready(Ok(response)),
+                    Ok(Poll::Pending) => Poll::Pending,
+                    Err(payload) => Poll::Ready(Err(payload)),
+                }
+            })
+            .await
+        }
+        handler => catch_unwind(AssertUnwindSafe(|| handler.respond_with_status(ctx))),
+    }
+}
+
+/// The message a panic was raised with, for `&str` and `String` payloads
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match (payload.downcast_ref::<&str>(), payload.downcast_ref::<String>()) {
+        (Some(message), _) => message.to_string(),
+        (None, Some(message)) => message.clone(),
+        (None, None) => "unknown panic payload".to_string(),
     }
 }
 
+/// Split a `/package.Service/Method` path into service and method names
+fn split_path(path: &str) -> (String, String) {
+    let path = path.trim_start_matches('/');
+    let (service, method) = path.split_once('/').unwrap_or((path, ""));
+    (service.to_string(), method.to_string())
+}
+
 #[async_trait]
 impl Server for Tonic {
     async fn run<C, F>(
         &self,
         services: Vec<Service>,
         collector: C,
+        options: RunOptions,
         on_ready: Option<F>,
     ) -> Result<C::Output, HarnessError>
     where
         C: Collector + 'static,
         F: FnOnce(SocketAddr) + Send + 'static,
     {
-        // Wrap collector in Mutex<Option<C>> so we can take it out at the end
-        let collector_holder: Arc<std::sync::Mutex<Option<C>>> =
-            Arc::new(std::sync::Mutex::new(Some(collector)));
+        // A limit of zero would never serve anything
+        let connection_limit = match options.max_connections {
+            Some(0) => {
+                return Err(HarnessError::ConfigurationError(
+                    "max_connections must be at least 1".to_string(),
+                ))
+            }
+            limit => limit.map(|n| Arc::new(Semaphore::new(n))),
+        };
+
+        // Wrap collector in a Mutex so we can take it out at the end
+        let collector_holder = Arc::new(std::sync::Mutex::new(CollectorSlot {
+            collector: Some(collector),
+            next_sequence: 0,
+        }));
         let erased_collector: Arc<dyn ErasedCollector> = collector_holder.clone();
 
         // Count total handlers
@@ -220,24 +646,43 @@ impl Server for Tonic {
             .map(|s| {
                 s.methods
                     .iter()
-                    .map(|m| m.handlers.len().max(1))
+                    .map(|m| match m.handlers.is_empty() {
+                        true => CompletionTracker::expected_calls(0),
+                        false => m.handlers.iter().map(Handler::completion_calls).sum(),
+                    })
                     .sum::<usize>()
             })
             .sum();
 
         // Create shutdown channel for auto-shutdown
         let (auto_shutdown_tx, mut auto_shutdown_rx) = oneshot::channel();
-        let completion_tracker = CompletionTracker::new(total_handlers, auto_shutdown_tx);
+        let mut completion_tracker = CompletionTracker::new(total_handlers, auto_shutdown_tx);
+        if let Some(expected) = options.expected_requests {
+            completion_tracker = completion_tracker.counting_requests(expected);
+        }
 
+        let (panic_tx, mut panic_rx) = mpsc::unbounded_channel::<String>();
         let state = Arc::new(ServerState::new(
             services,
+            options.default_handler,
             erased_collector,
             completion_tracker,
+            options.expected_requests.is_some(),
+            options.propagate_panics.then_some(panic_tx),
         ));
 
-        let listener = TcpListener::bind(self.addr)
-            .await
-            .map_err(|e| HarnessError::ServerError(e.to_string()))?;
+        let listener = match &self.listener {
+            Some(listener) => listener
+                .try_clone()
+                .and_then(|listener| {
+                    listener.set_nonblocking(true)?;
+                    TcpListener::from_std(listener)
+                })
+                .map_err(HarnessError::Bind)?,
+            None => TcpListener::bind(self.addr)
+                .await
+                .map_err(HarnessError::Bind)?,
+        };
 
         let addr = listener
             .local_addr()
@@ -248,40 +693,132 @@ impl Server for Tonic {
             callback(addr);
         }
 
+        // Connections that completed the HTTP/2 preface, and diagnostics for
+        // non-HTTP/2 clients that connected before any of them
+        let http2_connections = Arc::new(AtomicUsize::new(0));
+        let (protocol_error_tx, mut protocol_error_rx) = mpsc::unbounded_channel::<String>();
+
+        // Connection tasks, drained within the shutdown grace period
+        let mut connections = JoinSet::new();
+        let (draining_tx, draining_rx) = watch::channel(());
+
+        #[cfg(feature = "tls")]
+        let tls_acceptor = self.tls.clone().map(tokio_rustls::TlsAcceptor::from);
+
         // Run the server loop until auto-shutdown
         loop {
             tokio::select! {
                 result = listener.accept() => {
-                    if let Ok((stream, _)) = result {
+                    if let Ok((stream, peer)) = result {
                         let state = state.clone();
-                        let io = TokioIo::new(stream);
+                        let http2_connections = http2_connections.clone();
+                        let protocol_error_tx = protocol_error_tx.clone();
+                        let mut draining_rx = draining_rx.clone();
+                        let connection_limit = connection_limit.clone();
+                        #[cfg(feature = "tls")]
+                        let tls_acceptor = tls_acceptor.clone();
+
+                        connections.spawn(async move {
+                            // Connections over the limit stay accepted but unserved
+                            // until an earlier one closes
+                            let _permit = match connection_limit {
+                                Some(limit) => tokio::select! {
+                                    Ok(permit) = limit.acquire_owned() => Some(permit),
+                                    _ = draining_rx.changed() => return,
+                                },
+                                None => None,
+                            };
 
-                        tokio::spawn(async move {
                             let service = service_fn(move |req| {
                                 let state = state.clone();
                                 async move { handle_grpc_request(state, req).await }
                             });
 
-                            let _ = http2::Builder::new(TokioExecutor::new())
-                                .serve_connection(io, service)
-                                .await;
+                            // The HTTP/2 preface is encrypted, so TLS connections skip the check
+                            #[cfg(feature = "tls")]
+                            if let Some(acceptor) = tls_acceptor {
+                                if let Ok(stream) = acceptor.accept(stream).await {
+                                    let connection = http2::Builder::new(TokioExecutor::new())
+                                        .serve_connection(TokioIo::new(stream), service);
+                                    serve_until_drained(connection, draining_rx).await;
+                                }
+                                return;
+                            }
+
+                            if !starts_with_http2_preface(&stream).await {
+                                let message = format!(
+                                    "client {} did not start an HTTP/2 connection; \
+                                     the gRPC server only accepts HTTP/2 with prior knowledge (h2c)",
+                                    peer
+                                );
+                                if http2_connections.load(Ordering::SeqCst) == 0 {
+                                    let _ = protocol_error_tx.send(message.clone());
+                                }
+                                reject_non_http2(stream, &message).await;
+                                return;
+                            }
+                            http2_connections.fetch_add(1, Ordering::SeqCst);
+
+                            let io = TokioIo::new(stream);
+                            let connection = http2::Builder::new(TokioExecutor::new())
+                                .serve_connection(io, service);
+                            serve_until_drained(connection, draining_rx).await;
                         });
                     }
                 }
+                Some(_) = connections.join_next(), if !connections.is_empty() => {}
+                Some(message) = protocol_error_rx.recv() => {
+                    if self.strict_http2 {
+                        return Err(HarnessError::TransportError(message));
+                    }
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(%message, "rejected a client not speaking HTTP/2");
+                }
+                Some(message) = panic_rx.recv() => {
+                    return Err(HarnessError::HandlerPanic { message });
+                }
                 _ = &mut auto_shutdown_rx => {
+                    #[cfg(feature = "tracing")]
+                    tracing::info!("scenario complete, shutting down");
                     break;
                 }
             }
         }
 
+        // Let in-flight calls finish within the grace period, then close the
+        // remaining connections; without one they are left running detached
+        match options.shutdown_grace {
+            Some(grace) => {
+                let _ = draining_tx.send(());
+                let drained = async { while connections.join_next().await.is_some() {} };
+                let _ = tokio::time::timeout(grace, drained).await;
+                connections.shutdown().await;
+            }
+            None => connections.detach_all(),
+        }
+
+        // A panic reported by the call that completed the scenario
+        if let Ok(message) = panic_rx.try_recv() {
+            return Err(HarnessError::HandlerPanic { message });
+        }
+
+        let invalid_messages = state
+            .invalid_messages
+            .lock()
+            .map(|invalid| invalid.clone())
+            .unwrap_or_default();
+        if !invalid_messages.is_empty() {
+            return Err(HarnessError::InvalidMessages(invalid_messages));
+        }
+
         // Extract the collector and return its output
         let collector = collector_holder
             .lock()
             .map_err(|e| HarnessError::ServerError(e.to_string()))?
+            .collector
             .take()
             .ok_or_else(|| HarnessError::ServerError("Collector already taken".to_string()))?;
 
         Ok(collector.into_output())
     }
 }
-
@@ -0,0 +1,11 @@
+use tokio_rustls::rustls::ServerConfig;
+
+pub use server_harness_core::tls::TEST_CERT_PEM;
+
+/// Build a TLS server config from the bundled self-signed [`TEST_CERT_PEM`].
+///
+/// The config advertises `h2` through ALPN, as gRPC requires.
+/// Clients must trust that certificate (or skip verification) to connect.
+pub fn rustls_test_config() -> ServerConfig {
+    server_harness_core::tls::rustls_test_config(&[b"h2"])
+}
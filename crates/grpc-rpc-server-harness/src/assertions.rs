@@ -0,0 +1,253 @@
+//! Assertions over gRPC messages that panic with readable messages
+//!
+//! ```
+//! use grpc_rpc_server_harness::assertions::assert_message_eq;
+//! use grpc_rpc_server_harness::prelude::*;
+//!
+//! assert_message_eq(&Message::new(vec![0x08, 0x96, 0x01]), &[0x08, 0x96, 0x01]);
+//! ```
+
+pub use server_harness_core::RequestLog;
+use server_harness_core::LogEntry;
+
+use crate::entities::{CollectedRequest, Message};
+
+/// Bytes shown per row of a hex diff
+const ROW_LEN: usize = 16;
+
+/// Assert that `actual` carries exactly the `expected` bytes.
+///
+/// On mismatch, panics with the offset of the first differing byte and a hex
+/// dump of the row around it for both messages.
+#[track_caller]
+pub fn assert_message_eq(actual: &Message, expected: &[u8]) {
+    let actual = actual.data.as_slice();
+    let Some(offset) = first_difference(expected, actual) else {
+        return;
+    };
+    let row = offset - offset % ROW_LEN;
+    panic!(
+        "message mismatch at byte {} (expected {} bytes, got {})\n  \
+         expected @{:04x}: {}\n  \
+         actual   @{:04x}: {}\n  \
+         {}^^",
+        offset,
+        expected.len(),
+        actual.len(),
+        row,
+        hex_row(expected, row),
+        row,
+        hex_row(actual, row),
+        " ".repeat("actual   @0000: ".len() + 3 * (offset - row))
+    );
+}
+
+/// Assert that `message` decodes as a `T` equal to `expected`.
+///
+/// Panics with the decode error and the first bytes of `message` when it is
+/// not a valid `T`, or with both messages pretty-printed when they differ.
+#[track_caller]
+pub fn assert_decodes_to<T>(message: &Message, expected: &T)
+where
+    T: prost::Message + Default + PartialEq + std::fmt::Debug,
+{
+    let actual: T = match message.decode() {
+        Ok(actual) => actual,
+        Err(e) => panic!(
+            "message does not decode as {} ({})\n  bytes: {}",
+            std::any::type_name::<T>(),
+            e,
+            hex_row(&message.data, 0)
+        ),
+    };
+    if &actual != expected {
+        panic!(
+            "decoded {} mismatch\nexpected:\n{:#?}\nactual:\n{:#?}",
+            std::any::type_name::<T>(),
+            expected,
+            actual
+        );
+    }
+}
+
+impl LogEntry for CollectedRequest {
+    fn summary(&self) -> String {
+        format!("{}/{}", self.service, self.method)
+    }
+}
+
+/// gRPC assertions on a [`RequestLog`], checking the message of the request at `index`
+///
+/// ```
+/// use grpc_rpc_server_harness::prelude::*;
+///
+/// let log = RequestLog::from(vec![CollectedRequest::new(
+///     "test.UserService",
+///     "GetUser",
+///     Message::new(vec![0x08, 0x7b]),
+/// )]);
+/// log.assert_count(1).assert_message_eq(0, &[0x08, 0x7b]);
+/// ```
+pub trait RequestAssertions {
+    /// Assert that the request at `index` carries exactly the `expected` bytes,
+    /// see [`assert_message_eq`]
+    #[track_caller]
+    fn assert_message_eq(&self, index: usize, expected: &[u8]) -> &Self;
+
+    /// Assert that the message of the request at `index` decodes as a `T` equal
+    /// to `expected`, see [`assert_decodes_to`]
+    #[track_caller]
+    fn assert_decodes_to<T>(&self, index: usize, expected: &T) -> &Self
+    where
+        T: prost::Message + Default + PartialEq + std::fmt::Debug;
+}
+
+impl RequestAssertions for RequestLog<CollectedRequest> {
+    #[track_caller]
+    fn assert_message_eq(&self, index: usize, expected: &[u8]) -> &Self {
+        assert_message_eq(&self.request(index).message, expected);
+        self
+    }
+
+    #[track_caller]
+    fn assert_decodes_to<T>(&self, index: usize, expected: &T) -> &Self
+    where
+        T: prost::Message + Default + PartialEq + std::fmt::Debug,
+    {
+        assert_decodes_to(&self.request(index).message, expected);
+        self
+    }
+}
+
+/// Offset of the first byte where `actual` differs from `expected`, including
+/// one of them ending early
+fn first_difference(expected: &[u8], actual: &[u8]) -> Option<usize> {
+    match expected.iter().zip(actual).position(|(e, a)| e != a) {
+        Some(offset) => Some(offset),
+        None if expected.len() != actual.len() => Some(expected.len().min(actual.len())),
+        None => None,
+    }
+}
+
+/// The bytes of the row starting at `start` as space-separated hex
+fn hex_row(data: &[u8], start: usize) -> String {
+    let end = data.len().min(start + ROW_LEN);
+    let bytes: Vec<String> = data
+        .get(start..end)
+        .unwrap_or_default()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    bytes.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct User {
+        #[prost(int32, tag = "1")]
+        id: i32,
+        #[prost(string, tag = "2")]
+        name: String,
+    }
+
+    fn panic_message(f: impl FnOnce()) -> String {
+        let error = catch_unwind(AssertUnwindSafe(f)).expect_err("assertion should fail");
+        error.downcast_ref::<String>().cloned().unwrap_or_default()
+    }
+
+    #[test]
+    fn test_assertions_pass() {
+        let user = User {
+            id: 1,
+            name: "Alice".to_string(),
+        };
+        let message = Message::from_prost(&user);
+        assert_message_eq(&message, &message.data.clone());
+        assert_decodes_to(&message, &user);
+    }
+
+    #[test]
+    fn test_request_log_assertions() {
+        let user = User {
+            id: 1,
+            name: "Alice".to_string(),
+        };
+        let log = RequestLog::from(vec![
+            CollectedRequest::new("test.UserService", "GetUser", Message::from_prost(&user)),
+            CollectedRequest::new("test.UserService", "ListUsers", Message::new(vec![])),
+        ]);
+        log.assert_count(2)
+            .assert_decodes_to(0, &user)
+            .assert_message_eq(1, &[]);
+
+        let message = panic_message(|| {
+            log.assert_count(1);
+        });
+        assert_eq!(
+            message,
+            "expected 1 requests, got 2:\n  test.UserService/GetUser\n  test.UserService/ListUsers"
+        );
+
+        let message = panic_message(|| {
+            log.assert_message_eq(2, &[]);
+        });
+        assert_eq!(message, "no request at index 2, only 2 collected");
+    }
+
+    #[test]
+    fn test_assert_message_eq_shows_hex_diff() {
+        let message = panic_message(|| {
+            assert_message_eq(&Message::new(vec![0x08, 0x96, 0x02]), &[0x08, 0x96, 0x01, 0x10]);
+        });
+        let expected = "message mismatch at byte 2 (expected 4 bytes, got 3)\n  \
+             expected @0000: 08 96 01 10\n  \
+             actual   @0000: 08 96 02\n  ";
+        assert_eq!(message, format!("{}{}^^", expected, " ".repeat(22)));
+    }
+
+    #[test]
+    fn test_assert_message_eq_shows_row_of_difference() {
+        let expected: Vec<u8> = (0..20).collect();
+        let mut actual = expected.clone();
+        actual.truncate(18);
+        let message = panic_message(|| assert_message_eq(&Message::new(actual), &expected));
+        let expected = "message mismatch at byte 18 (expected 20 bytes, got 18)\n  \
+             expected @0010: 10 11 12 13\n  \
+             actual   @0010: 10 11\n  ";
+        assert_eq!(message, format!("{}{}^^", expected, " ".repeat(22)));
+    }
+
+    #[test]
+    fn test_assert_decodes_to_mismatch() {
+        let actual = Message::from_prost(&User {
+            id: 1,
+            name: "Alice".to_string(),
+        });
+        let message = panic_message(|| {
+            assert_decodes_to(
+                &actual,
+                &User {
+                    id: 2,
+                    name: "Alice".to_string(),
+                },
+            )
+        });
+        assert!(message.starts_with("decoded "), "{}", message);
+        assert!(message.contains("User mismatch\nexpected:\n"));
+        assert!(message.contains("id: 2,"));
+        assert!(message.contains("actual:\nUser {\n    id: 1,"));
+    }
+
+    #[test]
+    fn test_assert_decodes_to_invalid_bytes() {
+        let message = panic_message(|| {
+            assert_decodes_to(&Message::new(vec![0x0a, 0x05, 0x41]), &User::default())
+        });
+        assert!(message.starts_with("message does not decode as "), "{}", message);
+        assert!(message.ends_with("\n  bytes: 0a 05 41"));
+    }
+}
@@ -1,11 +1,25 @@
 use super::Message;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Instant;
 
 /// A collected gRPC request
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct CollectedRequest {
     pub service: String,
     pub method: String,
     pub message: Message,
+    /// Request metadata (HTTP/2 headers) keyed by lowercase name.
+    /// Binary `-bin` values are stored raw, as the base64 text sent on the wire.
+    pub metadata: HashMap<String, String>,
+    /// When the server collected the request
+    #[serde(skip)]
+    pub received_at: Instant,
+    /// Position of the request in collection order, starting at 0 for each server run
+    pub sequence: u64,
+    /// Whether a configured handler served the request, rather than a default
+    /// handler or the server's "not found" fallback
+    pub matched: bool,
 }
 
 impl CollectedRequest {
@@ -14,8 +28,28 @@ impl CollectedRequest {
             service: service.into(),
             method: method.into(),
             message,
+            metadata: HashMap::new(),
+            received_at: Instant::now(),
+            sequence: 0,
+            matched: true,
         }
     }
+
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    /// Decode the collected message into a prost type
+    pub fn decode<T: prost::Message + Default>(&self) -> Result<T, prost::DecodeError> {
+        self.message.decode()
+    }
+}
+
+impl server_harness_core::Matched for CollectedRequest {
+    fn matched(&self) -> bool {
+        self.matched
+    }
 }
 
 #[cfg(test)]
@@ -29,4 +63,35 @@ mod tests {
         assert_eq!(req.method, "GetUser");
         assert_eq!(req.message.data, vec![1, 2, 3]);
     }
+
+    #[test]
+    fn test_collected_request_serialize() {
+        let req = CollectedRequest::new("UserService", "GetUser", Message::new(vec![0xff]))
+            .with_metadata("x-request-id", "req-1");
+        assert_eq!(
+            serde_json::to_value(&req).unwrap(),
+            serde_json::json!({
+                "service": "UserService",
+                "method": "GetUser",
+                "message": {"base64": "/w=="},
+                "metadata": {"x-request-id": "req-1"},
+                "sequence": 0,
+                "matched": true
+            })
+        );
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct GetUserRequest {
+        #[prost(string, tag = "1")]
+        id: String,
+    }
+
+    #[test]
+    fn test_collected_request_decode() {
+        let message = Message::from_prost(&GetUserRequest { id: "42".into() });
+        let req = CollectedRequest::new("UserService", "GetUser", message);
+        let decoded: GetUserRequest = req.decode().unwrap();
+        assert_eq!(decoded.id, "42");
+    }
 }
@@ -1,4 +1,7 @@
 use super::Message;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
 
 /// Context passed to dynamic handlers
@@ -7,6 +10,8 @@ pub struct RequestContext {
     pub service: String,
     pub method: String,
     pub message: Message,
+    /// Request metadata keyed by lowercase name (`-bin` values are left base64-encoded)
+    pub metadata: HashMap<String, String>,
 }
 
 impl RequestContext {
@@ -15,20 +20,103 @@ impl RequestContext {
             service: service.into(),
             method: method.into(),
             message,
+            metadata: HashMap::new(),
         }
     }
+
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    /// Get a metadata value by name
+    pub fn get_metadata(&self, key: &str) -> Option<&str> {
+        self.metadata.get(&key.to_ascii_lowercase()).map(String::as_str)
+    }
+
+    /// Get the token from `authorization: Bearer <token>` metadata.
+    /// The scheme is matched case-insensitively.
+    pub fn authorization_bearer(&self) -> Option<&str> {
+        let (scheme, token) = self.get_metadata("authorization")?.trim().split_once(' ')?;
+        scheme
+            .eq_ignore_ascii_case("bearer")
+            .then(|| token.trim_start())
+    }
+}
+
+/// Response from a handler: a message plus the gRPC status to report
+#[derive(Debug, Clone)]
+pub struct HandlerResponse {
+    pub message: Message,
+    /// gRPC status code (`0` is OK)
+    pub status: u32,
+    /// Optional `grpc-message` sent alongside a non-OK status
+    pub status_message: Option<String>,
+}
+
+impl HandlerResponse {
+    pub fn new(message: Message) -> Self {
+        Self {
+            message,
+            status: 0,
+            status_message: None,
+        }
+    }
+
+    /// Create a response with the given status code and no message
+    pub fn status(code: u32) -> Self {
+        Self {
+            message: Message::empty(),
+            status: code,
+            status_message: None,
+        }
+    }
+
+    /// Create an error response with the given status code and message
+    pub fn error(code: u32, message: impl Into<String>) -> Self {
+        Self {
+            message: Message::empty(),
+            status: code,
+            status_message: Some(message.into()),
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.status == 0
+    }
+}
+
+impl From<Message> for HandlerResponse {
+    fn from(message: Message) -> Self {
+        Self::new(message)
+    }
 }
 
 /// Type alias for dynamic handler functions
-pub type HandlerFn = Arc<dyn Fn(&RequestContext) -> Message + Send + Sync>;
+pub type HandlerFn = Arc<dyn Fn(&RequestContext) -> HandlerResponse + Send + Sync>;
+
+/// Type alias for async dynamic handler functions
+pub type AsyncHandlerFn = Arc<
+    dyn Fn(&RequestContext) -> Pin<Box<dyn Future<Output = HandlerResponse> + Send>> + Send + Sync,
+>;
 
 /// A handler that returns either a static or dynamic gRPC response
 #[derive(Clone)]
 pub enum Handler {
-    /// Static response - always returns the same message
-    Static(Message),
+    /// Static response - always returns the same message and status
+    Static(HandlerResponse),
     /// Dynamic response - builds message based on the request context
     Dynamic(HandlerFn),
+    /// Async dynamic response - the returned future is awaited by the server
+    AsyncDynamic(AsyncHandlerFn),
+    /// Transport failure - the server resets the HTTP/2 stream instead of responding
+    ResetStream,
+    /// Transport failure - the response frame declares more bytes than it carries
+    Malformed,
+    /// Server-streaming response - each message is sent as its own frame
+    Stream(Vec<Message>),
+    /// Status-only response - the status is sent in the response headers, without a body
+    TrailersOnly(HandlerResponse),
 }
 
 impl std::fmt::Debug for Handler {
@@ -36,6 +124,13 @@ impl std::fmt::Debug for Handler {
         match self {
             Handler::Static(msg) => f.debug_tuple("Static").field(msg).finish(),
             Handler::Dynamic(_) => f.debug_tuple("Dynamic").field(&"<fn>").finish(),
+            Handler::AsyncDynamic(_) => f.debug_tuple("AsyncDynamic").field(&"<fn>").finish(),
+            Handler::ResetStream => f.write_str("ResetStream"),
+            Handler::Malformed => f.write_str("Malformed"),
+            Handler::Stream(messages) => f.debug_tuple("Stream").field(messages).finish(),
+            Handler::TrailersOnly(response) => {
+                f.debug_tuple("TrailersOnly").field(response).finish()
+            }
         }
     }
 }
@@ -43,32 +138,116 @@ impl std::fmt::Debug for Handler {
 impl Handler {
     /// Create a new static handler with a predefined message
     pub fn new(message: Message) -> Self {
-        Handler::Static(message)
+        Handler::Static(HandlerResponse::new(message))
     }
 
-    /// Create a dynamic handler that builds responses based on the request context
-    pub fn dynamic<F>(f: F) -> Self
+    /// Create a dynamic handler that builds responses based on the request context.
+    ///
+    /// The closure may return a [`Message`] or a [`HandlerResponse`] to also set a status.
+    pub fn dynamic<F, R>(f: F) -> Self
     where
-        F: Fn(&RequestContext) -> Message + Send + Sync + 'static,
+        F: Fn(&RequestContext) -> R + Send + Sync + 'static,
+        R: Into<HandlerResponse>,
     {
-        Handler::Dynamic(Arc::new(f))
+        Handler::Dynamic(Arc::new(move |ctx| f(ctx).into()))
+    }
+
+    /// Create a dynamic handler whose response is computed asynchronously,
+    /// e.g. to await shared test state or simulate latency.
+    ///
+    /// The future may resolve to a [`Message`] or a [`HandlerResponse`].
+    pub fn dynamic_async<F, Fut, R>(f: F) -> Self
+    where
+        F: Fn(&RequestContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = R> + Send + 'static,
+        R: Into<HandlerResponse>,
+    {
+        Handler::AsyncDynamic(Arc::new(move |ctx| {
+            let response = f(ctx);
+            Box::pin(async move { response.await.into() })
+        }))
     }
 
     /// Create a static handler from raw bytes
     pub fn from_bytes(data: impl Into<Vec<u8>>) -> Self {
-        Handler::Static(Message::new(data))
+        Handler::new(Message::new(data))
     }
 
     /// Create a static handler from a prost message
     pub fn from_prost<T: prost::Message>(msg: &T) -> Self {
-        Handler::Static(Message::from_prost(msg))
+        Handler::new(Message::from_prost(msg))
+    }
+
+    /// Create a static handler that responds with the given gRPC status code
+    pub fn status(code: u32) -> Self {
+        Handler::Static(HandlerResponse::status(code))
+    }
+
+    /// Create a static handler that fails with the given gRPC status code and message
+    pub fn error(code: u32, message: &str) -> Self {
+        Handler::Static(HandlerResponse::error(code, message))
+    }
+
+    /// Create a handler that resets the HTTP/2 stream (`RST_STREAM`) instead of
+    /// responding, e.g. to exercise client retries. Counts towards shutdown like
+    /// any other handler.
+    pub fn reset_stream() -> Self {
+        Handler::ResetStream
+    }
+
+    /// Create a handler that responds with a truncated message frame, whose
+    /// length prefix declares more bytes than the body carries, so clients
+    /// fail to decode it. Counts towards shutdown like any other handler.
+    pub fn malformed() -> Self {
+        Handler::Malformed
+    }
+
+    /// Create a handler answering a server-streaming call with `messages`, each
+    /// sent as its own frame before an OK status.
+    ///
+    /// Every message counts as a handler call towards shutdown, so the first
+    /// call to a stream of three messages counts three times.
+    pub fn stream(messages: impl IntoIterator<Item = Message>) -> Self {
+        Handler::Stream(messages.into_iter().collect())
     }
 
-    /// Get the response for a given request context
+    /// Create a handler answering with a gRPC "Trailers-Only" response: the
+    /// status and message are sent in the initial headers, with no body.
+    ///
+    /// Unlike [`Handler::error`], gRPC-Web calls also get the status as headers
+    /// rather than as a trailers frame, and an OK status (`0`) sends no message
+    /// frame either.
+    pub fn trailers_only(status: u32, message: &str) -> Self {
+        Handler::TrailersOnly(HandlerResponse::error(status, message))
+    }
+
+    /// Number of handler calls the first use of this handler counts towards shutdown
+    pub(crate) fn completion_calls(&self) -> usize {
+        match self {
+            Handler::Stream(messages) => messages.len().max(1),
+            _ => 1,
+        }
+    }
+
+    /// Get the response message for a given request context
     pub fn respond(&self, ctx: &RequestContext) -> Message {
+        self.respond_with_status(ctx).message
+    }
+
+    /// Get the full response, including its gRPC status, for a given request context.
+    /// Async handlers must be awaited, and transport failures and streams are
+    /// produced by the server, so this returns an `INTERNAL` status for them.
+    pub fn respond_with_status(&self, ctx: &RequestContext) -> HandlerResponse {
         match self {
-            Handler::Static(msg) => msg.clone(),
+            Handler::Static(response) | Handler::TrailersOnly(response) => response.clone(),
             Handler::Dynamic(f) => f(ctx),
+            Handler::AsyncDynamic(_) => HandlerResponse::error(13, "async handlers must be awaited"),
+            Handler::ResetStream | Handler::Malformed => {
+                HandlerResponse::error(13, "transport failures are produced by the server")
+            }
+            Handler::Stream(_) => {
+                HandlerResponse::error(13, "streams are produced by the server")
+            }
         }
     }
 
@@ -80,23 +259,33 @@ impl Handler {
         static EMPTY_MESSAGE: std::sync::LazyLock<Message> =
             std::sync::LazyLock::new(|| Message { data: Vec::new() });
         match self {
-            Handler::Static(msg) => msg,
-            Handler::Dynamic(_) => &EMPTY_MESSAGE,
+            Handler::Static(response) => &response.message,
+            Handler::Stream(messages) => messages.first().unwrap_or(&EMPTY_MESSAGE),
+            Handler::Dynamic(_)
+            | Handler::AsyncDynamic(_)
+            | Handler::ResetStream
+            | Handler::Malformed
+            | Handler::TrailersOnly(_) => &EMPTY_MESSAGE,
         }
     }
 
     /// Convert handler into the response message (for backwards compatibility)
     pub fn into_response(self) -> Message {
         match self {
-            Handler::Static(msg) => msg,
-            Handler::Dynamic(_) => Message::empty(),
+            Handler::Static(response) => response.message,
+            Handler::Stream(messages) => messages.into_iter().next().unwrap_or_else(Message::empty),
+            Handler::Dynamic(_)
+            | Handler::AsyncDynamic(_)
+            | Handler::ResetStream
+            | Handler::Malformed
+            | Handler::TrailersOnly(_) => Message::empty(),
         }
     }
 }
 
 impl From<Message> for Handler {
     fn from(message: Message) -> Self {
-        Handler::Static(message)
+        Handler::new(message)
     }
 }
 
@@ -147,6 +336,73 @@ mod tests {
         assert_eq!(response.data, vec![0xFF, 1, 2, 3]);
     }
 
+    #[test]
+    fn test_request_context_authorization_bearer() {
+        let ctx = RequestContext::new("Svc", "Method", Message::empty())
+            .with_metadata("authorization", "Bearer abc");
+        assert_eq!(ctx.authorization_bearer(), Some("abc"));
+
+        let ctx = RequestContext::new("Svc", "Method", Message::empty());
+        assert_eq!(ctx.authorization_bearer(), None);
+    }
+
+    #[test]
+    fn test_handler_error() {
+        let handler = Handler::error(8, "quota exceeded");
+        let ctx = RequestContext::new("Svc", "Method", Message::empty());
+        let response = handler.respond_with_status(&ctx);
+        assert_eq!(response.status, 8);
+        assert_eq!(response.status_message.as_deref(), Some("quota exceeded"));
+        assert!(response.message.is_empty());
+    }
+
+    #[test]
+    fn test_trailers_only_handler() {
+        let handler = Handler::trailers_only(7, "denied");
+        let ctx = RequestContext::new("Svc", "Method", Message::empty());
+        let response = handler.respond_with_status(&ctx);
+        assert_eq!(response.status, 7);
+        assert_eq!(response.status_message.as_deref(), Some("denied"));
+        assert!(handler.response().is_empty());
+    }
+
+    #[test]
+    fn test_transport_failure_handlers_are_driven_by_the_server() {
+        let ctx = RequestContext::new("Svc", "Method", Message::empty());
+        for handler in [Handler::reset_stream(), Handler::malformed()] {
+            assert_eq!(handler.respond_with_status(&ctx).status, 13);
+        }
+    }
+
+    #[test]
+    fn test_stream_handler() {
+        let handler = Handler::stream([Message::new(vec![1]), Message::new(vec![2])]);
+        assert_eq!(handler.completion_calls(), 2);
+        assert_eq!(handler.response().data, vec![1]);
+        assert_eq!(Handler::stream([]).completion_calls(), 1);
+        assert_eq!(Handler::from_bytes(vec![1]).completion_calls(), 1);
+
+        let ctx = RequestContext::new("Svc", "Method", Message::empty());
+        assert_eq!(handler.respond_with_status(&ctx).status, 13);
+    }
+
+    #[test]
+    fn test_dynamic_handler_with_status() {
+        let handler = Handler::dynamic(|ctx: &RequestContext| {
+            if ctx.message.is_empty() {
+                HandlerResponse::error(3, "empty request")
+            } else {
+                HandlerResponse::new(ctx.message.clone())
+            }
+        });
+
+        let ctx = RequestContext::new("Svc", "Method", Message::empty());
+        assert_eq!(handler.respond_with_status(&ctx).status, 3);
+
+        let ctx = RequestContext::new("Svc", "Method", Message::new(vec![1]));
+        assert!(handler.respond_with_status(&ctx).is_ok());
+    }
+
     #[test]
     fn test_dynamic_handler_based_on_method() {
         let handler = Handler::dynamic(|ctx: &RequestContext| {
@@ -163,4 +419,25 @@ mod tests {
         let ctx2 = RequestContext::new("UserService", "CreateUser", Message::empty());
         assert_eq!(handler.respond(&ctx2).data, vec![2, 0, 0]);
     }
+
+    #[tokio::test]
+    async fn test_async_dynamic_handler() {
+        let handler = Handler::dynamic_async(|ctx: &RequestContext| {
+            let method = ctx.method.clone();
+            async move {
+                tokio::task::yield_now().await;
+                HandlerResponse::error(5, &method)
+            }
+        });
+
+        let ctx = RequestContext::new("test.Service", "GetUser", Message::empty());
+        let Handler::AsyncDynamic(f) = &handler else {
+            panic!("expected an async dynamic handler");
+        };
+        let response = f(&ctx).await;
+        assert_eq!(response.status, 5);
+        assert_eq!(response.status_message.as_deref(), Some("GetUser"));
+        // Not awaitable synchronously
+        assert_eq!(handler.respond_with_status(&ctx).status, 13);
+    }
 }
@@ -1,9 +1,26 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde::{Serialize, Serializer};
+
 /// Represents a gRPC message (request or response)
 #[derive(Debug, Clone)]
 pub struct Message {
     pub data: Vec<u8>,
 }
 
+/// Serializes as a string, or as `{"base64": ...}` when the data is not UTF-8
+impl Serialize for Message {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match std::str::from_utf8(&self.data) {
+            Ok(text) => serializer.serialize_str(text),
+            Err(_) => {
+                let mut map = std::collections::BTreeMap::new();
+                map.insert("base64", BASE64.encode(&self.data));
+                map.serialize(serializer)
+            }
+        }
+    }
+}
+
 impl Message {
     pub fn new(data: impl Into<Vec<u8>>) -> Self {
         Self { data: data.into() }
@@ -44,6 +61,18 @@ impl From<Message> for Vec<u8> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_message_serialize() {
+        assert_eq!(
+            serde_json::to_value(Message::new("ping")).unwrap(),
+            serde_json::json!("ping")
+        );
+        assert_eq!(
+            serde_json::to_value(Message::new(vec![0x08, 0x96, 0x01])).unwrap(),
+            serde_json::json!({"base64": "CJYB"})
+        );
+    }
+
     #[test]
     fn test_message_new() {
         let msg = Message::new(vec![1, 2, 3]);
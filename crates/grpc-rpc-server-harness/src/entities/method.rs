@@ -1,10 +1,49 @@
 use super::Handler;
+use std::sync::Arc;
+
+/// Type alias for functions decoding a request message into a prost type
+pub type DecodeFn = Arc<dyn Fn(&[u8]) -> Result<(), prost::DecodeError> + Send + Sync>;
+
+/// Checks that request messages decode into a prost type, see [`Method::expecting`]
+#[derive(Clone)]
+pub struct MessageValidator {
+    /// Name of the expected message type, without its module path
+    pub type_name: &'static str,
+    pub decode: DecodeFn,
+}
+
+impl MessageValidator {
+    pub fn new<T: prost::Message + Default>() -> Self {
+        let type_name = std::any::type_name::<T>();
+        Self {
+            type_name: type_name.rsplit("::").next().unwrap_or(type_name),
+            decode: Arc::new(|data| T::decode(data).map(drop)),
+        }
+    }
+
+    /// Describe why `data` does not decode, or `None` when it does
+    pub(crate) fn failure(&self, data: &[u8]) -> Option<String> {
+        (self.decode)(data)
+            .err()
+            .map(|e| format!("expected {}: {}", self.type_name, e))
+    }
+}
+
+impl std::fmt::Debug for MessageValidator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("MessageValidator").field(&self.type_name).finish()
+    }
+}
 
 /// Represents a gRPC method within a service
 #[derive(Debug, Clone)]
 pub struct Method {
     pub name: String,
     pub handlers: Vec<Handler>,
+    /// Whether handlers restart from the first once the last has been used
+    pub cycle: bool,
+    /// Type request messages must decode into, if checked
+    pub validator: Option<MessageValidator>,
 }
 
 impl Method {
@@ -12,6 +51,8 @@ impl Method {
         Self {
             name: name.into(),
             handlers: Vec::new(),
+            cycle: false,
+            validator: None,
         }
     }
 
@@ -24,6 +65,23 @@ impl Method {
         self.handlers.extend(handlers);
         self
     }
+
+    /// Cycle through the handlers instead of repeating the last one
+    pub fn cycle(mut self) -> Self {
+        self.cycle = true;
+        self
+    }
+
+    /// Check that every request message decodes into `T`, to catch client
+    /// encoding bugs.
+    ///
+    /// Calls are still answered by the handlers; the run fails with
+    /// [`HarnessError::InvalidMessages`](crate::error::HarnessError::InvalidMessages)
+    /// once the server shuts down.
+    pub fn expecting<T: prost::Message + Default>(mut self) -> Self {
+        self.validator = Some(MessageValidator::new::<T>());
+        self
+    }
 }
 
 #[cfg(test)]
@@ -35,6 +93,7 @@ mod tests {
         let method = Method::new("GetUser");
         assert_eq!(method.name, "GetUser");
         assert!(method.handlers.is_empty());
+        assert!(!method.cycle);
     }
 
     #[test]
@@ -52,4 +111,22 @@ mod tests {
             .with_handler(Handler::from_bytes(vec![3]));
         assert_eq!(method.handlers.len(), 3);
     }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct GetUserRequest {
+        #[prost(string, tag = "1")]
+        id: String,
+    }
+
+    #[test]
+    fn test_method_expecting() {
+        let method = Method::new("GetUser").expecting::<GetUserRequest>();
+        let validator = method.validator.unwrap();
+        assert_eq!(validator.type_name, "GetUserRequest");
+
+        let valid = prost::Message::encode_to_vec(&GetUserRequest { id: "42".into() });
+        assert_eq!(validator.failure(&valid), None);
+        let failure = validator.failure(&[0x0a, 0x05, b'4']).unwrap();
+        assert!(failure.starts_with("expected GetUserRequest: "), "{}", failure);
+    }
 }
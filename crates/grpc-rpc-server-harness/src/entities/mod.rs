@@ -6,8 +6,8 @@ mod scenario;
 mod service;
 
 pub use execution_result::CollectedRequest;
-pub use handler::{Handler, RequestContext};
+pub use handler::{Handler, HandlerResponse, RequestContext};
 pub use message::Message;
-pub use method::Method;
+pub use method::{MessageValidator, Method};
 pub use scenario::Scenario;
 pub use service::Service;
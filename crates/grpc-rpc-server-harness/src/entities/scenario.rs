@@ -1,8 +1,10 @@
 use crate::entities::Service;
+use crate::use_cases::ports::RunOptions;
 
 /// A test scenario containing a server configuration, collector, and services
 pub struct Scenario<S, C> {
     pub(crate) server: S,
     pub(crate) collector: C,
     pub(crate) services: Vec<Service>,
+    pub(crate) options: RunOptions,
 }
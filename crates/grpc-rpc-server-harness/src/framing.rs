@@ -0,0 +1,155 @@
+//! gRPC length-prefixed message framing, for building request bodies and
+//! reading response bodies in tests.
+//!
+//! Each frame is a 1-byte compression flag, a 4-byte big-endian message
+//! length, then the message.
+
+use crate::error::HarnessError;
+
+const HEADER_LEN: usize = 5;
+
+/// Wrap `message` in an uncompressed frame
+///
+/// # Panics
+///
+/// Panics if `message` is longer than `u32::MAX` bytes, the largest length
+/// the frame header can carry.
+pub fn encode_frame(message: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(HEADER_LEN + message.len());
+    push_frame(&mut frame, 0x00, message);
+    frame
+}
+
+/// Get the message of the first frame in `body`.
+///
+/// Bytes after the frame, such as further messages or gRPC-Web trailers, are
+/// ignored. Fails when the body is shorter than the header or the length it
+/// declares, or when the frame is compressed.
+pub fn decode_frame(body: &[u8]) -> Result<&[u8], HarnessError> {
+    let (flag, message, _) = split_frame(body)?;
+    if flag & 0x01 != 0 {
+        return Err(HarnessError::ParseError(
+            "gRPC frame is compressed".to_string(),
+        ));
+    }
+    Ok(message)
+}
+
+/// Split `body` into the flag byte and message of its first frame, and the
+/// bytes following that frame
+pub(crate) fn split_frame(body: &[u8]) -> Result<(u8, &[u8], &[u8]), HarnessError> {
+    let Some((header, rest)) = body.split_first_chunk::<HEADER_LEN>() else {
+        return Err(HarnessError::ParseError(format!(
+            "gRPC frame header needs {} bytes, got {}",
+            HEADER_LEN,
+            body.len()
+        )));
+    };
+
+    let len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+    if rest.len() < len {
+        return Err(HarnessError::ParseError(format!(
+            "gRPC frame declares {} bytes, got {}",
+            len,
+            rest.len()
+        )));
+    }
+    let (message, rest) = rest.split_at(len);
+    Ok((header[0], message, rest))
+}
+
+/// Get the messages of every frame in `body`, e.g. of a server-streaming response.
+///
+/// Fails like [`decode_frame`] when any frame is truncated or compressed.
+pub fn decode_frames(mut body: &[u8]) -> Result<Vec<&[u8]>, HarnessError> {
+    let mut messages = Vec::new();
+    while !body.is_empty() {
+        let message = decode_frame(body)?;
+        body = &body[HEADER_LEN + message.len()..];
+        messages.push(message);
+    }
+    Ok(messages)
+}
+
+/// Append a length-prefixed frame: 1 flag byte + 4 bytes big-endian length
+pub(crate) fn push_frame(buf: &mut Vec<u8>, flag: u8, data: &[u8]) {
+    let len = u32::try_from(data.len()).expect("gRPC message longer than u32::MAX bytes");
+    buf.push(flag);
+    buf.extend_from_slice(&len.to_be_bytes());
+    buf.extend_from_slice(data);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let frame = encode_frame(b"hello");
+        assert_eq!(frame, [0, 0, 0, 0, 5, b'h', b'e', b'l', b'l', b'o']);
+        assert_eq!(decode_frame(&frame).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn test_empty_message() {
+        let frame = encode_frame(&[]);
+        assert_eq!(frame, [0, 0, 0, 0, 0]);
+        assert_eq!(decode_frame(&frame).unwrap(), b"");
+    }
+
+    #[test]
+    fn test_large_message() {
+        // Longer than a u16 length, so every header length byte matters
+        let message = vec![7u8; 70_000];
+        let frame = encode_frame(&message);
+        assert_eq!(&frame[..5], &[0, 0, 0x01, 0x11, 0x70]);
+        assert_eq!(decode_frame(&frame).unwrap(), message.as_slice());
+    }
+
+    #[test]
+    fn test_decode_ignores_trailing_bytes() {
+        let mut body = encode_frame(b"first");
+        body.extend(encode_frame(b"second"));
+        assert_eq!(decode_frame(&body).unwrap(), b"first");
+    }
+
+    #[test]
+    fn test_decode_frames() {
+        let mut body = encode_frame(b"first");
+        body.extend(encode_frame(b""));
+        body.extend(encode_frame(b"third"));
+        assert_eq!(decode_frames(&body).unwrap(), [&b"first"[..], b"", b"third"]);
+        assert!(decode_frames(&[]).unwrap().is_empty());
+
+        body.truncate(body.len() - 1);
+        assert!(decode_frames(&body).is_err());
+    }
+
+    #[test]
+    fn test_decode_rejects_short_body() {
+        assert!(decode_frame(&[]).is_err());
+        assert!(decode_frame(&[0, 0, 0]).is_err());
+
+        // Declares more bytes than follow the header
+        let mut frame = encode_frame(b"hello");
+        frame.truncate(8);
+        assert!(decode_frame(&frame).is_err());
+    }
+
+    #[test]
+    fn test_split_frame() {
+        let mut body = encode_frame(b"first");
+        body[0] = 0x01;
+        body.extend(encode_frame(b"second"));
+        let (flag, message, rest) = split_frame(&body).unwrap();
+        assert_eq!((flag, message), (0x01, &b"first"[..]));
+        assert_eq!(rest, encode_frame(b"second"));
+    }
+
+    #[test]
+    fn test_decode_rejects_compressed_frame() {
+        let mut frame = encode_frame(b"hello");
+        frame[0] = 0x01;
+        assert!(decode_frame(&frame).is_err());
+    }
+}
@@ -47,8 +47,10 @@
 //! ```
 
 mod adapters;
+pub mod assertions;
 pub mod entities;
 pub mod error;
+pub mod framing;
 pub mod use_cases;
 
 pub use error::HarnessError;
@@ -56,49 +58,111 @@ pub use error::HarnessError;
 #[cfg(feature = "tonic")]
 pub use adapters::gateways::Tonic;
 
+#[cfg(feature = "tls")]
+pub use adapters::gateways::{rustls_test_config, TEST_CERT_PEM};
+#[cfg(feature = "tls")]
+pub use tokio_rustls::rustls::ServerConfig as RustlsConfig;
+
 /// Default collector implementation that collects requests into a Vec
-pub struct DefaultCollector {
-    requests: std::sync::Mutex<Vec<entities::CollectedRequest>>,
-}
+pub type DefaultCollector = server_harness_core::DefaultCollector<entities::CollectedRequest>;
 
-impl DefaultCollector {
-    pub fn new() -> Self {
-        Self {
-            requests: std::sync::Mutex::new(Vec::new()),
-        }
-    }
+/// Collector that records requests as JSON, e.g. for snapshot tests
+///
+/// `into_output` returns an array with one object per request. Messages are
+/// strings, or `{"base64": ...}` when not valid UTF-8.
+pub type RecordingCollector = server_harness_core::RecordingCollector<entities::CollectedRequest>;
+
+/// Collector that fails when a request matched no configured handler
+///
+/// `into_output` returns every request as `Ok` when all of them matched, and the
+/// unmatched ones as `Err` otherwise. See [`CollectedRequest::matched`](entities::CollectedRequest::matched).
+pub type StrictCollector = server_harness_core::StrictCollector<entities::CollectedRequest>;
+
+/// Collector storing only the requests a predicate accepts, e.g. to ignore
+/// health checks, and delegating them to an inner collector
+///
+/// Filtered requests are still answered normally.
+pub use server_harness_core::FilteringCollector;
+
+/// Collectors that can be read mid-scenario through a shared `Arc` handle
+pub use server_harness_core::Snapshot;
+
+/// Collector that forwards requests into a channel instead of a Vec
+///
+/// [`ChannelCollector::new`] hands back the receiving half, so requests can be
+/// drained while the scenario runs; memory then only holds the requests not
+/// read yet. The channel ends once the run has finished.
+#[cfg(feature = "tonic")]
+pub struct ChannelCollector {
+    sender: tokio::sync::mpsc::UnboundedSender<entities::CollectedRequest>,
 }
 
-impl Default for DefaultCollector {
-    fn default() -> Self {
-        Self::new()
+#[cfg(feature = "tonic")]
+impl ChannelCollector {
+    /// Create the collector and the receiver yielding its requests
+    pub fn new() -> (
+        Self,
+        tokio::sync::mpsc::UnboundedReceiver<entities::CollectedRequest>,
+    ) {
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        (Self { sender }, receiver)
     }
 }
 
-impl use_cases::ports::Collector for DefaultCollector {
-    type Output = Vec<entities::CollectedRequest>;
+#[cfg(feature = "tonic")]
+impl use_cases::ports::Collector for ChannelCollector {
+    type Output = ();
 
     fn collect(&self, request: entities::CollectedRequest) {
-        if let Ok(mut requests) = self.requests.lock() {
-            requests.push(request);
-        }
+        // Requests are dropped once the caller stops listening
+        let _ = self.sender.send(request);
     }
 
-    fn into_output(self) -> Self::Output {
-        self.requests.into_inner().unwrap_or_default()
-    }
+    fn into_output(self) -> Self::Output {}
 }
 
 /// Prelude module for convenient imports
 pub mod prelude {
+    pub use crate::assertions::{RequestAssertions, RequestLog};
     pub use crate::entities::{
-        CollectedRequest, Handler, Message, Method, RequestContext, Service,
+        CollectedRequest, Handler, HandlerResponse, Message, Method, RequestContext, Service,
     };
     pub use crate::error::HarnessError;
     pub use crate::use_cases::ports::Collector;
     pub use crate::use_cases::ScenarioBuilder;
-    pub use crate::DefaultCollector;
+    pub use crate::{
+        DefaultCollector, FilteringCollector, RecordingCollector, Snapshot, StrictCollector,
+    };
 
     #[cfg(feature = "tonic")]
-    pub use crate::Tonic;
+    pub use crate::{ChannelCollector, Tonic};
+
+    #[cfg(feature = "tls")]
+    pub use crate::{rustls_test_config, RustlsConfig};
+}
+
+#[cfg(all(test, feature = "tonic"))]
+mod tests {
+    use super::*;
+    use crate::use_cases::ports::Collector;
+
+    #[test]
+    fn test_channel_collector_keeps_every_request() {
+        let (collector, mut receiver) = ChannelCollector::new();
+        for i in 0..1000u32 {
+            collector.collect(entities::CollectedRequest::new(
+                "test.Service",
+                "Call",
+                entities::Message::new(i.to_be_bytes().to_vec()),
+            ));
+        }
+
+        collector.into_output();
+        let mut count = 0;
+        while let Ok(request) = receiver.try_recv() {
+            assert_eq!(request.message.data, (count as u32).to_be_bytes());
+            count += 1;
+        }
+        assert_eq!(count, 1000);
+    }
 }
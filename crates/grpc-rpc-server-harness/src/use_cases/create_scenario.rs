@@ -1,8 +1,9 @@
 use std::net::SocketAddr;
+use std::time::Duration;
 
-use crate::entities::{Scenario, Service};
+use crate::entities::{Handler, Scenario, Service};
 use crate::error::HarnessError;
-use crate::use_cases::ports::{Collector, Server};
+use crate::use_cases::ports::{Collector, RunOptions, Server};
 
 /// Builder for creating scenarios with a fluent API
 ///
@@ -43,6 +44,7 @@ pub struct ScenarioBuilder<S, C> {
     server: Option<S>,
     collector: Option<C>,
     services: Vec<Service>,
+    options: RunOptions,
 }
 
 impl ScenarioBuilder<(), ()> {
@@ -52,6 +54,7 @@ impl ScenarioBuilder<(), ()> {
             server: None,
             collector: None,
             services: Vec::new(),
+            options: RunOptions::default(),
         }
     }
 }
@@ -69,6 +72,7 @@ impl<S, C> ScenarioBuilder<S, C> {
             server: Some(server),
             collector: self.collector,
             services: self.services,
+            options: self.options,
         }
     }
 
@@ -78,6 +82,7 @@ impl<S, C> ScenarioBuilder<S, C> {
             server: self.server,
             collector: Some(collector),
             services: self.services,
+            options: self.options,
         }
     }
 
@@ -92,6 +97,57 @@ impl<S, C> ScenarioBuilder<S, C> {
         self.services.extend(services);
         self
     }
+
+    /// Shut down after `n` handled requests rather than once every handler has been called.
+    ///
+    /// Combine with cyclic methods ([`Method::cycle`](crate::entities::Method::cycle))
+    /// for load-style tests.
+    pub fn expected_requests(mut self, n: usize) -> Self {
+        self.options.expected_requests = Some(n);
+        self
+    }
+
+    /// Keep serving in-flight calls for up to `grace` once the scenario completes.
+    ///
+    /// The server stops accepting connections when every method has been called;
+    /// calls still in flight then get `grace` to finish before their connections
+    /// are closed. Without a grace period they keep running in the background.
+    pub fn shutdown_grace(mut self, grace: Duration) -> Self {
+        self.options.shutdown_grace = Some(grace);
+        self
+    }
+
+    /// Fail the run with [`HarnessError::HandlerPanic`] when a dynamic handler panics.
+    ///
+    /// The panicking call is answered with `INTERNAL` and the run returns the
+    /// error right away; calls then count toward shutdown once their handler
+    /// returns. Without this the panic only resets the call's stream.
+    pub fn propagate_panics(mut self) -> Self {
+        self.options.propagate_panics = true;
+        self
+    }
+
+    /// Serve at most `n` connections at once, e.g. to test client behavior when
+    /// its connection pool cannot open more.
+    ///
+    /// Excess connections are delayed rather than reset: the server accepts
+    /// them but does not answer, not even the HTTP/2 handshake, until an earlier
+    /// connection closes. `n` must be at least 1, otherwise execution fails with
+    /// [`HarnessError::ConfigurationError`].
+    pub fn max_connections(mut self, n: usize) -> Self {
+        self.options.max_connections = Some(n);
+        self
+    }
+
+    /// Collect calls to unregistered methods and answer them with `handler`.
+    ///
+    /// Collected calls keep their real service and method names, so tests can
+    /// assert that the client called nothing unexpected. Default handler calls
+    /// only count toward shutdown with [`expected_requests`](Self::expected_requests).
+    pub fn default_handler(mut self, handler: Handler) -> Self {
+        self.options.default_handler = Some(handler);
+        self
+    }
 }
 
 impl<S: Server + 'static, C: Collector + 'static> ScenarioBuilder<S, C> {
@@ -101,6 +157,7 @@ impl<S: Server + 'static, C: Collector + 'static> ScenarioBuilder<S, C> {
             server: self.server.expect("Server must be set before building"),
             collector: self.collector.expect("Collector must be set before building"),
             services: self.services,
+            options: self.options,
         }
     }
 
@@ -119,7 +176,12 @@ impl<S: Server + 'static, C: Collector + 'static> Scenario<S, C> {
     /// Returns the collector's output type.
     pub async fn execute(self) -> Result<C::Output, HarnessError> {
         self.server
-            .run(self.services, self.collector, None::<fn(SocketAddr)>)
+            .run(
+                self.services,
+                self.collector,
+                self.options,
+                None::<fn(SocketAddr)>,
+            )
             .await
     }
 }
@@ -2,4 +2,4 @@ mod collector;
 mod server;
 
 pub use collector::Collector;
-pub use server::Server;
+pub use server::{RunOptions, Server};
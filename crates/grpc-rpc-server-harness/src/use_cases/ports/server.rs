@@ -1,10 +1,32 @@
 use async_trait::async_trait;
 use std::net::SocketAddr;
+use std::time::Duration;
 
 use super::Collector;
-use crate::entities::Service;
+use crate::entities::{Handler, Service};
 use crate::error::HarnessError;
 
+/// Options controlling how a server runs a scenario
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    /// If set, the server shuts down after this many requests have been handled
+    /// instead of once every handler has been called.
+    pub expected_requests: Option<usize>,
+    /// If set, calls to methods without a route are collected and answered by this
+    /// handler instead of failing with `UNIMPLEMENTED`.
+    pub default_handler: Option<Handler>,
+    /// If set, connections still in flight when the scenario completes are
+    /// drained for at most this long before the server stops.
+    pub shutdown_grace: Option<Duration>,
+    /// If set, a panicking handler fails the run with `HarnessError::HandlerPanic`
+    /// and its call is answered with `INTERNAL`, instead of resetting the stream.
+    pub propagate_panics: bool,
+    /// If set, at most this many connections are served at once; further
+    /// connections are accepted but wait, unanswered, until one closes. Running
+    /// fails with `HarnessError::ConfigurationError` if it is zero.
+    pub max_connections: Option<usize>,
+}
+
 /// Trait for gRPC server implementations
 #[async_trait]
 pub trait Server: Send + Sync + Clone {
@@ -19,6 +41,7 @@ pub trait Server: Send + Sync + Clone {
         &self,
         services: Vec<Service>,
         collector: C,
+        options: RunOptions,
         on_ready: Option<F>,
     ) -> Result<C::Output, HarnessError>
     where
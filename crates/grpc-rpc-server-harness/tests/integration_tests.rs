@@ -1,5 +1,6 @@
 //! Integration tests for grpc-rpc-server-harness
 
+use grpc_rpc_server_harness::framing::{decode_frame, decode_frames, encode_frame};
 use grpc_rpc_server_harness::prelude::*;
 use http_body_util::{BodyExt, Full};
 use hyper::body::Bytes;
@@ -9,24 +10,6 @@ use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::sync::Notify;
 
-/// Helper to create a gRPC request body with length prefix
-fn grpc_request_body(data: &[u8]) -> Vec<u8> {
-    let mut body = Vec::with_capacity(5 + data.len());
-    body.push(0); // No compression
-    body.extend_from_slice(&(data.len() as u32).to_be_bytes());
-    body.extend_from_slice(data);
-    body
-}
-
-/// Helper to parse gRPC response body (skip 5-byte header)
-fn parse_grpc_response(data: &[u8]) -> &[u8] {
-    if data.len() > 5 {
-        &data[5..]
-    } else {
-        &[]
-    }
-}
-
 #[tokio::test]
 async fn test_single_service_single_method() {
     let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
@@ -51,14 +34,14 @@ async fn test_single_service_single_method() {
             .method("POST")
             .uri(format!("http://{}/test.TestService/GetData", server_addr))
             .header("content-type", "application/grpc")
-            .body(Full::new(Bytes::from(grpc_request_body(&[10, 20, 30]))))
+            .body(Full::new(Bytes::from(encode_frame(&[10, 20, 30]))))
             .unwrap();
 
         let response = client.request(request).await.unwrap();
         assert_eq!(response.status(), 200);
 
         let body = response.into_body().collect().await.unwrap().to_bytes();
-        let response_data = parse_grpc_response(&body);
+        let response_data = decode_frame(&body).unwrap();
         assert_eq!(response_data, &[1, 2, 3, 4]);
     });
 
@@ -73,12 +56,13 @@ async fn test_single_service_single_method() {
         .build();
 
     // Use Server::run directly with on_ready callback
-    use grpc_rpc_server_harness::use_cases::ports::Server;
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
     let result = Tonic::bind(addr)
         .run(
             vec![Service::new("test.TestService")
                 .with_method(Method::new("GetData").with_handler(Handler::from_bytes(vec![1, 2, 3, 4])))],
             DefaultCollector::new(),
+            RunOptions::default(),
             Some(move |actual_addr: SocketAddr| {
                 *addr_holder.lock().unwrap() = Some(actual_addr);
                 addr_notify.notify_one();
@@ -117,33 +101,34 @@ async fn test_multiple_methods() {
             .method("POST")
             .uri(format!("http://{}/test.UserService/GetUser", server_addr))
             .header("content-type", "application/grpc")
-            .body(Full::new(Bytes::from(grpc_request_body(&[]))))
+            .body(Full::new(Bytes::from(encode_frame(&[]))))
             .unwrap();
 
         let response1 = client.request(request1).await.unwrap();
         let body1 = response1.into_body().collect().await.unwrap().to_bytes();
-        assert_eq!(parse_grpc_response(&body1), &[1, 1, 1]);
+        assert_eq!(decode_frame(&body1).unwrap(), &[1, 1, 1]);
 
         // Call CreateUser
         let request2 = hyper::Request::builder()
             .method("POST")
             .uri(format!("http://{}/test.UserService/CreateUser", server_addr))
             .header("content-type", "application/grpc")
-            .body(Full::new(Bytes::from(grpc_request_body(&[]))))
+            .body(Full::new(Bytes::from(encode_frame(&[]))))
             .unwrap();
 
         let response2 = client.request(request2).await.unwrap();
         let body2 = response2.into_body().collect().await.unwrap().to_bytes();
-        assert_eq!(parse_grpc_response(&body2), &[2, 2, 2]);
+        assert_eq!(decode_frame(&body2).unwrap(), &[2, 2, 2]);
     });
 
-    use grpc_rpc_server_harness::use_cases::ports::Server;
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
     let result = Tonic::bind(addr)
         .run(
             vec![Service::new("test.UserService")
                 .with_method(Method::new("GetUser").with_handler(Handler::from_bytes(vec![1, 1, 1])))
                 .with_method(Method::new("CreateUser").with_handler(Handler::from_bytes(vec![2, 2, 2])))],
             DefaultCollector::new(),
+            RunOptions::default(),
             Some(move |actual_addr: SocketAddr| {
                 *addr_holder.lock().unwrap() = Some(actual_addr);
                 addr_notify.notify_one();
@@ -178,16 +163,16 @@ async fn test_sequential_handlers() {
                 .method("POST")
                 .uri(format!("http://{}/test.Service/Call", server_addr))
                 .header("content-type", "application/grpc")
-                .body(Full::new(Bytes::from(grpc_request_body(&[]))))
+                .body(Full::new(Bytes::from(encode_frame(&[]))))
                 .unwrap();
 
             let response = client.request(request).await.unwrap();
             let body = response.into_body().collect().await.unwrap().to_bytes();
-            assert_eq!(parse_grpc_response(&body), &[expected]);
+            assert_eq!(decode_frame(&body).unwrap(), &[expected]);
         }
     });
 
-    use grpc_rpc_server_harness::use_cases::ports::Server;
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
     let result = Tonic::bind(addr)
         .run(
             vec![Service::new("test.Service")
@@ -199,6 +184,7 @@ async fn test_sequential_handlers() {
                         .with_handler(Handler::from_bytes(vec![3])),
                 )],
             DefaultCollector::new(),
+            RunOptions::default(),
             Some(move |actual_addr: SocketAddr| {
                 *addr_holder.lock().unwrap() = Some(actual_addr);
                 addr_notify.notify_one();
@@ -211,6 +197,113 @@ async fn test_sequential_handlers() {
     assert_eq!(result.len(), 4);
 }
 
+#[tokio::test]
+async fn test_collected_requests_are_sequenced() {
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
+
+    let (ready_tx, ready_rx) = tokio::sync::oneshot::channel::<SocketAddr>();
+
+    let client_task = tokio::spawn(async move {
+        let server_addr = ready_rx.await.unwrap();
+
+        let client = Client::builder(TokioExecutor::new())
+            .http2_only(true)
+            .build_http();
+
+        for data in [10u8, 20, 30] {
+            let request = hyper::Request::builder()
+                .method("POST")
+                .uri(format!("http://{}/test.Service/Call", server_addr))
+                .header("content-type", "application/grpc")
+                .body(Full::new(Bytes::from(encode_frame(&[data]))))
+                .unwrap();
+            let response = client.request(request).await.unwrap();
+            response.into_body().collect().await.unwrap();
+        }
+    });
+
+    let collected = Tonic::bind(([127, 0, 0, 1], 0))
+        .run(
+            vec![Service::new("test.Service").with_method(
+                Method::new("Call")
+                    .with_handler(Handler::from_bytes(vec![1]))
+                    .with_handler(Handler::from_bytes(vec![2]))
+                    .with_handler(Handler::from_bytes(vec![3])),
+            )],
+            DefaultCollector::new(),
+            RunOptions::default(),
+            Some(move |addr| {
+                ready_tx.send(addr).unwrap();
+            }),
+        )
+        .await
+        .unwrap();
+
+    client_task.await.unwrap();
+    let order: Vec<(u64, u8)> = collected
+        .iter()
+        .map(|r| (r.sequence, r.message.data[0]))
+        .collect();
+    assert_eq!(order, [(0, 10), (1, 20), (2, 30)]);
+    assert!(collected
+        .windows(2)
+        .all(|pair| pair[0].received_at <= pair[1].received_at));
+}
+
+#[tokio::test]
+async fn test_default_handler_collects_unregistered_methods() {
+    let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let addr_notify = Arc::new(Notify::new());
+    let addr_holder = Arc::new(std::sync::Mutex::new(None::<SocketAddr>));
+
+    let addr_notify_clone = addr_notify.clone();
+    let addr_holder_clone = addr_holder.clone();
+
+    let client_task = tokio::spawn(async move {
+        addr_notify_clone.notified().await;
+        let server_addr = addr_holder_clone.lock().unwrap().unwrap();
+
+        let client = Client::builder(TokioExecutor::new())
+            .http2_only(true)
+            .build_http();
+
+        for path in ["test.Other/Unexpected", "test.Service/Call"] {
+            let request = hyper::Request::builder()
+                .method("POST")
+                .uri(format!("http://{}/{}", server_addr, path))
+                .header("content-type", "application/grpc")
+                .body(Full::new(Bytes::from(encode_frame(&[1]))))
+                .unwrap();
+            let response = client.request(request).await.unwrap();
+            assert_eq!(response.headers().get("grpc-status").unwrap(), "0");
+        }
+    });
+
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
+    let result = Tonic::bind(addr)
+        .run(
+            vec![Service::new("test.Service")
+                .with_method(Method::new("Call").with_handler(Handler::from_bytes(vec![1])))],
+            DefaultCollector::new(),
+            RunOptions {
+                default_handler: Some(Handler::from_bytes(vec![])),
+                ..Default::default()
+            },
+            Some(move |actual_addr: SocketAddr| {
+                *addr_holder.lock().unwrap() = Some(actual_addr);
+                addr_notify.notify_one();
+            }),
+        )
+        .await
+        .unwrap();
+
+    client_task.await.unwrap();
+    assert_eq!(result.len(), 2);
+    assert_eq!(result[0].service, "test.Other");
+    assert_eq!(result[0].method, "Unexpected");
+    assert_eq!(result[1].method, "Call");
+}
+
 #[tokio::test]
 async fn test_unimplemented_method() {
     let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
@@ -233,7 +326,7 @@ async fn test_unimplemented_method() {
             .method("POST")
             .uri(format!("http://{}/test.Service/DoesNotExist", server_addr))
             .header("content-type", "application/grpc")
-            .body(Full::new(Bytes::from(grpc_request_body(&[]))))
+            .body(Full::new(Bytes::from(encode_frame(&[]))))
             .unwrap();
 
         let response = client.request(request).await.unwrap();
@@ -250,17 +343,18 @@ async fn test_unimplemented_method() {
             .method("POST")
             .uri(format!("http://{}/test.Service/Exists", server_addr))
             .header("content-type", "application/grpc")
-            .body(Full::new(Bytes::from(grpc_request_body(&[]))))
+            .body(Full::new(Bytes::from(encode_frame(&[]))))
             .unwrap();
         let _ = client.request(request2).await.unwrap();
     });
 
-    use grpc_rpc_server_harness::use_cases::ports::Server;
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
     let _ = Tonic::bind(addr)
         .run(
             vec![Service::new("test.Service")
                 .with_method(Method::new("Exists").with_handler(Handler::from_bytes(vec![1])))],
             DefaultCollector::new(),
+            RunOptions::default(),
             Some(move |actual_addr: SocketAddr| {
                 *addr_holder.lock().unwrap() = Some(actual_addr);
                 addr_notify.notify_one();
@@ -293,18 +387,18 @@ async fn test_dynamic_handler_echo() {
             .method("POST")
             .uri(format!("http://{}/test.EchoService/Echo", server_addr))
             .header("content-type", "application/grpc")
-            .body(Full::new(Bytes::from(grpc_request_body(&[1, 2, 3]))))
+            .body(Full::new(Bytes::from(encode_frame(&[1, 2, 3]))))
             .unwrap();
 
         let response = client.request(request).await.unwrap();
         let body = response.into_body().collect().await.unwrap().to_bytes();
-        let response_data = parse_grpc_response(&body);
+        let response_data = decode_frame(&body).unwrap();
 
         // Should be prefix 0xEE followed by input [1, 2, 3]
         assert_eq!(response_data, &[0xEE, 1, 2, 3]);
     });
 
-    use grpc_rpc_server_harness::use_cases::ports::Server;
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
     let _ = Tonic::bind(addr)
         .run(
             vec![Service::new("test.EchoService").with_method(
@@ -315,6 +409,7 @@ async fn test_dynamic_handler_echo() {
                 })),
             )],
             DefaultCollector::new(),
+            RunOptions::default(),
             Some(move |actual_addr: SocketAddr| {
                 *addr_holder.lock().unwrap() = Some(actual_addr);
                 addr_notify.notify_one();
@@ -326,6 +421,136 @@ async fn test_dynamic_handler_echo() {
     client_task.await.unwrap();
 }
 
+#[tokio::test]
+async fn test_async_dynamic_handler_awaits_before_responding() {
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
+
+    let (ready_tx, ready_rx) = tokio::sync::oneshot::channel::<SocketAddr>();
+
+    let client_task = tokio::spawn(async move {
+        let server_addr = ready_rx.await.unwrap();
+
+        let client = Client::builder(TokioExecutor::new())
+            .http2_only(true)
+            .build_http();
+
+        let request = hyper::Request::builder()
+            .method("POST")
+            .uri(format!("http://{}/test.SumService/Sum", server_addr))
+            .header("content-type", "application/grpc")
+            .body(Full::new(Bytes::from(encode_frame(&[1, 2, 3]))))
+            .unwrap();
+
+        let started = std::time::Instant::now();
+        let response = client.request(request).await.unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+
+        assert_eq!(decode_frame(&body).unwrap(), &[6]);
+        assert!(started.elapsed() >= std::time::Duration::from_millis(20));
+    });
+
+    Tonic::bind(([127, 0, 0, 1], 0))
+        .run(
+            vec![Service::new("test.SumService").with_method(
+                Method::new("Sum").with_handler(Handler::dynamic_async(|ctx| {
+                    let sum: u8 = ctx.message.data.iter().sum();
+                    async move {
+                        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                        Message::new(vec![sum])
+                    }
+                })),
+            )],
+            DefaultCollector::new(),
+            RunOptions::default(),
+            Some(move |addr| {
+                ready_tx.send(addr).unwrap();
+            }),
+        )
+        .await
+        .unwrap();
+
+    client_task.await.unwrap();
+}
+
+/// Run a scenario whose only method answers after `delay`, returning the
+/// client's result and how long the server ran
+async fn run_slow_call(
+    delay: std::time::Duration,
+    shutdown_grace: std::time::Duration,
+) -> (Result<Bytes, String>, std::time::Duration) {
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
+
+    let (ready_tx, ready_rx) = tokio::sync::oneshot::channel::<SocketAddr>();
+
+    let client_task = tokio::spawn(async move {
+        let server_addr = ready_rx.await.unwrap();
+
+        let client = Client::builder(TokioExecutor::new())
+            .http2_only(true)
+            .build_http();
+
+        let request = hyper::Request::builder()
+            .method("POST")
+            .uri(format!("http://{}/test.ReportService/Build", server_addr))
+            .header("content-type", "application/grpc")
+            .body(Full::new(Bytes::from(encode_frame(&[1]))))
+            .unwrap();
+
+        let response = client.request(request).await.map_err(|e| e.to_string())?;
+        let body = response.into_body().collect().await.map_err(|e| e.to_string())?;
+        Ok(body.to_bytes())
+    });
+
+    let started = std::time::Instant::now();
+    Tonic::bind(([127, 0, 0, 1], 0))
+        .run(
+            vec![Service::new("test.ReportService").with_method(
+                Method::new("Build").with_handler(Handler::dynamic_async(move |_| async move {
+                    tokio::time::sleep(delay).await;
+                    Message::new(vec![42])
+                })),
+            )],
+            DefaultCollector::new(),
+            RunOptions {
+                shutdown_grace: Some(shutdown_grace),
+                ..Default::default()
+            },
+            Some(move |addr| {
+                ready_tx.send(addr).unwrap();
+            }),
+        )
+        .await
+        .unwrap();
+    let elapsed = started.elapsed();
+
+    (client_task.await.unwrap(), elapsed)
+}
+
+#[tokio::test]
+async fn test_shutdown_grace_lets_slow_final_response_complete() {
+    let (result, elapsed) = run_slow_call(
+        std::time::Duration::from_millis(200),
+        std::time::Duration::from_secs(5),
+    )
+    .await;
+
+    assert_eq!(decode_frame(&result.unwrap()).unwrap(), &[42]);
+    // The server stops once the call is answered, not at the end of the grace period
+    assert!(elapsed < std::time::Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn test_shutdown_grace_closes_connections_when_exceeded() {
+    let (result, elapsed) = run_slow_call(
+        std::time::Duration::from_secs(10),
+        std::time::Duration::from_millis(100),
+    )
+    .await;
+
+    assert!(result.is_err(), "call should be cut off, got {:?}", result);
+    assert!(elapsed < std::time::Duration::from_secs(10));
+}
+
 #[tokio::test]
 async fn test_dynamic_handler_based_on_method_name() {
     let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
@@ -348,27 +573,27 @@ async fn test_dynamic_handler_based_on_method_name() {
             .method("POST")
             .uri(format!("http://{}/test.MultiService/GetData", server_addr))
             .header("content-type", "application/grpc")
-            .body(Full::new(Bytes::from(grpc_request_body(&[]))))
+            .body(Full::new(Bytes::from(encode_frame(&[]))))
             .unwrap();
 
         let response1 = client.request(request1).await.unwrap();
         let body1 = response1.into_body().collect().await.unwrap().to_bytes();
-        assert_eq!(parse_grpc_response(&body1), &[0x01, 0x01]);
+        assert_eq!(decode_frame(&body1).unwrap(), &[0x01, 0x01]);
 
         // Test SetData with 5 bytes of input
         let request2 = hyper::Request::builder()
             .method("POST")
             .uri(format!("http://{}/test.MultiService/SetData", server_addr))
             .header("content-type", "application/grpc")
-            .body(Full::new(Bytes::from(grpc_request_body(&[1, 2, 3, 4, 5]))))
+            .body(Full::new(Bytes::from(encode_frame(&[1, 2, 3, 4, 5]))))
             .unwrap();
 
         let response2 = client.request(request2).await.unwrap();
         let body2 = response2.into_body().collect().await.unwrap().to_bytes();
-        assert_eq!(parse_grpc_response(&body2), &[5]); // Length of input
+        assert_eq!(decode_frame(&body2).unwrap(), &[5]); // Length of input
     });
 
-    use grpc_rpc_server_harness::use_cases::ports::Server;
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
     let _ = Tonic::bind(addr)
         .run(
             vec![Service::new("test.MultiService")
@@ -388,6 +613,7 @@ async fn test_dynamic_handler_based_on_method_name() {
                     })),
                 )],
             DefaultCollector::new(),
+            RunOptions::default(),
             Some(move |actual_addr: SocketAddr| {
                 *addr_holder.lock().unwrap() = Some(actual_addr);
                 addr_notify.notify_one();
@@ -422,7 +648,7 @@ async fn test_auto_shutdown_after_all_handlers() {
             .method("POST")
             .uri(format!("http://{}/test.Service1/Call1", server_addr))
             .header("content-type", "application/grpc")
-            .body(Full::new(Bytes::from(grpc_request_body(&[]))))
+            .body(Full::new(Bytes::from(encode_frame(&[]))))
             .unwrap();
         let _ = client.request(request1).await.unwrap();
 
@@ -431,12 +657,12 @@ async fn test_auto_shutdown_after_all_handlers() {
             .method("POST")
             .uri(format!("http://{}/test.Service2/Call2", server_addr))
             .header("content-type", "application/grpc")
-            .body(Full::new(Bytes::from(grpc_request_body(&[]))))
+            .body(Full::new(Bytes::from(encode_frame(&[]))))
             .unwrap();
         let _ = client.request(request2).await.unwrap();
     });
 
-    use grpc_rpc_server_harness::use_cases::ports::Server;
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
     let result = tokio::time::timeout(
         std::time::Duration::from_secs(2),
         Tonic::bind(addr).run(
@@ -447,6 +673,7 @@ async fn test_auto_shutdown_after_all_handlers() {
                     .with_method(Method::new("Call2").with_handler(Handler::from_bytes(vec![2]))),
             ],
             DefaultCollector::new(),
+            RunOptions::default(),
             Some(move |actual_addr: SocketAddr| {
                 *addr_holder.lock().unwrap() = Some(actual_addr);
                 addr_notify.notify_one();
@@ -465,4 +692,1211 @@ async fn test_auto_shutdown_after_all_handlers() {
     // Verify collected requests
     let collected = result.unwrap().unwrap();
     assert_eq!(collected.len(), 2);
-}
\ No newline at end of file
+}
+#[tokio::test]
+async fn test_method_without_handlers_counts_once_toward_shutdown() {
+    let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let addr_notify = Arc::new(Notify::new());
+    let addr_holder = Arc::new(std::sync::Mutex::new(None::<SocketAddr>));
+
+    let addr_notify_clone = addr_notify.clone();
+    let addr_holder_clone = addr_holder.clone();
+
+    let client_task = tokio::spawn(async move {
+        addr_notify_clone.notified().await;
+        let server_addr = addr_holder_clone.lock().unwrap().unwrap();
+
+        let client = Client::builder(TokioExecutor::new())
+            .http2_only(true)
+            .build_http();
+
+        for path in ["test.Service/Empty", "test.Service/Call"] {
+            let request = hyper::Request::builder()
+                .method("POST")
+                .uri(format!("http://{}/{}", server_addr, path))
+                .header("content-type", "application/grpc")
+                .body(Full::new(Bytes::from(encode_frame(&[]))))
+                .unwrap();
+            let response = client.request(request).await.unwrap();
+            assert_eq!(response.status(), 200);
+        }
+    });
+
+    // A handler-less method must be called once, then no longer holds the server open
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
+    let result = tokio::time::timeout(
+        std::time::Duration::from_secs(2),
+        Tonic::bind(addr).run(
+            vec![Service::new("test.Service")
+                .with_method(Method::new("Empty"))
+                .with_method(Method::new("Call").with_handler(Handler::from_bytes(vec![1])))],
+            DefaultCollector::new(),
+            RunOptions::default(),
+            Some(move |actual_addr: SocketAddr| {
+                *addr_holder.lock().unwrap() = Some(actual_addr);
+                addr_notify.notify_one();
+            }),
+        ),
+    )
+    .await;
+
+    assert!(
+        result.is_ok(),
+        "Server should have shut down once every method was called"
+    );
+
+    client_task.await.unwrap();
+
+    let collected = result.unwrap().unwrap();
+    assert_eq!(collected.len(), 2);
+    assert_eq!(collected[0].method, "Empty");
+}
+
+#[tokio::test]
+async fn test_grpc_web_text_framing() {
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+
+    let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let addr_notify = Arc::new(Notify::new());
+    let addr_holder = Arc::new(std::sync::Mutex::new(None::<SocketAddr>));
+
+    let addr_notify_clone = addr_notify.clone();
+    let addr_holder_clone = addr_holder.clone();
+
+    let client_task = tokio::spawn(async move {
+        addr_notify_clone.notified().await;
+        let server_addr = addr_holder_clone.lock().unwrap().unwrap();
+
+        let client = Client::builder(TokioExecutor::new())
+            .http2_only(true)
+            .build_http();
+
+        let request = hyper::Request::builder()
+            .method("POST")
+            .uri(format!("http://{}/test.WebService/Call", server_addr))
+            .header("content-type", "application/grpc-web-text")
+            .body(Full::new(Bytes::from(BASE64.encode(encode_frame(&[7, 8, 9])))))
+            .unwrap();
+
+        let response = client.request(request).await.unwrap();
+        assert_eq!(
+            response.headers().get("content-type").unwrap(),
+            "application/grpc-web-text"
+        );
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let decoded = BASE64.decode(&body).unwrap();
+
+        // Data frame followed by a trailers frame
+        assert_eq!(&decoded[..9], &[0, 0, 0, 0, 4, 1, 2, 3, 4]);
+        assert_eq!(decoded[9], 0x80);
+        let trailers = std::str::from_utf8(&decoded[14..]).unwrap();
+        assert!(trailers.contains("grpc-status:0"));
+    });
+
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
+    let result = Tonic::bind(addr)
+        .run(
+            vec![Service::new("test.WebService")
+                .with_method(Method::new("Call").with_handler(Handler::from_bytes(vec![1, 2, 3, 4])))],
+            DefaultCollector::new(),
+            RunOptions::default(),
+            Some(move |actual_addr: SocketAddr| {
+                *addr_holder.lock().unwrap() = Some(actual_addr);
+                addr_notify.notify_one();
+            }),
+        )
+        .await
+        .unwrap();
+
+    client_task.await.unwrap();
+
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].message.data, vec![7, 8, 9]);
+}
+
+#[tokio::test]
+async fn test_error_status_handler() {
+    let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let addr_notify = Arc::new(Notify::new());
+    let addr_holder = Arc::new(std::sync::Mutex::new(None::<SocketAddr>));
+
+    let addr_notify_clone = addr_notify.clone();
+    let addr_holder_clone = addr_holder.clone();
+
+    let client_task = tokio::spawn(async move {
+        addr_notify_clone.notified().await;
+        let server_addr = addr_holder_clone.lock().unwrap().unwrap();
+
+        let client = Client::builder(TokioExecutor::new())
+            .http2_only(true)
+            .build_http();
+
+        let request = hyper::Request::builder()
+            .method("POST")
+            .uri(format!("http://{}/test.QuotaService/Consume", server_addr))
+            .header("content-type", "application/grpc")
+            .body(Full::new(Bytes::from(encode_frame(&[]))))
+            .unwrap();
+
+        let response = client.request(request).await.unwrap();
+        let headers = response.headers().clone();
+        assert_eq!(headers.get("grpc-status").unwrap(), "8"); // RESOURCE_EXHAUSTED
+        assert_eq!(headers.get("grpc-message").unwrap(), "quota exceeded");
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(body.is_empty());
+    });
+
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
+    let result = Tonic::bind(addr)
+        .run(
+            vec![Service::new("test.QuotaService")
+                .with_method(Method::new("Consume").with_handler(Handler::error(8, "quota exceeded")))],
+            DefaultCollector::new(),
+            RunOptions::default(),
+            Some(move |actual_addr: SocketAddr| {
+                *addr_holder.lock().unwrap() = Some(actual_addr);
+                addr_notify.notify_one();
+            }),
+        )
+        .await
+        .unwrap();
+
+    client_task.await.unwrap();
+    assert_eq!(result.len(), 1);
+}
+
+#[tokio::test]
+async fn test_trailers_only_handler() {
+    use hyper::body::Body;
+
+    let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let addr_notify = Arc::new(Notify::new());
+    let addr_holder = Arc::new(std::sync::Mutex::new(None::<SocketAddr>));
+
+    let addr_notify_clone = addr_notify.clone();
+    let addr_holder_clone = addr_holder.clone();
+
+    let client_task = tokio::spawn(async move {
+        addr_notify_clone.notified().await;
+        let server_addr = addr_holder_clone.lock().unwrap().unwrap();
+
+        let client = Client::builder(TokioExecutor::new())
+            .http2_only(true)
+            .build_http();
+
+        for content_type in ["application/grpc", "application/grpc-web"] {
+            let request = hyper::Request::builder()
+                .method("POST")
+                .uri(format!("http://{}/test.AuthService/Login", server_addr))
+                .header("content-type", content_type)
+                .body(Full::new(Bytes::from(encode_frame(&[]))))
+                .unwrap();
+
+            let response = client.request(request).await.unwrap();
+            let headers = response.headers().clone();
+            assert_eq!(headers.get("content-type").unwrap(), content_type);
+            assert_eq!(headers.get("grpc-status").unwrap(), "7"); // PERMISSION_DENIED
+            assert_eq!(headers.get("grpc-message").unwrap(), "denied");
+
+            // The headers frame ends the stream: no DATA frame and no trailers
+            assert!(response.body().is_end_stream());
+            let body = response.into_body().collect().await.unwrap();
+            assert!(body.trailers().is_none());
+            assert!(body.to_bytes().is_empty());
+        }
+    });
+
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
+    let result = Tonic::bind(addr)
+        .run(
+            vec![Service::new("test.AuthService").with_method(
+                Method::new("Login")
+                    .with_handler(Handler::trailers_only(7, "denied"))
+                    .with_handler(Handler::trailers_only(7, "denied")),
+            )],
+            DefaultCollector::new(),
+            RunOptions::default(),
+            Some(move |actual_addr: SocketAddr| {
+                *addr_holder.lock().unwrap() = Some(actual_addr);
+                addr_notify.notify_one();
+            }),
+        )
+        .await
+        .unwrap();
+
+    client_task.await.unwrap();
+    assert_eq!(result.len(), 2);
+}
+
+#[tokio::test]
+async fn test_transport_failure_handlers() {
+    let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let addr_notify = Arc::new(Notify::new());
+    let addr_holder = Arc::new(std::sync::Mutex::new(None::<SocketAddr>));
+
+    let addr_notify_clone = addr_notify.clone();
+    let addr_holder_clone = addr_holder.clone();
+
+    let client_task = tokio::spawn(async move {
+        addr_notify_clone.notified().await;
+        let server_addr = addr_holder_clone.lock().unwrap().unwrap();
+
+        let client = Client::builder(TokioExecutor::new())
+            .http2_only(true)
+            .build_http();
+        let request = |method: &str| {
+            hyper::Request::builder()
+                .method("POST")
+                .uri(format!("http://{}/test.FlakyService/{}", server_addr, method))
+                .header("content-type", "application/grpc")
+                .body(Full::new(Bytes::from(encode_frame(&[]))))
+                .unwrap()
+        };
+
+        // The stream is reset before any response is sent
+        let result = client.request(request("Reset")).await;
+        assert!(result.is_err(), "expected a stream error, got {:?}", result);
+
+        let response = client.request(request("Corrupt")).await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert!(response.headers().get("grpc-status").is_none());
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(decode_frame(&body).is_err());
+    });
+
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
+    let result = Tonic::bind(addr)
+        .run(
+            vec![Service::new("test.FlakyService")
+                .with_method(Method::new("Reset").with_handler(Handler::reset_stream()))
+                .with_method(Method::new("Corrupt").with_handler(Handler::malformed()))],
+            DefaultCollector::new(),
+            RunOptions::default(),
+            Some(move |actual_addr: SocketAddr| {
+                *addr_holder.lock().unwrap() = Some(actual_addr);
+                addr_notify.notify_one();
+            }),
+        )
+        .await
+        .unwrap();
+
+    client_task.await.unwrap();
+
+    // Both failures count towards shutdown and are collected
+    assert_eq!(result.len(), 2);
+    assert_eq!(result[0].method, "Reset");
+    assert_eq!(result[1].method, "Corrupt");
+}
+
+#[tokio::test]
+async fn test_server_streaming_handler() {
+    let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let addr_notify = Arc::new(Notify::new());
+    let addr_holder = Arc::new(std::sync::Mutex::new(None::<SocketAddr>));
+
+    let addr_notify_clone = addr_notify.clone();
+    let addr_holder_clone = addr_holder.clone();
+
+    let client_task = tokio::spawn(async move {
+        addr_notify_clone.notified().await;
+        let server_addr = addr_holder_clone.lock().unwrap().unwrap();
+
+        let client = Client::builder(TokioExecutor::new())
+            .http2_only(true)
+            .build_http();
+
+        let request = hyper::Request::builder()
+            .method("POST")
+            .uri(format!("http://{}/updates.UpdateService/Subscribe", server_addr))
+            .header("content-type", "application/grpc")
+            .body(Full::new(Bytes::from(encode_frame(&[]))))
+            .unwrap();
+
+        let response = client.request(request).await.unwrap();
+        assert_eq!(response.headers()["grpc-status"], "0");
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(decode_frames(&body).unwrap(), [&[1u8][..], &[2], &[3]]);
+    });
+
+    // The three messages count as three handler calls, so this single call
+    // completes the scenario
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
+    let result = Tonic::bind(addr)
+        .run(
+            vec![Service::new("updates.UpdateService").with_method(
+                Method::new("Subscribe").with_handler(Handler::stream([
+                    Message::new(vec![1]),
+                    Message::new(vec![2]),
+                    Message::new(vec![3]),
+                ])),
+            )],
+            DefaultCollector::new(),
+            RunOptions::default(),
+            Some(move |actual_addr: SocketAddr| {
+                *addr_holder.lock().unwrap() = Some(actual_addr);
+                addr_notify.notify_one();
+            }),
+        )
+        .await
+        .unwrap();
+
+    client_task.await.unwrap();
+
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].method, "Subscribe");
+}
+
+#[tokio::test]
+async fn test_gzip_compressed_messages() {
+    use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+    use std::io::{Read, Write};
+
+    let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let addr_notify = Arc::new(Notify::new());
+    let addr_holder = Arc::new(std::sync::Mutex::new(None::<SocketAddr>));
+
+    let addr_notify_clone = addr_notify.clone();
+    let addr_holder_clone = addr_holder.clone();
+
+    let client_task = tokio::spawn(async move {
+        addr_notify_clone.notified().await;
+        let server_addr = addr_holder_clone.lock().unwrap().unwrap();
+
+        let client = Client::builder(TokioExecutor::new())
+            .http2_only(true)
+            .build_http();
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"compressed request").unwrap();
+        let mut body = encode_frame(&encoder.finish().unwrap());
+        body[0] = 1; // Compressed flag
+
+        let request = hyper::Request::builder()
+            .method("POST")
+            .uri(format!("http://{}/test.ZipService/Echo", server_addr))
+            .header("content-type", "application/grpc")
+            .header("grpc-encoding", "gzip")
+            .header("grpc-accept-encoding", "gzip")
+            .body(Full::new(Bytes::from(body)))
+            .unwrap();
+
+        let response = client.request(request).await.unwrap();
+        assert_eq!(response.headers().get("grpc-status").unwrap(), "0");
+        assert_eq!(response.headers().get("grpc-encoding").unwrap(), "gzip");
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body[0], 1);
+        let mut decoded = Vec::new();
+        GzDecoder::new(&body[5..])
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, b"compressed request");
+    });
+
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
+    let result = Tonic::bind(addr)
+        .run(
+            vec![Service::new("test.ZipService").with_method(
+                Method::new("Echo").with_handler(Handler::dynamic(|ctx: &RequestContext| {
+                    ctx.message.clone()
+                })),
+            )],
+            DefaultCollector::new(),
+            RunOptions::default(),
+            Some(move |actual_addr: SocketAddr| {
+                *addr_holder.lock().unwrap() = Some(actual_addr);
+                addr_notify.notify_one();
+            }),
+        )
+        .await
+        .unwrap();
+
+    client_task.await.unwrap();
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].message.data, b"compressed request");
+}
+
+#[tokio::test]
+async fn test_malformed_request_frames_are_rejected() {
+    let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let addr_notify = Arc::new(Notify::new());
+    let addr_holder = Arc::new(std::sync::Mutex::new(None::<SocketAddr>));
+
+    let addr_notify_clone = addr_notify.clone();
+    let addr_holder_clone = addr_holder.clone();
+
+    let client_task = tokio::spawn(async move {
+        addr_notify_clone.notified().await;
+        let server_addr = addr_holder_clone.lock().unwrap().unwrap();
+
+        let client = Client::builder(TokioExecutor::new())
+            .http2_only(true)
+            .build_http();
+
+        let mut truncated = encode_frame(b"hello");
+        truncated.truncate(7);
+        let mut two_messages = encode_frame(b"first");
+        two_messages.extend(encode_frame(b"second"));
+
+        for (body, expected_status) in [
+            (Vec::new(), "13"),
+            (truncated, "13"),
+            (two_messages, "13"),
+            (encode_frame(b"valid"), "0"),
+        ] {
+            let request = hyper::Request::builder()
+                .method("POST")
+                .uri(format!("http://{}/test.FrameService/Echo", server_addr))
+                .header("content-type", "application/grpc")
+                .body(Full::new(Bytes::from(body)))
+                .unwrap();
+
+            let response = client.request(request).await.unwrap();
+            assert_eq!(response.headers().get("grpc-status").unwrap(), expected_status);
+        }
+    });
+
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
+    let result = Tonic::bind(addr)
+        .run(
+            vec![Service::new("test.FrameService").with_method(
+                Method::new("Echo").with_handler(Handler::dynamic(|ctx: &RequestContext| {
+                    ctx.message.clone()
+                })),
+            )],
+            DefaultCollector::new(),
+            RunOptions::default(),
+            Some(move |actual_addr: SocketAddr| {
+                *addr_holder.lock().unwrap() = Some(actual_addr);
+                addr_notify.notify_one();
+            }),
+        )
+        .await
+        .unwrap();
+
+    client_task.await.unwrap();
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].message.data, b"valid");
+}
+
+#[tokio::test]
+async fn test_collected_request_metadata() {
+    let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let addr_notify = Arc::new(Notify::new());
+    let addr_holder = Arc::new(std::sync::Mutex::new(None::<SocketAddr>));
+
+    let addr_notify_clone = addr_notify.clone();
+    let addr_holder_clone = addr_holder.clone();
+
+    let client_task = tokio::spawn(async move {
+        addr_notify_clone.notified().await;
+        let server_addr = addr_holder_clone.lock().unwrap().unwrap();
+
+        let client = Client::builder(TokioExecutor::new())
+            .http2_only(true)
+            .build_http();
+
+        let request = hyper::Request::builder()
+            .method("POST")
+            .uri(format!("http://{}/test.AuthService/Whoami", server_addr))
+            .header("content-type", "application/grpc")
+            .header("authorization", "Bearer x")
+            .header("x-request-id", "req-1")
+            .body(Full::new(Bytes::from(encode_frame(&[]))))
+            .unwrap();
+
+        let response = client.request(request).await.unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(decode_frame(&body).unwrap(), b"x");
+    });
+
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
+    let result = Tonic::bind(addr)
+        .run(
+            vec![Service::new("test.AuthService").with_method(
+                Method::new("Whoami").with_handler(Handler::dynamic(|ctx: &RequestContext| {
+                    Message::new(ctx.authorization_bearer().unwrap_or_default().as_bytes())
+                })),
+            )],
+            DefaultCollector::new(),
+            RunOptions::default(),
+            Some(move |actual_addr: SocketAddr| {
+                *addr_holder.lock().unwrap() = Some(actual_addr);
+                addr_notify.notify_one();
+            }),
+        )
+        .await
+        .unwrap();
+
+    client_task.await.unwrap();
+
+    assert_eq!(result[0].metadata.get("authorization").unwrap(), "Bearer x");
+    assert_eq!(result[0].metadata.get("x-request-id").unwrap(), "req-1");
+}
+
+#[tokio::test]
+async fn test_channel_collector_drains_during_run() {
+    let addr_notify = Arc::new(Notify::new());
+    let addr_holder = Arc::new(std::sync::Mutex::new(None::<SocketAddr>));
+    let addr_notify_clone = addr_notify.clone();
+    let addr_holder_clone = addr_holder.clone();
+
+    let (collector, mut receiver) = ChannelCollector::new();
+
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
+    let server_task = tokio::spawn(async move {
+        Tonic::default()
+            .run(
+                vec![Service::new("test.Service").with_method(
+                    Method::new("Call")
+                        .with_handler(Handler::from_bytes(vec![]))
+                        .with_handler(Handler::from_bytes(vec![]))
+                        .with_handler(Handler::from_bytes(vec![])),
+                )],
+                collector,
+                RunOptions::default(),
+                Some(move |actual_addr: SocketAddr| {
+                    *addr_holder_clone.lock().unwrap() = Some(actual_addr);
+                    addr_notify_clone.notify_one();
+                }),
+            )
+            .await
+    });
+
+    addr_notify.notified().await;
+    let server_addr = addr_holder.lock().unwrap().unwrap();
+    let client = Client::builder(TokioExecutor::new())
+        .http2_only(true)
+        .build_http();
+
+    for payload in [[1u8], [2], [3]] {
+        let request = hyper::Request::builder()
+            .method("POST")
+            .uri(format!("http://{}/test.Service/Call", server_addr))
+            .header("content-type", "application/grpc")
+            .body(Full::new(Bytes::from(encode_frame(&payload))))
+            .unwrap();
+        client.request(request).await.unwrap();
+
+        // Each request can be read before the scenario completes
+        let collected = receiver.recv().await.unwrap();
+        assert_eq!(collected.message.data, payload);
+        if payload != [3] {
+            assert!(!server_task.is_finished());
+        }
+    }
+
+    server_task.await.unwrap().unwrap();
+    assert!(receiver.recv().await.is_none());
+}
+
+/// Send a plain HTTP/1.1 request and return the raw response
+async fn send_http1_request(addr: SocketAddr) -> String {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(
+            b"POST /test.Service/Call HTTP/1.1\r\nhost: localhost\r\ncontent-length: 0\r\n\r\n",
+        )
+        .await
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response
+}
+
+#[tokio::test]
+async fn test_http1_client_gets_diagnostic() {
+    let addr_notify = Arc::new(Notify::new());
+    let addr_holder = Arc::new(std::sync::Mutex::new(None::<SocketAddr>));
+    let addr_notify_clone = addr_notify.clone();
+    let addr_holder_clone = addr_holder.clone();
+
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
+    let server_task = tokio::spawn(async move {
+        Tonic::default()
+            .run(
+                vec![Service::new("test.Service")
+                    .with_method(Method::new("Call").with_handler(Handler::from_bytes(vec![])))],
+                DefaultCollector::new(),
+                RunOptions::default(),
+                Some(move |actual_addr: SocketAddr| {
+                    *addr_holder_clone.lock().unwrap() = Some(actual_addr);
+                    addr_notify_clone.notify_one();
+                }),
+            )
+            .await
+    });
+
+    addr_notify.notified().await;
+    let server_addr = addr_holder.lock().unwrap().unwrap();
+
+    let response = tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        send_http1_request(server_addr),
+    )
+    .await
+    .expect("HTTP/1 client should not hang");
+
+    assert!(response.starts_with("HTTP/1.1 505"));
+    assert!(response.contains("HTTP/2"));
+
+    server_task.abort();
+}
+
+#[tokio::test]
+async fn test_strict_http2_fails_run_for_http1_client() {
+    let addr_notify = Arc::new(Notify::new());
+    let addr_holder = Arc::new(std::sync::Mutex::new(None::<SocketAddr>));
+    let addr_notify_clone = addr_notify.clone();
+    let addr_holder_clone = addr_holder.clone();
+
+    let client_task = tokio::spawn(async move {
+        addr_notify_clone.notified().await;
+        let server_addr = addr_holder_clone.lock().unwrap().unwrap();
+        send_http1_request(server_addr).await
+    });
+
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
+    let result = tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        Tonic::default().strict_http2().run(
+            vec![Service::new("test.Service")
+                .with_method(Method::new("Call").with_handler(Handler::from_bytes(vec![])))],
+            DefaultCollector::new(),
+            RunOptions::default(),
+            Some(move |actual_addr: SocketAddr| {
+                *addr_holder.lock().unwrap() = Some(actual_addr);
+                addr_notify.notify_one();
+            }),
+        ),
+    )
+    .await
+    .expect("run should fail instead of hanging");
+
+    assert!(matches!(result, Err(HarnessError::TransportError(ref msg)) if msg.contains("HTTP/2")));
+    assert!(client_task.await.unwrap().starts_with("HTTP/1.1 505"));
+}
+
+#[tokio::test]
+async fn test_propagate_panics_fails_run_with_handler_panic() {
+    let (ready_tx, ready_rx) = tokio::sync::oneshot::channel::<SocketAddr>();
+    let client_task = tokio::spawn(async move {
+        let server_addr = ready_rx.await.unwrap();
+        let client = Client::builder(TokioExecutor::new())
+            .http2_only(true)
+            .build_http();
+        let request = hyper::Request::builder()
+            .method("POST")
+            .uri(format!("http://{}/test.Service/Call", server_addr))
+            .header("content-type", "application/grpc")
+            .body(Full::new(Bytes::from(encode_frame(&[]))))
+            .unwrap();
+        // The run may stop before the response is written
+        let _ = client.request(request).await;
+    });
+
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
+    let options = RunOptions {
+        propagate_panics: true,
+        ..RunOptions::default()
+    };
+    // A second, never called handler keeps the scenario from completing
+    let result = tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        Tonic::default().run(
+            vec![Service::new("test.Service").with_method(
+                Method::new("Call")
+                    .with_handler(Handler::dynamic(|_| -> HandlerResponse {
+                        panic!("handler exploded")
+                    }))
+                    .with_handler(Handler::from_bytes(vec![])),
+            )],
+            DefaultCollector::new(),
+            options,
+            Some(move |addr: SocketAddr| {
+                let _ = ready_tx.send(addr);
+            }),
+        ),
+    )
+    .await
+    .expect("run should fail instead of hanging");
+
+    assert!(
+        matches!(result, Err(HarnessError::HandlerPanic { ref message }) if message == "handler exploded")
+    );
+    client_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_max_connections_delays_excess_connections() {
+    use hyper::client::conn::http2;
+    use std::time::Duration;
+
+    /// Open a dedicated HTTP/2 connection, driven on its own task
+    async fn connect(
+        addr: SocketAddr,
+    ) -> (http2::SendRequest<Full<Bytes>>, tokio::task::JoinHandle<()>) {
+        let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let (sender, connection) =
+            http2::handshake(TokioExecutor::new(), hyper_util::rt::TokioIo::new(stream))
+                .await
+                .unwrap();
+        let connection = tokio::spawn(async move {
+            let _ = connection.await;
+        });
+        (sender, connection)
+    }
+
+    fn call(method: &str) -> hyper::Request<Full<Bytes>> {
+        hyper::Request::builder()
+            .method("POST")
+            .uri(format!("http://localhost/test.PoolService/{}", method))
+            .header("content-type", "application/grpc")
+            .body(Full::new(Bytes::from(encode_frame(&[]))))
+            .unwrap()
+    }
+
+    let (ready_tx, ready_rx) = tokio::sync::oneshot::channel::<SocketAddr>();
+
+    let client_task = tokio::spawn(async move {
+        let addr = ready_rx.await.unwrap();
+
+        let (mut first, first_connection) = connect(addr).await;
+        let first_response = first.send_request(call("First")).await.unwrap();
+        assert_eq!(first_response.headers().get("grpc-status").unwrap(), "0");
+
+        // The second connection waits while the first is open
+        let (mut second, _second_connection) = connect(addr).await;
+        let pending = second.send_request(call("Second"));
+        tokio::pin!(pending);
+        let delayed = tokio::time::timeout(Duration::from_millis(200), &mut pending).await;
+        assert!(delayed.is_err(), "second connection was served while the first was open");
+
+        // Closing the first connection lets the second one through
+        drop((first, first_response));
+        first_connection.await.unwrap();
+        let response = tokio::time::timeout(Duration::from_secs(2), pending)
+            .await
+            .expect("second connection served once the first closed")
+            .unwrap();
+        assert_eq!(response.headers().get("grpc-status").unwrap(), "0");
+    });
+
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
+    let options = RunOptions {
+        max_connections: Some(1),
+        ..RunOptions::default()
+    };
+    let result = Tonic::default()
+        .run(
+            vec![Service::new("test.PoolService")
+                .with_method(Method::new("First").with_handler(Handler::from_bytes(vec![1])))
+                .with_method(Method::new("Second").with_handler(Handler::from_bytes(vec![2])))],
+            DefaultCollector::new(),
+            options,
+            Some(move |addr: SocketAddr| {
+                let _ = ready_tx.send(addr);
+            }),
+        )
+        .await
+        .unwrap();
+
+    client_task.await.unwrap();
+    assert_eq!(result.len(), 2);
+    assert_eq!(result[0].method, "First");
+    assert_eq!(result[1].method, "Second");
+}
+
+#[tokio::test]
+async fn test_max_connections_zero_is_rejected() {
+    let result = ScenarioBuilder::new()
+        .server(Tonic::default())
+        .collector(DefaultCollector::new())
+        .service(Service::new("test.PoolService").with_method(
+            Method::new("First").with_handler(Handler::from_bytes(vec![1])),
+        ))
+        .max_connections(0)
+        .build()
+        .execute()
+        .await;
+
+    assert!(matches!(result, Err(HarnessError::ConfigurationError(_))));
+}
+
+#[tokio::test]
+async fn test_tonic_rejects_zero_max_connections_before_binding() {
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
+    let options = RunOptions {
+        max_connections: Some(0),
+        ..RunOptions::default()
+    };
+    let result = Tonic::default()
+        .run(
+            vec![Service::new("test.PoolService")
+                .with_method(Method::new("First").with_handler(Handler::from_bytes(vec![1])))],
+            DefaultCollector::new(),
+            options,
+            Some(|_: SocketAddr| panic!("server bound despite zero max_connections")),
+        )
+        .await;
+
+    assert!(matches!(result, Err(HarnessError::ConfigurationError(_))));
+}
+
+#[tokio::test]
+async fn test_cyclic_method_until_expected_requests() {
+    let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let addr_notify = Arc::new(Notify::new());
+    let addr_holder = Arc::new(std::sync::Mutex::new(None::<SocketAddr>));
+
+    let addr_notify_clone = addr_notify.clone();
+    let addr_holder_clone = addr_holder.clone();
+
+    let client_task = tokio::spawn(async move {
+        addr_notify_clone.notified().await;
+        let server_addr = addr_holder_clone.lock().unwrap().unwrap();
+
+        let client = Client::builder(TokioExecutor::new())
+            .http2_only(true)
+            .build_http();
+
+        for expected in [1u8, 2, 1, 2] {
+            let request = hyper::Request::builder()
+                .method("POST")
+                .uri(format!("http://{}/test.Service/Call", server_addr))
+                .header("content-type", "application/grpc")
+                .body(Full::new(Bytes::from(encode_frame(&[]))))
+                .unwrap();
+
+            let response = client.request(request).await.unwrap();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            assert_eq!(decode_frame(&body).unwrap(), &[expected]);
+        }
+    });
+
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
+    let result = Tonic::bind(addr)
+        .run(
+            vec![Service::new("test.Service").with_method(
+                Method::new("Call")
+                    .cycle()
+                    .with_handler(Handler::from_bytes(vec![1]))
+                    .with_handler(Handler::from_bytes(vec![2])),
+            )],
+            DefaultCollector::new(),
+            RunOptions {
+                expected_requests: Some(4),
+                ..Default::default()
+            },
+            Some(move |actual_addr: SocketAddr| {
+                *addr_holder.lock().unwrap() = Some(actual_addr);
+                addr_notify.notify_one();
+            }),
+        )
+        .await
+        .unwrap();
+
+    client_task.await.unwrap();
+    assert_eq!(result.len(), 4);
+}
+
+#[cfg(feature = "tls")]
+#[tokio::test]
+async fn test_tls_method_with_self_signed_cert() {
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
+
+    let (ready_tx, ready_rx) = tokio::sync::oneshot::channel::<SocketAddr>();
+
+    let client_task = tokio::spawn(async move {
+        let server_addr = ready_rx.await.unwrap();
+
+        // reqwest negotiates HTTP/2 through ALPN
+        let client = reqwest::Client::builder()
+            .tls_danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        let response = client
+            .post(format!(
+                "https://localhost:{}/test.TestService/GetData",
+                server_addr.port()
+            ))
+            .header("content-type", "application/grpc")
+            .body(encode_frame(&[10, 20, 30]))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.version(), reqwest::Version::HTTP_2);
+
+        let body = response.bytes().await.unwrap();
+        assert_eq!(decode_frame(&body).unwrap(), &[1, 2, 3, 4]);
+    });
+
+    let collected = Tonic::bind_tls(([127, 0, 0, 1], 0), rustls_test_config())
+        .run(
+            vec![Service::new("test.TestService")
+                .with_method(Method::new("GetData").with_handler(Handler::from_bytes(vec![1, 2, 3, 4])))],
+            DefaultCollector::new(),
+            RunOptions::default(),
+            Some(move |addr| {
+                ready_tx.send(addr).unwrap();
+            }),
+        )
+        .await
+        .unwrap();
+
+    client_task.await.unwrap();
+    assert_eq!(collected.len(), 1);
+    assert_eq!(collected[0].message.data, vec![10, 20, 30]);
+}
+
+#[tokio::test]
+async fn test_strict_collector_reports_unmatched_calls() {
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
+
+    let (ready_tx, ready_rx) = tokio::sync::oneshot::channel::<SocketAddr>();
+
+    let client_task = tokio::spawn(async move {
+        let server_addr = ready_rx.await.unwrap();
+
+        let client = Client::builder(TokioExecutor::new())
+            .http2_only(true)
+            .build_http();
+
+        for path in ["test.TestService/Missing", "test.TestService/GetData"] {
+            let request = hyper::Request::builder()
+                .method("POST")
+                .uri(format!("http://{}/{}", server_addr, path))
+                .header("content-type", "application/grpc")
+                .body(Full::new(Bytes::from(encode_frame(&[1]))))
+                .unwrap();
+            client.request(request).await.unwrap();
+        }
+    });
+
+    let result = Tonic::bind(([127, 0, 0, 1], 0))
+        .run(
+            vec![Service::new("test.TestService")
+                .with_method(Method::new("GetData").with_handler(Handler::from_bytes(vec![2])))],
+            StrictCollector::new(),
+            RunOptions::default(),
+            Some(move |addr| {
+                ready_tx.send(addr).unwrap();
+            }),
+        )
+        .await
+        .unwrap();
+
+    client_task.await.unwrap();
+
+    let unmatched = result.unwrap_err();
+    assert_eq!(unmatched.len(), 1);
+    assert_eq!(unmatched[0].service, "test.TestService");
+    assert_eq!(unmatched[0].method, "Missing");
+    assert!(!unmatched[0].matched);
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct GetUserRequest {
+    #[prost(string, tag = "1")]
+    id: String,
+}
+
+#[tokio::test]
+async fn test_expecting_reports_malformed_messages() {
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
+
+    let (ready_tx, ready_rx) = tokio::sync::oneshot::channel::<SocketAddr>();
+
+    let client_task = tokio::spawn(async move {
+        let server_addr = ready_rx.await.unwrap();
+
+        let client = Client::builder(TokioExecutor::new())
+            .http2_only(true)
+            .build_http();
+
+        let valid = prost::Message::encode_to_vec(&GetUserRequest { id: "42".into() });
+        // Field 1 declares 5 bytes of string but carries 1
+        let truncated = vec![0x0a, 0x05, b'4'];
+        for message in [valid, truncated] {
+            let request = hyper::Request::builder()
+                .method("POST")
+                .uri(format!("http://{}/test.UserService/GetUser", server_addr))
+                .header("content-type", "application/grpc")
+                .body(Full::new(Bytes::from(encode_frame(&message))))
+                .unwrap();
+            let response = client.request(request).await.unwrap();
+            // Invalid calls are still answered by the handlers
+            assert_eq!(response.headers().get("grpc-status").unwrap(), "0");
+        }
+    });
+
+    let result = Tonic::bind(([127, 0, 0, 1], 0))
+        .run(
+            vec![Service::new("test.UserService").with_method(
+                Method::new("GetUser")
+                    .expecting::<GetUserRequest>()
+                    .with_handlers([Handler::from_bytes(vec![1]), Handler::from_bytes(vec![2])]),
+            )],
+            DefaultCollector::new(),
+            RunOptions::default(),
+            Some(move |addr| {
+                ready_tx.send(addr).unwrap();
+            }),
+        )
+        .await;
+
+    client_task.await.unwrap();
+
+    match result {
+        Err(HarnessError::InvalidMessages(failures)) => {
+            assert_eq!(failures.len(), 1);
+            assert!(
+                failures[0].starts_with("/test.UserService/GetUser: expected GetUserRequest: "),
+                "{}",
+                failures[0]
+            );
+        }
+        other => panic!("expected InvalidMessages, got {:?}", other.map(|r| r.len())),
+    }
+}
+
+#[cfg(feature = "tracing")]
+#[tokio::test]
+async fn test_tracing_events() {
+    let logs = server_harness_test_support::CapturedLogs::start();
+
+    let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let addr_notify = Arc::new(Notify::new());
+    let addr_holder = Arc::new(std::sync::Mutex::new(None::<SocketAddr>));
+
+    let addr_notify_clone = addr_notify.clone();
+    let addr_holder_clone = addr_holder.clone();
+
+    let client_task = tokio::spawn(async move {
+        addr_notify_clone.notified().await;
+        let server_addr = addr_holder_clone.lock().unwrap().unwrap();
+
+        let client = Client::builder(TokioExecutor::new())
+            .http2_only(true)
+            .build_http();
+
+        for path in ["test.TestService/Missing", "test.TestService/GetData"] {
+            let request = hyper::Request::builder()
+                .method("POST")
+                .uri(format!("http://{}/{}", server_addr, path))
+                .header("content-type", "application/grpc")
+                .body(Full::new(Bytes::from(encode_frame(&[1]))))
+                .unwrap();
+            client.request(request).await.unwrap();
+        }
+    });
+
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
+    Tonic::bind(addr)
+        .run(
+            vec![Service::new("test.TestService")
+                .with_method(Method::new("GetData").with_handler(Handler::from_bytes(vec![2])))],
+            DefaultCollector::new(),
+            RunOptions::default(),
+            Some(move |actual_addr: SocketAddr| {
+                *addr_holder.lock().unwrap() = Some(actual_addr);
+                addr_notify.notify_one();
+            }),
+        )
+        .await
+        .unwrap();
+
+    client_task.await.unwrap();
+
+    let logs = logs.contents();
+    assert!(logs.contains("request received"), "{}", logs);
+    assert!(logs.contains("call{path=/test.TestService/GetData}: "), "{}", logs);
+    assert!(logs.contains("no handler matched"), "{}", logs);
+    assert!(logs.contains("handler selected"), "{}", logs);
+    assert!(logs.contains("index=0 count=1"), "{}", logs);
+    assert!(logs.contains("scenario complete, shutting down"), "{}", logs);
+}
+
+#[tokio::test]
+async fn test_serves_over_ipv6() {
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
+
+    let (ready_tx, ready_rx) = tokio::sync::oneshot::channel::<SocketAddr>();
+    let client_task = tokio::spawn(async move {
+        let server_addr = ready_rx.await.unwrap();
+        assert!(server_addr.is_ipv6());
+        assert_ne!(server_addr.port(), 0);
+
+        let client = Client::builder(TokioExecutor::new())
+            .http2_only(true)
+            .build_http();
+        let request = hyper::Request::builder()
+            .method("POST")
+            .uri(format!("http://{}/test.TestService/GetData", server_addr))
+            .header("content-type", "application/grpc")
+            .body(Full::new(Bytes::from(encode_frame(&[1]))))
+            .unwrap();
+        let response = client.request(request).await.unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(decode_frame(&body).unwrap(), &[2]);
+    });
+
+    let collected = Tonic::bind((std::net::Ipv6Addr::LOCALHOST, 0))
+        .run(
+            vec![Service::new("test.TestService")
+                .with_method(Method::new("GetData").with_handler(Handler::from_bytes(vec![2])))],
+            DefaultCollector::new(),
+            RunOptions::default(),
+            Some(move |addr: SocketAddr| {
+                let _ = ready_tx.send(addr);
+            }),
+        )
+        .await
+        .unwrap();
+
+    client_task.await.unwrap();
+    assert_eq!(collected.len(), 1);
+}
+
+#[tokio::test]
+async fn test_serves_on_pre_bound_listener() {
+    use grpc_rpc_server_harness::use_cases::ports::{RunOptions, Server};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let server_addr = listener.local_addr().unwrap();
+
+    // The port is known before the server runs
+    let client_task = tokio::spawn(async move {
+        let client = Client::builder(TokioExecutor::new())
+            .http2_only(true)
+            .build_http();
+        let request = hyper::Request::builder()
+            .method("POST")
+            .uri(format!("http://{}/test.TestService/GetData", server_addr))
+            .header("content-type", "application/grpc")
+            .body(Full::new(Bytes::from(encode_frame(&[1]))))
+            .unwrap();
+        let response = client.request(request).await.unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(decode_frame(&body).unwrap(), &[2]);
+    });
+
+    let collected = Tonic::from_listener(listener)
+        .run(
+            vec![Service::new("test.TestService")
+                .with_method(Method::new("GetData").with_handler(Handler::from_bytes(vec![2])))],
+            DefaultCollector::new(),
+            RunOptions::default(),
+            None::<fn(SocketAddr)>,
+        )
+        .await
+        .unwrap();
+
+    client_task.await.unwrap();
+    assert_eq!(collected.len(), 1);
+}
@@ -8,6 +8,7 @@
 //! The server automatically shuts down once all handlers have been called.
 
 use http_endpoint_server_harness::prelude::*;
+use http_endpoint_server_harness::testing::wait_for_ready;
 use std::net::SocketAddr;
 use std::time::Duration;
 
@@ -21,20 +22,11 @@ async fn main() -> Result<(), HarnessError> {
 
     // Spawn a task that will make HTTP requests to our server
     let requests_task = tokio::spawn(async move {
-        // Wait for server to be ready
+        // Wait for server to be ready; a TCP probe is never collected
+        wait_for_ready(addr, Duration::from_secs(5))
+            .await
+            .expect("Server did not start");
         let client = reqwest::Client::new();
-        loop {
-            if client
-                .get(format!("http://{}/", addr))
-                .timeout(Duration::from_millis(50))
-                .send()
-                .await
-                .is_ok()
-            {
-                break;
-            }
-            tokio::time::sleep(Duration::from_millis(10)).await;
-        }
 
         println!("Server is ready at http://{}\n", addr);
 
@@ -0,0 +1,123 @@
+use crate::entities::{Encoding, Response};
+use std::io::Write;
+
+/// Apply the response's compression if `accept_encoding` allows it.
+///
+/// The body is encoded and `content-encoding` set; `vary` is always set so
+/// caches keep compressed and plain responses apart.
+pub(super) fn compress(mut response: Response, accept_encoding: Option<&str>) -> Response {
+    let Some(encoding) = response.compression else {
+        return response;
+    };
+    response
+        .headers
+        .insert("vary".to_string(), "accept-encoding".to_string());
+    if !accepts(accept_encoding.unwrap_or(""), encoding) {
+        return response;
+    }
+
+    match encode(&response.body, encoding) {
+        Ok(body) => {
+            response.body = body;
+            response
+                .headers
+                .retain(|key, _| !key.eq_ignore_ascii_case("content-length"));
+            response
+                .headers
+                .insert("content-encoding".to_string(), encoding.as_str().to_string());
+            response
+        }
+        Err(_) => response,
+    }
+}
+
+/// Whether an `Accept-Encoding` header allows `encoding`, honoring `*` and `q=0`
+fn accepts(accept_encoding: &str, encoding: Encoding) -> bool {
+    let mut wildcard = false;
+    for item in accept_encoding.split(',') {
+        let mut params = item.split(';');
+        let coding = params.next().unwrap_or("").trim();
+        let allowed = !params.any(|param| {
+            param
+                .trim()
+                .strip_prefix("q=")
+                .and_then(|q| q.trim().parse::<f32>().ok())
+                .is_some_and(|q| q == 0.0)
+        });
+        if coding.eq_ignore_ascii_case(encoding.as_str()) {
+            return allowed;
+        }
+        if coding == "*" {
+            wildcard = allowed;
+        }
+    }
+    wildcard
+}
+
+fn encode(body: &[u8], encoding: Encoding) -> std::io::Result<Vec<u8>> {
+    match encoding {
+        Encoding::Gzip => {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(body)?;
+            encoder.finish()
+        }
+        Encoding::Deflate => {
+            let mut encoder =
+                flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(body)?;
+            encoder.finish()
+        }
+        Encoding::Br => {
+            let mut encoded = Vec::new();
+            {
+                let mut encoder = brotli::CompressorWriter::new(&mut encoded, 4096, 5, 22);
+                encoder.write_all(body)?;
+            }
+            Ok(encoded)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_accepts() {
+        assert!(accepts("gzip, deflate, br", Encoding::Br));
+        assert!(accepts("GZIP;q=0.5", Encoding::Gzip));
+        assert!(accepts("*", Encoding::Deflate));
+        assert!(!accepts("gzip;q=0", Encoding::Gzip));
+        assert!(!accepts("*, br;q=0", Encoding::Br));
+        assert!(!accepts("deflate", Encoding::Gzip));
+        assert!(!accepts("", Encoding::Gzip));
+    }
+
+    #[test]
+    fn test_compress_round_trips() {
+        let response = Response::ok()
+            .with_body("hello hello hello")
+            .with_compression(Encoding::Deflate);
+        let compressed = compress(response, Some("deflate"));
+        assert_eq!(compressed.headers.get("content-encoding").unwrap(), "deflate");
+
+        let mut decoded = String::new();
+        flate2::read::ZlibDecoder::new(compressed.body.as_slice())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, "hello hello hello");
+    }
+
+    #[test]
+    fn test_compress_skipped_when_not_accepted() {
+        let response = Response::ok()
+            .with_body("hello")
+            .with_compression(Encoding::Br);
+        let response = compress(response, Some("gzip"));
+        assert_eq!(response.body, b"hello");
+        assert!(!response.headers.contains_key("content-encoding"));
+        assert_eq!(response.headers.get("vary").unwrap(), "accept-encoding");
+    }
+}
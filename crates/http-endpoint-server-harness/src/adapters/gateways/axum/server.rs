@@ -1,38 +1,155 @@
 use async_trait::async_trait;
 use axum::{
-    body::Body,
-    extract::State,
-    http::{Request as AxumRequest, StatusCode},
+    body::{Body, Bytes},
+    extract::{
+        connect_info::{ConnectInfo, Connected},
+        Path, State,
+    },
+    http::{HeaderMap, HeaderName, HeaderValue, Request as AxumRequest, StatusCode},
     response::IntoResponse,
-    routing::MethodRouter,
+    routing::{MethodRouter, Route},
+    serve::IncomingStream,
     Router,
 };
+use futures::StreamExt;
+use http_body::Frame;
+use http_body_util::StreamBody;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
 use std::collections::HashMap;
-use std::net::SocketAddr;
+use std::convert::Infallible;
+use std::future::{Future, IntoFuture};
+use std::net::{Ipv6Addr, SocketAddr};
+#[cfg(unix)]
+use std::path::PathBuf;
 use std::sync::{
-    atomic::{AtomicUsize, Ordering},
+    atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
     Arc,
 };
+use std::time::Duration;
 use tokio::sync::{oneshot, Mutex};
+use tower::{Layer, Service};
+use server_harness_core::CompletionTracker;
 
-use crate::entities::{Endpoint, Handler, Method, Request};
+use crate::entities::handler::{pick_weighted, total_weight};
+use crate::entities::{
+    AllowConfig, BodyHandler, Endpoint, Handler, HeaderMatch, Method, Request,
+};
 use crate::error::HarnessError;
-use crate::use_cases::ports::{Collector, Server};
+use crate::use_cases::ports::{
+    BoundAddr, Collector, RequestCallback, RunOptions, Server, DEFAULT_MAX_BODY_SIZE,
+};
+
+/// Function applying a caller's layer to the router before serving
+type RouterLayerFn = Arc<dyn Fn(Router) -> Router + Send + Sync>;
 
 /// Axum-based HTTP server implementation
 #[derive(Clone)]
 pub struct Axum {
     addr: SocketAddr,
+    router_layers: Vec<RouterLayerFn>,
+    #[cfg(feature = "tls")]
+    tls: Option<Arc<crate::RustlsConfig>>,
+    #[cfg(unix)]
+    uds: Option<PathBuf>,
+    /// Listener bound by the caller, served instead of binding `addr`
+    listener: Option<Arc<std::net::TcpListener>>,
 }
 
 impl Axum {
     pub fn new(addr: SocketAddr) -> Self {
-        Self { addr }
+        Self {
+            addr,
+            router_layers: Vec::new(),
+            #[cfg(feature = "tls")]
+            tls: None,
+            #[cfg(unix)]
+            uds: None,
+            listener: None,
+        }
     }
 
     pub fn bind(addr: impl Into<SocketAddr>) -> Self {
         Self::new(addr.into())
     }
+
+    /// Bind every IPv6 interface on an ephemeral port.
+    ///
+    /// Dual-stack systems, such as Linux by default, accept IPv4 clients on
+    /// the same socket. The reported address is `[::]:port`; clients reach
+    /// it at `[::1]:port`.
+    pub fn bind_any_v6() -> Self {
+        Self::new((Ipv6Addr::UNSPECIFIED, 0).into())
+    }
+
+    /// Bind a server terminating TLS with `config` on every connection
+    #[cfg(feature = "tls")]
+    pub fn bind_tls(
+        addr: impl Into<SocketAddr>,
+        config: impl Into<Arc<crate::RustlsConfig>>,
+    ) -> Self {
+        Self {
+            tls: Some(config.into()),
+            ..Self::bind(addr)
+        }
+    }
+
+    /// Bind a server listening on a Unix domain socket at `path`.
+    ///
+    /// The socket file is removed once the server shuts down. Use
+    /// [`Axum::run_bound`] to be notified when it is ready.
+    #[cfg(unix)]
+    pub fn bind_uds(path: impl Into<PathBuf>) -> Self {
+        Self {
+            uds: Some(path.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Serve on a listener the caller has already bound, e.g. with custom
+    /// socket options or inherited through socket activation.
+    ///
+    /// Each run serves a duplicate of the listener's socket, so the same
+    /// server can be run again.
+    pub fn from_listener(listener: std::net::TcpListener) -> Self {
+        let addr = listener
+            .local_addr()
+            .unwrap_or_else(|_| ([127, 0, 0, 1], 0).into());
+        Self {
+            listener: Some(Arc::new(listener)),
+            ..Self::new(addr)
+        }
+    }
+
+    /// Wrap every route, including the fallback for unmatched requests, in a
+    /// tower `layer`, e.g. a middleware adding headers or a rate limiter.
+    ///
+    /// Layers apply in the order they are added, the last one running first.
+    /// Responses a layer produces without calling the harness are neither
+    /// collected nor counted.
+    pub fn with_router_layer<L>(mut self, layer: L) -> Self
+    where
+        L: Layer<Route> + Clone + Send + Sync + 'static,
+        L::Service: Service<AxumRequest<Body>> + Clone + Send + Sync + 'static,
+        <L::Service as Service<AxumRequest<Body>>>::Response: IntoResponse + 'static,
+        <L::Service as Service<AxumRequest<Body>>>::Error: Into<Infallible> + 'static,
+        <L::Service as Service<AxumRequest<Body>>>::Future: Send + 'static,
+    {
+        self.router_layers
+            .push(Arc::new(move |router: Router| router.layer(layer.clone())));
+        self
+    }
+}
+
+/// Removes a Unix socket file when dropped
+#[cfg(unix)]
+struct SocketFile(PathBuf);
+
+#[cfg(unix)]
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
 }
 
 impl Default for Axum {
@@ -41,34 +158,56 @@ impl Default for Axum {
     }
 }
 
-/// Shared state for tracking completion
+/// Identifier and TLS details of an accepted connection, attached to every
+/// request it carries
 #[derive(Clone)]
-struct CompletionTracker {
-    /// Total number of handlers across all endpoints
-    total_handlers: usize,
-    /// Number of handlers that have been called at least once
-    handlers_called: Arc<AtomicUsize>,
-    /// Shutdown signal sender (wrapped in Mutex for Clone)
-    shutdown_tx: Arc<Mutex<Option<oneshot::Sender<()>>>>,
+struct ConnectionInfo {
+    id: u64,
+    sni: Option<String>,
+    alpn: Option<String>,
 }
 
-impl CompletionTracker {
-    fn new(total_handlers: usize, shutdown_tx: oneshot::Sender<()>) -> Self {
-        Self {
-            total_handlers,
-            handlers_called: Arc::new(AtomicUsize::new(0)),
-            shutdown_tx: Arc::new(Mutex::new(Some(shutdown_tx))),
+impl ConnectionInfo {
+    fn next() -> Self {
+        static NEXT_CONNECTION_ID: AtomicU64 = AtomicU64::new(1);
+        ConnectionInfo {
+            id: NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed),
+            sni: None,
+            alpn: None,
         }
     }
 
-    /// Called when a handler is used for the first time
-    async fn handler_called(&self) {
-        let called = self.handlers_called.fetch_add(1, Ordering::SeqCst) + 1;
-        if called >= self.total_handlers {
-            // All handlers have been called, trigger shutdown
-            if let Some(tx) = self.shutdown_tx.lock().await.take() {
-                let _ = tx.send(());
-            }
+    /// Copy the connection's details onto a request it carried
+    fn record(&self, request: &mut Request) {
+        request.connection_id = self.id;
+        request.sni = self.sni.clone();
+        request.alpn = self.alpn.clone();
+    }
+}
+
+impl Connected<IncomingStream<'_, tokio::net::TcpListener>> for ConnectionInfo {
+    fn connect_info(_stream: IncomingStream<'_, tokio::net::TcpListener>) -> Self {
+        Self::next()
+    }
+}
+
+#[cfg(unix)]
+impl Connected<IncomingStream<'_, tokio::net::UnixListener>> for ConnectionInfo {
+    fn connect_info(_stream: IncomingStream<'_, tokio::net::UnixListener>) -> Self {
+        Self::next()
+    }
+}
+
+#[cfg(feature = "tls")]
+impl Connected<IncomingStream<'_, super::tls::TlsListener>> for ConnectionInfo {
+    fn connect_info(stream: IncomingStream<'_, super::tls::TlsListener>) -> Self {
+        let (_, tls) = stream.io().get_ref();
+        ConnectionInfo {
+            sni: tls.server_name().map(str::to_string),
+            alpn: tls
+                .alpn_protocol()
+                .map(|protocol| String::from_utf8_lossy(protocol).into_owned()),
+            ..Self::next()
         }
     }
 }
@@ -76,85 +215,313 @@ impl CompletionTracker {
 /// Type-erased collector trait for internal use
 trait ErasedCollector: Send + Sync {
     fn collect(&self, request: Request);
+    fn collects_unmatched(&self) -> bool;
 }
 
-impl<C: Collector> ErasedCollector for std::sync::Mutex<Option<C>> {
-    fn collect(&self, request: Request) {
-        if let Ok(guard) = self.lock() {
-            if let Some(ref collector) = *guard {
-                collector.collect(request);
+/// The scenario's collector and the sequence number of the next collected request
+struct CollectorSlot<C> {
+    collector: Option<C>,
+    next_sequence: u64,
+    on_request: Option<RequestCallback>,
+}
+
+impl<C: Collector> ErasedCollector for std::sync::Mutex<CollectorSlot<C>> {
+    fn collect(&self, mut request: Request) {
+        let callback = match self.lock() {
+            Ok(mut slot) => {
+                // Stamped under the lock so sequence numbers follow collection order
+                request.received_at = std::time::Instant::now();
+                request.sequence = slot.next_sequence;
+                slot.next_sequence += 1;
+                let callback = slot
+                    .on_request
+                    .clone()
+                    .map(|callback| (callback, request.clone()));
+                if let Some(ref collector) = slot.collector {
+                    collector.collect(request);
+                }
+                callback
             }
+            Err(_) => None,
+        };
+        // Called once the lock is released, so callbacks may snapshot the collector
+        if let Some((callback, request)) = callback {
+            callback.call(&request);
         }
     }
+
+    fn collects_unmatched(&self) -> bool {
+        self.lock()
+            .ok()
+            .and_then(|slot| slot.collector.as_ref().map(Collector::collects_unmatched))
+            .unwrap_or(false)
+    }
+}
+
+/// Endpoints sharing a path and method, tried in order by their header matches
+#[derive(Clone)]
+struct RouteState {
+    endpoints: Arc<Vec<EndpointState>>,
 }
 
 /// State shared with Axum handlers using type erasure
 #[derive(Clone)]
 struct EndpointState {
     handlers: Arc<Vec<Handler>>,
+    /// Handlers picked by body, each flagged once it has been used
+    body_handlers: Arc<Vec<(BodyHandler, AtomicBool)>>,
+    /// Whether handlers restart from the first once exhausted
+    cycle: bool,
+    header_matches: Arc<Vec<HeaderMatch>>,
+    /// Calls to the endpoint, whichever handler answered them
     call_count: Arc<AtomicUsize>,
+    /// Calls answered by the sequential handlers
+    sequential_count: Arc<AtomicUsize>,
+    /// Number of first calls counting towards completion: of the sequential
+    /// handlers, or of the endpoint when it expects a number of calls
+    completion_calls: usize,
+    /// Whether the endpoint expects a number of calls
+    expects_calls: bool,
+    /// Held while a handler runs on serialized endpoints
+    gate: Option<Arc<Mutex<()>>>,
+    /// CORS policy whose headers are added to responses
+    cors: Option<Arc<AllowConfig>>,
+    /// Largest request body buffered, in bytes
+    max_body_size: usize,
+    /// Whether this serves requests matching no endpoint
+    is_default: bool,
+    /// Generator picking the responses of random handlers
+    rng: Arc<std::sync::Mutex<SmallRng>>,
     collector: Arc<dyn ErasedCollector>,
     completion_tracker: CompletionTracker,
 }
 
+impl EndpointState {
+    /// Resolve random handlers to the handler they pick for this request
+    fn pick<'a>(&self, mut handler: &'a Handler) -> &'a Handler {
+        while let Handler::Random(handlers) = handler {
+            let total = total_weight(handlers);
+            if total == 0 {
+                break;
+            }
+            let roll = self.rng.lock().map_or(0, |mut rng| rng.random_range(0..total));
+            match pick_weighted(handlers, roll) {
+                Some(picked) => handler = picked,
+                None => break,
+            }
+        }
+        handler
+    }
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "request",
+        skip_all,
+        fields(method = %request.method(), path = %request.uri().path())
+    )
+)]
 async fn handle_request(
-    State(state): State<EndpointState>,
+    State(route): State<RouteState>,
+    path_params: Option<Path<HashMap<String, String>>>,
+    ConnectInfo(connection): ConnectInfo<ConnectionInfo>,
     request: AxumRequest<Body>,
 ) -> impl IntoResponse {
-    // Parse method
-    let method = match request.method().as_str() {
-        "GET" => Method::Get,
-        "POST" => Method::Post,
-        "PUT" => Method::Put,
-        "PATCH" => Method::Patch,
-        "DELETE" => Method::Delete,
-        "HEAD" => Method::Head,
-        "OPTIONS" => Method::Options,
-        _ => Method::Get,
+    let Some(method) = parse_method(request.method()) else {
+        #[cfg(feature = "tracing")]
+        tracing::warn!("unsupported request method");
+        return not_implemented();
     };
-
     let path = request.uri().path().to_string();
-    let headers: HashMap<String, String> = request
-        .headers()
+    let http_version = format!("{:?}", request.version());
+    let path_params = path_params.map(|Path(params)| params).unwrap_or_default();
+    let headers = header_map(request.headers());
+    #[cfg(feature = "tracing")]
+    tracing::debug!("request received");
+
+    // Dispatch to the first endpoint whose header conditions match
+    let Some(state) = route
+        .endpoints
         .iter()
-        .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
-        .collect();
-
-    let body = axum::body::to_bytes(request.into_body(), usize::MAX)
-        .await
-        .map(|b| b.to_vec())
-        .unwrap_or_default();
-
-    // Collect the request
-    let collected_request = Request {
-        method,
-        path,
-        headers,
-        body,
+        .find(|endpoint| endpoint.header_matches.iter().all(|m| m.matches(&headers)))
+    else {
+        #[cfg(feature = "tracing")]
+        tracing::warn!("no handler matched the request headers");
+        return axum::http::Response::builder()
+            .status(StatusCode::NOT_ACCEPTABLE)
+            .body(Body::from("No endpoint matches the request headers"))
+            .unwrap();
+    };
+
+    // Bodies are buffered before a handler is picked, so oversized ones are
+    // neither collected nor counted towards completion. Endpoints with
+    // streaming handlers only have their declared length checked up front,
+    // their buffered handlers read the body once picked.
+    let max_body_size = state.max_body_size;
+    if content_length(&headers).is_some_and(|length| length > max_body_size) {
+        return payload_too_large();
+    }
+    let streams = state
+        .handlers
+        .iter()
+        .any(|handler| matches!(handler, Handler::Streaming(_)));
+    let (body, buffered) = if streams {
+        (request.into_body(), None)
+    } else {
+        match axum::body::to_bytes(request.into_body(), max_body_size).await {
+            Ok(bytes) => (Body::empty(), Some(bytes.to_vec())),
+            Err(_) => return payload_too_large(),
+        }
     };
-    state.collector.collect(collected_request.clone());
 
-    // Get the response from the handler (sequential through handlers)
+    // Pick the first handler whose body predicate matches, otherwise go
+    // sequentially through the handlers
     let call_index = state.call_count.fetch_add(1, Ordering::SeqCst);
-    let handler_count = state.handlers.len();
-    let handler_index = call_index.min(handler_count.saturating_sub(1));
+    let body_handler = buffered.as_deref().and_then(|body| {
+        state
+            .body_handlers
+            .iter()
+            .find(|(body_handler, _)| (body_handler.predicate)(body))
+    });
+    let (handler, first_call) = match body_handler {
+        Some((body_handler, called)) => {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("body handler selected");
+            (Some(&body_handler.handler), !called.swap(true, Ordering::SeqCst))
+        }
+        None => {
+            let sequential_index = state.sequential_count.fetch_add(1, Ordering::SeqCst);
+            let handler_count = state.handlers.len();
+            let handler_index = match handler_count {
+                0 => 0,
+                _ if state.cycle => sequential_index % handler_count,
+                _ => sequential_index.min(handler_count - 1),
+            };
+            #[cfg(feature = "tracing")]
+            if handler_count > 0 {
+                tracing::debug!(index = handler_index, count = handler_count, "handler selected");
+            }
+            (
+                state.handlers.get(handler_index),
+                sequential_index < state.completion_calls,
+            )
+        }
+    };
 
-    // Check if this is a new handler being called for the first time
-    if call_index < handler_count {
-        state.completion_tracker.handler_called().await;
-    }
+    // First calls of a handler, or calls up to an expectation, count towards
+    // completion once the request is collected; default handler calls only
+    // count towards `expected_requests`
+    let first_call = match state.expects_calls {
+        true => call_index < state.completion_calls,
+        false => first_call,
+    };
+    let first_call = !state.is_default && first_call;
 
-    if let Some(handler) = state.handlers.get(handler_index) {
-        let response = handler.respond(&collected_request);
+    // Serialized endpoints handle one request at a time
+    let _guard = match &state.gate {
+        Some(gate) => Some(gate.lock().await),
+        None => None,
+    };
+
+    let accept_encoding = headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("accept-encoding"))
+        .map(|(_, value)| value.clone());
+
+    let mut collected_request = Request::new(method, path);
+    collected_request.path_params = path_params;
+    collected_request.headers = headers;
+    collected_request.http_version = http_version;
+    collected_request.matched = !state.is_default;
+    connection.record(&mut collected_request);
+
+    let response = match handler {
+        Some(Handler::Streaming(f)) => {
+            // Collect the request without its body, which is handed to the handler unbuffered
+            state.collector.collect(collected_request.clone());
+            state.completion_tracker.request_handled(first_call).await;
+            let body = body
+                .into_data_stream()
+                .map(|chunk| {
+                    chunk
+                        .map(|bytes| bytes.to_vec())
+                        .map_err(|e| HarnessError::ServerError(e.to_string()))
+                })
+                .boxed();
+            // The handler takes the request, so keep its headers for echoing
+            let request_headers = collected_request.headers.clone();
+            let mut response = f(collected_request, body).await;
+            response.apply_echo_headers(&request_headers);
+            Some(response)
+        }
+        handler => {
+            collected_request.body = match buffered {
+                Some(bytes) => bytes,
+                // Endpoints with streaming handlers leave the body unread, and an
+                // oversized one gives back its sequential call so it isn't counted
+                None => match axum::body::to_bytes(body, max_body_size).await {
+                    Ok(bytes) => bytes.to_vec(),
+                    Err(_) => {
+                        state.call_count.fetch_sub(1, Ordering::SeqCst);
+                        state.sequential_count.fetch_sub(1, Ordering::SeqCst);
+                        return payload_too_large();
+                    }
+                },
+            };
+
+            // Collect the request
+            state.collector.collect(collected_request.clone());
+            state.completion_tracker.request_handled(first_call).await;
+            let handler = handler.map(|handler| state.pick(handler));
+            let response = match handler {
+                Some(Handler::AsyncDynamic(f)) => Some(f(&collected_request).await),
+                Some(Handler::Indexed(f)) => Some(f(call_index)),
+                handler => handler.map(|handler| handler.respond(&collected_request)),
+            };
+            response.map(|mut response| {
+                response.apply_echo_headers(&collected_request.headers);
+                response
+            })
+        }
+    };
+
+    if let Some(mut response) = response {
+        if let Some(cors) = &state.cors {
+            for (key, value) in cors.response_headers() {
+                if !response.headers.keys().any(|k| k.eq_ignore_ascii_case(key)) {
+                    response.headers.insert(key.to_string(), value);
+                }
+            }
+        }
+        // Chunked bodies and bodies followed by trailers are streamed as is
+        let streamed = !response.chunks.is_empty() || !response.trailers.is_empty();
+        let response = match streamed {
+            false => super::compression::compress(response, accept_encoding.as_deref()),
+            true => response,
+        };
         let status = StatusCode::from_u16(response.status).unwrap_or(StatusCode::OK);
         let mut builder = axum::http::Response::builder().status(status);
 
         for (key, value) in &response.headers {
             builder = builder.header(key.as_str(), value.as_str());
         }
+        for cookie in &response.cookies {
+            builder = builder.header("set-cookie", cookie.as_str());
+        }
+        if !response.trailers.is_empty() {
+            let mut names: Vec<&str> = response.trailers.keys().map(String::as_str).collect();
+            names.sort_unstable();
+            builder = builder.header("trailer", names.join(", "));
+        }
 
+        let body = match (streamed, response.chunks.is_empty()) {
+            (false, _) => Body::from(response.body),
+            (true, true) => chunked_body(vec![response.body], Duration::ZERO, response.trailers),
+            (true, false) => chunked_body(response.chunks, response.chunk_delay, response.trailers),
+        };
         builder
-            .body(Body::from(response.body.clone()))
+            .body(body)
             .unwrap_or_else(|_| {
                 axum::http::Response::builder()
                     .status(StatusCode::INTERNAL_SERVER_ERROR)
@@ -162,6 +529,8 @@ async fn handle_request(
                     .unwrap()
             })
     } else {
+        #[cfg(feature = "tracing")]
+        tracing::warn!("no handler matched, the endpoint has no handlers");
         axum::http::Response::builder()
             .status(StatusCode::NOT_FOUND)
             .body(Body::from("No handler configured"))
@@ -169,7 +538,190 @@ async fn handle_request(
     }
 }
 
-fn create_method_router(method: Method) -> MethodRouter<EndpointState> {
+/// Stream `chunks` as separate body frames, pausing `delay` between them,
+/// then `trailers` in a trailers frame unless empty
+fn chunked_body(chunks: Vec<Vec<u8>>, delay: Duration, trailers: HashMap<String, String>) -> Body {
+    let data = futures::stream::iter(chunks.into_iter().enumerate())
+        .then(move |(index, chunk)| async move {
+            if index > 0 {
+                tokio::time::sleep(delay).await;
+            }
+            Frame::data(Bytes::from(chunk))
+        });
+    let trailers = (!trailers.is_empty()).then(|| Frame::trailers(trailer_map(trailers)));
+    let frames = data
+        .chain(futures::stream::iter(trailers))
+        .map(Ok::<_, Infallible>);
+    Body::new(StreamBody::new(frames))
+}
+
+/// Convert trailers to a header map, skipping invalid names and values
+fn trailer_map(trailers: HashMap<String, String>) -> HeaderMap {
+    trailers
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let name = HeaderName::from_bytes(name.as_bytes()).ok()?;
+            Some((name, HeaderValue::from_str(&value).ok()?))
+        })
+        .collect()
+}
+
+/// Answer requests matching no route with a plain 404, collecting them as
+/// unmatched for collectors that ask for it.
+///
+/// They never count towards completion, so probes hitting an unmatched path
+/// do not change when the scenario shuts down.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "request",
+        skip_all,
+        fields(method = %request.method(), path = %request.uri().path())
+    )
+)]
+async fn handle_unmatched(
+    collector: Arc<dyn ErasedCollector>,
+    max_body_size: usize,
+    request: AxumRequest<Body>,
+) -> axum::response::Response {
+    let Some(method) = parse_method(request.method()) else {
+        #[cfg(feature = "tracing")]
+        tracing::warn!("unsupported request method");
+        return not_implemented();
+    };
+    #[cfg(feature = "tracing")]
+    tracing::warn!("no handler matched");
+
+    if collector.collects_unmatched() {
+        let mut collected_request = Request::new(method, request.uri().path());
+        collected_request.headers = header_map(request.headers());
+        collected_request.http_version = format!("{:?}", request.version());
+        collected_request.matched = false;
+        if let Some(ConnectInfo(connection)) = request.extensions().get::<ConnectInfo<ConnectionInfo>>() {
+            connection.record(&mut collected_request);
+        }
+        let body = axum::body::to_bytes(request.into_body(), max_body_size).await;
+        collected_request.body = match body {
+            Ok(bytes) => bytes.to_vec(),
+            Err(_) => return payload_too_large(),
+        };
+        collector.collect(collected_request);
+    }
+    StatusCode::NOT_FOUND.into_response()
+}
+
+/// Map a request method onto the harness methods, `None` for methods the
+/// harness does not model (e.g. `TRACE` or `CONNECT`)
+fn parse_method(method: &axum::http::Method) -> Option<Method> {
+    match method.as_str() {
+        "GET" => Some(Method::Get),
+        "POST" => Some(Method::Post),
+        "PUT" => Some(Method::Put),
+        "PATCH" => Some(Method::Patch),
+        "DELETE" => Some(Method::Delete),
+        "HEAD" => Some(Method::Head),
+        "OPTIONS" => Some(Method::Options),
+        _ => None,
+    }
+}
+
+/// Answer a request whose body exceeds the size limit with 413, without collecting it
+fn payload_too_large() -> axum::response::Response {
+    #[cfg(feature = "tracing")]
+    tracing::warn!("request body too large");
+    axum::http::Response::builder()
+        .status(StatusCode::PAYLOAD_TOO_LARGE)
+        .body(Body::from("Request body too large"))
+        .unwrap()
+}
+
+/// Declared `Content-Length` of a request, if any
+fn content_length(headers: &HashMap<String, String>) -> Option<usize> {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse().ok())
+}
+
+/// Answer a method the harness does not model with 501, without collecting it
+fn not_implemented() -> axum::response::Response {
+    axum::http::Response::builder()
+        .status(StatusCode::NOT_IMPLEMENTED)
+        .body(Body::from("Unsupported request method"))
+        .unwrap()
+}
+
+fn header_map(headers: &axum::http::HeaderMap) -> HashMap<String, String> {
+    headers
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
+        .collect()
+}
+
+/// Run `serve` until it has drained its connections, giving up `grace` after
+/// `draining` fires when a grace period is set
+async fn drain_within<F>(
+    serve: F,
+    draining: oneshot::Receiver<()>,
+    grace: Option<Duration>,
+) -> std::io::Result<()>
+where
+    F: Future<Output = std::io::Result<()>>,
+{
+    let Some(grace) = grace else {
+        return serve.await;
+    };
+    tokio::select! {
+        served = serve => served,
+        _ = async {
+            let _ = draining.await;
+            tokio::time::sleep(grace).await;
+        } => Ok(()),
+    }
+}
+
+/// Per-endpoint call progress, used to report uncalled handlers and
+/// unmet call expectations
+struct EndpointProgress {
+    label: String,
+    path: String,
+    /// Sequential handlers expected to be called
+    sequential_calls: usize,
+    expected_calls: Option<usize>,
+    call_count: Arc<AtomicUsize>,
+    sequential_count: Arc<AtomicUsize>,
+    body_handlers: Arc<Vec<(BodyHandler, AtomicBool)>>,
+}
+
+impl EndpointProgress {
+    fn check_expectation(&self) -> Result<(), HarnessError> {
+        let actual = self.call_count.load(Ordering::SeqCst);
+        match self.expected_calls {
+            Some(expected) if expected != actual => Err(HarnessError::ExpectationFailed {
+                path: self.path.clone(),
+                expected,
+                actual,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn uncalled(&self) -> Vec<String> {
+        let called = self.sequential_count.load(Ordering::SeqCst);
+        let body_uncalled = self
+            .body_handlers
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, called))| !called.load(Ordering::SeqCst))
+            .map(|(index, _)| format!("{} (body handler #{})", self.label, index));
+        (called..self.sequential_calls)
+            .map(|index| format!("{} (handler #{})", self.label, index))
+            .chain(body_uncalled)
+            .collect()
+    }
+}
+
+fn create_method_router(method: Method) -> MethodRouter<RouteState> {
     match method {
         Method::Get => axum::routing::get(handle_request),
         Method::Post => axum::routing::post(handle_request),
@@ -181,77 +733,375 @@ fn create_method_router(method: Method) -> MethodRouter<EndpointState> {
     }
 }
 
-#[async_trait]
-impl Server for Axum {
-    async fn run<C, F>(
+/// Listener bound ahead of serving a scenario
+enum Listener {
+    Tcp(tokio::net::TcpListener),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener, SocketFile),
+}
+
+impl Listener {
+    fn addr(&self) -> Result<BoundAddr, HarnessError> {
+        match self {
+            Listener::Tcp(listener) => listener
+                .local_addr()
+                .map(BoundAddr::Tcp)
+                .map_err(|e| HarnessError::ServerError(e.to_string())),
+            #[cfg(unix)]
+            Listener::Unix(_, SocketFile(path)) => Ok(BoundAddr::Unix(path.clone())),
+        }
+    }
+}
+
+/// A server whose listener is already bound, returned by [`Axum::prepare`]
+pub struct BoundServer {
+    server: Axum,
+    listener: Listener,
+}
+
+impl BoundServer {
+    /// Serve `endpoints` on the bound listener until every handler has been
+    /// called, like [`Server::run`] with default options
+    pub async fn serve<C>(
+        self,
+        endpoints: Vec<Endpoint>,
+        collector: C,
+    ) -> Result<C::Output, HarnessError>
+    where
+        C: Collector + 'static,
+    {
+        self.serve_with_options(endpoints, collector, RunOptions::default())
+            .await
+    }
+
+    /// Serve `endpoints` on the bound listener with `options`
+    pub async fn serve_with_options<C>(
+        self,
+        endpoints: Vec<Endpoint>,
+        collector: C,
+        options: RunOptions,
+    ) -> Result<C::Output, HarnessError>
+    where
+        C: Collector + 'static,
+    {
+        self.server
+            .serve(self.listener, endpoints, collector, options)
+            .await
+    }
+}
+
+impl Axum {
+    /// Bind the listener without serving yet, so the address (e.g. an
+    /// ephemeral port) is known before requests are sent.
+    ///
+    /// Serve a scenario with [`BoundServer::serve`]. Unix sockets have no
+    /// TCP address; use [`Axum::run_bound`] for them.
+    pub async fn prepare(&self) -> Result<(BoundServer, SocketAddr), HarnessError> {
+        let listener = self.listen().await?;
+        let BoundAddr::Tcp(addr) = listener.addr()? else {
+            return Err(HarnessError::ServerError(
+                "prepare needs a TCP address; use Axum::run_bound with a Unix socket".to_string(),
+            ));
+        };
+        let server = BoundServer {
+            server: self.clone(),
+            listener,
+        };
+        Ok((server, addr))
+    }
+
+    /// Run like [`Server::run`], reporting the TCP or Unix socket address
+    /// the server is bound to through `on_ready`.
+    pub async fn run_bound<C, F>(
         &self,
         endpoints: Vec<Endpoint>,
         collector: C,
+        options: RunOptions,
         on_ready: Option<F>,
     ) -> Result<C::Output, HarnessError>
     where
         C: Collector + 'static,
-        F: FnOnce(SocketAddr) + Send + 'static,
+        F: FnOnce(BoundAddr) + Send + 'static,
     {
-        // Wrap collector in Mutex<Option<C>> so we can take it out at the end
-        let collector_holder: Arc<std::sync::Mutex<Option<C>>> =
-            Arc::new(std::sync::Mutex::new(Some(collector)));
+        let listener = self.listen().await?;
+        if let Some(callback) = on_ready {
+            callback(listener.addr()?);
+        }
+        self.serve(listener, endpoints, collector, options).await
+    }
+
+    async fn listen(&self) -> Result<Listener, HarnessError> {
+        #[cfg(unix)]
+        if let Some(path) = &self.uds {
+            let listener = tokio::net::UnixListener::bind(path).map_err(HarnessError::Bind)?;
+            return Ok(Listener::Unix(listener, SocketFile(path.clone())));
+        }
+
+        if let Some(listener) = &self.listener {
+            let listener = listener
+                .try_clone()
+                .and_then(|listener| {
+                    listener.set_nonblocking(true)?;
+                    tokio::net::TcpListener::from_std(listener)
+                })
+                .map_err(HarnessError::Bind)?;
+            return Ok(Listener::Tcp(listener));
+        }
+
+        let listener = tokio::net::TcpListener::bind(self.addr)
+            .await
+            .map_err(HarnessError::Bind)?;
+        Ok(Listener::Tcp(listener))
+    }
+
+    async fn serve<C>(
+        &self,
+        listener: Listener,
+        endpoints: Vec<Endpoint>,
+        collector: C,
+        options: RunOptions,
+    ) -> Result<C::Output, HarnessError>
+    where
+        C: Collector + 'static,
+    {
+        // Wrap collector in a Mutex so we can take it out at the end
+        let collector_holder = Arc::new(std::sync::Mutex::new(CollectorSlot {
+            collector: Some(collector),
+            next_sequence: 0,
+            on_request: options.on_request.clone(),
+        }));
         let erased_collector: Arc<dyn ErasedCollector> = collector_holder.clone();
 
-        // Count total handlers
-        let total_handlers: usize = endpoints.iter().map(|e| e.handlers.len().max(1)).sum();
+        // Count total handlers; endpoints with a call expectation complete
+        // once called that many times. Endpoints answering by body need no
+        // sequential handler.
+        let sequential_calls = |e: &Endpoint| {
+            match e.handlers.is_empty() && !e.body_handlers.is_empty() {
+                true => 0,
+                false => CompletionTracker::expected_calls(e.handlers.len()),
+            }
+        };
+        let completion_calls = |e: &Endpoint| {
+            e.expected_calls
+                .unwrap_or_else(|| sequential_calls(e) + e.body_handlers.len())
+        };
+        let preflights = crate::entities::cors::preflights(&endpoints);
+        let total_handlers: usize = endpoints.iter().map(completion_calls).sum::<usize>()
+            + preflights.iter().filter(|(_, counted)| *counted).count();
 
         // Create shutdown channel for auto-shutdown
         let (auto_shutdown_tx, auto_shutdown_rx) = oneshot::channel();
-        let completion_tracker = CompletionTracker::new(total_handlers, auto_shutdown_tx);
+        let mut completion_tracker = CompletionTracker::new(total_handlers, auto_shutdown_tx);
+        if let Some(expected) = options.expected_requests {
+            completion_tracker = completion_tracker.counting_requests(expected);
+        }
+        let requests_handled = completion_tracker.handlers_called();
 
-        let mut router: Router<EndpointState> = Router::new();
+        let max_body_size = options.max_body_size.unwrap_or(DEFAULT_MAX_BODY_SIZE);
+        // Each endpoint draws from its own generator, so concurrent calls to
+        // other endpoints leave its sequence unchanged
+        let seed = options.seed;
+        let rng = |index: usize| {
+            let rng = match seed {
+                Some(seed) => SmallRng::seed_from_u64(seed.wrapping_add(index as u64)),
+                None => SmallRng::from_os_rng(),
+            };
+            Arc::new(std::sync::Mutex::new(rng))
+        };
+        let mut routes: Vec<(String, Method, Vec<EndpointState>)> = Vec::new();
+        let mut progress = Vec::with_capacity(endpoints.len());
 
-        for endpoint in endpoints {
+        // Uncounted preflights neither complete the scenario nor report as uncalled
+        let endpoints = endpoints.into_iter().map(|e| (e, true)).chain(preflights);
+        for (index, (endpoint, counted)) in endpoints.enumerate() {
+            let endpoint_completion_calls = match counted {
+                true => endpoint.expected_calls.unwrap_or_else(|| sequential_calls(&endpoint)),
+                false => 0,
+            };
+            let endpoint_sequential_calls = sequential_calls(&endpoint);
+            let body_handlers = endpoint
+                .body_handlers
+                .into_iter()
+                .map(|body_handler| (body_handler, AtomicBool::new(false)))
+                .collect();
             let state = EndpointState {
                 handlers: Arc::new(endpoint.handlers),
+                body_handlers: Arc::new(body_handlers),
+                cycle: endpoint.cycle,
+                header_matches: Arc::new(endpoint.header_matches),
                 call_count: Arc::new(AtomicUsize::new(0)),
+                sequential_count: Arc::new(AtomicUsize::new(0)),
+                completion_calls: endpoint_completion_calls,
+                expects_calls: endpoint.expected_calls.is_some(),
+                gate: endpoint.serialized.then(|| Arc::new(Mutex::new(()))),
+                cors: endpoint.cors.map(Arc::new),
+                max_body_size,
+                is_default: false,
+                rng: rng(index),
                 collector: erased_collector.clone(),
                 completion_tracker: completion_tracker.clone(),
             };
+            if counted {
+                progress.push(EndpointProgress {
+                    label: format!("{} {}", endpoint.method, endpoint.path),
+                    path: endpoint.path.clone(),
+                    sequential_calls: endpoint_sequential_calls,
+                    expected_calls: endpoint.expected_calls,
+                    call_count: state.call_count.clone(),
+                    sequential_count: state.sequential_count.clone(),
+                    body_handlers: state.body_handlers.clone(),
+                });
+            }
+
+            // Group endpoints sharing a path and method into one route; aliases
+            // share the endpoint's state
+            for endpoint_path in std::iter::once(endpoint.path).chain(endpoint.aliases) {
+                match routes
+                    .iter_mut()
+                    .find(|(path, method, _)| *path == endpoint_path && *method == endpoint.method)
+                {
+                    Some((_, _, states)) => states.push(state.clone()),
+                    None => routes.push((endpoint_path, endpoint.method, vec![state.clone()])),
+                }
+            }
+        }
+
+        let mut router: Router<RouteState> = Router::new();
+        for (path, method, states) in routes {
+            let state = RouteState {
+                endpoints: Arc::new(states),
+            };
+            router = router
+                .route(&path, create_method_router(method))
+                .with_state(state);
+        }
 
-            let method_router = create_method_router(endpoint.method);
-            router = router.route(&endpoint.path, method_router).with_state(state);
+        // Requests matching no route, or a route's path but not its method,
+        // go to the default handler
+        let mut fallback = Vec::new();
+        if let Some(handler) = options.default_handler {
+            fallback.push(EndpointState {
+                handlers: Arc::new(vec![handler]),
+                body_handlers: Arc::new(Vec::new()),
+                cycle: false,
+                header_matches: Arc::new(Vec::new()),
+                call_count: Arc::new(AtomicUsize::new(0)),
+                sequential_count: Arc::new(AtomicUsize::new(0)),
+                completion_calls: 0,
+                expects_calls: false,
+                gate: None,
+                cors: None,
+                max_body_size,
+                is_default: true,
+                rng: rng(usize::MAX),
+                collector: erased_collector.clone(),
+                completion_tracker: completion_tracker.clone(),
+            });
+            router = router
+                .fallback(handle_request)
+                .method_not_allowed_fallback(handle_request);
+        }
+        if fallback.is_empty() {
+            let collector = erased_collector.clone();
+            router = router
+                .fallback(move |request| handle_unmatched(collector, max_body_size, request));
         }
 
         // Convert to Router<()> for serving
-        let router = router.with_state(EndpointState {
-            handlers: Arc::new(vec![]),
-            call_count: Arc::new(AtomicUsize::new(0)),
-            collector: erased_collector.clone(),
-            completion_tracker: completion_tracker.clone(),
+        let router = router.with_state(RouteState {
+            endpoints: Arc::new(fallback),
         });
+        let router = self
+            .router_layers
+            .iter()
+            .fold(router, |router, layer| layer(router));
 
-        let listener = tokio::net::TcpListener::bind(self.addr)
-            .await
-            .map_err(|e| HarnessError::ServerError(e.to_string()))?;
+        // Connections left open at shutdown drain until the grace period ends
+        let (draining_tx, draining_rx) = oneshot::channel();
+        let manual_shutdown = options.manual_shutdown;
+        let shutdown = async {
+            match manual_shutdown {
+                Some(trigger) => {
+                    trigger.triggered().await;
+                    #[cfg(feature = "tracing")]
+                    tracing::info!("shutdown triggered, shutting down");
+                }
+                None => {
+                    if auto_shutdown_rx.await.is_ok() {
+                        #[cfg(feature = "tracing")]
+                        tracing::info!("scenario complete, shutting down");
+                    }
+                }
+            }
+            let _ = draining_tx.send(());
+        };
 
-        let addr = listener
-            .local_addr()
-            .map_err(|e| HarnessError::ServerError(e.to_string()))?;
+        // Serve and wait for auto-shutdown; the Unix socket file is removed
+        // once serving ends
+        #[cfg(unix)]
+        let mut _socket_file = None;
 
-        // Call the on_ready callback if provided
-        if let Some(callback) = on_ready {
-            callback(addr);
-        }
+        let serve = match listener {
+            #[cfg(unix)]
+            Listener::Unix(listener, socket_file) => {
+                _socket_file = Some(socket_file);
+                axum::serve(listener, router.into_make_service_with_connect_info::<ConnectionInfo>())
+                    .with_graceful_shutdown(shutdown)
+                    .into_future()
+            }
+            Listener::Tcp(listener) => {
+                #[cfg(feature = "tls")]
+                if let Some(config) = &self.tls {
+                    let listener = super::tls::TlsListener::new(listener, config.clone())
+                        .map_err(|e| HarnessError::ServerError(e.to_string()))?;
+                    axum::serve(listener, router.into_make_service_with_connect_info::<ConnectionInfo>())
+                        .with_graceful_shutdown(shutdown)
+                        .into_future()
+                } else {
+                    axum::serve(listener, router.into_make_service_with_connect_info::<ConnectionInfo>())
+                        .with_graceful_shutdown(shutdown)
+                        .into_future()
+                }
+                #[cfg(not(feature = "tls"))]
+                axum::serve(listener, router.into_make_service_with_connect_info::<ConnectionInfo>())
+                    .with_graceful_shutdown(shutdown)
+                    .into_future()
+            }
+        };
 
-        // Serve and wait for auto-shutdown
-        axum::serve(listener, router)
-            .with_graceful_shutdown(async {
-                auto_shutdown_rx.await.ok();
-            })
-            .await
-            .map_err(|e| HarnessError::ServerError(e.to_string()))?;
+        let serve = drain_within(serve, draining_rx, options.shutdown_grace);
+
+        let served = match options.all_called_within {
+            Some(grace) => match tokio::time::timeout(grace, serve).await {
+                Ok(served) => served,
+                Err(_) => {
+                    for endpoint in &progress {
+                        endpoint.check_expectation()?;
+                    }
+                    let mut uncalled: Vec<String> =
+                        progress.iter().flat_map(EndpointProgress::uncalled).collect();
+                    if let Some(expected) = options.expected_requests {
+                        uncalled.push(format!(
+                            "{} of {} expected requests received",
+                            requests_handled.load(Ordering::SeqCst),
+                            expected
+                        ));
+                    }
+                    return Err(HarnessError::IncompleteScenario(uncalled));
+                }
+            },
+            None => serve.await,
+        };
+        served.map_err(|e| HarnessError::Serve(Box::new(e)))?;
+        for endpoint in &progress {
+            endpoint.check_expectation()?;
+        }
 
         // Extract the collector and return its output
         let collector = collector_holder
             .lock()
             .map_err(|e| HarnessError::ServerError(e.to_string()))?
+            .collector
             .take()
             .ok_or_else(|| HarnessError::ServerError("Collector already taken".to_string()))?;
 
@@ -259,3 +1109,34 @@ impl Server for Axum {
     }
 }
 
+#[async_trait]
+impl Server for Axum {
+    async fn run<C, F>(
+        &self,
+        endpoints: Vec<Endpoint>,
+        collector: C,
+        options: RunOptions,
+        on_ready: Option<F>,
+    ) -> Result<C::Output, HarnessError>
+    where
+        C: Collector + 'static,
+        F: FnOnce(SocketAddr) + Send + 'static,
+    {
+        #[cfg(unix)]
+        if self.uds.is_some() && on_ready.is_some() {
+            return Err(HarnessError::ServerError(
+                "on_ready needs a TCP address; use Axum::run_bound with a Unix socket".to_string(),
+            ));
+        }
+
+        let on_ready = on_ready.map(|callback| {
+            move |addr| {
+                if let BoundAddr::Tcp(addr) = addr {
+                    callback(addr);
+                }
+            }
+        });
+        self.run_bound(endpoints, collector, options, on_ready).await
+    }
+}
+
@@ -2,4 +2,7 @@
 pub mod axum;
 
 #[cfg(feature = "axum")]
-pub use self::axum::Axum;
+pub use self::axum::{Axum, BoundServer};
+
+#[cfg(feature = "tls")]
+pub use self::axum::{rustls_test_config, TEST_CERT_PEM};
@@ -0,0 +1,240 @@
+//! Assertions over the requests collected by a scenario
+
+use serde_json::Value;
+
+pub use server_harness_core::RequestLog;
+use server_harness_core::LogEntry;
+
+use crate::entities::Request;
+use crate::matchers::JsonMatcher;
+
+impl LogEntry for Request {
+    fn summary(&self) -> String {
+        format!("{:?} {}", self.method, self.path)
+    }
+}
+
+/// HTTP assertions on a [`RequestLog`], panicking with readable messages
+///
+/// ```
+/// use http_endpoint_server_harness::prelude::*;
+///
+/// let log = RequestLog::from(vec![
+///     Request::new(Method::Post, "/api/users").with_body(r#"{"name": "Alice", "id": 1}"#),
+/// ]);
+/// log.assert_count(1)
+///     .assert_path(0, "/api/users")
+///     .assert_json_body(0, &json!({"id": 1, "name": "Alice"}));
+/// ```
+pub trait RequestAssertions {
+    /// Assert that the request at `index` was sent to `expected`
+    #[track_caller]
+    fn assert_path(&self, index: usize, expected: &str) -> &Self;
+
+    /// Assert that the body of the request at `index` is JSON equal to `expected`.
+    ///
+    /// Values are compared as [`serde_json::Value`], so key order does not matter.
+    #[track_caller]
+    fn assert_json_body(&self, index: usize, expected: &Value) -> &Self;
+
+    /// Assert that the body of the request at `index` is JSON conforming to `matcher`,
+    /// e.g. to ignore generated ids or timestamps.
+    #[track_caller]
+    fn assert_json_matches(&self, index: usize, matcher: &JsonMatcher) -> &Self;
+}
+
+impl RequestAssertions for RequestLog<Request> {
+    #[track_caller]
+    fn assert_path(&self, index: usize, expected: &str) -> &Self {
+        let request = self.request(index);
+        if request.path != expected {
+            panic!(
+                "request {} path mismatch\n  expected: {}\n  actual:   {}",
+                index, expected, request.path
+            );
+        }
+        self
+    }
+
+    #[track_caller]
+    fn assert_json_body(&self, index: usize, expected: &Value) -> &Self {
+        let actual = json_body(index, self.request(index));
+
+        if &actual != expected {
+            let mut differences = Vec::new();
+            diff_values("$", expected, &actual, &mut differences);
+            panic!(
+                "request {} JSON body mismatch\n{}\nexpected:\n{}\nactual:\n{}",
+                index,
+                differences.join("\n"),
+                pretty(expected),
+                pretty(&actual)
+            );
+        }
+        self
+    }
+
+    #[track_caller]
+    fn assert_json_matches(&self, index: usize, matcher: &JsonMatcher) -> &Self {
+        let actual = json_body(index, self.request(index));
+        let mismatches = matcher.mismatches(&actual);
+        if !mismatches.is_empty() {
+            panic!(
+                "request {} JSON body does not match\n{}\nactual:\n{}",
+                index,
+                mismatches.join("\n"),
+                pretty(&actual)
+            );
+        }
+        self
+    }
+}
+
+#[track_caller]
+fn json_body(index: usize, request: &Request) -> Value {
+    match serde_json::from_slice(&request.body) {
+        Ok(actual) => actual,
+        Err(e) => panic!(
+            "request {} body is not JSON ({}): {}",
+            index,
+            e,
+            String::from_utf8_lossy(&request.body)
+        ),
+    }
+}
+
+fn pretty(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+}
+
+/// Describe every place `actual` differs from `expected`, one line per difference
+fn diff_values(path: &str, expected: &Value, actual: &Value, differences: &mut Vec<String>) {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (key, expected_value) in expected {
+                let field = format!("{}.{}", path, key);
+                match actual.get(key) {
+                    Some(actual_value) => {
+                        diff_values(&field, expected_value, actual_value, differences)
+                    }
+                    None => differences.push(format!("  {}: missing", field)),
+                }
+            }
+            for key in actual.keys().filter(|key| !expected.contains_key(*key)) {
+                differences.push(format!("  {}.{}: unexpected", path, key));
+            }
+        }
+        (Value::Array(expected), Value::Array(actual)) if expected.len() == actual.len() => {
+            for (i, (expected_item, actual_item)) in expected.iter().zip(actual).enumerate() {
+                diff_values(
+                    &format!("{}[{}]", path, i),
+                    expected_item,
+                    actual_item,
+                    differences,
+                );
+            }
+        }
+        _ if expected != actual => {
+            differences.push(format!("  {}: expected {}, got {}", path, expected, actual))
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::Method;
+    use serde_json::json;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    fn log() -> RequestLog<Request> {
+        RequestLog::from(vec![
+            Request::new(Method::Post, "/api/users")
+                .with_body(r#"{"name": "Alice", "roles": ["admin"], "id": 1}"#),
+            Request::new(Method::Get, "/api/health"),
+        ])
+    }
+
+    fn panic_message(f: impl FnOnce()) -> String {
+        let error = catch_unwind(AssertUnwindSafe(f)).expect_err("assertion should fail");
+        error.downcast_ref::<String>().cloned().unwrap_or_default()
+    }
+
+    #[test]
+    fn test_assertions_pass() {
+        let log = log();
+        log.assert_count(2)
+            .assert_path(0, "/api/users")
+            .assert_path(1, "/api/health")
+            .assert_json_body(0, &json!({"id": 1, "roles": ["admin"], "name": "Alice"}));
+        assert_eq!(log.len(), 2);
+        assert_eq!(log.into_inner()[1].path, "/api/health");
+    }
+
+    #[test]
+    fn test_assert_count_lists_received_requests() {
+        let message = panic_message(|| {
+            log().assert_count(1);
+        });
+        assert!(message.contains("expected 1 requests, got 2"));
+        assert!(message.contains("Post /api/users"));
+        assert!(message.contains("Get /api/health"));
+    }
+
+    #[test]
+    fn test_assert_path_mismatch() {
+        let message = panic_message(|| {
+            log().assert_path(1, "/api/status");
+        });
+        assert_eq!(
+            message,
+            "request 1 path mismatch\n  expected: /api/status\n  actual:   /api/health"
+        );
+    }
+
+    #[test]
+    fn test_assert_out_of_range() {
+        let message = panic_message(|| {
+            log().assert_path(5, "/api/users");
+        });
+        assert_eq!(message, "no request at index 5, only 2 collected");
+    }
+
+    #[test]
+    fn test_assert_json_body_lists_differences() {
+        let message = panic_message(|| {
+            log().assert_json_body(
+                0,
+                &json!({"id": 2, "name": "Alice", "roles": ["user"], "email": null}),
+            );
+        });
+        assert!(message.starts_with("request 0 JSON body mismatch\n"));
+        assert!(message.contains("  $.id: expected 2, got 1"));
+        assert!(message.contains("  $.roles[0]: expected \"user\", got \"admin\""));
+        assert!(message.contains("  $.email: missing"));
+        assert!(message.contains("actual:\n{"));
+    }
+
+    #[test]
+    fn test_assert_json_matches() {
+        use crate::matchers::{any_number, any_string};
+
+        let matcher = JsonMatcher::object([("id", any_number()), ("name", "Alice".into())]);
+        log().assert_json_matches(0, &matcher);
+
+        let message = panic_message(|| {
+            log().assert_json_matches(0, &JsonMatcher::object([("id", any_string())]));
+        });
+        assert!(message.starts_with("request 0 JSON body does not match\n"));
+        assert!(message.contains("  $.id: expected any string, got 1"));
+    }
+
+    #[test]
+    fn test_assert_json_body_not_json() {
+        let message = panic_message(|| {
+            log().assert_json_body(1, &json!({}));
+        });
+        assert!(message.starts_with("request 1 body is not JSON"));
+    }
+}
@@ -0,0 +1,116 @@
+//! Collapse identical requests, e.g. to count client retries in idempotency tests
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::entities::{Method, Request};
+use server_harness_core::{Collector, Snapshot};
+
+/// Identity of a request for deduplication: method, path and body
+///
+/// Headers are left out, so retries differing only in e.g. a timestamp or
+/// attempt header still count as the same request.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RequestKey {
+    pub method: Method,
+    pub path: String,
+    pub body: Vec<u8>,
+}
+
+impl From<&Request> for RequestKey {
+    fn from(request: &Request) -> Self {
+        Self {
+            method: request.method,
+            path: request.path.clone(),
+            body: request.body.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Distinct {
+    requests: Vec<(Request, usize)>,
+    index: HashMap<RequestKey, usize>,
+}
+
+/// Collector keeping each distinct request once with the number of times it
+/// was received
+///
+/// `into_output` returns the first occurrence of every [`RequestKey`] in
+/// arrival order, paired with its count.
+#[derive(Debug, Default)]
+pub struct DedupCollector {
+    distinct: Mutex<Distinct>,
+}
+
+impl DedupCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Collector<Request> for DedupCollector {
+    type Output = Vec<(Request, usize)>;
+
+    fn collect(&self, request: Request) {
+        if let Ok(mut distinct) = self.distinct.lock() {
+            let Distinct { requests, index } = &mut *distinct;
+            match index.get(&RequestKey::from(&request)) {
+                Some(&position) => requests[position].1 += 1,
+                None => {
+                    index.insert(RequestKey::from(&request), requests.len());
+                    requests.push((request, 1));
+                }
+            }
+        }
+    }
+
+    fn into_output(self) -> Self::Output {
+        self.distinct
+            .into_inner()
+            .map(|distinct| distinct.requests)
+            .unwrap_or_default()
+    }
+}
+
+impl Snapshot<Request> for DedupCollector {
+    fn snapshot(&self) -> Self::Output {
+        self.distinct
+            .lock()
+            .map(|distinct| distinct.requests.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dedup_counts_identical_requests() {
+        let collector = DedupCollector::new();
+        collector.collect(Request::new(Method::Post, "/api/pay").with_body("{\"id\":1}"));
+        collector.collect(Request::new(Method::Post, "/api/pay").with_body("{\"id\":2}"));
+        collector.collect(
+            Request::new(Method::Post, "/api/pay")
+                .with_body("{\"id\":1}")
+                .with_header("x-attempt", "2"),
+        );
+        collector.collect(Request::new(Method::Put, "/api/pay").with_body("{\"id\":1}"));
+
+        let distinct = collector.into_output();
+        let counts: Vec<_> = distinct
+            .iter()
+            .map(|(request, count)| (request.method, request.body_as_str().unwrap(), *count))
+            .collect();
+        assert_eq!(
+            counts,
+            [
+                (Method::Post, "{\"id\":1}", 2),
+                (Method::Post, "{\"id\":2}", 1),
+                (Method::Put, "{\"id\":1}", 1),
+            ]
+        );
+        assert!(distinct[0].0.headers.is_empty(), "keeps the first occurrence");
+    }
+}
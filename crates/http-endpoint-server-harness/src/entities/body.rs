@@ -0,0 +1,51 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Serialize a body as a string, or as `{"base64": ...}` when it is not UTF-8
+pub(super) fn serialize<S: Serializer>(body: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    match std::str::from_utf8(body) {
+        Ok(text) => serializer.serialize_str(text),
+        Err(_) => {
+            let mut map = std::collections::BTreeMap::new();
+            map.insert("base64", BASE64.encode(body));
+            map.serialize(serializer)
+        }
+    }
+}
+
+/// Deserialize a body written by [`serialize`]
+pub(super) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Body {
+        Text(String),
+        Base64 { base64: String },
+    }
+
+    match Body::deserialize(deserializer)? {
+        Body::Text(text) => Ok(text.into_bytes()),
+        Body::Base64 { base64 } => BASE64.decode(base64).map_err(serde::de::Error::custom),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Wrapper(#[serde(with = "super")] Vec<u8>);
+
+    #[test]
+    fn test_body_round_trip() {
+        for body in [b"hello".to_vec(), vec![0xde, 0xad, 0xbe, 0xef]] {
+            let value = serde_json::to_value(Wrapper(body.clone())).unwrap();
+            assert_eq!(serde_json::from_value::<Wrapper>(value).unwrap(), Wrapper(body));
+        }
+    }
+
+    #[test]
+    fn test_body_rejects_invalid_base64() {
+        let value = serde_json::json!({"base64": "not base64!"});
+        assert!(serde_json::from_value::<Wrapper>(value).is_err());
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::Duration;
+
+/// `SameSite` attribute of a cookie
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+impl SameSite {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        }
+    }
+}
+
+/// Attributes of a cookie set with [`Response::with_cookie`](super::Response::with_cookie)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CookieAttributes {
+    pub path: Option<String>,
+    pub domain: Option<String>,
+    pub max_age: Option<Duration>,
+    pub http_only: bool,
+    pub secure: bool,
+    pub same_site: Option<SameSite>,
+}
+
+impl CookieAttributes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn path(mut self, path: impl Into<String>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    pub fn domain(mut self, domain: impl Into<String>) -> Self {
+        self.domain = Some(domain.into());
+        self
+    }
+
+    /// Expire the cookie after `max_age`; zero asks the client to delete it
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    pub fn http_only(mut self) -> Self {
+        self.http_only = true;
+        self
+    }
+
+    pub fn secure(mut self) -> Self {
+        self.secure = true;
+        self
+    }
+
+    pub fn same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = Some(same_site);
+        self
+    }
+}
+
+/// Build a `Set-Cookie` header value
+pub(crate) fn set_cookie(name: &str, value: &str, attributes: &CookieAttributes) -> String {
+    let mut cookie = format!("{}={}", name, value);
+    if let Some(path) = &attributes.path {
+        let _ = write!(cookie, "; Path={}", path);
+    }
+    if let Some(domain) = &attributes.domain {
+        let _ = write!(cookie, "; Domain={}", domain);
+    }
+    if let Some(max_age) = attributes.max_age {
+        let _ = write!(cookie, "; Max-Age={}", max_age.as_secs());
+    }
+    if attributes.http_only {
+        cookie.push_str("; HttpOnly");
+    }
+    if attributes.secure {
+        cookie.push_str("; Secure");
+    }
+    if let Some(same_site) = attributes.same_site {
+        let _ = write!(cookie, "; SameSite={}", same_site.as_str());
+    }
+    cookie
+}
+
+/// Parse a `Cookie` header into name/value pairs. Pairs without `=` are
+/// skipped and the last of duplicate names wins.
+pub(crate) fn parse(header: &str) -> HashMap<String, String> {
+    header
+        .split(';')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(name, value)| (name.trim(), value.trim()))
+        .filter(|(name, _)| !name.is_empty())
+        .map(|(name, value)| (name.to_string(), value.trim_matches('"').to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let cookies = parse("session=abc123; theme=\"dark\";flag; =x; lang=en=US");
+        assert_eq!(cookies.len(), 3);
+        assert_eq!(cookies["session"], "abc123");
+        assert_eq!(cookies["theme"], "dark");
+        assert_eq!(cookies["lang"], "en=US");
+    }
+
+    #[test]
+    fn test_set_cookie() {
+        assert_eq!(set_cookie("a", "1", &CookieAttributes::new()), "a=1");
+        let attributes = CookieAttributes::new()
+            .path("/")
+            .domain("example.com")
+            .max_age(Duration::from_secs(3600))
+            .http_only()
+            .secure()
+            .same_site(SameSite::Lax);
+        assert_eq!(
+            set_cookie("session", "xyz", &attributes),
+            "session=xyz; Path=/; Domain=example.com; Max-Age=3600; HttpOnly; Secure; SameSite=Lax"
+        );
+    }
+}
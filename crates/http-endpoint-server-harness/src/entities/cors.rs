@@ -0,0 +1,204 @@
+use super::Method;
+#[cfg(feature = "axum")]
+use super::{Endpoint, Handler, Request, Response};
+#[cfg(feature = "axum")]
+use std::sync::Arc;
+use std::time::Duration;
+
+/// CORS policy of an endpoint, see [`Endpoint::with_cors`](super::Endpoint::with_cors)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllowConfig {
+    /// Value of `Access-Control-Allow-Origin`
+    pub origin: String,
+    /// Allowed methods; empty allows the methods of the endpoints sharing the path
+    pub methods: Vec<Method>,
+    /// Allowed headers; empty echoes the preflight's `Access-Control-Request-Headers`
+    pub headers: Vec<String>,
+    /// Whether `Access-Control-Allow-Credentials: true` is sent
+    pub credentials: bool,
+    /// How long browsers may cache the preflight response
+    pub max_age: Option<Duration>,
+    /// Whether the preflight counts as a handler towards auto-shutdown
+    pub count_preflight: bool,
+}
+
+impl Default for AllowConfig {
+    fn default() -> Self {
+        Self::any_origin()
+    }
+}
+
+impl AllowConfig {
+    /// Allow requests from any origin (`*`)
+    pub fn any_origin() -> Self {
+        Self::origin("*")
+    }
+
+    /// Allow requests from a single origin, e.g. `https://app.example.com`
+    pub fn origin(origin: impl Into<String>) -> Self {
+        Self {
+            origin: origin.into(),
+            methods: Vec::new(),
+            headers: Vec::new(),
+            credentials: false,
+            max_age: None,
+            count_preflight: false,
+        }
+    }
+
+    pub fn allow_methods(mut self, methods: impl IntoIterator<Item = Method>) -> Self {
+        self.methods.extend(methods);
+        self
+    }
+
+    pub fn allow_headers<I, S>(mut self, headers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.headers.extend(headers.into_iter().map(Into::into));
+        self
+    }
+
+    /// Allow credentials; browsers then require a concrete origin rather than `*`
+    pub fn allow_credentials(mut self) -> Self {
+        self.credentials = true;
+        self
+    }
+
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Count the preflight like any other handler, so the scenario keeps
+    /// running until it has been answered
+    pub fn counting_preflight(mut self) -> Self {
+        self.count_preflight = true;
+        self
+    }
+
+    /// Headers added to the endpoint's actual responses
+    #[cfg(feature = "axum")]
+    pub(crate) fn response_headers(&self) -> Vec<(&'static str, String)> {
+        let mut headers = vec![("access-control-allow-origin", self.origin.clone())];
+        if self.credentials {
+            headers.push(("access-control-allow-credentials", "true".to_string()));
+        }
+        headers
+    }
+
+    /// Answer a preflight with 204, allowing `methods` unless methods are configured
+    #[cfg(feature = "axum")]
+    fn preflight(&self, methods: &[Method], request: &Request) -> Response {
+        let methods = if self.methods.is_empty() { methods } else { &self.methods };
+        let methods = methods
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut response = Response::new(204).with_header("access-control-allow-methods", methods);
+        for (key, value) in self.response_headers() {
+            response = response.with_header(key, value);
+        }
+
+        let headers = match self.headers.is_empty() {
+            true => request.header("access-control-request-headers").map(str::to_string),
+            false => Some(self.headers.join(", ")),
+        };
+        if let Some(headers) = headers {
+            response = response.with_header("access-control-allow-headers", headers);
+        }
+        if let Some(max_age) = self.max_age {
+            response = response.with_header("access-control-max-age", max_age.as_secs().to_string());
+        }
+        response
+    }
+}
+
+/// Build one `OPTIONS` endpoint per path with a CORS policy, paired with
+/// whether it counts towards auto-shutdown.
+///
+/// The first policy registered for a path answers its preflights, allowing
+/// the methods of every endpoint with a policy on that path. Paths with an
+/// explicit `OPTIONS` endpoint are left alone.
+#[cfg(feature = "axum")]
+pub(crate) fn preflights(endpoints: &[Endpoint]) -> Vec<(Endpoint, bool)> {
+    let mut policies: Vec<(&str, &AllowConfig, Vec<Method>)> = Vec::new();
+    for endpoint in endpoints {
+        let Some(cors) = &endpoint.cors else {
+            continue;
+        };
+        for endpoint_path in endpoint.paths() {
+            match policies.iter_mut().find(|(path, _, _)| *path == endpoint_path) {
+                Some((_, _, methods)) if !methods.contains(&endpoint.method) => {
+                    methods.push(endpoint.method)
+                }
+                Some(_) => {}
+                None => policies.push((endpoint_path, cors, vec![endpoint.method])),
+            }
+        }
+    }
+
+    policies
+        .into_iter()
+        .filter(|(path, _, _)| {
+            !endpoints
+                .iter()
+                .any(|e| e.method == Method::Options && e.paths().any(|p| p == *path))
+        })
+        .map(|(path, cors, methods)| {
+            let counted = cors.count_preflight;
+            let cors = Arc::new(cors.clone());
+            let handler = Handler::dynamic(move |request| cors.preflight(&methods, request));
+            (Endpoint::new(path, Method::Options).with_handler(handler), counted)
+        })
+        .collect()
+}
+
+#[cfg(all(test, feature = "axum"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preflight_echoes_requested_headers() {
+        let cors = AllowConfig::origin("https://app.example.com")
+            .allow_credentials()
+            .max_age(Duration::from_secs(600));
+        let mut request = Request::new(Method::Options, "/api/users");
+        request
+            .headers
+            .insert("access-control-request-headers".to_string(), "x-token".to_string());
+
+        let response = cors.preflight(&[Method::Get, Method::Post], &request);
+        assert_eq!(response.status, 204);
+        assert_eq!(response.headers["access-control-allow-origin"], "https://app.example.com");
+        assert_eq!(response.headers["access-control-allow-methods"], "GET, POST");
+        assert_eq!(response.headers["access-control-allow-headers"], "x-token");
+        assert_eq!(response.headers["access-control-allow-credentials"], "true");
+        assert_eq!(response.headers["access-control-max-age"], "600");
+    }
+
+    #[test]
+    fn test_preflights_merge_methods_per_path() {
+        let endpoints = vec![
+            Endpoint::new("/api/users", Method::Get).with_cors(AllowConfig::any_origin()),
+            Endpoint::new("/api/users", Method::Post).with_cors(AllowConfig::any_origin()),
+            Endpoint::new("/api/orders", Method::Get).with_cors(AllowConfig::any_origin()),
+            Endpoint::new("/api/orders", Method::Options),
+            Endpoint::new("/api/health", Method::Get),
+        ];
+
+        let preflights = preflights(&endpoints);
+        assert_eq!(preflights.len(), 1);
+        let (endpoint, counted) = &preflights[0];
+        assert_eq!(endpoint.path, "/api/users");
+        assert_eq!(endpoint.method, Method::Options);
+        assert!(!counted);
+
+        let response = endpoint.handlers[0].respond(&Request::new(Method::Options, "/api/users"));
+        assert_eq!(response.headers["access-control-allow-methods"], "GET, POST");
+        assert!(!response.headers.contains_key("access-control-allow-headers"));
+    }
+}
@@ -1,22 +1,138 @@
-use super::{Handler, Method};
+use super::{AllowConfig, Handler, Method, Response};
+use crate::error::HarnessError;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Type alias for header predicate functions.
+/// Header names in the map are lowercase.
+pub type HeaderPredicateFn = Arc<dyn Fn(&HashMap<String, String>) -> bool + Send + Sync>;
+
+/// Type alias for request body predicate functions
+pub type BodyPredicateFn = Arc<dyn Fn(&[u8]) -> bool + Send + Sync>;
+
+/// A handler answering the requests whose body satisfies a predicate,
+/// see [`Endpoint::when_body`]
+#[derive(Clone)]
+pub struct BodyHandler {
+    pub predicate: BodyPredicateFn,
+    pub handler: Handler,
+}
+
+impl std::fmt::Debug for BodyHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BodyHandler")
+            .field("predicate", &"<fn>")
+            .field("handler", &self.handler)
+            .finish()
+    }
+}
+
+/// A condition on request headers that an endpoint requires
+#[derive(Clone)]
+pub enum HeaderMatch {
+    /// The header (name matched case-insensitively) has exactly this value
+    Equals { name: String, value: String },
+    /// The headers satisfy a custom predicate
+    Predicate(HeaderPredicateFn),
+}
+
+impl std::fmt::Debug for HeaderMatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeaderMatch::Equals { name, value } => f
+                .debug_struct("Equals")
+                .field("name", name)
+                .field("value", value)
+                .finish(),
+            HeaderMatch::Predicate(_) => f.debug_tuple("Predicate").field(&"<fn>").finish(),
+        }
+    }
+}
+
+impl HeaderMatch {
+    /// Check the condition against request headers
+    pub fn matches(&self, headers: &HashMap<String, String>) -> bool {
+        match self {
+            HeaderMatch::Equals { name, value } => headers
+                .iter()
+                .any(|(key, actual)| key.eq_ignore_ascii_case(name) && actual == value),
+            HeaderMatch::Predicate(f) => f(headers),
+        }
+    }
+}
 
 /// Represents an HTTP endpoint with a path, method, and handlers
 #[derive(Debug, Clone)]
 pub struct Endpoint {
     pub path: String,
+    /// Additional paths served by the same handlers, see [`Endpoint::with_path`]
+    pub aliases: Vec<String>,
     pub method: Method,
     pub handlers: Vec<Handler>,
+    /// Handlers picked by request body before the sequential `handlers`
+    pub body_handlers: Vec<BodyHandler>,
+    /// Whether handler execution is serialized so overlapping requests queue
+    pub serialized: bool,
+    /// Whether handlers restart from the first once the last has been used
+    pub cycle: bool,
+    /// Header conditions a request must meet to be dispatched to this endpoint
+    pub header_matches: Vec<HeaderMatch>,
+    /// Exact number of calls verified once the scenario ends
+    pub expected_calls: Option<usize>,
+    /// CORS policy answering preflights to this endpoint's path
+    pub cors: Option<AllowConfig>,
 }
 
 impl Endpoint {
     pub fn new(path: impl Into<String>, method: Method) -> Self {
         Self {
             path: path.into(),
+            aliases: Vec::new(),
             method,
             handlers: Vec::new(),
+            body_handlers: Vec::new(),
+            serialized: false,
+            cycle: false,
+            header_matches: Vec::new(),
+            expected_calls: None,
+            cors: None,
         }
     }
 
+    /// Also serve the endpoint under `path`, e.g. `/users` next to `/v1/users`.
+    ///
+    /// Every path draws from the same handlers and call count, so the endpoint
+    /// counts towards shutdown once whichever path its calls arrive on.
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.aliases.push(path.into());
+        self
+    }
+
+    /// The endpoint's path followed by its aliases
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.path.as_str()).chain(self.aliases.iter().map(String::as_str))
+    }
+
+    /// Serve the endpoint and its aliases under `prefix`, see
+    /// [`ScenarioBuilder::prefix`](crate::use_cases::ScenarioBuilder::prefix)
+    pub(crate) fn prefixed(mut self, prefix: &str) -> Self {
+        let prefix = prefix.trim_end_matches('/');
+        if prefix.is_empty() {
+            return self;
+        }
+        let join = |path: &mut String| {
+            // Relative paths are left for check_conflicts to reject
+            if path == "/" {
+                *path = prefix.to_string();
+            } else if path.starts_with('/') {
+                path.insert_str(0, prefix);
+            }
+        };
+        join(&mut self.path);
+        self.aliases.iter_mut().for_each(join);
+        self
+    }
+
     pub fn with_handler(mut self, handler: Handler) -> Self {
         self.handlers.push(handler);
         self
@@ -26,6 +142,153 @@ impl Endpoint {
         self.handlers.extend(handlers);
         self
     }
+
+    /// Answer requests whose body satisfies `predicate` with `handler`.
+    ///
+    /// Body handlers are tried in order before the sequential handlers, which
+    /// answer requests no predicate accepts. Each counts as called towards
+    /// shutdown once it has answered a request. Endpoints with a
+    /// [`Handler::streaming`] handler do not buffer bodies, so they never
+    /// match body handlers.
+    pub fn when_body<F>(mut self, predicate: F, handler: Handler) -> Self
+    where
+        F: Fn(&[u8]) -> bool + Send + Sync + 'static,
+    {
+        self.body_handlers.push(BodyHandler {
+            predicate: Arc::new(predicate),
+            handler,
+        });
+        self
+    }
+
+    /// Respond with `f(call_index)`, where `call_index` is the zero-based index
+    /// of the call to this endpoint.
+    ///
+    /// Like a single handler this counts as called after the first request, so
+    /// pair it with [`expect_calls`](Self::expect_calls) or `expected_requests`
+    /// to keep the scenario running for the calls the closure anticipates.
+    pub fn responding_with<F>(self, f: F) -> Self
+    where
+        F: Fn(usize) -> Response + Send + Sync + 'static,
+    {
+        self.with_handler(Handler::indexed(f))
+    }
+
+    /// Answer each request with one of `handlers`, picked with probability
+    /// proportional to its weight, see [`Handler::random`]
+    pub fn random(self, handlers: Vec<(u32, Handler)>) -> Self {
+        self.with_handler(Handler::random(handlers))
+    }
+
+    /// Serialize handler execution so concurrent requests to this endpoint queue
+    /// and are handled one after another
+    pub fn serialized(mut self) -> Self {
+        self.serialized = true;
+        self
+    }
+
+    /// Cycle through the handlers instead of repeating the last one
+    pub fn cycle(mut self) -> Self {
+        self.cycle = true;
+        self
+    }
+
+    /// Require this endpoint to be called exactly `n` times.
+    ///
+    /// The scenario keeps running until the endpoint has been called `n` times,
+    /// and execution fails with [`HarnessError::ExpectationFailed`](crate::HarnessError::ExpectationFailed)
+    /// if the count differs once it ends.
+    pub fn expect_calls(mut self, n: usize) -> Self {
+        self.expected_calls = Some(n);
+        self
+    }
+
+    /// Answer CORS preflights to this endpoint's path with 204 and the
+    /// `Access-Control-Allow-*` headers of `cors`, and add
+    /// `Access-Control-Allow-Origin` to its actual responses.
+    ///
+    /// Preflights are collected like other requests but do not count towards
+    /// auto-shutdown unless [`AllowConfig::counting_preflight`] is set. An
+    /// explicit `OPTIONS` endpoint on the same path takes precedence.
+    pub fn with_cors(mut self, cors: AllowConfig) -> Self {
+        self.cors = Some(cors);
+        self
+    }
+
+    /// Only dispatch requests carrying header `name` with exactly `value`.
+    ///
+    /// Endpoints sharing a path and method are tried in order; the first whose
+    /// header conditions all match handles the request.
+    pub fn with_header_match(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.header_matches.push(HeaderMatch::Equals {
+            name: name.into(),
+            value: value.into(),
+        });
+        self
+    }
+
+    /// Only dispatch requests whose headers satisfy `predicate`
+    pub fn with_header_predicate<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&HashMap<String, String>) -> bool + Send + Sync + 'static,
+    {
+        self.header_matches.push(HeaderMatch::Predicate(Arc::new(predicate)));
+        self
+    }
+
+    /// Check whether request headers meet every header condition
+    pub fn matches_headers(&self, headers: &HashMap<String, String>) -> bool {
+        self.header_matches.iter().all(|m| m.matches(headers))
+    }
+}
+
+/// Reject endpoints the router cannot serve as registered: paths not starting
+/// with `/`, a path and method registered again after an endpoint without
+/// header conditions (the later one would be unreachable), and paths only
+/// differing by parameter names such as `/users/{id}` and `/users/{name}`.
+pub(crate) fn check_conflicts(endpoints: &[Endpoint]) -> Result<(), HarnessError> {
+    // Aliases are checked like endpoints of their own
+    let routes: Vec<(&Endpoint, &str)> = endpoints
+        .iter()
+        .flat_map(|endpoint| endpoint.paths().map(move |path| (endpoint, path)))
+        .collect();
+    for (i, &(endpoint, path)) in routes.iter().enumerate() {
+        if !path.starts_with('/') {
+            return Err(HarnessError::ConfigurationError(format!(
+                "Endpoint {} {} must have a path starting with '/'",
+                endpoint.method, path
+            )));
+        }
+        for &(earlier, earlier_path) in &routes[..i] {
+            if earlier_path == path {
+                if earlier.method == endpoint.method && earlier.header_matches.is_empty() {
+                    return Err(HarnessError::ConfigurationError(format!(
+                        "Endpoint {} {} is registered twice; the first registration has no \
+                         header match, so the second is unreachable",
+                        endpoint.method, path
+                    )));
+                }
+            } else if route_shape(earlier_path) == route_shape(path) {
+                return Err(HarnessError::ConfigurationError(format!(
+                    "Endpoints {} {} and {} {} conflict, their paths only differ by \
+                     parameter names",
+                    earlier.method, earlier_path, endpoint.method, path
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The path with parameter names erased, e.g. `/users/{}` for `/users/{id}`
+fn route_shape(path: &str) -> Vec<&str> {
+    path.split('/')
+        .map(|segment| match segment.strip_prefix('{') {
+            Some(param) if param.starts_with('*') => "{*}",
+            Some(_) => "{}",
+            None => segment,
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -36,8 +299,78 @@ mod tests {
     fn test_endpoint_new() {
         let endpoint = Endpoint::new("/api/test", Method::Get);
         assert_eq!(endpoint.path, "/api/test");
+        assert!(endpoint.aliases.is_empty());
         assert_eq!(endpoint.method, Method::Get);
         assert!(endpoint.handlers.is_empty());
+        assert!(endpoint.body_handlers.is_empty());
+        assert!(!endpoint.serialized);
+        assert!(!endpoint.cycle);
+        assert!(endpoint.header_matches.is_empty());
+        assert_eq!(endpoint.expected_calls, None);
+        assert!(endpoint.cors.is_none());
+    }
+
+    #[test]
+    fn test_endpoint_expect_calls() {
+        let endpoint = Endpoint::new("/api/test", Method::Get).expect_calls(2);
+        assert_eq!(endpoint.expected_calls, Some(2));
+    }
+
+    #[test]
+    fn test_endpoint_header_match_is_case_insensitive_on_name() {
+        let endpoint =
+            Endpoint::new("/api/test", Method::Get).with_header_match("Accept", "application/xml");
+        let headers = HashMap::from([("accept".to_string(), "application/xml".to_string())]);
+        assert!(endpoint.matches_headers(&headers));
+
+        let headers = HashMap::from([("accept".to_string(), "application/json".to_string())]);
+        assert!(!endpoint.matches_headers(&headers));
+    }
+
+    #[test]
+    fn test_endpoint_header_predicate() {
+        let endpoint = Endpoint::new("/api/test", Method::Get)
+            .with_header_predicate(|headers| headers.contains_key("x-debug"));
+        assert!(endpoint.matches_headers(&HashMap::from([("x-debug".to_string(), "1".to_string())])));
+        assert!(!endpoint.matches_headers(&HashMap::new()));
+    }
+
+    #[test]
+    fn test_check_conflicts() {
+        let get = |path: &str| Endpoint::new(path, Method::Get);
+        assert!(check_conflicts(&[get("/users"), Endpoint::new("/users", Method::Post)]).is_ok());
+        assert!(check_conflicts(&[
+            get("/users").with_header_match("accept", "application/xml"),
+            get("/users"),
+        ])
+        .is_ok());
+
+        let error = check_conflicts(&[get("/users"), get("/users")]).unwrap_err();
+        assert!(matches!(error, HarnessError::ConfigurationError(_)));
+        assert!(error.to_string().contains("GET /users is registered twice"));
+
+        let error = check_conflicts(&[get("/users/{id}"), get("/users/{name}")]).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("GET /users/{id} and GET /users/{name} conflict"));
+
+        assert!(check_conflicts(&[get("users")]).is_err());
+
+        // Aliases conflict like endpoints of their own
+        assert!(check_conflicts(&[get("/v1/users").with_path("/users")]).is_ok());
+        let error = check_conflicts(&[get("/v1/users").with_path("/users"), get("/users")]);
+        assert!(error.unwrap_err().to_string().contains("GET /users is registered twice"));
+        assert!(check_conflicts(&[get("/users").with_path("users")]).is_err());
+    }
+
+    #[test]
+    fn test_endpoint_prefixed() {
+        let endpoint = Endpoint::new("/users", Method::Get)
+            .with_path("/")
+            .with_path("users")
+            .prefixed("/api/v2/");
+        assert_eq!(endpoint.paths().collect::<Vec<_>>(), ["/api/v2/users", "/api/v2", "users"]);
+        assert_eq!(Endpoint::new("/", Method::Get).prefixed("/").path, "/");
     }
 
     #[test]
@@ -47,6 +380,13 @@ mod tests {
         assert_eq!(endpoint.handlers.len(), 1);
     }
 
+    #[test]
+    fn test_endpoint_responding_with() {
+        let endpoint = Endpoint::new("/api/test", Method::Get).responding_with(|_| Response::ok());
+        assert_eq!(endpoint.handlers.len(), 1);
+        assert!(matches!(endpoint.handlers[0], Handler::Indexed(_)));
+    }
+
     #[test]
     fn test_endpoint_with_multiple_handlers() {
         let endpoint = Endpoint::new("/api/test", Method::Get)
@@ -1,9 +1,28 @@
-use super::{Request, Response};
+use super::{Encoding, Request, Response};
+use crate::error::HarnessError;
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use std::future::Future;
+use std::io;
+use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Type alias for dynamic handler functions
 pub type HandlerFn = Arc<dyn Fn(&Request) -> Response + Send + Sync>;
 
+/// Type alias for async dynamic handler functions
+pub type AsyncHandlerFn = Arc<dyn Fn(&Request) -> BoxFuture<'static, Response> + Send + Sync>;
+
+/// Type alias for handler functions computing the response from the call index
+pub type IndexedHandlerFn = Arc<dyn Fn(usize) -> Response + Send + Sync>;
+
+/// Stream of request body chunks, as received from the client
+pub type BodyStream = BoxStream<'static, Result<Vec<u8>, HarnessError>>;
+
+/// Type alias for streaming handler functions
+pub type StreamingHandlerFn = Arc<dyn Fn(Request, BodyStream) -> BoxFuture<'static, Response> + Send + Sync>;
+
 /// A handler that returns either a static or dynamic HTTP response
 #[derive(Clone)]
 pub enum Handler {
@@ -11,6 +30,16 @@ pub enum Handler {
     Static(Response),
     /// Dynamic response - builds response based on the request
     Dynamic(HandlerFn),
+    /// Async dynamic response - the returned future is awaited by the server
+    AsyncDynamic(AsyncHandlerFn),
+    /// Streaming response - reads the request body incrementally.
+    /// The request passed to the handler (and collected) has an empty body.
+    Streaming(StreamingHandlerFn),
+    /// Indexed response - built from the zero-based index of the call to the endpoint
+    Indexed(IndexedHandlerFn),
+    /// Random response - a handler picked per request with probability
+    /// proportional to its weight
+    Random(Vec<(u32, Handler)>),
 }
 
 impl std::fmt::Debug for Handler {
@@ -18,6 +47,10 @@ impl std::fmt::Debug for Handler {
         match self {
             Handler::Static(response) => f.debug_tuple("Static").field(response).finish(),
             Handler::Dynamic(_) => f.debug_tuple("Dynamic").field(&"<fn>").finish(),
+            Handler::AsyncDynamic(_) => f.debug_tuple("AsyncDynamic").field(&"<fn>").finish(),
+            Handler::Streaming(_) => f.debug_tuple("Streaming").field(&"<fn>").finish(),
+            Handler::Indexed(_) => f.debug_tuple("Indexed").field(&"<fn>").finish(),
+            Handler::Random(handlers) => f.debug_tuple("Random").field(handlers).finish(),
         }
     }
 }
@@ -36,11 +69,174 @@ impl Handler {
         Handler::Dynamic(Arc::new(f))
     }
 
+    /// Create a dynamic handler sharing `state` with the test, e.g. an atomic
+    /// counter or a `Mutex` the test inspects after the scenario.
+    ///
+    /// ```
+    /// use http_endpoint_server_harness::prelude::*;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// let hits = Arc::new(AtomicUsize::new(0));
+    /// let handler = Handler::stateful(hits.clone(), |hits, _req| {
+    ///     let n = hits.fetch_add(1, Ordering::SeqCst) + 1;
+    ///     Response::ok().with_json(&json!({"hits": n}))
+    /// });
+    /// ```
+    pub fn stateful<S, F>(state: Arc<S>, f: F) -> Self
+    where
+        S: Send + Sync + ?Sized + 'static,
+        F: Fn(&S, &Request) -> Response + Send + Sync + 'static,
+    {
+        Handler::Dynamic(Arc::new(move |request| f(&state, request)))
+    }
+
+    /// Create a dynamic handler whose response is computed asynchronously,
+    /// e.g. to await shared test state or simulate latency
+    pub fn dynamic_async<F, Fut>(f: F) -> Self
+    where
+        F: Fn(&Request) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Response> + Send + 'static,
+    {
+        Handler::AsyncDynamic(Arc::new(move |request| Box::pin(f(request))))
+    }
+
+    /// Create a streaming handler that receives the request body as a stream of chunks.
+    ///
+    /// The body is never buffered by the server, so the handler can count bytes
+    /// or return early to abort the upload.
+    pub fn streaming<F, Fut>(f: F) -> Self
+    where
+        F: Fn(Request, BodyStream) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Response> + Send + 'static,
+    {
+        Handler::Streaming(Arc::new(move |request, body| Box::pin(f(request, body))))
+    }
+
+    /// Create a handler that builds each response from the zero-based index of
+    /// the call to its endpoint, e.g. to fail the first calls and succeed after.
+    pub fn indexed<F>(f: F) -> Self
+    where
+        F: Fn(usize) -> Response + Send + Sync + 'static,
+    {
+        Handler::Indexed(Arc::new(f))
+    }
+
+    /// Create a handler answering each request with one of `handlers`, picked
+    /// with probability proportional to its weight, e.g. to simulate a flaky
+    /// backend.
+    ///
+    /// The picks follow [`ScenarioBuilder::seed`](crate::ScenarioBuilder::seed)
+    /// when set. The handler counts as called after the first request, so pair
+    /// it with `expected_requests` to keep the scenario running. Streaming
+    /// handlers cannot be picked and answer with a 500.
+    pub fn random(handlers: Vec<(u32, Handler)>) -> Self {
+        Handler::Random(handlers)
+    }
+
+    /// Create the handlers of a retried call: `failures` handlers answering with
+    /// `fail_response`, followed by `success`, e.g. to fail twice with 503 before
+    /// succeeding.
+    ///
+    /// Add them with [`Endpoint::with_handlers`](super::Endpoint::with_handlers).
+    /// Each is a handler of its own, so the scenario completes once the client
+    /// has retried through to `success`.
+    pub fn fail_then(failures: usize, fail_response: Response, success: Handler) -> Vec<Handler> {
+        let mut handlers = vec![Handler::new(fail_response); failures];
+        handlers.push(success);
+        handlers
+    }
+
+    /// Create a handler that renders a JSON template against each request.
+    ///
+    /// String values that are entirely a placeholder such as `${path.params.id}`
+    /// or `${body.name}` are substituted from the incoming request. Unknown
+    /// placeholders resolve to `null`. The response is always a 200 JSON response.
+    pub fn template(template: serde_json::Value) -> Self {
+        Handler::dynamic(move |request: &Request| {
+            Response::ok().with_json(&super::template::render(&template, request))
+        })
+    }
+
+    /// Create a handler that serves `full_body` honoring the request's `Range` header.
+    ///
+    /// Returns 206 with `Content-Range` for a single byte range, the full body with 200
+    /// when no range is requested, and 416 for multiple or invalid ranges.
+    pub fn ranged(full_body: Vec<u8>) -> Self {
+        Handler::dynamic(move |request: &Request| super::range::respond(&full_body, request))
+    }
+
+    /// Create a static handler streaming `chunks` with `delay` between them,
+    /// e.g. for clients consuming JSON lines as they arrive.
+    /// See [`Response::with_chunks`].
+    pub fn chunked<I, B>(chunks: I, delay: Duration) -> Self
+    where
+        I: IntoIterator<Item = B>,
+        B: Into<Vec<u8>>,
+    {
+        Handler::Static(Response::ok().with_chunks(chunks, delay))
+    }
+
+    /// Create a static handler trickling `body` one byte at a time with
+    /// `byte_interval` between bytes, slow-loris style, to exercise a client's
+    /// read timeouts. The full body takes `(len - 1) * byte_interval` to arrive.
+    pub fn drip(body: Vec<u8>, byte_interval: Duration) -> Self {
+        Handler::chunked(body.chunks(1).map(<[u8]>::to_vec), byte_interval)
+    }
+
+    /// Create a static handler streaming server-sent events with `delay` between them.
+    ///
+    /// Each event is sent as its own `data:` chunk (one `data:` line per line of
+    /// the event) with `content-type: text/event-stream`.
+    pub fn sse<I, S>(events: I, delay: Duration) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let chunks = events.into_iter().map(|event| {
+            let mut chunk: String = event
+                .as_ref()
+                .lines()
+                .map(|line| format!("data: {}\n", line))
+                .collect();
+            chunk.push('\n');
+            chunk
+        });
+        Handler::chunked(chunks, delay)
+            .with_header("content-type", "text/event-stream")
+            .with_header("cache-control", "no-cache")
+    }
+
     /// Create a static handler from a JSON value
     pub fn from_json<T: serde::Serialize>(value: &T) -> Self {
         Handler::Static(Response::ok().with_json(value))
     }
 
+    /// Create a static handler serving raw bytes with the given content type
+    pub fn from_bytes(content_type: &str, bytes: Vec<u8>) -> Self {
+        Handler::Static(
+            Response::ok()
+                .with_header("content-type", content_type)
+                .with_body(bytes),
+        )
+    }
+
+    /// Create a static handler serving plain UTF-8 text
+    pub fn from_text(text: &str) -> Self {
+        Handler::from_bytes("text/plain; charset=utf-8", text.as_bytes().to_vec())
+    }
+
+    /// Create a static handler redirecting to `location`, see [`Response::redirect`]
+    pub fn redirect(status: u16, location: impl Into<String>) -> Self {
+        Handler::Static(Response::redirect(status, location))
+    }
+
+    /// Create a static handler serving the contents of the file at `path`.
+    /// See [`Response::with_body_from_file`] for how the content type is set.
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        Response::ok().with_body_from_file(path).map(Handler::Static)
+    }
+
     /// Modify the status code (only works for static handlers, returns a new static handler)
     pub fn with_status(self, status: u16) -> Self {
         match self {
@@ -48,7 +244,12 @@ impl Handler {
                 response.status = status;
                 Handler::Static(response)
             }
-            Handler::Dynamic(_) => self, // Cannot modify dynamic handler
+            // Cannot modify dynamic handler
+            Handler::Dynamic(_)
+            | Handler::AsyncDynamic(_)
+            | Handler::Streaming(_)
+            | Handler::Indexed(_)
+            | Handler::Random(_) => self,
         }
     }
 
@@ -56,15 +257,55 @@ impl Handler {
     pub fn with_header(self, key: impl Into<String>, value: impl Into<String>) -> Self {
         match self {
             Handler::Static(response) => Handler::Static(response.with_header(key, value)),
-            Handler::Dynamic(_) => self, // Cannot modify dynamic handler
+            // Cannot modify dynamic handler
+            Handler::Dynamic(_)
+            | Handler::AsyncDynamic(_)
+            | Handler::Streaming(_)
+            | Handler::Indexed(_)
+            | Handler::Random(_) => self,
+        }
+    }
+
+    /// Compress responses with `encoding` when the request's `Accept-Encoding` allows it.
+    /// Works for every kind of handler; the collected request is unaffected.
+    pub fn with_compression(self, encoding: Encoding) -> Self {
+        match self {
+            Handler::Static(response) => Handler::Static(response.with_compression(encoding)),
+            Handler::Dynamic(f) => {
+                Handler::Dynamic(Arc::new(move |request| f(request).with_compression(encoding)))
+            }
+            Handler::AsyncDynamic(f) => Handler::AsyncDynamic(Arc::new(move |request| {
+                let response = f(request);
+                Box::pin(async move { response.await.with_compression(encoding) })
+            })),
+            Handler::Streaming(f) => Handler::Streaming(Arc::new(move |request, body| {
+                let response = f(request, body);
+                Box::pin(async move { response.await.with_compression(encoding) })
+            })),
+            Handler::Indexed(f) => {
+                Handler::Indexed(Arc::new(move |index| f(index).with_compression(encoding)))
+            }
+            Handler::Random(handlers) => Handler::Random(
+                handlers
+                    .into_iter()
+                    .map(|(weight, handler)| (weight, handler.with_compression(encoding)))
+                    .collect(),
+            ),
         }
     }
 
-    /// Get the response for a given request
+    /// Get the response for a given request.
+    /// Async and streaming handlers must be awaited, indexed handlers need the
+    /// call index and random handlers the scenario's generator; they are driven
+    /// by the server, so this returns a 500 for them.
     pub fn respond(&self, request: &Request) -> Response {
         match self {
             Handler::Static(response) => response.clone(),
             Handler::Dynamic(f) => f(request),
+            Handler::AsyncDynamic(_)
+            | Handler::Streaming(_)
+            | Handler::Indexed(_)
+            | Handler::Random(_) => Response::internal_error(),
         }
     }
 
@@ -73,11 +314,34 @@ impl Handler {
     pub fn response(&self) -> Response {
         match self {
             Handler::Static(response) => response.clone(),
-            Handler::Dynamic(_) => Response::new(200),
+            Handler::Dynamic(_)
+            | Handler::AsyncDynamic(_)
+            | Handler::Streaming(_)
+            | Handler::Indexed(_)
+            | Handler::Random(_) => Response::new(200),
         }
     }
 }
 
+/// The handler of a [`Handler::Random`] that `roll`, taken in
+/// `0..total_weight(handlers)`, falls on
+#[cfg(feature = "axum")]
+pub(crate) fn pick_weighted(handlers: &[(u32, Handler)], mut roll: u64) -> Option<&Handler> {
+    handlers.iter().find_map(|(weight, handler)| match roll.checked_sub(u64::from(*weight)) {
+        Some(rest) => {
+            roll = rest;
+            None
+        }
+        None => Some(handler),
+    })
+}
+
+/// Sum of the weights of a [`Handler::Random`]
+#[cfg(feature = "axum")]
+pub(crate) fn total_weight(handlers: &[(u32, Handler)]) -> u64 {
+    handlers.iter().map(|(weight, _)| u64::from(*weight)).sum()
+}
+
 impl From<Response> for Handler {
     fn from(response: Response) -> Self {
         Handler::Static(response)
@@ -88,15 +352,9 @@ impl From<Response> for Handler {
 mod tests {
     use super::*;
     use crate::entities::Method;
-    use std::collections::HashMap;
 
     fn create_test_request(method: Method, path: &str, body: &[u8]) -> Request {
-        Request {
-            method,
-            path: path.to_string(),
-            headers: HashMap::new(),
-            body: body.to_vec(),
-        }
+        Request::new(method, path).with_body(body)
     }
 
     #[test]
@@ -106,6 +364,21 @@ mod tests {
         assert!(matches!(handler, Handler::Static(_)));
     }
 
+    #[test]
+    fn test_handler_stateful() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let counter = Arc::new(AtomicUsize::new(0));
+        let handler = Handler::stateful(counter.clone(), |counter, _| {
+            let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+            Response::ok().with_body(n.to_string())
+        });
+        let req = create_test_request(Method::Get, "/", &[]);
+        assert_eq!(handler.respond(&req).body, b"1");
+        assert_eq!(handler.clone().respond(&req).body, b"2");
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+    }
+
     #[test]
     fn test_handler_from_json() {
         let handler = Handler::from_json(&serde_json::json!({"test": true}));
@@ -115,6 +388,68 @@ mod tests {
         assert!(response.headers.get("content-type").unwrap().contains("application/json"));
     }
 
+    #[test]
+    fn test_handler_from_bytes() {
+        let handler = Handler::from_bytes("application/xml", b"<ok/>".to_vec());
+        let response = handler.respond(&create_test_request(Method::Get, "/", &[]));
+        assert_eq!(response.status, 200);
+        assert_eq!(response.headers.get("content-type").unwrap(), "application/xml");
+        assert_eq!(response.body, b"<ok/>");
+    }
+
+    #[test]
+    fn test_handler_from_text() {
+        let handler = Handler::from_text("hello");
+        let response = handler.respond(&create_test_request(Method::Get, "/", &[]));
+        assert_eq!(
+            response.headers.get("content-type").unwrap(),
+            "text/plain; charset=utf-8"
+        );
+        assert_eq!(response.body, b"hello");
+    }
+
+    #[test]
+    fn test_handler_sse() {
+        let handler = Handler::sse(["ready", "line 1\nline 2"], Duration::from_millis(10));
+        let response = handler.respond(&create_test_request(Method::Get, "/", &[]));
+        assert_eq!(response.headers.get("content-type").unwrap(), "text/event-stream");
+        assert_eq!(
+            response.chunks,
+            vec![b"data: ready\n\n".to_vec(), b"data: line 1\ndata: line 2\n\n".to_vec()]
+        );
+        assert_eq!(response.chunk_delay, Duration::from_millis(10));
+    }
+
+    #[test]
+    fn test_handler_fail_then() {
+        let handlers = Handler::fail_then(2, Response::new(503), Handler::from_text("ok"));
+        let request = create_test_request(Method::Get, "/", &[]);
+        let statuses: Vec<u16> = handlers.iter().map(|h| h.respond(&request).status).collect();
+        assert_eq!(statuses, [503, 503, 200]);
+        assert_eq!(Handler::fail_then(0, Response::new(503), Handler::from_text("ok")).len(), 1);
+    }
+
+    #[test]
+    fn test_handler_drip() {
+        let handler = Handler::drip(b"abc".to_vec(), Duration::from_millis(10));
+        let response = handler.respond(&create_test_request(Method::Get, "/", &[]));
+        assert_eq!(response.chunks, vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec()]);
+        assert_eq!(response.chunk_delay, Duration::from_millis(10));
+        assert_eq!(response.body, b"abc");
+    }
+
+    #[test]
+    fn test_handler_from_file() {
+        let path = std::env::temp_dir().join(format!("harness-handler-{}.txt", std::process::id()));
+        std::fs::write(&path, b"fixture").unwrap();
+        let handler = Handler::from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let response = handler.unwrap().respond(&create_test_request(Method::Get, "/", &[]));
+        assert_eq!(response.body, b"fixture");
+        assert!(!response.headers.contains_key("content-type"));
+    }
+
     #[test]
     fn test_handler_with_status() {
         let handler = Handler::from_json(&serde_json::json!({})).with_status(201);
@@ -122,6 +457,16 @@ mod tests {
         assert_eq!(handler.respond(&req).status, 201);
     }
 
+    #[test]
+    fn test_handler_with_compression() {
+        let req = create_test_request(Method::Get, "/", &[]);
+        let handler = Handler::from_text("hi").with_compression(Encoding::Deflate);
+        assert_eq!(handler.respond(&req).compression, Some(Encoding::Deflate));
+
+        let handler = Handler::dynamic(|_: &Request| Response::ok()).with_compression(Encoding::Br);
+        assert_eq!(handler.respond(&req).compression, Some(Encoding::Br));
+    }
+
     #[test]
     fn test_handler_from_response() {
         let response = Response::new(404);
@@ -164,6 +509,65 @@ mod tests {
         assert!(String::from_utf8_lossy(&response.body).contains("Hello, World!"));
     }
 
+    #[tokio::test]
+    async fn test_async_dynamic_handler() {
+        let handler = Handler::dynamic_async(|req: &Request| {
+            let path = req.path.clone();
+            async move {
+                tokio::task::yield_now().await;
+                Response::new(200).with_body(path)
+            }
+        });
+
+        let req = create_test_request(Method::Get, "/api/slow", &[]);
+        let Handler::AsyncDynamic(f) = &handler else {
+            panic!("expected an async dynamic handler");
+        };
+        assert_eq!(f(&req).await.body, b"/api/slow");
+        // Not awaitable synchronously
+        assert_eq!(handler.respond(&req).status, 500);
+    }
+
+    #[test]
+    fn test_indexed_handler() {
+        let handler = Handler::indexed(|index| Response::new(if index < 2 { 503 } else { 200 }));
+        let Handler::Indexed(f) = &handler else {
+            panic!("expected an indexed handler");
+        };
+        assert_eq!(f(0).status, 503);
+        assert_eq!(f(2).status, 200);
+        // Needs the call index from the server
+        assert_eq!(handler.respond(&create_test_request(Method::Get, "/", &[])).status, 500);
+    }
+
+    #[cfg(feature = "axum")]
+    #[test]
+    fn test_pick_weighted() {
+        let handlers = vec![
+            (3, Handler::new(Response::new(200))),
+            (0, Handler::new(Response::new(404))),
+            (1, Handler::new(Response::new(503))),
+        ];
+        assert_eq!(total_weight(&handlers), 4);
+        let status = |roll| pick_weighted(&handlers, roll).unwrap().response().status;
+        assert_eq!([0, 1, 2, 3].map(status), [200, 200, 200, 503]);
+        assert!(pick_weighted(&handlers, 4).is_none());
+    }
+
+    #[test]
+    fn test_template_handler() {
+        let handler = Handler::template(serde_json::json!({
+            "id": "${path.params.id}",
+            "name": "${body.name}"
+        }));
+
+        let mut req = create_test_request(Method::Post, "/users/7", b"{\"name\": \"Ada\"}");
+        req.path_params.insert("id".to_string(), "7".to_string());
+        let response = handler.respond(&req);
+        let body: serde_json::Value = serde_json::from_slice(&response.body).unwrap();
+        assert_eq!(body, serde_json::json!({"id": "7", "name": "Ada"}));
+    }
+
     #[test]
     fn test_dynamic_handler_based_on_method() {
         let handler = Handler::dynamic(|req: &Request| {
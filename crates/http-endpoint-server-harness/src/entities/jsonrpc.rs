@@ -0,0 +1,250 @@
+use super::{Endpoint, Handler, Method, Request, Response};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// Error code for a body that is not valid JSON
+pub const PARSE_ERROR: i64 = -32700;
+/// Error code for an envelope without a string `method`
+pub const INVALID_REQUEST: i64 = -32600;
+/// Error code for a method without a registered handler
+pub const METHOD_NOT_FOUND: i64 = -32601;
+/// Error code used when a handler fails without a JSON-RPC error body
+pub const SERVER_ERROR: i64 = -32000;
+
+/// A JSON-RPC 2.0 service answering `POST` requests on one path, with
+/// handlers registered by method name.
+///
+/// Each method handler receives the request with the call's `params` as JSON
+/// body. A successful (2xx) response body becomes the call's `result`; any
+/// other response becomes its `error`, see [`JsonRpcEndpoint::error`]. Batches
+/// are answered with an array, and notifications (calls without `id`) get no
+/// response entry.
+///
+/// ```
+/// use http_endpoint_server_harness::prelude::*;
+///
+/// let endpoint: Endpoint = JsonRpcEndpoint::new("/rpc")
+///     .with_method("eth_blockNumber", Handler::from_json(&json!("0x10")))
+///     .into();
+/// assert_eq!(endpoint.method, Method::Post);
+/// ```
+#[derive(Debug, Clone)]
+pub struct JsonRpcEndpoint {
+    pub path: String,
+    pub methods: Vec<(String, Handler)>,
+}
+
+/// A JSON-RPC call parsed from a collected request, see [`Request::json_rpc_calls`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonRpcCall {
+    pub method: String,
+    /// `None` when the call sent no `params`
+    pub params: Option<Value>,
+    /// `None` for notifications
+    pub id: Option<Value>,
+}
+
+impl JsonRpcEndpoint {
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            methods: Vec::new(),
+        }
+    }
+
+    /// Answer calls to `method` with `handler`. Only static and dynamic
+    /// handlers are supported; other kinds answer with an internal error.
+    pub fn with_method(mut self, method: impl Into<String>, handler: Handler) -> Self {
+        self.methods.push((method.into(), handler));
+        self
+    }
+
+    /// Build a response a method handler returns to fail the call with a
+    /// JSON-RPC error object
+    pub fn error(code: i64, message: impl Into<String>) -> Response {
+        Response::new(500).with_json(&json!({"code": code, "message": message.into()}))
+    }
+
+    /// Build the `POST` endpoint dispatching calls to the registered methods.
+    ///
+    /// A batch is a single HTTP request, so it counts as one call towards
+    /// auto-shutdown; use [`Endpoint::expect_calls`] to wait for several.
+    pub fn into_endpoint(self) -> Endpoint {
+        let path = self.path.clone();
+        let methods = Arc::new(self.methods);
+        let handler = Handler::dynamic(move |request| dispatch(&methods, request));
+        Endpoint::new(path, Method::Post).with_handler(handler)
+    }
+}
+
+impl From<JsonRpcEndpoint> for Endpoint {
+    fn from(endpoint: JsonRpcEndpoint) -> Self {
+        endpoint.into_endpoint()
+    }
+}
+
+impl JsonRpcCall {
+    /// Parse the calls of a JSON-RPC body, one per batch entry.
+    /// Entries without a string `method` are skipped.
+    pub(crate) fn parse(body: &Value) -> Vec<Self> {
+        let calls = match body {
+            Value::Array(calls) => calls.as_slice(),
+            call => std::slice::from_ref(call),
+        };
+        calls.iter().filter_map(Self::from_value).collect()
+    }
+
+    fn from_value(call: &Value) -> Option<Self> {
+        let call = call.as_object()?;
+        Some(Self {
+            method: call.get("method")?.as_str()?.to_string(),
+            params: call.get("params").cloned(),
+            id: call.get("id").cloned(),
+        })
+    }
+}
+
+fn dispatch(methods: &[(String, Handler)], request: &Request) -> Response {
+    let Some(body) = request.json_body() else {
+        return Response::ok().with_json(&error_value(Value::Null, PARSE_ERROR, "Parse error"));
+    };
+
+    let replies: Vec<Value> = match &body {
+        Value::Array(calls) if calls.is_empty() => {
+            vec![error_value(Value::Null, INVALID_REQUEST, "Invalid Request")]
+        }
+        Value::Array(calls) => calls
+            .iter()
+            .filter_map(|call| reply(methods, request, call))
+            .collect(),
+        call => reply(methods, request, call).into_iter().collect(),
+    };
+
+    match replies.first() {
+        None => Response::new(204),
+        Some(_) if body.is_array() => Response::ok().with_json(&replies),
+        Some(reply) => Response::ok().with_json(reply),
+    }
+}
+
+/// Answer one call, or `None` for a notification
+fn reply(methods: &[(String, Handler)], request: &Request, call: &Value) -> Option<Value> {
+    let Some(parsed) = JsonRpcCall::from_value(call) else {
+        return Some(error_value(Value::Null, INVALID_REQUEST, "Invalid Request"));
+    };
+    let id = parsed.id?;
+
+    let Some((_, handler)) = methods.iter().find(|(name, _)| *name == parsed.method) else {
+        return Some(error_value(id, METHOD_NOT_FOUND, "Method not found"));
+    };
+
+    let params = parsed.params.unwrap_or(Value::Null);
+    let mut call_request = request.clone();
+    call_request.body = serde_json::to_vec(&params).unwrap_or_default();
+    let response = handler.respond(&call_request);
+
+    let value = serde_json::from_slice(&response.body)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&response.body).into_owned()));
+    if (200..300).contains(&response.status) {
+        return Some(json!({"jsonrpc": "2.0", "result": value, "id": id}));
+    }
+    let error = match value {
+        Value::Object(error) if error.contains_key("code") => Value::Object(error),
+        other => json!({"code": SERVER_ERROR, "message": "Server error", "data": other}),
+    };
+    Some(json!({"jsonrpc": "2.0", "error": error, "id": id}))
+}
+
+fn error_value(id: Value, code: i64, message: &str) -> Value {
+    json!({"jsonrpc": "2.0", "error": {"code": code, "message": message}, "id": id})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoint() -> Endpoint {
+        JsonRpcEndpoint::new("/rpc")
+            .with_method("eth_blockNumber", Handler::from_json(&json!("0x10")))
+            .with_method(
+                "add",
+                Handler::dynamic(|req| {
+                    let params = req.json_body().unwrap_or_default();
+                    match (params[0].as_i64(), params[1].as_i64()) {
+                        (Some(a), Some(b)) => Response::ok().with_json(&json!(a + b)),
+                        _ => JsonRpcEndpoint::error(-32602, "Invalid params"),
+                    }
+                }),
+            )
+            .into()
+    }
+
+    fn call(body: Value) -> (u16, Option<Value>) {
+        let request = Request::new(Method::Post, "/rpc").with_body(body.to_string());
+        let response = endpoint().handlers[0].respond(&request);
+        (response.status, serde_json::from_slice(&response.body).ok())
+    }
+
+    #[test]
+    fn test_single_call() {
+        let (status, reply) =
+            call(json!({"jsonrpc": "2.0", "method": "add", "params": [1, 2], "id": 1}));
+        assert_eq!(status, 200);
+        assert_eq!(reply, Some(json!({"jsonrpc": "2.0", "result": 3, "id": 1})));
+
+        let (_, reply) = call(json!({"jsonrpc": "2.0", "method": "add", "params": [], "id": "a"}));
+        assert_eq!(
+            reply,
+            Some(json!({
+                "jsonrpc": "2.0",
+                "error": {"code": -32602, "message": "Invalid params"},
+                "id": "a"
+            }))
+        );
+    }
+
+    #[test]
+    fn test_errors_and_notifications() {
+        let (_, reply) = call(json!({"jsonrpc": "2.0", "method": "missing", "id": 2}));
+        assert_eq!(reply.unwrap()["error"]["code"], METHOD_NOT_FOUND);
+
+        let (_, reply) = call(json!({"jsonrpc": "2.0", "id": 3}));
+        assert_eq!(reply.unwrap()["error"]["code"], INVALID_REQUEST);
+
+        let (_, reply) = call(json!([]));
+        assert_eq!(reply.unwrap()[0]["error"]["code"], INVALID_REQUEST);
+
+        let (status, reply) = call(json!({"jsonrpc": "2.0", "method": "eth_blockNumber"}));
+        assert_eq!((status, reply), (204, None));
+
+        let request = Request::new(Method::Post, "/rpc").with_body("{");
+        let response = endpoint().handlers[0].respond(&request);
+        let reply: Value = serde_json::from_slice(&response.body).unwrap();
+        assert_eq!(reply["error"]["code"], PARSE_ERROR);
+        assert_eq!(reply["id"], Value::Null);
+    }
+
+    #[test]
+    fn test_parse_calls() {
+        let body = json!([
+            {"jsonrpc": "2.0", "method": "add", "params": [1, 2], "id": 1},
+            {"jsonrpc": "2.0", "method": "ping"},
+            {"jsonrpc": "2.0", "id": 2},
+        ]);
+        assert_eq!(
+            JsonRpcCall::parse(&body),
+            [
+                JsonRpcCall {
+                    method: "add".to_string(),
+                    params: Some(json!([1, 2])),
+                    id: Some(json!(1)),
+                },
+                JsonRpcCall {
+                    method: "ping".to_string(),
+                    params: None,
+                    id: None,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,84 @@
+//! JSON merge-patch helpers for dynamic handlers mocking `PATCH` endpoints
+
+use serde_json::Value;
+
+/// Apply `patch` onto `base` following RFC 7386 JSON merge-patch semantics.
+///
+/// Object members of the patch are merged recursively, `null` members delete
+/// the key from `base`, and any non-object patch replaces `base` entirely.
+///
+/// ```
+/// use http_endpoint_server_harness::entities::merge::merge_json;
+/// use serde_json::json;
+///
+/// let mut user = json!({"name": "Alice", "address": {"city": "Paris", "zip": "75001"}});
+/// merge_json(&mut user, &json!({"address": {"zip": null}, "role": "admin"}));
+/// assert_eq!(user, json!({"name": "Alice", "address": {"city": "Paris"}, "role": "admin"}));
+/// ```
+pub fn merge_json(base: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *base = patch.clone();
+        return;
+    };
+    if !base.is_object() {
+        *base = Value::Object(Default::default());
+    }
+    let Value::Object(base) = base else {
+        unreachable!()
+    };
+
+    for (key, value) in patch {
+        if value.is_null() {
+            base.remove(key);
+        } else {
+            merge_json(base.entry(key.as_str()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn merged(mut base: Value, patch: Value) -> Value {
+        merge_json(&mut base, &patch);
+        base
+    }
+
+    #[test]
+    fn test_null_deletes_member() {
+        assert_eq!(
+            merged(json!({"a": "b", "c": "d"}), json!({"a": null})),
+            json!({"c": "d"})
+        );
+        assert_eq!(merged(json!({"a": "b"}), json!({"missing": null})), json!({"a": "b"}));
+    }
+
+    #[test]
+    fn test_nested_objects_merge() {
+        assert_eq!(
+            merged(
+                json!({"title": "Hello", "author": {"givenName": "John", "familyName": "Doe"}}),
+                json!({"title": "Hi", "author": {"familyName": null}, "tags": ["x"]})
+            ),
+            json!({"title": "Hi", "author": {"givenName": "John"}, "tags": ["x"]})
+        );
+        assert_eq!(
+            merged(json!({"a": {"b": "c"}}), json!({"a": {"d": {"e": null, "f": 1}}})),
+            json!({"a": {"b": "c", "d": {"f": 1}}})
+        );
+    }
+
+    #[test]
+    fn test_non_objects_replace() {
+        // Examples from RFC 7386 appendix A
+        assert_eq!(merged(json!({"a": ["b"]}), json!({"a": "c"})), json!({"a": "c"}));
+        assert_eq!(merged(json!({"a": [{"b": "c"}]}), json!({"a": [1]})), json!({"a": [1]}));
+        assert_eq!(merged(json!(["a", "b"]), json!(["c", "d"])), json!(["c", "d"]));
+        assert_eq!(merged(json!({"a": "b"}), json!(["c"])), json!(["c"]));
+        assert_eq!(merged(json!({"a": "foo"}), json!(null)), json!(null));
+        assert_eq!(merged(json!("string"), json!({"a": "b"})), json!({"a": "b"}));
+        assert_eq!(merged(json!({"e": null}), json!({"a": 1})), json!({"e": null, "a": 1}));
+    }
+}
@@ -1,5 +1,8 @@
+use serde::{Deserialize, Serialize};
+
 /// HTTP methods supported by the harness
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
 pub enum Method {
     Get,
     Post,
@@ -1,13 +1,28 @@
-mod endpoint;
-mod handler;
+mod body;
+mod cookie;
+pub(crate) mod cors;
+pub(crate) mod endpoint;
+pub(crate) mod handler;
+pub mod jsonrpc;
+pub mod merge;
 mod method;
+#[cfg(feature = "multipart")]
+mod multipart;
+mod range;
+pub(crate) mod recording;
 mod request;
 mod response;
 mod scenario;
+mod template;
 
-pub use endpoint::Endpoint;
-pub use handler::Handler;
+pub use cookie::{CookieAttributes, SameSite};
+pub use cors::AllowConfig;
+pub use endpoint::{BodyHandler, Endpoint, HeaderMatch};
+pub use handler::{BodyStream, Handler};
+pub use jsonrpc::{JsonRpcCall, JsonRpcEndpoint};
 pub use method::Method;
+#[cfg(feature = "multipart")]
+pub use multipart::MultipartPart;
 pub use request::Request;
-pub use response::Response;
+pub use response::{Encoding, Response};
 pub use scenario::Scenario;
@@ -0,0 +1,154 @@
+use super::Request;
+
+/// One part of a `multipart/form-data` request body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultipartPart {
+    /// Form field name, from `Content-Disposition`
+    pub name: String,
+    /// Uploaded file name, when the part is a file
+    pub filename: Option<String>,
+    /// `Content-Type` of the part, if sent
+    pub content_type: Option<String>,
+    pub bytes: Vec<u8>,
+}
+
+/// Split the body of a `multipart/form-data` request into its parts.
+///
+/// Returns `None` when the request is not multipart, has no boundary, or the
+/// body is malformed.
+pub(crate) fn parse(request: &Request) -> Option<Vec<MultipartPart>> {
+    let boundary = boundary(request.header("content-type")?)?;
+    let delimiter = format!("--{}", boundary).into_bytes();
+    let body = request.body.as_slice();
+
+    let mut rest = &body[find(body, &delimiter)? + delimiter.len()..];
+    let mut parts = Vec::new();
+    loop {
+        if rest.starts_with(b"--") {
+            return Some(parts);
+        }
+        rest = rest.strip_prefix(b"\r\n")?;
+
+        let headers_end = find(rest, b"\r\n\r\n")?;
+        let headers = std::str::from_utf8(&rest[..headers_end]).ok()?;
+        rest = &rest[headers_end + 4..];
+
+        let mut closing = b"\r\n".to_vec();
+        closing.extend_from_slice(&delimiter);
+        let content_end = find(rest, &closing)?;
+        parts.push(part(headers, rest[..content_end].to_vec())?);
+        rest = &rest[content_end + closing.len()..];
+    }
+}
+
+/// Build a part from its header block; parts without a field name are malformed
+fn part(headers: &str, bytes: Vec<u8>) -> Option<MultipartPart> {
+    let mut name = None;
+    let mut filename = None;
+    let mut content_type = None;
+
+    for line in headers.split("\r\n") {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        if key.trim().eq_ignore_ascii_case("content-disposition") {
+            name = parameter(value, "name");
+            filename = parameter(value, "filename");
+        } else if key.trim().eq_ignore_ascii_case("content-type") {
+            content_type = Some(value.trim().to_string());
+        }
+    }
+
+    Some(MultipartPart {
+        name: name?,
+        filename,
+        content_type,
+        bytes,
+    })
+}
+
+/// Get the boundary of a `multipart/form-data` content type
+fn boundary(content_type: &str) -> Option<String> {
+    let (mime, _) = content_type.split_once(';')?;
+    if !mime.trim().eq_ignore_ascii_case("multipart/form-data") {
+        return None;
+    }
+    parameter(content_type, "boundary").filter(|boundary| !boundary.is_empty())
+}
+
+/// Get a `key=value` parameter of a header value, unquoting it if needed
+fn parameter(header: &str, key: &str) -> Option<String> {
+    header.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim().eq_ignore_ascii_case(key).then(|| {
+            let value = value.trim();
+            value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .unwrap_or(value)
+                .to_string()
+        })
+    })
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::Method;
+
+    fn multipart_request(body: &str) -> Request {
+        Request::new(Method::Post, "/upload")
+            .with_header("Content-Type", "multipart/form-data; boundary=\"XyZ\"")
+            .with_body(body.replace('\n', "\r\n"))
+    }
+
+    #[test]
+    fn test_parse_fields_and_files() {
+        let request = multipart_request(
+            "preamble\n--XyZ\nContent-Disposition: form-data; name=\"title\"\n\nhello\n\
+             --XyZ\nContent-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\n\
+             Content-Type: text/plain\n\nline 1\nline 2\n--XyZ--\n",
+        );
+
+        let parts = request.multipart().unwrap();
+        assert_eq!(
+            parts,
+            vec![
+                MultipartPart {
+                    name: "title".to_string(),
+                    filename: None,
+                    content_type: None,
+                    bytes: b"hello".to_vec(),
+                },
+                MultipartPart {
+                    name: "file".to_string(),
+                    filename: Some("a.txt".to_string()),
+                    content_type: Some("text/plain".to_string()),
+                    bytes: b"line 1\r\nline 2".to_vec(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_non_multipart() {
+        let request = Request::new(Method::Post, "/upload")
+            .with_header("content-type", "application/json")
+            .with_body("{}");
+        assert_eq!(request.multipart(), None);
+        assert_eq!(Request::new(Method::Post, "/upload").multipart(), None);
+    }
+
+    #[test]
+    fn test_parse_rejects_truncated_body() {
+        let request =
+            multipart_request("--XyZ\nContent-Disposition: form-data; name=\"title\"\n\nhel");
+        assert_eq!(request.multipart(), None);
+    }
+}
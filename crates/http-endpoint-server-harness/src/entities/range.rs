@@ -0,0 +1,81 @@
+use super::{Request, Response};
+
+/// Serve `body` honoring the request's `Range` header.
+///
+/// Without a `Range` header the full body is returned with 200. A single
+/// satisfiable `bytes=` range returns 206 with `Content-Range`. Multiple,
+/// malformed or unsatisfiable ranges return 416.
+pub(crate) fn respond(body: &[u8], request: &Request) -> Response {
+    let total = body.len();
+    let Some(header) = request.header("range") else {
+        return Response::ok()
+            .with_header("accept-ranges", "bytes")
+            .with_body(body.to_vec());
+    };
+
+    match parse_range(header, total) {
+        Some((start, end)) => Response::new(206)
+            .with_header("accept-ranges", "bytes")
+            .with_header("content-range", format!("bytes {}-{}/{}", start, end, total))
+            .with_body(body[start..=end].to_vec()),
+        None => Response::new(416)
+            .with_header("accept-ranges", "bytes")
+            .with_header("content-range", format!("bytes */{}", total)),
+    }
+}
+
+/// Parse a single `bytes=` range into inclusive `(start, end)` offsets
+fn parse_range(header: &str, total: usize) -> Option<(usize, usize)> {
+    let spec = header.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') || total == 0 {
+        return None;
+    }
+
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        // Suffix range: the last N bytes
+        ("", suffix) => {
+            let len: usize = suffix.parse().ok()?;
+            if len == 0 {
+                return None;
+            }
+            (total.saturating_sub(len), total - 1)
+        }
+        (start, "") => (start.parse().ok()?, total - 1),
+        (start, end) => {
+            let end: usize = end.parse().ok()?;
+            (start.parse().ok()?, end.min(total - 1))
+        }
+    };
+
+    (start <= end && start < total).then_some((start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::Method;
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("bytes=0-9", 100), Some((0, 9)));
+        assert_eq!(parse_range("bytes=90-", 100), Some((90, 99)));
+        assert_eq!(parse_range("bytes=-10", 100), Some((90, 99)));
+        assert_eq!(parse_range("bytes=95-200", 100), Some((95, 99)));
+    }
+
+    #[test]
+    fn test_parse_range_rejects_invalid() {
+        assert_eq!(parse_range("bytes=0-9,20-29", 100), None);
+        assert_eq!(parse_range("bytes=100-", 100), None);
+        assert_eq!(parse_range("bytes=9-0", 100), None);
+        assert_eq!(parse_range("items=0-9", 100), None);
+    }
+
+    #[test]
+    fn test_respond_without_range() {
+        let response = respond(b"hello", &Request::new(Method::Get, "/"));
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"hello");
+    }
+}
@@ -0,0 +1,67 @@
+use super::{Endpoint, Handler, Method, Response};
+use crate::error::HarnessError;
+use serde::Deserialize;
+
+/// A recorded request and the response it received.
+/// Recorded requests may carry more fields; only the method and path are replayed.
+#[derive(Deserialize)]
+struct Exchange {
+    request: RecordedRequest,
+    response: Response,
+}
+
+#[derive(Deserialize)]
+struct RecordedRequest {
+    method: Method,
+    path: String,
+}
+
+/// Build one endpoint per distinct method and path of `recording`, an array of
+/// `{"request": ..., "response": ...}` pairs, serving the recorded responses in order
+pub(crate) fn endpoints(recording: &serde_json::Value) -> Result<Vec<Endpoint>, HarnessError> {
+    let exchanges = Vec::<Exchange>::deserialize(recording)
+        .map_err(|e| HarnessError::ParseError(format!("invalid recording: {}", e)))?;
+
+    let mut endpoints: Vec<Endpoint> = Vec::new();
+    for Exchange { request, response } in exchanges {
+        let handler = Handler::new(response);
+        match endpoints
+            .iter_mut()
+            .find(|endpoint| endpoint.method == request.method && endpoint.path == request.path)
+        {
+            Some(endpoint) => endpoint.handlers.push(handler),
+            None => endpoints.push(Endpoint::new(request.path, request.method).with_handler(handler)),
+        }
+    }
+    Ok(endpoints)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_endpoints_group_exchanges_in_order() {
+        let recording = json!([
+            {"request": {"method": "GET", "path": "/a", "sequence": 0}, "response": {"status": 200, "body": "first"}},
+            {"request": {"method": "POST", "path": "/a"}, "response": {"status": 201}},
+            {"request": {"method": "GET", "path": "/a"}, "response": {"status": 200, "body": {"base64": "3q0="}}}
+        ]);
+
+        let endpoints = endpoints(&recording).unwrap();
+        assert_eq!(endpoints.len(), 2);
+        assert_eq!((endpoints[0].method, endpoints[0].path.as_str()), (Method::Get, "/a"));
+        assert_eq!(endpoints[0].handlers.len(), 2);
+        assert_eq!(endpoints[0].handlers[0].response().body, b"first");
+        assert_eq!(endpoints[0].handlers[1].response().body, vec![0xde, 0xad]);
+        assert_eq!(endpoints[1].method, Method::Post);
+        assert_eq!(endpoints[1].handlers[0].response().status, 201);
+    }
+
+    #[test]
+    fn test_endpoints_reject_invalid_recording() {
+        let recording = json!([{"request": {"method": "GET"}, "response": {"status": 200}}]);
+        assert!(matches!(endpoints(&recording), Err(HarnessError::ParseError(_))));
+    }
+}
@@ -1,12 +1,37 @@
+use serde::Serialize;
 use std::collections::HashMap;
+use std::time::Instant;
 
 /// Represents an HTTP request received by the harness
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Request {
     pub method: super::Method,
     pub path: String,
+    pub path_params: HashMap<String, String>,
     pub headers: HashMap<String, String>,
+    #[serde(serialize_with = "super::body::serialize")]
     pub body: Vec<u8>,
+    /// Protocol version the request arrived over, `"HTTP/1.1"` or `"HTTP/2.0"`
+    pub http_version: String,
+    /// When the server collected the request
+    #[serde(skip)]
+    pub received_at: Instant,
+    /// Position of the request in collection order, starting at 0 for each server run
+    pub sequence: u64,
+    /// Whether a configured handler served the request, rather than a default
+    /// handler or the server's "not found" fallback
+    pub matched: bool,
+    /// Identifier of the connection the request arrived on, unique within the process.
+    /// Requests sharing it reused one kept-alive connection.
+    #[serde(skip)]
+    pub connection_id: u64,
+    /// Server name (SNI) the client sent in its TLS handshake, `None` over plain TCP
+    /// or when the client sent none, e.g. when connecting to an IP address
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sni: Option<String>,
+    /// Protocol negotiated through ALPN during the TLS handshake, such as `"h2"`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alpn: Option<String>,
 }
 
 impl Request {
@@ -14,11 +39,24 @@ impl Request {
         Self {
             method,
             path: path.into(),
+            path_params: HashMap::new(),
             headers: HashMap::new(),
             body: Vec::new(),
+            http_version: "HTTP/1.1".to_string(),
+            received_at: Instant::now(),
+            sequence: 0,
+            matched: true,
+            connection_id: 0,
+            sni: None,
+            alpn: None,
         }
     }
 
+    pub fn with_path_param(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.path_params.insert(key.into(), value.into());
+        self
+    }
+
     pub fn with_header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
         self.headers.insert(key.into(), value.into());
         self
@@ -32,36 +70,134 @@ impl Request {
     pub fn body_as_str(&self) -> Option<&str> {
         std::str::from_utf8(&self.body).ok()
     }
+
+    /// Parse the body as JSON, returning `None` when it is empty or not valid JSON
+    pub fn json_body(&self) -> Option<serde_json::Value> {
+        serde_json::from_slice(&self.body).ok()
+    }
+
+    /// Parse the body as JSON-RPC calls, one per batch entry. Empty when the
+    /// body is not JSON; entries without a `method` are skipped.
+    pub fn json_rpc_calls(&self) -> Vec<super::JsonRpcCall> {
+        self.json_body()
+            .map(|body| super::JsonRpcCall::parse(&body))
+            .unwrap_or_default()
+    }
+
+    /// Get a header value by name, matched case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Parse the `Cookie` header into cookie values by name, empty without the header
+    pub fn cookies(&self) -> HashMap<String, String> {
+        self.header("cookie")
+            .map(super::cookie::parse)
+            .unwrap_or_default()
+    }
+
+    /// Get the token from an `Authorization: Bearer <token>` header.
+    /// The header name and scheme are matched case-insensitively.
+    pub fn authorization_bearer(&self) -> Option<&str> {
+        let (scheme, token) = self.header("authorization")?.trim().split_once(' ')?;
+        scheme
+            .eq_ignore_ascii_case("bearer")
+            .then(|| token.trim_start())
+    }
+
+    /// Parse a `multipart/form-data` body into its parts, using the boundary
+    /// from the `content-type` header. Returns `None` for other or malformed bodies.
+    #[cfg(feature = "multipart")]
+    pub fn multipart(&self) -> Option<Vec<super::MultipartPart>> {
+        super::multipart::parse(self)
+    }
+}
+
+impl server_harness_core::Matched for Request {
+    fn matched(&self) -> bool {
+        self.matched
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::entities::Method;
-    use std::collections::HashMap;
+
+    #[test]
+    fn test_request_serialize() {
+        let request = Request::new(Method::Post, "/upload")
+            .with_header("content-type", "text/plain")
+            .with_body("hi");
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({
+                "method": "POST",
+                "path": "/upload",
+                "path_params": {},
+                "headers": {"content-type": "text/plain"},
+                "body": "hi",
+                "http_version": "HTTP/1.1",
+                "sequence": 0,
+                "matched": true
+            })
+        );
+
+        let binary = Request::new(Method::Put, "/upload").with_body(vec![0xff, 0x00]);
+        assert_eq!(
+            serde_json::to_value(&binary).unwrap()["body"],
+            serde_json::json!({"base64": "/wA="})
+        );
+    }
 
     #[test]
     fn test_request_body_as_str() {
-        let request = Request {
-            method: Method::Post,
-            path: "/test".to_string(),
-            headers: HashMap::new(),
-            body: b"Hello World".to_vec(),
-        };
+        let request = Request::new(Method::Post, "/test").with_body(b"Hello World".to_vec());
         assert_eq!(request.body_as_str(), Some("Hello World"));
     }
 
     #[test]
     fn test_request_body_as_str_invalid_utf8() {
-        let request = Request {
-            method: Method::Post,
-            path: "/test".to_string(),
-            headers: HashMap::new(),
-            body: vec![0xFF, 0xFE],
-        };
+        let request = Request::new(Method::Post, "/test").with_body(vec![0xFF, 0xFE]);
         assert_eq!(request.body_as_str(), None);
     }
 
+    #[test]
+    fn test_request_json_body() {
+        let request = Request::new(Method::Patch, "/test").with_body(r#"{"name":"Bob"}"#);
+        assert_eq!(request.json_body(), Some(serde_json::json!({"name": "Bob"})));
+        assert_eq!(Request::new(Method::Patch, "/test").json_body(), None);
+        let request = Request::new(Method::Patch, "/test").with_body("not json");
+        assert_eq!(request.json_body(), None);
+    }
+
+    #[test]
+    fn test_request_with_path_param() {
+        let request = Request::new(Method::Get, "/api/users/123").with_path_param("id", "123");
+        assert_eq!(request.path_params.get("id").map(String::as_str), Some("123"));
+    }
+
+    #[test]
+    fn test_request_authorization_bearer() {
+        let request = Request::new(Method::Get, "/").with_header("authorization", "Bearer xyz");
+        assert_eq!(request.authorization_bearer(), Some("xyz"));
+
+        let request = Request::new(Method::Get, "/").with_header("Authorization", "bearer xyz");
+        assert_eq!(request.authorization_bearer(), Some("xyz"));
+    }
+
+    #[test]
+    fn test_request_authorization_bearer_missing() {
+        let request = Request::new(Method::Get, "/");
+        assert_eq!(request.authorization_bearer(), None);
+
+        let request = Request::new(Method::Get, "/").with_header("authorization", "Basic dXNlcg==");
+        assert_eq!(request.authorization_bearer(), None);
+    }
+
     #[test]
     fn test_request_method_display() {
         assert_eq!(format!("{}", Method::Get), "GET");
@@ -1,11 +1,56 @@
+use super::CookieAttributes;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+/// Content coding the server may apply to a response body
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Gzip,
+    Deflate,
+    Br,
+}
+
+impl Encoding {
+    /// Token used in `Accept-Encoding` and `Content-Encoding`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Gzip => "gzip",
+            Encoding::Deflate => "deflate",
+            Encoding::Br => "br",
+        }
+    }
+}
 
 /// Represents an HTTP response to be sent by the harness
-#[derive(Debug, Clone)]
+///
+/// Serialized bodies are strings, or `{"base64": ...}` when not valid UTF-8.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Response {
     pub status: u16,
+    #[serde(default)]
     pub headers: HashMap<String, String>,
+    /// `Set-Cookie` header values, sent as one header each
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cookies: Vec<String>,
+    #[serde(default, with = "super::body")]
     pub body: Vec<u8>,
+    /// Encoding applied to the body when the request advertises support for it
+    #[serde(skip)]
+    pub compression: Option<Encoding>,
+    /// Chunks the body is streamed in, `chunk_delay` apart; empty sends the body at once
+    #[serde(skip)]
+    pub chunks: Vec<Vec<u8>>,
+    #[serde(skip)]
+    pub chunk_delay: Duration,
+    /// Request headers copied onto the response when it is sent
+    #[serde(skip)]
+    pub echo_headers: Vec<String>,
+    /// Headers sent in a trailers frame after the body
+    #[serde(skip)]
+    pub trailers: HashMap<String, String>,
 }
 
 impl Response {
@@ -13,7 +58,13 @@ impl Response {
         Self {
             status,
             headers: HashMap::new(),
+            cookies: Vec::new(),
             body: Vec::new(),
+            compression: None,
+            chunks: Vec::new(),
+            chunk_delay: Duration::ZERO,
+            echo_headers: Vec::new(),
+            trailers: HashMap::new(),
         }
     }
 
@@ -33,11 +84,30 @@ impl Response {
         Self::new(500)
     }
 
+    /// Redirect to `location` with a 3xx `status`, such as 301, 302, 303,
+    /// 307 or 308. Relative locations like `/b` are resolved by the client.
+    pub fn redirect(status: u16, location: impl Into<String>) -> Self {
+        Self::new(status).with_header("location", location)
+    }
+
     pub fn with_header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
         self.headers.insert(key.into(), value.into());
         self
     }
 
+    /// Set a cookie on the client with a `Set-Cookie` header. Several cookies
+    /// can be set on one response.
+    pub fn with_cookie(
+        mut self,
+        name: impl AsRef<str>,
+        value: impl AsRef<str>,
+        attributes: CookieAttributes,
+    ) -> Self {
+        let cookie = super::cookie::set_cookie(name.as_ref(), value.as_ref(), &attributes);
+        self.cookies.push(cookie);
+        self
+    }
+
     pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
         self.body = body.into();
         self
@@ -48,6 +118,75 @@ impl Response {
         self.body = serde_json::to_vec(value).unwrap_or_default();
         self
     }
+
+    /// Read the body from the file at `path`, keeping large fixtures out of test source.
+    ///
+    /// Files with a `.json` extension also set `content-type: application/json`.
+    pub fn with_body_from_file(mut self, path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        self.body = std::fs::read(path)?;
+        let is_json = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+        if is_json {
+            self.headers.insert("content-type".to_string(), "application/json".to_string());
+        }
+        Ok(self)
+    }
+
+    /// Stream the body as `chunks`, pausing `delay` between consecutive chunks.
+    ///
+    /// `body` holds the chunks concatenated. Chunked responses are never compressed.
+    pub fn with_chunks<I, B>(mut self, chunks: I, delay: Duration) -> Self
+    where
+        I: IntoIterator<Item = B>,
+        B: Into<Vec<u8>>,
+    {
+        self.chunks = chunks.into_iter().map(Into::into).collect();
+        self.chunk_delay = delay;
+        self.body = self.chunks.concat();
+        self
+    }
+
+    /// Send `name: value` as a trailer after the body, e.g. `grpc-status`.
+    ///
+    /// The body is then streamed in a chunked encoding with a `Trailer` header
+    /// naming the trailers, and never compressed. Over HTTP/1.1 the server only
+    /// sends trailers to clients whose request carries `TE: trailers`.
+    pub fn with_trailer(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.trailers.insert(name.into(), value.into());
+        self
+    }
+
+    /// Copy the request header `name` onto the response when it is sent, e.g.
+    /// to echo `X-Request-Id`. Requests without the header leave the response
+    /// unchanged.
+    pub fn echo_header(mut self, name: impl Into<String>) -> Self {
+        self.echo_headers.push(name.into());
+        self
+    }
+
+    /// Copy the headers listed by [`Response::echo_header`] from `request_headers`,
+    /// matching names case-insensitively
+    #[cfg(feature = "axum")]
+    pub(crate) fn apply_echo_headers(&mut self, request_headers: &HashMap<String, String>) {
+        for name in std::mem::take(&mut self.echo_headers) {
+            let value = request_headers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(&name))
+                .map(|(_, value)| value.clone());
+            if let Some(value) = value {
+                self.headers.insert(name, value);
+            }
+        }
+    }
+
+    /// Compress the body with `encoding` if the client's `Accept-Encoding` allows it;
+    /// otherwise the body is sent as is
+    pub fn with_compression(mut self, encoding: Encoding) -> Self {
+        self.compression = Some(encoding);
+        self
+    }
 }
 
 impl Default for Response {
@@ -68,12 +207,35 @@ mod tests {
         assert!(response.body.is_empty());
     }
 
+    #[test]
+    fn test_response_redirect() {
+        let response = Response::redirect(308, "/v2/users");
+        assert_eq!(response.status, 308);
+        assert_eq!(response.headers["location"], "/v2/users");
+        assert!(response.body.is_empty());
+    }
+
     #[test]
     fn test_response_with_body() {
         let response = Response::new(200).with_body("Hello");
         assert_eq!(response.body, b"Hello");
     }
 
+    #[test]
+    fn test_response_with_chunks() {
+        let response = Response::ok().with_chunks(["a", "bc"], Duration::from_millis(5));
+        assert_eq!(response.chunks, vec![b"a".to_vec(), b"bc".to_vec()]);
+        assert_eq!(response.chunk_delay, Duration::from_millis(5));
+        assert_eq!(response.body, b"abc");
+    }
+
+    #[test]
+    fn test_response_with_trailer() {
+        let response = Response::ok().with_trailer("grpc-status", "0");
+        assert_eq!(response.trailers["grpc-status"], "0");
+        assert!(Response::ok().trailers.is_empty());
+    }
+
     #[test]
     fn test_response_with_json_body() {
         let response = Response::new(200).with_json(&serde_json::json!({"key": "value"}));
@@ -81,12 +243,38 @@ mod tests {
         assert!(response.headers.get("content-type").unwrap().contains("application/json"));
     }
 
+    #[test]
+    fn test_response_with_body_from_file() {
+        let path = std::env::temp_dir().join(format!("harness-fixture-{}.json", std::process::id()));
+        std::fs::write(&path, br#"{"users":[]}"#).unwrap();
+        let response = Response::ok().with_body_from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let response = response.unwrap();
+        assert_eq!(response.body, br#"{"users":[]}"#);
+        assert_eq!(response.headers.get("content-type").unwrap(), "application/json");
+    }
+
+    #[test]
+    fn test_response_with_body_from_missing_file() {
+        let path = std::env::temp_dir().join("harness-fixture-missing.txt");
+        let error = Response::ok().with_body_from_file(path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    }
+
     #[test]
     fn test_response_with_header() {
         let response = Response::new(200).with_header("X-Custom", "value");
         assert_eq!(response.headers.get("X-Custom").unwrap(), "value");
     }
 
+    #[test]
+    fn test_response_with_compression() {
+        let response = Response::ok().with_compression(Encoding::Gzip);
+        assert_eq!(response.compression, Some(Encoding::Gzip));
+        assert_eq!(Encoding::Br.as_str(), "br");
+    }
+
     #[test]
     fn test_response_ok() {
         let response = Response::ok();
@@ -110,5 +298,27 @@ mod tests {
         let response = Response::internal_error();
         assert_eq!(response.status, 500);
     }
+
+    #[cfg(feature = "axum")]
+    #[test]
+    fn test_apply_echo_headers() {
+        let mut response = Response::ok()
+            .echo_header("X-Request-Id")
+            .echo_header("X-Missing");
+        let request_headers =
+            HashMap::from([("x-request-id".to_string(), "req-1".to_string())]);
+        response.apply_echo_headers(&request_headers);
+        assert_eq!(response.headers["X-Request-Id"], "req-1");
+        assert!(!response.headers.contains_key("X-Missing"));
+        assert!(response.echo_headers.is_empty());
+    }
+
+    #[test]
+    fn test_with_cookie() {
+        let response = Response::ok()
+            .with_cookie("session", "abc", CookieAttributes::new().http_only())
+            .with_cookie("theme", "dark", CookieAttributes::new());
+        assert_eq!(response.cookies, ["session=abc; HttpOnly", "theme=dark"]);
+    }
 }
 
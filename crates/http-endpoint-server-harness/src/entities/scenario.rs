@@ -1,8 +1,10 @@
 use super::Endpoint;
+use crate::use_cases::ports::RunOptions;
 
 /// A test scenario containing a server configuration, collector, and endpoints
 pub struct Scenario<S, C> {
     pub(crate) server: S,
     pub(crate) collector: C,
     pub(crate) endpoints: Vec<Endpoint>,
+    pub(crate) options: RunOptions,
 }
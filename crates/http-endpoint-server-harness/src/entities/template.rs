@@ -0,0 +1,118 @@
+use super::Request;
+use serde_json::Value;
+
+/// Render a JSON template against a request.
+///
+/// String leaves that are entirely a `${...}` placeholder are replaced with the
+/// value they refer to. Supported placeholders:
+///
+/// - `${method}` and `${path}`
+/// - `${path.params.<name>}` for captured path segments
+/// - `${headers.<name>}` (case-insensitive)
+/// - `${body}` or `${body.<field>.<field>}` for JSON request bodies
+///   (numeric segments index into arrays)
+///
+/// Unknown placeholders resolve to `null`.
+pub(crate) fn render(template: &Value, request: &Request) -> Value {
+    let body = serde_json::from_slice::<Value>(&request.body).ok();
+    render_value(template, request, body.as_ref())
+}
+
+fn render_value(template: &Value, request: &Request, body: Option<&Value>) -> Value {
+    match template {
+        Value::String(s) => match placeholder(s) {
+            Some(expr) => resolve(expr, request, body).unwrap_or(Value::Null),
+            None => template.clone(),
+        },
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| render_value(item, request, body))
+                .collect(),
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), render_value(v, request, body)))
+                .collect(),
+        ),
+        _ => template.clone(),
+    }
+}
+
+/// Return the inner expression if the string is exactly `${expr}`
+fn placeholder(s: &str) -> Option<&str> {
+    s.strip_prefix("${")?.strip_suffix('}').map(str::trim)
+}
+
+fn resolve(expr: &str, request: &Request, body: Option<&Value>) -> Option<Value> {
+    let segments: Vec<&str> = expr.split('.').collect();
+    match segments.as_slice() {
+        ["method"] => Some(Value::String(request.method.to_string())),
+        ["path"] => Some(Value::String(request.path.clone())),
+        ["path", "params", name] => request.path_params.get(*name).cloned().map(Value::String),
+        ["headers", name] => request.header(name).map(|value| Value::String(value.to_string())),
+        ["body", fields @ ..] => {
+            let mut current = body?;
+            for field in fields {
+                current = match current {
+                    Value::Array(items) => items.get(field.parse::<usize>().ok()?)?,
+                    other => other.get(field)?,
+                };
+            }
+            Some(current.clone())
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::Method;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_path_param() {
+        let request = Request::new(Method::Get, "/users/42").with_path_param("id", "42");
+        let rendered = render(&json!({"id": "${path.params.id}"}), &request);
+        assert_eq!(rendered, json!({"id": "42"}));
+    }
+
+    #[test]
+    fn test_render_body_fields() {
+        let request = Request::new(Method::Post, "/users")
+            .with_body(r#"{"name": "Alice", "tags": ["a", "b"], "age": 30}"#);
+        let rendered = render(
+            &json!({"name": "${body.name}", "tag": "${body.tags.1}", "age": "${body.age}"}),
+            &request,
+        );
+        assert_eq!(rendered, json!({"name": "Alice", "tag": "b", "age": 30}));
+    }
+
+    #[test]
+    fn test_render_method_path_and_headers() {
+        let request = Request::new(Method::Put, "/items").with_header("x-request-id", "abc");
+        let rendered = render(
+            &json!(["${method}", "${path}", "${headers.X-Request-Id}"]),
+            &request,
+        );
+        assert_eq!(rendered, json!(["PUT", "/items", "abc"]));
+    }
+
+    #[test]
+    fn test_render_unknown_placeholder_is_null() {
+        let request = Request::new(Method::Get, "/");
+        let rendered = render(
+            &json!({"a": "${body.missing}", "b": "${nope}", "c": "${path.params.id}"}),
+            &request,
+        );
+        assert_eq!(rendered, json!({"a": null, "b": null, "c": null}));
+    }
+
+    #[test]
+    fn test_render_leaves_partial_strings_untouched() {
+        let request = Request::new(Method::Get, "/");
+        let rendered = render(&json!({"greeting": "Hello ${body.name}", "n": 1}), &request);
+        assert_eq!(rendered, json!({"greeting": "Hello ${body.name}", "n": 1}));
+    }
+}
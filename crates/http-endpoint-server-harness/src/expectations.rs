@@ -0,0 +1,157 @@
+//! Verifying the order in which endpoints were called
+
+use crate::entities::{Method, Request};
+use crate::error::HarnessError;
+
+/// How collected requests are compared against an [`ExpectationSet`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OrderMode {
+    /// The requests are exactly the expected ones, in order
+    #[default]
+    Strict,
+    /// The expected requests appear in order, other requests may come between them
+    Subsequence,
+}
+
+/// An ordered list of `(method, path)` calls to verify collected requests against
+///
+/// ```
+/// use http_endpoint_server_harness::expectations::ExpectationSet;
+/// use http_endpoint_server_harness::prelude::*;
+///
+/// let collected = vec![
+///     Request::new(Method::Post, "/login"),
+///     Request::new(Method::Get, "/api/profile"),
+///     Request::new(Method::Post, "/logout"),
+/// ];
+/// ExpectationSet::new()
+///     .then(Method::Post, "/login")
+///     .then(Method::Post, "/logout")
+///     .subsequence()
+///     .verify(&collected)
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ExpectationSet {
+    expected: Vec<(Method, String)>,
+    mode: OrderMode,
+}
+
+impl ExpectationSet {
+    /// Create an empty set matching in [`OrderMode::Strict`] mode
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expect a call to `path` with `method` after the previous expectations
+    pub fn then(mut self, method: Method, path: impl Into<String>) -> Self {
+        self.expected.push((method, path.into()));
+        self
+    }
+
+    /// Allow other requests before, between and after the expected ones
+    pub fn subsequence(mut self) -> Self {
+        self.mode = OrderMode::Subsequence;
+        self
+    }
+
+    /// Check `requests`, in collection order, against the expectations.
+    ///
+    /// Fails with [`HarnessError::OrderViolation`] listing the expected calls
+    /// and the calls actually received.
+    pub fn verify(&self, requests: &[Request]) -> Result<(), HarnessError> {
+        let mut actual = requests.iter().map(|r| (r.method, r.path.as_str()));
+        let expected = self.expected.iter().map(|(m, p)| (*m, p.as_str()));
+        let in_order = match self.mode {
+            OrderMode::Strict => expected.eq(actual),
+            OrderMode::Subsequence => expected
+                .into_iter()
+                .all(|call| actual.any(|received| received == call)),
+        };
+        if in_order {
+            return Ok(());
+        }
+
+        Err(HarnessError::OrderViolation {
+            expected: self
+                .expected
+                .iter()
+                .map(|(method, path)| format!("{} {}", method, path))
+                .collect(),
+            actual: requests
+                .iter()
+                .map(|r| format!("{} {}", r.method, r.path))
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn requests(calls: &[(Method, &str)]) -> Vec<Request> {
+        calls.iter().map(|(method, path)| Request::new(*method, *path)).collect()
+    }
+
+    fn session() -> ExpectationSet {
+        ExpectationSet::new()
+            .then(Method::Post, "/login")
+            .then(Method::Get, "/api/data")
+            .then(Method::Post, "/logout")
+    }
+
+    #[test]
+    fn test_strict_in_order() {
+        let collected = requests(&[
+            (Method::Post, "/login"),
+            (Method::Get, "/api/data"),
+            (Method::Post, "/logout"),
+        ]);
+        assert!(session().verify(&collected).is_ok());
+
+        // Strict mode rejects extra calls
+        let mut extra = collected.clone();
+        extra.push(Request::new(Method::Get, "/api/data"));
+        assert!(session().verify(&extra).is_err());
+    }
+
+    #[test]
+    fn test_out_of_order_fails() {
+        let collected = requests(&[
+            (Method::Get, "/api/data"),
+            (Method::Post, "/login"),
+            (Method::Post, "/logout"),
+        ]);
+        for set in [session(), session().subsequence()] {
+            match set.verify(&collected) {
+                Err(HarnessError::OrderViolation { expected, actual }) => {
+                    assert_eq!(expected, ["POST /login", "GET /api/data", "POST /logout"]);
+                    assert_eq!(actual, ["GET /api/data", "POST /login", "POST /logout"]);
+                }
+                other => panic!("expected an order violation, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_subsequence_allows_other_calls() {
+        let collected = requests(&[
+            (Method::Get, "/health"),
+            (Method::Post, "/login"),
+            (Method::Get, "/api/data"),
+            (Method::Get, "/api/other"),
+            (Method::Post, "/logout"),
+        ]);
+        assert!(session().subsequence().verify(&collected).is_ok());
+        assert!(session().verify(&collected).is_err());
+
+        // Each received call satisfies at most one expectation
+        let collected = requests(&[(Method::Post, "/login"), (Method::Get, "/api/data")]);
+        let twice = ExpectationSet::new()
+            .then(Method::Get, "/api/data")
+            .then(Method::Get, "/api/data")
+            .subsequence();
+        assert!(twice.verify(&collected).is_err());
+    }
+}
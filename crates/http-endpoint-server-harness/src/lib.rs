@@ -51,58 +51,81 @@
 //! ```
 
 mod adapters;
+pub mod assertions;
+pub mod dedup;
 pub mod entities;
 pub mod error;
+pub mod expectations;
+pub mod matchers;
+pub mod metrics;
+#[cfg(feature = "axum")]
+pub mod testing;
 pub mod use_cases;
 
 pub use error::HarnessError;
 
 #[cfg(feature = "axum")]
-pub use adapters::gateways::Axum;
+pub use adapters::gateways::{Axum, BoundServer};
+
+#[cfg(feature = "tls")]
+pub use adapters::gateways::{rustls_test_config, TEST_CERT_PEM};
+#[cfg(feature = "tls")]
+pub use tokio_rustls::rustls::ServerConfig as RustlsConfig;
 
 /// Default collector implementation that collects requests into a Vec
-pub struct DefaultCollector {
-    requests: std::sync::Mutex<Vec<entities::Request>>,
-}
+pub type DefaultCollector = server_harness_core::DefaultCollector<entities::Request>;
 
-impl DefaultCollector {
-    pub fn new() -> Self {
-        Self {
-            requests: std::sync::Mutex::new(Vec::new()),
-        }
-    }
-}
+/// Collector that records requests as JSON, e.g. for snapshot tests
+///
+/// `into_output` returns an array with one object per request. Bodies are
+/// strings, or `{"base64": ...}` when not valid UTF-8.
+pub type RecordingCollector = server_harness_core::RecordingCollector<entities::Request>;
 
-impl Default for DefaultCollector {
-    fn default() -> Self {
-        Self::new()
-    }
-}
+/// Collector that fails when a request matched no configured handler
+///
+/// `into_output` returns every request as `Ok` when all of them matched, and the
+/// unmatched ones as `Err` otherwise. See [`Request::matched`](entities::Request::matched).
+pub type StrictCollector = server_harness_core::StrictCollector<entities::Request>;
 
-impl use_cases::ports::Collector for DefaultCollector {
-    type Output = Vec<entities::Request>;
+/// Collector storing only the requests a predicate accepts, e.g. to ignore
+/// health checks, and delegating them to an inner collector
+///
+/// Filtered requests are still answered normally.
+pub use server_harness_core::FilteringCollector;
 
-    fn collect(&self, request: entities::Request) {
-        if let Ok(mut requests) = self.requests.lock() {
-            requests.push(request);
-        }
-    }
+/// Collectors that can be read mid-scenario through a shared `Arc` handle
+pub use server_harness_core::Snapshot;
 
-    fn into_output(self) -> Self::Output {
-        self.requests.into_inner().unwrap_or_default()
-    }
-}
+pub use dedup::DedupCollector;
+pub use metrics::MetricsCollector;
 
 /// Prelude module for convenient imports
 pub mod prelude {
-    pub use crate::entities::{Endpoint, Handler, Method, Request, Response};
+    pub use crate::assertions::{RequestAssertions, RequestLog};
+    pub use crate::entities::{
+        AllowConfig, CookieAttributes, Encoding, Endpoint, Handler, JsonRpcEndpoint, Method,
+        Request, Response, SameSite,
+    };
     pub use crate::error::HarnessError;
+    pub use crate::expectations::ExpectationSet;
+    pub use crate::matchers::JsonMatcher;
     pub use crate::use_cases::ports::Collector;
     pub use crate::use_cases::ScenarioBuilder;
-    pub use crate::DefaultCollector;
+    pub use crate::{
+        DedupCollector, DefaultCollector, FilteringCollector, MetricsCollector, RecordingCollector,
+        Snapshot, StrictCollector,
+    };
 
+    #[cfg(feature = "axum")]
+    pub use crate::use_cases::ScenarioHandle;
     #[cfg(feature = "axum")]
     pub use crate::Axum;
 
+    #[cfg(feature = "tls")]
+    pub use crate::{rustls_test_config, RustlsConfig};
+
+    #[cfg(feature = "multipart")]
+    pub use crate::entities::MultipartPart;
+
     pub use serde_json::json;
 }
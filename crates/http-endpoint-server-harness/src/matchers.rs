@@ -0,0 +1,205 @@
+//! Matching the shape of JSON bodies without exact equality
+
+use serde_json::Value;
+
+use crate::entities::Request;
+
+/// A pattern over JSON values that ignores volatile leaves such as
+/// generated ids or timestamps
+///
+/// Objects match when every listed key matches; keys the pattern does not
+/// list are ignored. Arrays match element by element and must have the same
+/// length. Any other value must be equal.
+///
+/// ```
+/// use http_endpoint_server_harness::matchers::{any_number, JsonMatcher};
+/// use http_endpoint_server_harness::prelude::*;
+///
+/// let matcher = JsonMatcher::object([("id", any_number()), ("name", "x".into())]);
+/// assert!(matcher.matches(&json!({"id": 42, "name": "x"})));
+/// assert!(!matcher.matches(&json!({"id": "42", "name": "x"})));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonMatcher {
+    /// Any value, including `null`
+    Any,
+    /// Any string
+    AnyString,
+    /// Any number
+    AnyNumber,
+    /// A value equal to this one
+    Exact(Value),
+    /// An object whose listed keys match
+    Object(Vec<(String, JsonMatcher)>),
+    /// An array of the same length whose elements match in order
+    Array(Vec<JsonMatcher>),
+}
+
+/// Match any value, including `null`
+pub fn any() -> JsonMatcher {
+    JsonMatcher::Any
+}
+
+/// Match any string
+pub fn any_string() -> JsonMatcher {
+    JsonMatcher::AnyString
+}
+
+/// Match any number
+pub fn any_number() -> JsonMatcher {
+    JsonMatcher::AnyNumber
+}
+
+impl JsonMatcher {
+    /// Match an object whose listed keys match, ignoring other keys
+    pub fn object<I, K>(fields: I) -> Self
+    where
+        I: IntoIterator<Item = (K, JsonMatcher)>,
+        K: Into<String>,
+    {
+        JsonMatcher::Object(fields.into_iter().map(|(k, m)| (k.into(), m)).collect())
+    }
+
+    /// Match an array element by element
+    pub fn array(items: impl IntoIterator<Item = JsonMatcher>) -> Self {
+        JsonMatcher::Array(items.into_iter().collect())
+    }
+
+    /// Check whether `value` conforms to the pattern
+    pub fn matches(&self, value: &Value) -> bool {
+        self.mismatches(value).is_empty()
+    }
+
+    /// Check whether the request body is JSON conforming to the pattern,
+    /// e.g. to pick a response inside a dynamic handler
+    pub fn matches_request(&self, request: &Request) -> bool {
+        request.json_body().is_some_and(|body| self.matches(&body))
+    }
+
+    /// Describe every place `value` does not conform, one line per mismatch
+    pub fn mismatches(&self, value: &Value) -> Vec<String> {
+        let mut mismatches = Vec::new();
+        self.collect_mismatches("$", value, &mut mismatches);
+        mismatches
+    }
+
+    fn collect_mismatches(&self, path: &str, value: &Value, mismatches: &mut Vec<String>) {
+        match (self, value) {
+            (JsonMatcher::Any, _)
+            | (JsonMatcher::AnyString, Value::String(_))
+            | (JsonMatcher::AnyNumber, Value::Number(_)) => {}
+            (JsonMatcher::AnyString, _) => {
+                mismatches.push(format!("  {}: expected any string, got {}", path, value))
+            }
+            (JsonMatcher::AnyNumber, _) => {
+                mismatches.push(format!("  {}: expected any number, got {}", path, value))
+            }
+            (JsonMatcher::Exact(expected), _) if expected != value => {
+                mismatches.push(format!("  {}: expected {}, got {}", path, expected, value))
+            }
+            (JsonMatcher::Exact(_), _) => {}
+            (JsonMatcher::Object(fields), Value::Object(object)) => {
+                for (key, matcher) in fields {
+                    let field = format!("{}.{}", path, key);
+                    match object.get(key) {
+                        Some(value) => matcher.collect_mismatches(&field, value, mismatches),
+                        None => mismatches.push(format!("  {}: missing", field)),
+                    }
+                }
+            }
+            (JsonMatcher::Array(items), Value::Array(values)) if items.len() == values.len() => {
+                for (i, (matcher, value)) in items.iter().zip(values).enumerate() {
+                    matcher.collect_mismatches(&format!("{}[{}]", path, i), value, mismatches);
+                }
+            }
+            (JsonMatcher::Array(items), Value::Array(values)) => mismatches.push(format!(
+                "  {}: expected {} items, got {}",
+                path,
+                items.len(),
+                values.len()
+            )),
+            (JsonMatcher::Object(_), _) => {
+                mismatches.push(format!("  {}: expected an object, got {}", path, value))
+            }
+            (JsonMatcher::Array(_), _) => {
+                mismatches.push(format!("  {}: expected an array, got {}", path, value))
+            }
+        }
+    }
+}
+
+impl From<Value> for JsonMatcher {
+    fn from(value: Value) -> Self {
+        JsonMatcher::Exact(value)
+    }
+}
+
+impl From<&str> for JsonMatcher {
+    fn from(value: &str) -> Self {
+        JsonMatcher::Exact(Value::from(value))
+    }
+}
+
+impl From<bool> for JsonMatcher {
+    fn from(value: bool) -> Self {
+        JsonMatcher::Exact(Value::from(value))
+    }
+}
+
+impl From<i64> for JsonMatcher {
+    fn from(value: i64) -> Self {
+        JsonMatcher::Exact(Value::from(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::Method;
+    use serde_json::json;
+
+    #[test]
+    fn test_object_ignores_volatile_leaves() {
+        let matcher = JsonMatcher::object([("id", any_number()), ("name", "x".into())]);
+        assert!(matcher.matches(&json!({"id": 1, "name": "x"})));
+        assert!(matcher.matches(&json!({"id": 2.5, "name": "x", "createdAt": "2024-01-01"})));
+        assert!(!matcher.matches(&json!({"id": 1, "name": "y"})));
+        assert!(!matcher.matches(&json!({"name": "x"})));
+        assert!(!matcher.matches(&json!([1, "x"])));
+    }
+
+    #[test]
+    fn test_nested_matchers() {
+        let matcher = JsonMatcher::object([
+            ("token", any_string()),
+            (
+                "user",
+                JsonMatcher::object([
+                    ("id", any()),
+                    ("roles", JsonMatcher::array([any_string(), "admin".into()])),
+                ]),
+            ),
+        ]);
+        let body = json!({"token": "abc", "user": {"id": null, "roles": ["user", "admin"]}});
+        assert!(matcher.matches(&body));
+
+        let body = json!({"token": 7, "user": {"roles": ["user"]}});
+        assert_eq!(
+            matcher.mismatches(&body),
+            [
+                "  $.token: expected any string, got 7",
+                "  $.user.id: missing",
+                "  $.user.roles: expected 2 items, got 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_matches_request() {
+        let matcher = JsonMatcher::object([("id", any_number())]);
+        let request = Request::new(Method::Post, "/api/users").with_body(r#"{"id": 3}"#);
+        assert!(matcher.matches_request(&request));
+        let request = Request::new(Method::Post, "/api/users").with_body("not json");
+        assert!(!matcher.matches_request(&request));
+    }
+}
@@ -0,0 +1,226 @@
+//! Aggregate request statistics, for scenarios that only need counts and sizes
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+
+use crate::entities::{Method, Request};
+use server_harness_core::{Collector, Snapshot};
+
+/// Aggregate statistics over the requests of a scenario
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metrics {
+    /// Requests per method and path
+    pub counts: HashMap<(Method, String), usize>,
+    pub total_requests: usize,
+    /// Body bytes received across all requests
+    pub total_bytes: usize,
+    /// Smallest request body in bytes, `None` until a request is received
+    pub min_body_size: Option<usize>,
+    /// Largest request body in bytes, `None` until a request is received
+    pub max_body_size: Option<usize>,
+    /// Request body sizes bucketed by magnitude
+    pub body_sizes: SizeHistogram,
+}
+
+/// Number of request bodies per size bucket
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SizeHistogram {
+    /// Requests without a body
+    pub empty: usize,
+    /// Bodies of 1 byte up to 1 KiB, exclusive
+    pub under_1kib: usize,
+    /// Bodies of 1 KiB up to 1 MiB, exclusive
+    pub under_1mib: usize,
+    /// Bodies of 1 MiB or more
+    pub larger: usize,
+}
+
+impl SizeHistogram {
+    /// Number of bodies counted across all buckets
+    pub fn total(&self) -> usize {
+        self.empty + self.under_1kib + self.under_1mib + self.larger
+    }
+
+    fn record(&mut self, size: usize) {
+        let bucket = match size {
+            0 => &mut self.empty,
+            1..1024 => &mut self.under_1kib,
+            1024..1_048_576 => &mut self.under_1mib,
+            _ => &mut self.larger,
+        };
+        *bucket += 1;
+    }
+}
+
+impl Metrics {
+    /// Number of requests received for `method` and `path`
+    pub fn count(&self, method: Method, path: &str) -> usize {
+        self.counts
+            .get(&(method, path.to_string()))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Average request body size in bytes, `None` until a request is received
+    pub fn avg_body_size(&self) -> Option<f64> {
+        (self.total_requests > 0).then(|| self.total_bytes as f64 / self.total_requests as f64)
+    }
+
+    /// Render the metrics in the Prometheus text exposition format
+    pub fn to_prometheus(&self) -> String {
+        let mut counts: Vec<_> = self.counts.iter().collect();
+        counts.sort_by(|((a_method, a_path), _), ((b_method, b_path), _)| {
+            (a_path, a_method.to_string()).cmp(&(b_path, b_method.to_string()))
+        });
+
+        let mut out = String::from("# TYPE http_requests_total counter\n");
+        for ((method, path), count) in counts {
+            let _ = writeln!(
+                out,
+                "http_requests_total{{method=\"{}\",path=\"{}\"}} {}",
+                method,
+                escape_label(path),
+                count
+            );
+        }
+        let _ = writeln!(out, "# TYPE http_request_body_bytes_total counter");
+        let _ = writeln!(out, "http_request_body_bytes_total {}", self.total_bytes);
+        if let (Some(min), Some(max)) = (self.min_body_size, self.max_body_size) {
+            let _ = writeln!(out, "# TYPE http_request_body_bytes_min gauge");
+            let _ = writeln!(out, "http_request_body_bytes_min {}", min);
+            let _ = writeln!(out, "# TYPE http_request_body_bytes_max gauge");
+            let _ = writeln!(out, "http_request_body_bytes_max {}", max);
+        }
+        out
+    }
+
+    fn record(&mut self, request: &Request) {
+        let size = request.body.len();
+        *self
+            .counts
+            .entry((request.method, request.path.clone()))
+            .or_insert(0) += 1;
+        self.total_requests += 1;
+        self.total_bytes += size;
+        self.min_body_size = Some(self.min_body_size.map_or(size, |min| min.min(size)));
+        self.max_body_size = Some(self.max_body_size.map_or(size, |max| max.max(size)));
+        self.body_sizes.record(size);
+    }
+}
+
+/// Escape a Prometheus label value
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Collector keeping only aggregate [`Metrics`] instead of every request,
+/// e.g. for load-style scenarios
+#[derive(Debug, Default)]
+pub struct MetricsCollector {
+    metrics: Mutex<Metrics>,
+}
+
+impl MetricsCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Collector<Request> for MetricsCollector {
+    type Output = Metrics;
+
+    fn collect(&self, request: Request) {
+        if let Ok(mut metrics) = self.metrics.lock() {
+            metrics.record(&request);
+        }
+    }
+
+    fn into_output(self) -> Self::Output {
+        self.metrics.into_inner().unwrap_or_default()
+    }
+}
+
+impl Snapshot<Request> for MetricsCollector {
+    fn snapshot(&self) -> Self::Output {
+        self.metrics
+            .lock()
+            .map(|metrics| metrics.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collected() -> Metrics {
+        let collector = MetricsCollector::new();
+        collector.collect(Request::new(Method::Post, "/api/users").with_body("{\"a\":1}"));
+        collector.collect(Request::new(Method::Post, "/api/users").with_body("{}"));
+        collector.collect(Request::new(Method::Get, "/api/users"));
+        collector.into_output()
+    }
+
+    #[test]
+    fn test_metrics_aggregate_requests() {
+        let metrics = collected();
+        assert_eq!(metrics.count(Method::Post, "/api/users"), 2);
+        assert_eq!(metrics.count(Method::Get, "/api/users"), 1);
+        assert_eq!(metrics.count(Method::Delete, "/api/users"), 0);
+        assert_eq!(metrics.total_requests, 3);
+        assert_eq!(metrics.total_bytes, 9);
+        assert_eq!(metrics.min_body_size, Some(0));
+        assert_eq!(metrics.max_body_size, Some(7));
+        assert_eq!(metrics.avg_body_size(), Some(3.0));
+    }
+
+    #[test]
+    fn test_body_size_histogram() {
+        let collector = MetricsCollector::new();
+        for size in [0, 0, 1, 1023, 1024, 1_048_575, 1_048_576, 5_000_000] {
+            collector.collect(Request::new(Method::Post, "/upload").with_body(vec![b'x'; size]));
+        }
+
+        let histogram = collector.into_output().body_sizes;
+        assert_eq!(
+            histogram,
+            SizeHistogram {
+                empty: 2,
+                under_1kib: 2,
+                under_1mib: 2,
+                larger: 2,
+            }
+        );
+        assert_eq!(histogram.total(), 8);
+    }
+
+    #[test]
+    fn test_empty_metrics() {
+        let metrics = MetricsCollector::new().into_output();
+        assert_eq!(metrics.total_requests, 0);
+        assert_eq!(metrics.min_body_size, None);
+        assert_eq!(metrics.avg_body_size(), None);
+        assert_eq!(metrics.body_sizes.total(), 0);
+    }
+
+    #[test]
+    fn test_to_prometheus() {
+        assert_eq!(
+            collected().to_prometheus(),
+            "# TYPE http_requests_total counter\n\
+             http_requests_total{method=\"GET\",path=\"/api/users\"} 1\n\
+             http_requests_total{method=\"POST\",path=\"/api/users\"} 2\n\
+             # TYPE http_request_body_bytes_total counter\n\
+             http_request_body_bytes_total 9\n\
+             # TYPE http_request_body_bytes_min gauge\n\
+             http_request_body_bytes_min 0\n\
+             # TYPE http_request_body_bytes_max gauge\n\
+             http_request_body_bytes_max 7\n"
+        );
+        assert_eq!(escape_label("a\"b\\c"), "a\\\"b\\\\c");
+    }
+}
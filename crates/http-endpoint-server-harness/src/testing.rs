@@ -0,0 +1,54 @@
+//! Helpers for tests driving a scenario from a client task
+
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use crate::error::HarnessError;
+
+/// Delay between connection attempts in [`wait_for_ready`]
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Wait until a server accepts TCP connections on `addr`, polling until
+/// `timeout` elapses.
+///
+/// Only a TCP connection is opened, so unlike an HTTP probe the check is never
+/// collected nor answered by a handler.
+pub async fn wait_for_ready(addr: SocketAddr, timeout: Duration) -> Result<(), HarnessError> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        let connect = tokio::net::TcpStream::connect(addr);
+        let error = match tokio::time::timeout_at(deadline, connect).await {
+            Ok(Ok(_)) => return Ok(()),
+            Ok(Err(e)) => e,
+            Err(_) => io::Error::from(io::ErrorKind::TimedOut),
+        };
+        if tokio::time::Instant::now() + POLL_INTERVAL >= deadline {
+            return Err(HarnessError::IoError(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("server at {} not ready within {:?}: {}", addr, timeout, error),
+            )));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_wait_for_ready_times_out_without_server() {
+        // Bind then drop a listener to get a port nothing listens on
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let error = wait_for_ready(addr, Duration::from_millis(50)).await.unwrap_err();
+        assert!(
+            matches!(error, HarnessError::IoError(ref e) if e.kind() == io::ErrorKind::TimedOut)
+        );
+        assert!(error.to_string().contains("not ready within 50ms"));
+    }
+}
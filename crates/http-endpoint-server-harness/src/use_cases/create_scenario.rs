@@ -1,8 +1,10 @@
 use std::net::SocketAddr;
+use std::time::Duration;
 
-use crate::entities::{Endpoint, Scenario};
+use crate::entities::endpoint::check_conflicts;
+use crate::entities::{Endpoint, Handler, Request, Scenario};
 use crate::error::HarnessError;
-use crate::use_cases::ports::{Collector, Server};
+use crate::use_cases::ports::{Collector, RequestCallback, RunOptions, Server, ShutdownTrigger};
 
 /// Builder for creating scenarios with a fluent API
 ///
@@ -46,6 +48,8 @@ pub struct ScenarioBuilder<S, C> {
     server: Option<S>,
     collector: Option<C>,
     endpoints: Vec<Endpoint>,
+    prefix: Option<String>,
+    options: RunOptions,
 }
 
 impl ScenarioBuilder<(), ()> {
@@ -55,8 +59,21 @@ impl ScenarioBuilder<(), ()> {
             server: None,
             collector: None,
             endpoints: Vec::new(),
+            prefix: None,
+            options: RunOptions::default(),
         }
     }
+
+    /// Create a scenario builder replaying recorded traffic.
+    ///
+    /// `recording` is a JSON array of `{"request": ..., "response": ...}` pairs, where
+    /// requests are shaped like [`RecordingCollector`](crate::RecordingCollector) output
+    /// and responses like serialized [`Response`](crate::entities::Response)s. Each
+    /// distinct method and path becomes an endpoint answering with its recorded
+    /// responses in order.
+    pub fn from_recording(recording: &serde_json::Value) -> Result<Self, HarnessError> {
+        Ok(Self::new().endpoints(crate::entities::recording::endpoints(recording)?))
+    }
 }
 
 impl Default for ScenarioBuilder<(), ()> {
@@ -72,6 +89,8 @@ impl<S, C> ScenarioBuilder<S, C> {
             server: Some(server),
             collector: self.collector,
             endpoints: self.endpoints,
+            prefix: self.prefix,
+            options: self.options,
         }
     }
 
@@ -81,12 +100,15 @@ impl<S, C> ScenarioBuilder<S, C> {
             server: self.server,
             collector: Some(collector),
             endpoints: self.endpoints,
+            prefix: self.prefix,
+            options: self.options,
         }
     }
 
-    /// Add an endpoint to the scenario
-    pub fn endpoint(mut self, endpoint: Endpoint) -> Self {
-        self.endpoints.push(endpoint);
+    /// Add an endpoint to the scenario, or a service such as a
+    /// [`JsonRpcEndpoint`](crate::entities::JsonRpcEndpoint) building one
+    pub fn endpoint(mut self, endpoint: impl Into<Endpoint>) -> Self {
+        self.endpoints.push(endpoint.into());
         self
     }
 
@@ -95,25 +117,151 @@ impl<S, C> ScenarioBuilder<S, C> {
         self.endpoints.extend(endpoints);
         self
     }
+
+    /// Serve every endpoint under `prefix`, e.g. `/api/v2`, instead of repeating it in each path.
+    ///
+    /// The prefix applies to endpoints added before and after this call, and to their
+    /// aliases. Collected requests keep the full path the client used.
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Fail instead of waiting forever when not every handler is called in time.
+    ///
+    /// If the server has not auto-shut down within `grace`, execution returns
+    /// [`HarnessError::IncompleteScenario`] listing the handlers that were never called.
+    pub fn assert_all_called(mut self, grace: Duration) -> Self {
+        self.options.all_called_within = Some(grace);
+        self
+    }
+
+    /// Shut down after `n` handled requests rather than once every handler has been called.
+    ///
+    /// Combine with cyclic endpoints ([`Endpoint::cycle`]) for load-style tests.
+    /// With [`assert_all_called`](Self::assert_all_called), the grace period
+    /// bounds how long the server waits for all `n` requests.
+    pub fn expected_requests(mut self, n: usize) -> Self {
+        self.options.expected_requests = Some(n);
+        self
+    }
+
+    /// Keep serving in-flight requests for up to `grace` once the scenario completes.
+    ///
+    /// The server stops accepting connections when every handler has been called,
+    /// then waits for slow responses still being written. With a grace period it
+    /// stops waiting after `grace` instead of waiting for every in-flight request.
+    pub fn shutdown_grace(mut self, grace: Duration) -> Self {
+        self.options.shutdown_grace = Some(grace);
+        self
+    }
+
+    /// Refuse request bodies larger than `bytes` with 413 Payload Too Large,
+    /// without calling a handler or counting the request toward shutdown.
+    ///
+    /// Defaults to [`DEFAULT_MAX_BODY_SIZE`](crate::use_cases::ports::DEFAULT_MAX_BODY_SIZE).
+    pub fn max_body_size(mut self, bytes: usize) -> Self {
+        self.options.max_body_size = Some(bytes);
+        self
+    }
+
+    /// Keep the server running once every handler has been called, until
+    /// [`ScenarioHandle::shutdown`] is called on the handle returned by
+    /// [`start`](ScenarioBuilder::start).
+    ///
+    /// This lets a scenario act as a long-lived fixture, e.g. to make more calls
+    /// after the expected ones and assert on them before stopping it.
+    /// [`execute`](Scenario::execute) rejects such scenarios, as it could never return.
+    pub fn manual_shutdown(mut self) -> Self {
+        self.options.manual_shutdown = Some(ShutdownTrigger::new());
+        self
+    }
+
+    /// Seed the random generator of [`Handler::random`] handlers so the
+    /// sequence of picked responses is the same on every run
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.options.seed = Some(seed);
+        self
+    }
+
+    /// Call `f` with every collected request, in addition to the collector,
+    /// e.g. to notify another task as requests arrive.
+    ///
+    /// Requests are served concurrently, so `f` may run on several threads at
+    /// once; it sees the request as the collector does.
+    pub fn on_request<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Request) + Send + Sync + 'static,
+    {
+        self.options.on_request = Some(RequestCallback::new(f));
+        self
+    }
+
+    /// Collect requests matching no endpoint and answer them with `handler`.
+    ///
+    /// Collected requests keep their real method and path, so tests can assert
+    /// that the client called nothing unexpected. Default handler calls only
+    /// count toward shutdown with [`expected_requests`](Self::expected_requests).
+    pub fn default_handler(mut self, handler: Handler) -> Self {
+        self.options.default_handler = Some(handler);
+        self
+    }
 }
 
 impl<S: Server + 'static, C: Collector + 'static> ScenarioBuilder<S, C> {
-    /// Build the scenario
+    /// The endpoints with the scenario's prefix applied
+    fn routed_endpoints(endpoints: Vec<Endpoint>, prefix: Option<String>) -> Vec<Endpoint> {
+        match prefix {
+            Some(prefix) => endpoints
+                .into_iter()
+                .map(|endpoint| endpoint.prefixed(&prefix))
+                .collect(),
+            None => endpoints,
+        }
+    }
+
+    /// Build the scenario.
+    ///
+    /// Conflicting endpoints are reported when the scenario runs; use
+    /// [`try_build`](Self::try_build) to check them here.
     pub fn build(self) -> Scenario<S, C> {
         Scenario {
             server: self.server.expect("Server must be set before building"),
             collector: self.collector.expect("Collector must be set before building"),
-            endpoints: self.endpoints,
+            endpoints: Self::routed_endpoints(self.endpoints, self.prefix),
+            options: self.options,
         }
     }
 
+    /// Build the scenario, failing with [`HarnessError::ConfigurationError`]
+    /// when the server or collector is missing or endpoints conflict, e.g. the
+    /// same path and method registered twice without header matches
+    pub fn try_build(self) -> Result<Scenario<S, C>, HarnessError> {
+        let server = self.server.ok_or_else(|| {
+            HarnessError::ConfigurationError("Server must be set before building".to_string())
+        })?;
+        let collector = self.collector.ok_or_else(|| {
+            HarnessError::ConfigurationError("Collector must be set before building".to_string())
+        })?;
+        let endpoints = Self::routed_endpoints(self.endpoints, self.prefix);
+        check_conflicts(&endpoints)?;
+        Ok(Scenario {
+            server,
+            collector,
+            endpoints,
+            options: self.options,
+        })
+    }
+
+    /// Start the scenario directly from the builder, see [`Scenario::start`]
+    #[cfg(feature = "axum")]
+    pub async fn start(self) -> Result<ScenarioHandle<C::Output>, HarnessError> {
+        self.build().start().await
+    }
+
     /// Execute the scenario directly from the builder
     pub async fn execute(self) -> Result<C::Output, HarnessError> {
-        let scenario = self.build();
-        scenario
-            .server
-            .run(scenario.endpoints, scenario.collector, None::<fn(SocketAddr)>)
-            .await
+        self.build().execute().await
     }
 }
 
@@ -122,12 +270,102 @@ impl<S: Server + 'static, C: Collector + 'static> Scenario<S, C> {
     ///
     /// Starts the server and waits until all handlers have been called.
     /// The server automatically shuts down once all handlers have been called.
-    /// Returns the collector's output type.
+    /// Returns the collector's output type, or a configuration error before
+    /// binding when endpoints conflict or the scenario uses
+    /// [`ScenarioBuilder::manual_shutdown`], which needs [`start`](Self::start).
     pub async fn execute(self) -> Result<C::Output, HarnessError> {
+        check_conflicts(&self.endpoints)?;
+        if self.options.manual_shutdown.is_some() {
+            return Err(HarnessError::ConfigurationError(
+                "manual_shutdown scenarios never stop by themselves; run them with start"
+                    .to_string(),
+            ));
+        }
         self.server
-            .run(self.endpoints, self.collector, None::<fn(SocketAddr)>)
+            .run(
+                self.endpoints,
+                self.collector,
+                self.options,
+                None::<fn(SocketAddr)>,
+            )
             .await
     }
+
+    /// Start serving in the background and return once the server is ready.
+    ///
+    /// The returned handle knows the address the server listens on, e.g. an
+    /// ephemeral port. With [`ScenarioBuilder::manual_shutdown`] the server runs
+    /// until [`ScenarioHandle::shutdown`]; otherwise it still stops once every
+    /// handler has been called.
+    #[cfg(feature = "axum")]
+    pub async fn start(self) -> Result<ScenarioHandle<C::Output>, HarnessError> {
+        check_conflicts(&self.endpoints)?;
+        let shutdown = self.options.manual_shutdown.clone();
+        let (ready_tx, ready_rx) = tokio::sync::oneshot::channel();
+        let task = tokio::spawn(async move {
+            self.server
+                .run(
+                    self.endpoints,
+                    self.collector,
+                    self.options,
+                    Some(move |addr| {
+                        let _ = ready_tx.send(addr);
+                    }),
+                )
+                .await
+        });
+
+        match ready_rx.await {
+            Ok(addr) => Ok(ScenarioHandle {
+                addr,
+                shutdown,
+                task,
+            }),
+            // The server failed before it was ready
+            Err(_) => Err(join(task).await.err().unwrap_or_else(|| {
+                HarnessError::ServerError("server stopped before it was ready".to_string())
+            })),
+        }
+    }
+}
+
+/// A scenario served in the background, see [`Scenario::start`]
+#[cfg(feature = "axum")]
+pub struct ScenarioHandle<O> {
+    addr: SocketAddr,
+    shutdown: Option<ShutdownTrigger>,
+    task: tokio::task::JoinHandle<Result<O, HarnessError>>,
+}
+
+#[cfg(feature = "axum")]
+impl<O> ScenarioHandle<O> {
+    /// Address the server is listening on
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Stop the server and return the collector's output.
+    ///
+    /// Without [`ScenarioBuilder::manual_shutdown`] this waits for the scenario to
+    /// complete on its own, like [`output`](Self::output).
+    pub async fn shutdown(self) -> Result<O, HarnessError> {
+        if let Some(trigger) = &self.shutdown {
+            trigger.trigger();
+        }
+        self.output().await
+    }
+
+    /// Wait for the server to stop by itself and return the collector's output
+    pub async fn output(self) -> Result<O, HarnessError> {
+        join(self.task).await
+    }
+}
+
+#[cfg(feature = "axum")]
+async fn join<O>(
+    task: tokio::task::JoinHandle<Result<O, HarnessError>>,
+) -> Result<O, HarnessError> {
+    task.await.map_err(|e| HarnessError::Serve(Box::new(e)))?
 }
 
 #[cfg(test)]
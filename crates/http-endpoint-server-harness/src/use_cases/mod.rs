@@ -1,4 +1,6 @@
 mod create_scenario;
 pub mod ports;
 
+#[cfg(feature = "axum")]
+pub use create_scenario::ScenarioHandle;
 pub use create_scenario::ScenarioBuilder;
@@ -13,5 +13,28 @@ pub trait Collector: Send + Sync {
 
     /// Consume the collector and return the final output
     fn into_output(self) -> Self::Output;
+
+    /// Whether the server should also collect requests matching no configured
+    /// handler, which it otherwise answers without collecting.
+    fn collects_unmatched(&self) -> bool {
+        false
+    }
 }
 
+/// Every core collector of [`Request`]s, such as the default and recording
+/// collectors, is a harness collector
+impl<T: server_harness_core::Collector<Request>> Collector for T {
+    type Output = T::Output;
+
+    fn collect(&self, request: Request) {
+        server_harness_core::Collector::collect(self, request)
+    }
+
+    fn into_output(self) -> Self::Output {
+        server_harness_core::Collector::into_output(self)
+    }
+
+    fn collects_unmatched(&self) -> bool {
+        server_harness_core::Collector::collects_unmatched(self)
+    }
+}
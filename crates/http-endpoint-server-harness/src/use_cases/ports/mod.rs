@@ -2,4 +2,6 @@ mod collector;
 mod server;
 
 pub use collector::Collector;
-pub use server::Server;
+pub use server::{
+    BoundAddr, RequestCallback, RunOptions, Server, ShutdownTrigger, DEFAULT_MAX_BODY_SIZE,
+};
@@ -1,8 +1,112 @@
-use async_trait::async_trait;
-use crate::entities::Endpoint;
-use crate::error::HarnessError;
 use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::sync::watch;
+
 use super::Collector;
+use crate::entities::{Endpoint, Handler, Request};
+use crate::error::HarnessError;
+
+/// Largest request body the server buffers unless
+/// [`RunOptions::max_body_size`] is set: 16 MiB
+pub const DEFAULT_MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+/// Options controlling how a server runs a scenario
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    /// If set, the server fails with [`HarnessError::IncompleteScenario`] when
+    /// not every handler has been called within this duration.
+    pub all_called_within: Option<Duration>,
+    /// If set, the server shuts down after this many requests have been handled
+    /// instead of once every handler has been called.
+    pub expected_requests: Option<usize>,
+    /// If set, requests matching no endpoint are collected and answered by this
+    /// handler instead of the server's plain 404.
+    pub default_handler: Option<Handler>,
+    /// If set, connections still in flight when the scenario completes are
+    /// drained for at most this long before the server stops.
+    pub shutdown_grace: Option<Duration>,
+    /// Largest request body in bytes, [`DEFAULT_MAX_BODY_SIZE`] if unset. Larger
+    /// requests get 413 Payload Too Large and are neither collected nor counted.
+    pub max_body_size: Option<usize>,
+    /// If set, the server keeps running once every handler has been called and
+    /// only shuts down when this trigger fires.
+    pub manual_shutdown: Option<ShutdownTrigger>,
+    /// If set, random handlers pick responses deterministically from this seed
+    pub seed: Option<u64>,
+    /// If set, called with every collected request in addition to the collector
+    pub on_request: Option<RequestCallback>,
+}
+
+/// Callback invoked with each collected request, see [`RunOptions::on_request`]
+///
+/// Connections are served concurrently, so it may be called from several
+/// threads at once.
+#[derive(Clone)]
+pub struct RequestCallback(Arc<dyn Fn(&Request) + Send + Sync>);
+
+impl RequestCallback {
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&Request) + Send + Sync + 'static,
+    {
+        Self(Arc::new(f))
+    }
+
+    pub fn call(&self, request: &Request) {
+        (self.0)(request)
+    }
+}
+
+impl std::fmt::Debug for RequestCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("RequestCallback").field(&"<fn>").finish()
+    }
+}
+
+/// Shuts down a server running with [`RunOptions::manual_shutdown`]
+///
+/// Clones share the same trigger, so one can be handed to the server and
+/// another kept to fire it.
+#[derive(Debug, Clone)]
+pub struct ShutdownTrigger(Arc<watch::Sender<bool>>);
+
+impl Default for ShutdownTrigger {
+    fn default() -> Self {
+        Self(Arc::new(watch::Sender::new(false)))
+    }
+}
+
+impl ShutdownTrigger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask the server to shut down; firing again has no effect
+    pub fn trigger(&self) {
+        self.0.send_replace(true);
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Resolve once the trigger has fired
+    pub async fn triggered(&self) {
+        // The sender lives in `self`, so waiting only ends once it fires
+        let _ = self.0.subscribe().wait_for(|fired| *fired).await;
+    }
+}
+
+/// Address a server is listening on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BoundAddr {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+}
 
 /// Trait for HTTP server implementations
 #[async_trait]
@@ -18,6 +122,7 @@ pub trait Server: Send + Sync + Clone {
         &self,
         endpoints: Vec<Endpoint>,
         collector: C,
+        options: RunOptions,
         on_ready: Option<F>,
     ) -> Result<C::Output, HarnessError>
     where
@@ -25,3 +130,27 @@ pub trait Server: Send + Sync + Clone {
         F: FnOnce(SocketAddr) + Send + 'static;
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_shutdown_trigger_wakes_every_waiter() {
+        let trigger = ShutdownTrigger::new();
+        let waiters: Vec<_> = (0..2)
+            .map(|_| {
+                let trigger = trigger.clone();
+                tokio::spawn(async move { trigger.triggered().await })
+            })
+            .collect();
+        tokio::task::yield_now().await;
+
+        trigger.trigger();
+        for waiter in waiters {
+            waiter.await.unwrap();
+        }
+        assert!(trigger.is_triggered());
+        // Already fired: resolves right away
+        trigger.triggered().await;
+    }
+}
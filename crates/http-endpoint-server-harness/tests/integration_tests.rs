@@ -1,6 +1,7 @@
 //! Integration tests for http-endpoint-server-harness
 
 use http_endpoint_server_harness::prelude::*;
+use http_endpoint_server_harness::testing::wait_for_ready;
 use std::net::SocketAddr;
 use std::time::Duration;
 
@@ -18,21 +19,9 @@ fn get_test_addr() -> SocketAddr {
     SocketAddr::from(([127, 0, 0, 1], get_available_port()))
 }
 
-/// Helper to wait for server to be ready with retries
+/// Helper to wait for server to be ready
 async fn wait_for_server(addr: SocketAddr) {
-    let client = reqwest::Client::new();
-    for _ in 0..50 {
-        if client
-            .get(format!("http://{}/", addr))
-            .timeout(Duration::from_millis(50))
-            .send()
-            .await
-            .is_ok()
-        {
-            return;
-        }
-        tokio::time::sleep(Duration::from_millis(10)).await;
-    }
+    wait_for_ready(addr, Duration::from_secs(1)).await.unwrap();
 }
 
 #[tokio::test]
@@ -75,6 +64,28 @@ async fn test_single_endpoint_get() {
     assert_eq!(collected[0].path, "/api/test");
 }
 
+#[tokio::test]
+async fn test_prepared_server_knows_its_port_before_serving() {
+    let (server, addr) = Axum::default().prepare().await.unwrap();
+    assert_ne!(addr.port(), 0);
+
+    let serve = tokio::spawn(server.serve(
+        vec![Endpoint::new("/api/test", Method::Get)
+            .with_handler(Handler::from_json(&json!({"status": "ok"})))],
+        DefaultCollector::new(),
+    ));
+
+    // The listener is bound already, so the first request needs no retries
+    let response = reqwest::get(format!("http://{}/api/test", addr))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let collected = serve.await.unwrap().unwrap();
+    assert_eq!(collected.len(), 1);
+    assert_eq!(collected[0].path, "/api/test");
+}
+
 #[tokio::test]
 async fn test_single_endpoint_post() {
     let addr = get_test_addr();
@@ -114,6 +125,127 @@ async fn test_single_endpoint_post() {
     assert!(collected[0].body_as_str().unwrap().contains("test"));
 }
 
+#[tokio::test]
+async fn test_oversized_body_is_refused() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        let response = client
+            .post(format!("http://{}/api/upload", addr))
+            .body(vec![b'x'; 64])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 413);
+
+        // Without a Content-Length the limit applies while buffering
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let chunk = "x".repeat(40);
+        let request = format!(
+            "POST /api/upload HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n{:x}\r\n{}\r\n0\r\n\r\n",
+            chunk.len(),
+            chunk
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 413"), "{}", response);
+
+        let response = client
+            .post(format!("http://{}/api/upload", addr))
+            .body("small")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 201);
+    });
+
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/upload", Method::Post).with_handler(Handler::new(Response::created())),
+        )
+        .max_body_size(16)
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+
+    // Refused uploads were neither collected nor counted towards shutdown
+    assert_eq!(collected.len(), 1);
+    assert_eq!(collected[0].body, b"small");
+}
+
+#[tokio::test]
+async fn test_oversized_chunked_body_on_streaming_endpoint_is_not_counted() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        // The buffered handler reads the chunked body only once it is picked
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let chunk = "x".repeat(40);
+        let request = format!(
+            "POST /api/upload HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n{:x}\r\n{}\r\n0\r\n\r\n",
+            chunk.len(),
+            chunk
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 413"), "{}", response);
+
+        // The refused upload left the buffered handler first in line
+        let client = reqwest::Client::new();
+        let response = client
+            .post(format!("http://{}/api/upload", addr))
+            .body("small")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 201);
+
+        let response = client
+            .post(format!("http://{}/api/upload", addr))
+            .body("streamed")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+    });
+
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/upload", Method::Post)
+                .with_handler(Handler::new(Response::created()))
+                .with_handler(Handler::streaming(|_req, _body| async { Response::ok() })),
+        )
+        .max_body_size(16)
+        .assert_all_called(Duration::from_secs(5))
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+
+    assert_eq!(collected.len(), 2);
+    assert_eq!(collected[0].body, b"small");
+}
+
 #[tokio::test]
 async fn test_multiple_handlers_sequential() {
     let addr = get_test_addr();
@@ -175,6 +307,84 @@ async fn test_multiple_handlers_sequential() {
     assert_eq!(collected.len(), 3);
 }
 
+#[tokio::test]
+async fn test_path_aliases_share_sequential_handlers() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        let mut pages = Vec::new();
+        for path in ["/v1/users", "/users"] {
+            let body: serde_json::Value = client
+                .get(format!("http://{}{}", addr, path))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            pages.push(body["page"].clone());
+        }
+        assert_eq!(pages, [json!(1), json!(2)]);
+    });
+
+    // Both handlers are used once across the aliases, completing the scenario
+    let collected = tokio::time::timeout(
+        Duration::from_secs(5),
+        ScenarioBuilder::new()
+            .server(Axum::bind(addr))
+            .collector(DefaultCollector::new())
+            .endpoint(
+                Endpoint::new("/v1/users", Method::Get)
+                    .with_path("/users")
+                    .with_handler(Handler::from_json(&json!({"page": 1})))
+                    .with_handler(Handler::from_json(&json!({"page": 2}))),
+            )
+            .execute(),
+    )
+    .await
+    .expect("scenario should complete once each handler is called")
+    .unwrap();
+
+    requests_task.await.unwrap();
+    let paths: Vec<_> = collected.iter().map(|r| r.path.as_str()).collect();
+    assert_eq!(paths, ["/v1/users", "/users"]);
+}
+
+#[tokio::test]
+async fn test_redirect_chain_collects_every_hop() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        // reqwest follows redirects by default
+        let response = reqwest::get(format!("http://{}/a", addr)).await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.url().path(), "/c");
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["hop"], "c");
+    });
+
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(Endpoint::new("/a", Method::Get).with_handler(Handler::redirect(302, "/b")))
+        .endpoint(Endpoint::new("/b", Method::Get).with_handler(Handler::redirect(307, "/c")))
+        .endpoint(
+            Endpoint::new("/c", Method::Get).with_handler(Handler::from_json(&json!({"hop": "c"}))),
+        )
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+    let paths: Vec<_> = collected.iter().map(|r| r.path.as_str()).collect();
+    assert_eq!(paths, ["/a", "/b", "/c"]);
+}
+
 #[tokio::test]
 async fn test_multiple_endpoints() {
     let addr = get_test_addr();
@@ -299,6 +509,150 @@ async fn test_custom_headers() {
     requests_task.await.unwrap();
 }
 
+#[tokio::test]
+async fn test_echo_header_copies_request_id() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        let mut echoed = Vec::new();
+        for request_id in ["req-1", "req-2"] {
+            let response = client
+                .get(format!("http://{}/api/trace", addr))
+                .header("X-Request-Id", request_id)
+                .send()
+                .await
+                .unwrap();
+            echoed.push(response.headers()["x-request-id"].to_str().unwrap().to_string());
+        }
+        echoed
+    });
+
+    ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/trace", Method::Get)
+                .with_handler(Handler::new(Response::ok().echo_header("X-Request-Id")))
+                .expect_calls(2),
+        )
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    assert_eq!(requests_task.await.unwrap(), ["req-1", "req-2"]);
+}
+
+#[tokio::test]
+async fn test_cookies_round_trip() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        let response = client
+            .get(format!("http://{}/api/session", addr))
+            .header("Cookie", "session=old-token; theme=dark")
+            .send()
+            .await
+            .unwrap();
+        let set_cookies: Vec<String> = response
+            .headers()
+            .get_all("set-cookie")
+            .iter()
+            .map(|value| value.to_str().unwrap().to_string())
+            .collect();
+        let body: serde_json::Value = response.json().await.unwrap();
+        (set_cookies, body)
+    });
+
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/session", Method::Get).with_handler(Handler::dynamic(|req| {
+                let cookies = req.cookies();
+                let previous = cookies.get("session").cloned().unwrap_or_default();
+                Response::ok()
+                    .with_json(&json!({"previous": previous}))
+                    .with_cookie(
+                        "session",
+                        "new-token",
+                        CookieAttributes::new().path("/").http_only(),
+                    )
+                    .with_cookie("visited", "1", CookieAttributes::new())
+            })),
+        )
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    let (set_cookies, body) = requests_task.await.unwrap();
+    assert_eq!(body, json!({"previous": "old-token"}));
+    assert_eq!(set_cookies, ["session=new-token; Path=/; HttpOnly", "visited=1"]);
+
+    let cookies = collected[0].cookies();
+    assert_eq!(cookies["session"], "old-token");
+    assert_eq!(cookies["theme"], "dark");
+}
+
+#[tokio::test]
+async fn test_router_layer_sets_header_on_all_routes() {
+    async fn add_server_header(mut response: axum::response::Response) -> axum::response::Response {
+        response
+            .headers_mut()
+            .insert("x-served-by", "harness".parse().unwrap());
+        response
+    }
+
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        let mut served_by = Vec::new();
+        for path in ["/api/missing", "/api/users", "/api/orders"] {
+            let response = client
+                .get(format!("http://{}{}", addr, path))
+                .send()
+                .await
+                .unwrap();
+            served_by.push((
+                response.status().as_u16(),
+                response.headers()["x-served-by"].to_str().unwrap().to_string(),
+            ));
+        }
+        served_by
+    });
+
+    let collected = ScenarioBuilder::new()
+        .server(
+            Axum::bind(addr)
+                .with_router_layer(axum::middleware::map_response(add_server_header)),
+        )
+        .collector(DefaultCollector::new())
+        .endpoints(["/api/users", "/api/orders"].map(|path| {
+            Endpoint::new(path, Method::Get).with_handler(Handler::new(Response::ok()))
+        }))
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    let harness = "harness".to_string();
+    assert_eq!(
+        requests_task.await.unwrap(),
+        [(404, harness.clone()), (200, harness.clone()), (200, harness)]
+    );
+    assert_eq!(collected.len(), 2);
+}
+
 #[tokio::test]
 async fn test_put_method() {
     let addr = get_test_addr();
@@ -480,6 +834,46 @@ async fn test_concurrent_requests() {
     assert_eq!(collected.len(), 10);
 }
 
+#[tokio::test]
+async fn test_concurrent_requests_are_sequenced() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        let futures: Vec<_> = (0..10)
+            .map(|i| client.get(format!("http://{}/api/items/{}", addr, i)).send())
+            .collect();
+        for response in futures::future::join_all(futures).await {
+            assert_eq!(response.unwrap().status(), 200);
+        }
+    });
+
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/items/{id}", Method::Get)
+                .cycle()
+                .with_handler(Handler::from_json(&json!({"ok": true}))),
+        )
+        .expected_requests(10)
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+
+    // Sequence numbers follow collection order, as do arrival times
+    let sequences: Vec<u64> = collected.iter().map(|r| r.sequence).collect();
+    assert_eq!(sequences, (0..10).collect::<Vec<_>>());
+    assert!(collected
+        .windows(2)
+        .all(|pair| pair[0].received_at <= pair[1].received_at));
+}
+
 #[tokio::test]
 async fn test_dynamic_handler_echo_path() {
     let addr = get_test_addr();
@@ -519,7 +913,7 @@ async fn test_dynamic_handler_echo_path() {
 }
 
 #[tokio::test]
-async fn test_dynamic_handler_echo_body() {
+async fn test_dynamic_handler_path_params() {
     let addr = get_test_addr();
 
     let requests_task = tokio::spawn(async move {
@@ -527,8 +921,7 @@ async fn test_dynamic_handler_echo_body() {
 
         let client = reqwest::Client::new();
         let response: serde_json::Value = client
-            .post(format!("http://{}/echo", addr))
-            .json(&json!({"message": "hello"}))
+            .get(format!("http://{}/api/users/123", addr))
             .send()
             .await
             .unwrap()
@@ -536,22 +929,17 @@ async fn test_dynamic_handler_echo_body() {
             .await
             .unwrap();
 
-        assert_eq!(response["received"]["message"], "hello");
+        assert_eq!(response["id"], "123");
     });
 
-    ScenarioBuilder::new()
+    let collected = ScenarioBuilder::new()
         .server(Axum::bind(addr))
         .collector(DefaultCollector::new())
         .endpoint(
-            Endpoint::new("/echo", Method::Post).with_handler(Handler::dynamic(|req| {
-                if let Some(body) = req.body_as_str() {
-                    if let Ok(json) = serde_json::from_str::<serde_json::Value>(body) {
-                        return Response::new(200).with_json(&json!({
-                            "received": json
-                        }));
-                    }
-                }
-                Response::new(400).with_body("Invalid JSON")
+            Endpoint::new("/api/users/{id}", Method::Get).with_handler(Handler::dynamic(|req| {
+                Response::new(200).with_json(&json!({
+                    "id": req.path_params.get("id")
+                }))
             })),
         )
         .build()
@@ -560,13 +948,59 @@ async fn test_dynamic_handler_echo_body() {
         .unwrap();
 
     requests_task.await.unwrap();
+
+    assert_eq!(collected[0].path_params.get("id").unwrap(), "123");
 }
 
 #[tokio::test]
-async fn test_dynamic_handler_based_on_header() {
+async fn test_dynamic_handler_echo_body() {
     let addr = get_test_addr();
 
-    // Use the same dynamic handler twice to allow 2 requests
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        let response: serde_json::Value = client
+            .post(format!("http://{}/echo", addr))
+            .json(&json!({"message": "hello"}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+
+        assert_eq!(response["received"]["message"], "hello");
+    });
+
+    ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/echo", Method::Post).with_handler(Handler::dynamic(|req| {
+                if let Some(body) = req.body_as_str() {
+                    if let Ok(json) = serde_json::from_str::<serde_json::Value>(body) {
+                        return Response::new(200).with_json(&json!({
+                            "received": json
+                        }));
+                    }
+                }
+                Response::new(400).with_body("Invalid JSON")
+            })),
+        )
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_dynamic_handler_based_on_header() {
+    let addr = get_test_addr();
+
+    // Use the same dynamic handler twice to allow 2 requests
     let auth_handler = Handler::dynamic(|req| {
         if let Some(auth) = req.headers.get("authorization") {
             if auth.starts_with("Bearer ") {
@@ -621,6 +1055,238 @@ async fn test_dynamic_handler_based_on_header() {
     requests_task.await.unwrap();
 }
 
+#[tokio::test]
+async fn test_async_dynamic_handler_awaits_shared_state() {
+    let addr = get_test_addr();
+    let visits = std::sync::Arc::new(tokio::sync::Mutex::new(0));
+
+    let handler = {
+        let visits = visits.clone();
+        Handler::dynamic_async(move |_req| {
+            let visits = visits.clone();
+            async move {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                let mut visits = visits.lock().await;
+                *visits += 1;
+                Response::ok().with_json(&json!({"visits": *visits}))
+            }
+        })
+    };
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        let mut counts = Vec::new();
+        for _ in 0..2 {
+            let response: serde_json::Value = client
+                .get(format!("http://{}/api/visits", addr))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            counts.push(response["visits"].clone());
+        }
+        counts
+    });
+
+    ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/visits", Method::Get)
+                .with_handler(handler.clone())
+                .with_handler(handler),
+        )
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    assert_eq!(requests_task.await.unwrap(), [json!(1), json!(2)]);
+    assert_eq!(*visits.lock().await, 2);
+}
+
+#[tokio::test]
+async fn test_stateful_handler_shares_counter_with_test() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let addr = get_test_addr();
+    let counter = std::sync::Arc::new(AtomicUsize::new(0));
+    let handler = Handler::stateful(counter.clone(), |counter, _req| {
+        let count = counter.fetch_add(1, Ordering::SeqCst) + 1;
+        Response::ok().with_json(&json!({"count": count}))
+    });
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        let mut counts = Vec::new();
+        for _ in 0..3 {
+            let response: serde_json::Value = client
+                .post(format!("http://{}/api/counter", addr))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            counts.push(response["count"].clone());
+        }
+        counts
+    });
+
+    ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/counter", Method::Post)
+                .with_handler(handler)
+                .expect_calls(3),
+        )
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    assert_eq!(requests_task.await.unwrap(), [json!(1), json!(2), json!(3)]);
+    assert_eq!(counter.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_shutdown_grace_lets_slow_final_response_complete() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let response = reqwest::Client::new()
+            .get(format!("http://{}/api/slow", addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        response.json::<serde_json::Value>().await.unwrap()
+    });
+
+    let started = std::time::Instant::now();
+    ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/slow", Method::Get).with_handler(Handler::dynamic_async(|_req| async {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                Response::ok().with_json(&json!({"done": true}))
+            })),
+        )
+        .shutdown_grace(Duration::from_secs(5))
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    // The server stops once the response is written, not at the end of the grace period
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(requests_task.await.unwrap(), json!({"done": true}));
+}
+
+#[tokio::test]
+async fn test_slow_final_upload_is_collected_before_shutdown() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        client
+            .post(format!("http://{}/api/upload", addr))
+            .body("first")
+            .send()
+            .await
+            .unwrap();
+
+        // The final body arrives well after its headers and the grace period
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let headers = "POST /api/upload HTTP/1.1\r\nhost: localhost\r\ncontent-length: 5\r\n\r\n";
+        stream.write_all(headers.as_bytes()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let _ = stream.write_all(b"final").await;
+        let mut response = Vec::new();
+        let _ = stream.read_to_end(&mut response).await;
+    });
+
+    // The streaming handler leaves the buffered one to read its body once picked
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/upload", Method::Post)
+                .with_handler(Handler::streaming(|_req, _body| async { Response::ok() }))
+                .with_handler(Handler::new(Response::ok())),
+        )
+        .shutdown_grace(Duration::from_millis(50))
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+    assert_eq!(collected.len(), 2);
+    assert_eq!(collected[1].body, b"final");
+}
+
+#[tokio::test]
+async fn test_pooled_requests_share_a_connection_id() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        // One pooled client keeps its connection alive; a second one opens its own
+        let client = reqwest::Client::new();
+        for _ in 0..2 {
+            client
+                .get(format!("http://{}/api/pooled", addr))
+                .send()
+                .await
+                .unwrap()
+                .bytes()
+                .await
+                .unwrap();
+        }
+        reqwest::Client::new()
+            .get(format!("http://{}/api/pooled", addr))
+            .send()
+            .await
+            .unwrap();
+    });
+
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/pooled", Method::Get)
+                .with_handler(Handler::from_text("ok"))
+                .expect_calls(3),
+        )
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+
+    assert_eq!(collected.len(), 3);
+    assert_ne!(collected[0].connection_id, 0);
+    assert_eq!(collected[0].connection_id, collected[1].connection_id);
+    assert_ne!(collected[1].connection_id, collected[2].connection_id);
+}
+
 #[tokio::test]
 async fn test_auto_shutdown_after_all_handlers() {
     let addr = get_test_addr();
@@ -668,6 +1334,52 @@ async fn test_auto_shutdown_after_all_handlers() {
     assert_eq!(collected.len(), 2);
 }
 
+#[tokio::test]
+async fn test_endpoint_without_handlers_counts_once_toward_shutdown() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        let resp1 = client
+            .get(format!("http://{}/api/empty", addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp1.status(), 404);
+
+        let resp2 = client
+            .get(format!("http://{}/api/step", addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp2.status(), 200);
+    });
+
+    // A handler-less endpoint must be hit once, then no longer holds the server open
+    let collected = tokio::time::timeout(
+        Duration::from_secs(2),
+        ScenarioBuilder::new()
+            .server(Axum::bind(addr))
+            .collector(DefaultCollector::new())
+            .endpoint(Endpoint::new("/api/empty", Method::Get))
+            .endpoint(
+                Endpoint::new("/api/step", Method::Get)
+                    .with_handler(Handler::from_json(&json!({"step": 1}))),
+            )
+            .build()
+            .execute(),
+    )
+    .await
+    .expect("server should shut down once every endpoint was hit")
+    .unwrap();
+
+    requests_task.await.unwrap();
+    assert_eq!(collected.len(), 2);
+    assert_eq!(collected[0].path, "/api/empty");
+}
+
 #[tokio::test]
 async fn test_auto_shutdown_with_sequential_handlers() {
     let addr = get_test_addr();
@@ -708,3 +1420,2054 @@ async fn test_auto_shutdown_with_sequential_handlers() {
     requests_task.await.unwrap();
     assert_eq!(collected.len(), 3);
 }
+
+#[tokio::test]
+async fn test_assert_all_called_reports_uncalled_handlers() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        client
+            .get(format!("http://{}/api/called", addr))
+            .send()
+            .await
+            .unwrap();
+    });
+
+    let result = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/called", Method::Get)
+                .with_handler(Handler::from_json(&json!({"ok": true}))),
+        )
+        .endpoint(
+            Endpoint::new("/api/forgotten", Method::Post)
+                .with_handler(Handler::from_json(&json!({"ok": true}))),
+        )
+        .assert_all_called(Duration::from_millis(500))
+        .build()
+        .execute()
+        .await;
+
+    requests_task.await.unwrap();
+
+    match result {
+        Err(HarnessError::IncompleteScenario(uncalled)) => {
+            assert_eq!(uncalled, vec!["POST /api/forgotten (handler #0)".to_string()]);
+        }
+        other => panic!("expected IncompleteScenario, got {:?}", other.map(|c| c.len())),
+    }
+}
+
+#[tokio::test]
+async fn test_expect_calls_satisfied() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        for _ in 0..2 {
+            let response = client
+                .get(format!("http://{}/api/ping", addr))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), 200);
+        }
+    });
+
+    // A single handler is reused, and the scenario waits for both calls
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/ping", Method::Get)
+                .with_handler(Handler::from_json(&json!({"pong": true})))
+                .expect_calls(2),
+        )
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+    assert_eq!(collected.len(), 2);
+}
+
+#[tokio::test]
+async fn test_expect_calls_violated_on_completion() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        client
+            .delete(format!("http://{}/api/users/1", addr))
+            .send()
+            .await
+            .unwrap();
+        client
+            .get(format!("http://{}/api/users/1", addr))
+            .send()
+            .await
+            .unwrap();
+    });
+
+    let result = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/users/1", Method::Get)
+                .with_handler(Handler::from_json(&json!({"id": 1}))),
+        )
+        .endpoint(
+            Endpoint::new("/api/users/1", Method::Delete)
+                .with_handler(Handler::from_json(&json!({"deleted": true})))
+                .expect_calls(0),
+        )
+        .build()
+        .execute()
+        .await;
+
+    requests_task.await.unwrap();
+
+    match result {
+        Err(HarnessError::ExpectationFailed {
+            path,
+            expected,
+            actual,
+        }) => {
+            assert_eq!(path, "/api/users/1");
+            assert_eq!(expected, 0);
+            assert_eq!(actual, 1);
+        }
+        other => panic!("expected ExpectationFailed, got {:?}", other.map(|c| c.len())),
+    }
+}
+
+#[tokio::test]
+async fn test_indexed_handler_fails_then_succeeds() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        let mut statuses = Vec::new();
+        for _ in 0..3 {
+            let response = client
+                .get(format!("http://{}/api/flaky", addr))
+                .send()
+                .await
+                .unwrap();
+            statuses.push(response.status().as_u16());
+        }
+        statuses
+    });
+
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/flaky", Method::Get)
+                .responding_with(|index| match index {
+                    0 | 1 => Response::new(503),
+                    _ => Response::ok().with_json(&json!({"attempt": index})),
+                })
+                .expect_calls(3),
+        )
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    assert_eq!(requests_task.await.unwrap(), vec![503, 503, 200]);
+    assert_eq!(collected.len(), 3);
+}
+
+#[tokio::test]
+async fn test_expect_calls_violated_on_timeout() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        client
+            .get(format!("http://{}/api/ping", addr))
+            .send()
+            .await
+            .unwrap();
+    });
+
+    let result = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/ping", Method::Get)
+                .with_handler(Handler::from_json(&json!({"pong": true})))
+                .expect_calls(2),
+        )
+        .assert_all_called(Duration::from_millis(500))
+        .build()
+        .execute()
+        .await;
+
+    requests_task.await.unwrap();
+
+    match result {
+        Err(HarnessError::ExpectationFailed {
+            expected, actual, ..
+        }) => {
+            assert_eq!(expected, 2);
+            assert_eq!(actual, 1);
+        }
+        other => panic!("expected ExpectationFailed, got {:?}", other.map(|c| c.len())),
+    }
+}
+
+#[tokio::test]
+async fn test_streaming_handler_counts_body_bytes() {
+    use futures::StreamExt;
+
+    let addr = get_test_addr();
+    let body_size = 4 * 1024 * 1024;
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        let response: serde_json::Value = client
+            .post(format!("http://{}/upload", addr))
+            .body(vec![b'x'; body_size])
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+
+        assert_eq!(response["bytes"], body_size);
+        assert!(response["chunks"].as_u64().unwrap() > 1);
+    });
+
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/upload", Method::Post).with_handler(Handler::streaming(
+                |_req, mut body| async move {
+                    let (mut bytes, mut chunks) = (0, 0);
+                    while let Some(chunk) = body.next().await {
+                        bytes += chunk.unwrap().len();
+                        chunks += 1;
+                    }
+                    Response::ok().with_json(&json!({"bytes": bytes, "chunks": chunks}))
+                },
+            )),
+        )
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+
+    assert_eq!(collected.len(), 1);
+    assert!(collected[0].body.is_empty());
+}
+
+#[tokio::test]
+async fn test_ranged_handler_partial_content() {
+    let addr = get_test_addr();
+    let full_body: Vec<u8> = (0..100u8).collect();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        let response = client
+            .get(format!("http://{}/download", addr))
+            .header("Range", "bytes=0-9")
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), 206);
+        assert_eq!(response.headers()["content-range"], "bytes 0-9/100");
+        assert_eq!(response.headers()["accept-ranges"], "bytes");
+        let body = response.bytes().await.unwrap();
+        assert_eq!(body.as_ref(), &(0..10u8).collect::<Vec<_>>()[..]);
+    });
+
+    ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(Endpoint::new("/download", Method::Get).with_handler(Handler::ranged(full_body)))
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_serialized_endpoint_handles_requests_one_at_a_time() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let addr = get_test_addr();
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let max_in_flight = Arc::new(AtomicUsize::new(0));
+
+    let handler = {
+        let in_flight = in_flight.clone();
+        let max_in_flight = max_in_flight.clone();
+        Handler::dynamic(move |_req| {
+            let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            max_in_flight.fetch_max(current, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(100));
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+            Response::ok()
+        })
+    };
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        let url = format!("http://{}/api/locked", addr);
+        let (first, second) = tokio::join!(client.post(&url).send(), client.post(&url).send());
+        assert_eq!(first.unwrap().status(), 200);
+        assert_eq!(second.unwrap().status(), 200);
+    });
+
+    ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/locked", Method::Post)
+                .with_handler(handler.clone())
+                .with_handler(handler)
+                .serialized(),
+        )
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+    assert_eq!(max_in_flight.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_compressed_response_round_trips() {
+    use std::io::Read;
+
+    let addr = get_test_addr();
+    let payload = json!({"items": ["a", "b", "c"], "total": 3});
+    let expected = payload.clone();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        let response = client
+            .get(format!("http://{}/api/items", addr))
+            .header("accept-encoding", "gzip")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.headers()["content-encoding"], "gzip");
+        let compressed = response.bytes().await.unwrap();
+
+        let mut decoded = Vec::new();
+        flate2::read::GzDecoder::new(compressed.as_ref())
+            .read_to_end(&mut decoded)
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&decoded).unwrap();
+        assert_eq!(body, expected);
+
+        // Without Accept-Encoding the body is sent as is
+        let response = client
+            .get(format!("http://{}/api/items", addr))
+            .send()
+            .await
+            .unwrap();
+        assert!(response.headers().get("content-encoding").is_none());
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body, expected);
+    });
+
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/items", Method::Get)
+                .with_handler(Handler::from_json(&payload).with_compression(Encoding::Gzip))
+                .with_handler(Handler::from_json(&payload).with_compression(Encoding::Gzip)),
+        )
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+    assert_eq!(collected.len(), 2);
+    assert_eq!(collected[0].header("accept-encoding"), Some("gzip"));
+    assert!(collected[0].body.is_empty());
+}
+
+#[tokio::test]
+async fn test_sse_handler_streams_events_apart() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let mut response = reqwest::get(format!("http://{}/events", addr))
+            .await
+            .unwrap();
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+
+        let mut received = Vec::new();
+        let mut arrivals = Vec::new();
+        while let Some(chunk) = response.chunk().await.unwrap() {
+            received.extend_from_slice(&chunk);
+            arrivals.push(std::time::Instant::now());
+        }
+        assert_eq!(received, b"data: one\n\ndata: two\n\ndata: three\n\n");
+        assert_eq!(arrivals.len(), 3);
+        for pair in arrivals.windows(2) {
+            assert!(pair[1] - pair[0] >= Duration::from_millis(40));
+        }
+    });
+
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/events", Method::Get)
+                .with_handler(Handler::sse(["one", "two", "three"], Duration::from_millis(50))),
+        )
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+    assert_eq!(collected.len(), 1);
+}
+
+#[tokio::test]
+async fn test_drip_handler_trickles_body() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let started = std::time::Instant::now();
+        let mut response = reqwest::get(format!("http://{}/slow", addr)).await.unwrap();
+        let mut received = Vec::new();
+        let mut chunks = 0;
+        while let Some(chunk) = response.chunk().await.unwrap() {
+            received.extend_from_slice(&chunk);
+            chunks += 1;
+        }
+        assert_eq!(received, b"drip!");
+        assert_eq!(chunks, 5);
+        // Four pauses between the five bytes
+        assert!(started.elapsed() >= Duration::from_millis(160));
+    });
+
+    // The trickle finishes within the grace period once the scenario completes
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/slow", Method::Get)
+                .with_handler(Handler::drip(b"drip!".to_vec(), Duration::from_millis(40))),
+        )
+        .shutdown_grace(Duration::from_secs(5))
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+    assert_eq!(collected.len(), 1);
+}
+
+#[tokio::test]
+async fn test_cors_preflight_then_get() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        let response = client
+            .request(reqwest::Method::OPTIONS, format!("http://{}/api/users", addr))
+            .header("origin", "https://app.example.com")
+            .header("access-control-request-method", "GET")
+            .header("access-control-request-headers", "x-token")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 204);
+        let headers = response.headers();
+        assert_eq!(headers["access-control-allow-origin"], "https://app.example.com");
+        assert_eq!(headers["access-control-allow-methods"], "GET");
+        assert_eq!(headers["access-control-allow-headers"], "x-token");
+        assert_eq!(headers["access-control-max-age"], "600");
+
+        // The preflight does not count, so the server is still up for the real call
+        let response = client
+            .get(format!("http://{}/api/users", addr))
+            .header("origin", "https://app.example.com")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(
+            response.headers()["access-control-allow-origin"],
+            "https://app.example.com"
+        );
+    });
+
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/users", Method::Get)
+                .with_handler(Handler::from_json(&json!([])))
+                .with_cors(
+                    AllowConfig::origin("https://app.example.com").max_age(Duration::from_secs(600)),
+                ),
+        )
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+
+    assert_eq!(collected.len(), 2);
+    assert_eq!(collected[0].method, Method::Options);
+    assert_eq!(collected[1].method, Method::Get);
+}
+
+#[tokio::test]
+async fn test_shared_collector_snapshot_mid_scenario() {
+    let addr = get_test_addr();
+    let collector = std::sync::Arc::new(DefaultCollector::new());
+    let handle = collector.clone();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        client
+            .get(format!("http://{}/api/first", addr))
+            .send()
+            .await
+            .unwrap();
+
+        // The server is still waiting for /api/second
+        let so_far = handle.snapshot();
+        assert_eq!(so_far.len(), 1);
+        assert_eq!(so_far[0].path, "/api/first");
+
+        client
+            .get(format!("http://{}/api/second", addr))
+            .send()
+            .await
+            .unwrap();
+    });
+
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(collector)
+        .endpoint(Endpoint::new("/api/first", Method::Get).with_handler(Handler::new(Response::ok())))
+        .endpoint(Endpoint::new("/api/second", Method::Get).with_handler(Handler::new(Response::ok())))
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+    assert_eq!(collected.len(), 2);
+}
+
+#[tokio::test]
+async fn test_recording_collector_serializes_requests() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        client
+            .get(format!("http://{}/api/items/7", addr))
+            .send()
+            .await
+            .unwrap();
+        client
+            .post(format!("http://{}/api/items", addr))
+            .header("x-trace", "abc")
+            .body(vec![0xde, 0xad, 0xbe, 0xef])
+            .send()
+            .await
+            .unwrap();
+    });
+
+    let recorded = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(RecordingCollector::new())
+        .endpoint(
+            Endpoint::new("/api/items/{id}", Method::Get)
+                .with_handler(Handler::from_json(&json!({"id": 7}))),
+        )
+        .endpoint(
+            Endpoint::new("/api/items", Method::Post)
+                .with_handler(Handler::new(Response::new(201))),
+        )
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+
+    let recorded = recorded.as_array().unwrap();
+    assert_eq!(recorded.len(), 2);
+
+    assert_eq!(recorded[0]["method"], "GET");
+    assert_eq!(recorded[0]["path"], "/api/items/7");
+    assert_eq!(recorded[0]["path_params"], json!({"id": "7"}));
+    assert_eq!(recorded[0]["body"], "");
+
+    assert_eq!(recorded[1]["method"], "POST");
+    assert_eq!(recorded[1]["path"], "/api/items");
+    assert_eq!(recorded[1]["headers"]["x-trace"], "abc");
+    assert_eq!(recorded[1]["body"], json!({"base64": "3q2+7w=="}));
+}
+
+/// Send the recorded traffic's requests, returning each response as a
+/// serialized `Response` keeping its status, content type and body
+async fn send_replay_requests(addr: SocketAddr) -> Vec<serde_json::Value> {
+    wait_for_server(addr).await;
+
+    let client = reqwest::Client::new();
+    let requests = [
+        client.get(format!("http://{}/api/items/7", addr)),
+        client.post(format!("http://{}/api/items", addr)).body("new"),
+        client.get(format!("http://{}/api/items/7", addr)),
+    ];
+    let mut responses = Vec::new();
+    for request in requests {
+        let response = request.send().await.unwrap();
+        let mut recorded = Response::new(response.status().as_u16());
+        if let Some(content_type) = response.headers().get("content-type") {
+            recorded = recorded.with_header("content-type", content_type.to_str().unwrap());
+        }
+        let recorded = recorded.with_body(response.bytes().await.unwrap().to_vec());
+        responses.push(serde_json::to_value(&recorded).unwrap());
+    }
+    responses
+}
+
+#[tokio::test]
+async fn test_replay_recorded_scenario() {
+    // Record live traffic
+    let addr = get_test_addr();
+    let requests_task = tokio::spawn(send_replay_requests(addr));
+    let recorded = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(RecordingCollector::new())
+        .endpoint(
+            Endpoint::new("/api/items/7", Method::Get)
+                .with_handler(Handler::from_json(&json!({"id": 7})))
+                .with_handler(Handler::from_json(&json!({"id": 7, "updated": true}))),
+        )
+        .endpoint(
+            Endpoint::new("/api/items", Method::Post)
+                .with_handler(Handler::from_bytes("application/octet-stream", vec![0xff, 0x00])),
+        )
+        .build()
+        .execute()
+        .await
+        .unwrap();
+    let responses = requests_task.await.unwrap();
+
+    let exchanges: Vec<serde_json::Value> = recorded
+        .as_array()
+        .unwrap()
+        .iter()
+        .zip(&responses)
+        .map(|(request, response)| json!({"request": request, "response": response}))
+        .collect();
+    let serialized = serde_json::to_string(&exchanges).unwrap();
+    assert!(serialized.contains(r#""body":{"base64":"/wA="}"#));
+
+    // Replay it
+    let addr = get_test_addr();
+    let requests_task = tokio::spawn(send_replay_requests(addr));
+    let replayed = ScenarioBuilder::from_recording(&serde_json::from_str(&serialized).unwrap())
+        .unwrap()
+        .server(Axum::bind(addr))
+        .collector(RecordingCollector::new())
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    assert_eq!(requests_task.await.unwrap(), responses);
+    let paths = |recorded: &serde_json::Value| -> Vec<(serde_json::Value, serde_json::Value)> {
+        recorded
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|request| (request["method"].clone(), request["path"].clone()))
+            .collect()
+    };
+    assert_eq!(paths(&replayed), paths(&recorded));
+}
+
+#[tokio::test]
+async fn test_default_handler_collects_unregistered_paths() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        // A readiness probe would be collected too, so retry the first request instead
+        let client = reqwest::Client::new();
+        let mut response = None;
+        for _ in 0..50 {
+            match client
+                .post(format!("http://{}/api/unmocked", addr))
+                .body("surprise")
+                .send()
+                .await
+            {
+                Ok(r) => {
+                    response = Some(r);
+                    break;
+                }
+                Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+            }
+        }
+        assert_eq!(response.unwrap().status(), 418);
+
+        let response = client
+            .get(format!("http://{}/api/known", addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+    });
+
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/known", Method::Get)
+                .with_handler(Handler::from_json(&json!({"ok": true}))),
+        )
+        .default_handler(Handler::new(Response::new(418)))
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+
+    assert_eq!(collected.len(), 2);
+    assert_eq!(collected[0].method, Method::Post);
+    assert_eq!(collected[0].path, "/api/unmocked");
+    assert_eq!(collected[0].body, b"surprise");
+    assert!(!collected[0].matched);
+    assert_eq!(collected[1].path, "/api/known");
+    assert!(collected[1].matched);
+}
+
+#[tokio::test]
+async fn test_unsupported_method_is_not_collected_as_get() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        // A readiness probe would be collected by the default handler, so
+        // retry the first request instead
+        let client = reqwest::Client::new();
+        let trace = reqwest::Method::from_bytes(b"TRACE").unwrap();
+        let mut response = None;
+        for _ in 0..50 {
+            match client
+                .request(trace.clone(), format!("http://{}/api/known", addr))
+                .send()
+                .await
+            {
+                Ok(r) => {
+                    response = Some(r);
+                    break;
+                }
+                Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+            }
+        }
+        assert_eq!(response.unwrap().status(), 501);
+
+        let response = client
+            .request(trace, format!("http://{}/api/unmocked", addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 501);
+
+        let response = client
+            .get(format!("http://{}/api/known", addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+    });
+
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/known", Method::Get)
+                .with_handler(Handler::from_json(&json!({"ok": true}))),
+        )
+        .default_handler(Handler::new(Response::new(418)))
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+
+    assert_eq!(collected.len(), 1);
+    assert_eq!(collected[0].method, Method::Get);
+    assert_eq!(collected[0].path, "/api/known");
+}
+
+#[tokio::test]
+async fn test_strict_collector_reports_unmatched_requests() {
+    use http_endpoint_server_harness::use_cases::ports::{RunOptions, Server};
+
+    let (ready_tx, ready_rx) = tokio::sync::oneshot::channel::<SocketAddr>();
+
+    // Readiness probes would be reported as unmatched, so wait for `on_ready` instead
+    let requests_task = tokio::spawn(async move {
+        let addr = ready_rx.await.unwrap();
+
+        let client = reqwest::Client::new();
+        let response = client
+            .post(format!("http://{}/api/unmocked", addr))
+            .body("surprise")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 404);
+        client
+            .get(format!("http://{}/api/known", addr))
+            .send()
+            .await
+            .unwrap();
+    });
+
+    let result = Axum::bind(([127, 0, 0, 1], 0))
+        .run(
+            vec![Endpoint::new("/api/known", Method::Get)
+                .with_handler(Handler::from_json(&json!({"ok": true})))],
+            StrictCollector::new(),
+            RunOptions::default(),
+            Some(move |addr| {
+                ready_tx.send(addr).unwrap();
+            }),
+        )
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+
+    let unmatched = result.unwrap_err();
+    assert_eq!(unmatched.len(), 1);
+    assert_eq!(unmatched[0].method, Method::Post);
+    assert_eq!(unmatched[0].path, "/api/unmocked");
+    assert_eq!(unmatched[0].body, b"surprise");
+    assert!(!unmatched[0].matched);
+}
+
+#[tokio::test]
+async fn test_endpoints_dispatched_by_accept_header() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        let url = format!("http://{}/api/report", addr);
+
+        let response = client
+            .get(&url)
+            .header("Accept", "text/csv")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 406);
+
+        let response = client
+            .get(&url)
+            .header("Accept", "application/xml")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.text().await.unwrap(), "<report/>");
+
+        let response = client
+            .get(&url)
+            .header("Accept", "application/json")
+            .send()
+            .await
+            .unwrap();
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["format"], "json");
+    });
+
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/report", Method::Get)
+                .with_header_match("accept", "application/xml")
+                .with_handler(Handler::new(Response::ok().with_body("<report/>"))),
+        )
+        .endpoint(
+            Endpoint::new("/api/report", Method::Get)
+                .with_header_predicate(|headers| {
+                    headers.get("accept").is_some_and(|v| v.contains("json"))
+                })
+                .with_handler(Handler::from_json(&json!({"format": "json"}))),
+        )
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+
+    assert_eq!(collected.len(), 2);
+}
+
+#[tokio::test]
+async fn test_cyclic_endpoint_until_expected_requests() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        let mut bodies = Vec::new();
+        for _ in 0..5 {
+            let response = client
+                .get(format!("http://{}/api/ping", addr))
+                .send()
+                .await
+                .unwrap();
+            bodies.push(response.text().await.unwrap());
+        }
+        bodies
+    });
+
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/ping", Method::Get)
+                .cycle()
+                .with_handler(Handler::new(Response::ok().with_body("a")))
+                .with_handler(Handler::new(Response::ok().with_body("b"))),
+        )
+        .expected_requests(5)
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    assert_eq!(requests_task.await.unwrap(), ["a", "b", "a", "b", "a"]);
+    assert_eq!(collected.len(), 5);
+}
+
+#[cfg(feature = "tls")]
+#[tokio::test]
+async fn test_tls_endpoint_with_self_signed_cert() {
+    use http_endpoint_server_harness::use_cases::ports::{RunOptions, Server};
+
+    let (ready_tx, ready_rx) = tokio::sync::oneshot::channel::<SocketAddr>();
+
+    let requests_task = tokio::spawn(async move {
+        let addr = ready_rx.await.unwrap();
+
+        let client = reqwest::Client::builder()
+            .tls_danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        let response = client
+            .get(format!("https://localhost:{}/api/secure", addr.port()))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        response.json::<serde_json::Value>().await.unwrap()
+    });
+
+    let server = Axum::bind_tls(([127, 0, 0, 1], 0), rustls_test_config());
+    let collected = server
+        .run(
+            vec![Endpoint::new("/api/secure", Method::Get)
+                .with_handler(Handler::from_json(&json!({"secure": true})))],
+            DefaultCollector::new(),
+            RunOptions::default(),
+            Some(move |addr| {
+                ready_tx.send(addr).unwrap();
+            }),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(requests_task.await.unwrap(), json!({"secure": true}));
+    assert_eq!(collected.len(), 1);
+    assert_eq!(collected[0].path, "/api/secure");
+}
+
+#[cfg(feature = "tls")]
+#[tokio::test]
+async fn test_tls_stalled_handshake_does_not_block_other_clients() {
+    use http_endpoint_server_harness::use_cases::ports::{RunOptions, Server};
+
+    let (ready_tx, ready_rx) = tokio::sync::oneshot::channel::<SocketAddr>();
+
+    let requests_task = tokio::spawn(async move {
+        let addr = ready_rx.await.unwrap();
+
+        // Connects but never starts its handshake
+        let _stalled = tokio::net::TcpStream::connect(addr).await.unwrap();
+
+        let client = reqwest::Client::builder()
+            .tls_danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        let request = client
+            .get(format!("https://localhost:{}/api/secure", addr.port()))
+            .send();
+        let response = tokio::time::timeout(Duration::from_secs(5), request)
+            .await
+            .expect("request should not wait for the stalled handshake")
+            .unwrap();
+        assert_eq!(response.status(), 200);
+    });
+
+    let server = Axum::bind_tls(([127, 0, 0, 1], 0), rustls_test_config());
+    let collected = server
+        .run(
+            vec![Endpoint::new("/api/secure", Method::Get)
+                .with_handler(Handler::from_json(&json!({"secure": true})))],
+            DefaultCollector::new(),
+            RunOptions::default(),
+            Some(move |addr| {
+                ready_tx.send(addr).unwrap();
+            }),
+        )
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+    assert_eq!(collected.len(), 1);
+}
+
+#[tokio::test]
+async fn test_prefix_routes_every_endpoint() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        let response = client.get(format!("http://{}/users", addr)).send().await.unwrap();
+        assert_eq!(response.status(), 404);
+
+        for path in ["/api/v2/users", "/api/v2/users/7"] {
+            let response = client.get(format!("http://{}{}", addr, path)).send().await.unwrap();
+            assert_eq!(response.status(), 200);
+        }
+    });
+
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .prefix("/api/v2")
+        .endpoint(Endpoint::new("/users", Method::Get).with_handler(Handler::from_json(&json!([]))))
+        .endpoint(
+            Endpoint::new("/users/{id}", Method::Get)
+                .with_handler(Handler::from_json(&json!({"id": 7}))),
+        )
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+
+    assert_eq!(collected.len(), 2);
+    assert_eq!(collected[0].path, "/api/v2/users");
+    assert_eq!(collected[1].path, "/api/v2/users/7");
+    assert_eq!(collected[1].path_params["id"], "7");
+}
+
+#[tokio::test]
+async fn test_fail_then_handlers_until_client_retry_succeeds() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let response = client.get(format!("http://{}/api/data", addr)).send().await.unwrap();
+            if response.status() != 503 {
+                assert_eq!(response.status(), 200);
+                return (attempts, response.json::<serde_json::Value>().await.unwrap());
+            }
+        }
+    });
+
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(Endpoint::new("/api/data", Method::Get).with_handlers(Handler::fail_then(
+            2,
+            Response::new(503),
+            Handler::from_json(&json!({"data": "success"})),
+        )))
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    let (attempts, body) = requests_task.await.unwrap();
+    assert_eq!(attempts, 3);
+    assert_eq!(body, json!({"data": "success"}));
+    assert_eq!(collected.len(), 3);
+}
+
+#[tokio::test]
+async fn test_collects_http_version() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let http1 = reqwest::Client::builder().http1_only().build().unwrap();
+        let response = http1.get(format!("http://{}/api/version", addr)).send().await.unwrap();
+        assert_eq!(response.version(), reqwest::Version::HTTP_11);
+
+        let http2 = reqwest::Client::builder().http2_prior_knowledge().build().unwrap();
+        let response = http2.get(format!("http://{}/api/version", addr)).send().await.unwrap();
+        assert_eq!(response.version(), reqwest::Version::HTTP_2);
+    });
+
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/version", Method::Get)
+                .with_handler(Handler::from_json(&json!({})))
+                .with_handler(Handler::from_json(&json!({}))),
+        )
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+
+    assert_eq!(collected.len(), 2);
+    assert_eq!(collected[0].http_version, "HTTP/1.1");
+    assert_eq!(collected[1].http_version, "HTTP/2.0");
+    assert_eq!(collected[0].sni, None, "plain TCP carries no SNI");
+    assert_eq!(collected[0].alpn, None);
+}
+
+#[cfg(feature = "tls")]
+#[tokio::test]
+async fn test_tls_collects_sni_and_alpn() {
+    use http_endpoint_server_harness::use_cases::ports::{RunOptions, Server};
+
+    let (ready_tx, ready_rx) = tokio::sync::oneshot::channel::<SocketAddr>();
+
+    let requests_task = tokio::spawn(async move {
+        let addr = ready_rx.await.unwrap();
+
+        // The client sends the URL's host as SNI, resolved to the harness
+        let client = reqwest::Client::builder()
+            .tls_danger_accept_invalid_certs(true)
+            .resolve("api.example.test", addr)
+            .build()
+            .unwrap();
+        let response = client
+            .get(format!("https://api.example.test:{}/api/secure", addr.port()))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+    });
+
+    let server = Axum::bind_tls(([127, 0, 0, 1], 0), rustls_test_config());
+    let collected = server
+        .run(
+            vec![Endpoint::new("/api/secure", Method::Get)
+                .with_handler(Handler::from_json(&json!({"secure": true})))],
+            DefaultCollector::new(),
+            RunOptions::default(),
+            Some(move |addr| {
+                ready_tx.send(addr).unwrap();
+            }),
+        )
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+    assert_eq!(collected.len(), 1);
+    assert_eq!(collected[0].sni.as_deref(), Some("api.example.test"));
+    assert_eq!(collected[0].alpn.as_deref(), Some("h2"));
+    assert_eq!(collected[0].http_version, "HTTP/2.0");
+}
+
+#[cfg(feature = "multipart")]
+#[tokio::test]
+async fn test_multipart_upload_parts() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let form = reqwest::multipart::Form::new()
+            .text("title", "report")
+            .part(
+                "file",
+                reqwest::multipart::Part::bytes(vec![7u8; 1024])
+                    .file_name("report.bin")
+                    .mime_str("application/octet-stream")
+                    .unwrap(),
+            );
+        let response = reqwest::Client::new()
+            .post(format!("http://{}/api/upload", addr))
+            .multipart(form)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 201);
+    });
+
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/upload", Method::Post).with_handler(Handler::dynamic(
+                |req: &Request| match req.multipart() {
+                    Some(parts) if parts.iter().any(|p| p.filename.is_some()) => {
+                        Response::new(201)
+                    }
+                    _ => Response::new(400),
+                },
+            )),
+        )
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+
+    let parts = collected[0].multipart().unwrap();
+    assert_eq!(parts.len(), 2);
+    assert_eq!(parts[0].name, "title");
+    assert_eq!(parts[0].bytes, b"report");
+    assert_eq!(parts[1].name, "file");
+    assert_eq!(parts[1].filename.as_deref(), Some("report.bin"));
+    assert_eq!(
+        parts[1].content_type.as_deref(),
+        Some("application/octet-stream")
+    );
+    assert_eq!(parts[1].bytes.len(), 1024);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_unix_domain_socket() {
+    use http_body_util::{BodyExt, Empty};
+    use http_endpoint_server_harness::use_cases::ports::BoundAddr;
+    use hyper::body::Bytes;
+    use hyper_util::rt::TokioIo;
+
+    let path = std::env::temp_dir().join(format!("harness-{}.sock", get_available_port()));
+    let (ready_tx, ready_rx) = tokio::sync::oneshot::channel::<BoundAddr>();
+
+    let requests_task = tokio::spawn(async move {
+        let BoundAddr::Unix(path) = ready_rx.await.unwrap() else {
+            panic!("expected a Unix socket address");
+        };
+
+        let stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+        let (mut sender, connection) =
+            hyper::client::conn::http1::handshake(TokioIo::new(stream))
+                .await
+                .unwrap();
+        tokio::spawn(connection);
+
+        let request = hyper::Request::get("/api/sidecar")
+            .header("host", "localhost")
+            .body(Empty::<Bytes>::new())
+            .unwrap();
+        let response = sender.send_request(request).await.unwrap();
+        assert_eq!(response.status(), 200);
+        response.into_body().collect().await.unwrap().to_bytes()
+    });
+
+    let collected = Axum::bind_uds(&path)
+        .run_bound(
+            vec![Endpoint::new("/api/sidecar", Method::Get)
+                .with_handler(Handler::from_json(&json!({"ok": true})))],
+            DefaultCollector::new(),
+            Default::default(),
+            Some(move |addr| {
+                ready_tx.send(addr).unwrap();
+            }),
+        )
+        .await
+        .unwrap();
+
+    let body = requests_task.await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body, json!({"ok": true}));
+    assert_eq!(collected.len(), 1);
+    assert_eq!(collected[0].path, "/api/sidecar");
+    assert!(!path.exists(), "socket file should be removed on shutdown");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_prepare_rejects_unix_domain_socket() {
+    let path = std::env::temp_dir().join(format!("harness-{}.sock", get_available_port()));
+
+    let result = Axum::bind_uds(&path).prepare().await;
+    assert!(matches!(result, Err(HarnessError::ServerError(_))));
+    assert!(!path.exists(), "socket file should be removed");
+}
+
+#[cfg(feature = "tracing")]
+#[tokio::test]
+async fn test_tracing_events() {
+    let logs = server_harness_test_support::CapturedLogs::start();
+
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        let response = client
+            .get(format!("http://{}/api/missing", addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 404);
+        client
+            .get(format!("http://{}/api/users", addr))
+            .send()
+            .await
+            .unwrap();
+    });
+
+    ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/users", Method::Get)
+                .with_handler(Handler::from_json(&json!([]))),
+        )
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+
+    let logs = logs.contents();
+    assert!(logs.contains("no handler matched"), "{}", logs);
+    assert!(logs.contains("request received"), "{}", logs);
+    assert!(logs.contains("request{method=GET path=/api/users}: "), "{}", logs);
+    assert!(logs.contains("handler selected"), "{}", logs);
+    assert!(logs.contains("index=0 count=1"), "{}", logs);
+    assert!(logs.contains("scenario complete, shutting down"), "{}", logs);
+}
+
+#[tokio::test]
+async fn test_filtering_collector_skips_health_checks() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        let response = client
+            .get(format!("http://{}/health", addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+
+        client
+            .get(format!("http://{}/api/users", addr))
+            .send()
+            .await
+            .unwrap();
+    });
+
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(FilteringCollector::new(
+            DefaultCollector::new(),
+            |request: &Request| request.path != "/health",
+        ))
+        .endpoint(Endpoint::new("/health", Method::Get).with_handler(Handler::new(Response::ok())))
+        .endpoint(
+            Endpoint::new("/api/users", Method::Get)
+                .with_handler(Handler::from_json(&json!([]))),
+        )
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+
+    assert_eq!(collected.len(), 1);
+    assert_eq!(collected[0].path, "/api/users");
+}
+
+#[tokio::test]
+async fn test_manual_shutdown_keeps_serving_until_shut_down() {
+    let handle = ScenarioBuilder::new()
+        .server(Axum::bind(([127, 0, 0, 1], 0)))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/users", Method::Get)
+                .with_handler(Handler::from_json(&json!([{"id": 1}]))),
+        )
+        .manual_shutdown()
+        .start()
+        .await
+        .unwrap();
+    let url = format!("http://{}/api/users", handle.addr());
+
+    // The only handler is called by the first request; the server keeps serving
+    let client = reqwest::Client::new();
+    for _ in 0..3 {
+        let response = client.get(&url).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.json::<serde_json::Value>().await.unwrap(), json!([{"id": 1}]));
+    }
+
+    let collected = handle.shutdown().await.unwrap();
+    assert_eq!(collected.len(), 3);
+    assert!(client.get(&url).send().await.is_err());
+}
+
+#[tokio::test]
+async fn test_execute_rejects_manual_shutdown() {
+    let result = ScenarioBuilder::new()
+        .server(Axum::bind(([127, 0, 0, 1], 0)))
+        .collector(DefaultCollector::new())
+        .endpoint(Endpoint::new("/api/ping", Method::Get).with_handler(Handler::new(Response::ok())))
+        .manual_shutdown()
+        .execute()
+        .await;
+    assert!(matches!(result, Err(HarnessError::ConfigurationError(_))));
+}
+
+#[tokio::test]
+async fn test_started_scenario_completes_on_its_own() {
+    let handle = ScenarioBuilder::new()
+        .server(Axum::bind(([127, 0, 0, 1], 0)))
+        .collector(DefaultCollector::new())
+        .endpoint(Endpoint::new("/api/ping", Method::Get).with_handler(Handler::new(Response::ok())))
+        .start()
+        .await
+        .unwrap();
+
+    reqwest::get(format!("http://{}/api/ping", handle.addr()))
+        .await
+        .unwrap();
+
+    let collected = handle.output().await.unwrap();
+    assert_eq!(collected.len(), 1);
+}
+
+#[tokio::test]
+async fn test_body_handlers_route_by_request_body() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        let url = format!("http://{}/api/items", addr);
+        let mut responses = Vec::new();
+        for body in [
+            json!({"action": "delete", "id": 1}),
+            json!({"action": "create"}),
+            json!({"action": "archive"}),
+        ] {
+            let response = client.post(&url).json(&body).send().await.unwrap();
+            responses.push(response.json::<serde_json::Value>().await.unwrap());
+        }
+        responses
+    });
+
+    let action_is = |action: &'static str| {
+        move |body: &[u8]| {
+            serde_json::from_slice::<serde_json::Value>(body)
+                .is_ok_and(|body| body["action"] == action)
+        }
+    };
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/items", Method::Post)
+                .when_body(action_is("create"), Handler::from_json(&json!({"created": true})))
+                .when_body(action_is("delete"), Handler::from_json(&json!({"deleted": true})))
+                .with_handler(Handler::from_json(&json!({"error": "unknown action"}))),
+        )
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    let responses = requests_task.await.unwrap();
+    assert_eq!(
+        responses,
+        [
+            json!({"deleted": true}),
+            json!({"created": true}),
+            json!({"error": "unknown action"}),
+        ]
+    );
+    assert_eq!(collected.len(), 3);
+}
+
+#[tokio::test]
+async fn test_metrics_collector_aggregates_requests() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        for body in ["a", "abc", "abcdef"] {
+            client
+                .post(format!("http://{}/api/events", addr))
+                .body(body)
+                .send()
+                .await
+                .unwrap();
+        }
+        client
+            .get(format!("http://{}/api/events", addr))
+            .send()
+            .await
+            .unwrap();
+    });
+
+    let metrics = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(MetricsCollector::new())
+        .endpoint(Endpoint::new("/api/events", Method::Post).with_handler(Handler::new(Response::ok())))
+        .endpoint(Endpoint::new("/api/events", Method::Get).with_handler(Handler::new(Response::ok())))
+        .expected_requests(4)
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+
+    assert_eq!(metrics.count(Method::Post, "/api/events"), 3);
+    assert_eq!(metrics.count(Method::Get, "/api/events"), 1);
+    assert_eq!(metrics.total_requests, 4);
+    assert_eq!(metrics.total_bytes, 10);
+    assert_eq!(metrics.min_body_size, Some(0));
+    assert_eq!(metrics.max_body_size, Some(6));
+    assert_eq!(metrics.avg_body_size(), Some(2.5));
+}
+
+#[tokio::test]
+async fn test_dedup_collector_counts_retries() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        for body in ["order-1", "order-1", "order-2", "order-1"] {
+            client
+                .post(format!("http://{}/api/orders", addr))
+                .body(body)
+                .send()
+                .await
+                .unwrap();
+        }
+    });
+
+    let distinct = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DedupCollector::new())
+        .endpoint(Endpoint::new("/api/orders", Method::Post).with_handler(Handler::new(Response::ok())))
+        .expected_requests(4)
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+
+    assert_eq!(distinct.len(), 2);
+    assert_eq!(distinct[0].0.body_as_str(), Some("order-1"));
+    assert_eq!(distinct[0].1, 3);
+    assert_eq!(distinct[1].0.body_as_str(), Some("order-2"));
+    assert_eq!(distinct[1].1, 1);
+}
+
+#[tokio::test]
+async fn test_wait_for_ready_probe_is_not_collected() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_ready(addr, Duration::from_secs(1)).await.unwrap();
+        reqwest::get(format!("http://{}/api/ping", addr)).await.unwrap();
+    });
+
+    // A strict collector would report an HTTP probe as unexpected
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(StrictCollector::new())
+        .endpoint(Endpoint::new("/api/ping", Method::Get).with_handler(Handler::new(Response::ok())))
+        .build()
+        .execute()
+        .await
+        .unwrap()
+        .expect("no unexpected requests");
+
+    requests_task.await.unwrap();
+    assert_eq!(collected.len(), 1);
+}
+
+#[tokio::test]
+async fn test_probes_do_not_affect_collection_or_shutdown() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        // A bare connection and an unmatched path before the real requests
+        drop(tokio::net::TcpStream::connect(addr).await.unwrap());
+        let client = reqwest::Client::new();
+        let response = client
+            .get(format!("http://{}/health", addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 404);
+
+        // Had the probes counted, the server would stop after the first call
+        for expected in ["first", "second"] {
+            let response = client
+                .get(format!("http://{}/", addr))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.text().await.unwrap(), expected);
+        }
+    });
+
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/", Method::Get)
+                .with_handler(Handler::new(Response::ok().with_body("first")))
+                .with_handler(Handler::new(Response::ok().with_body("second"))),
+        )
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    requests_task.await.unwrap();
+
+    assert_eq!(collected.len(), 2);
+    assert!(collected.iter().all(|request| request.path == "/"));
+}
+
+#[tokio::test]
+async fn test_json_rpc_methods_and_batch() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        let single: serde_json::Value = client
+            .post(format!("http://{}/rpc", addr))
+            .json(&json!({"jsonrpc": "2.0", "method": "eth_blockNumber", "id": 1}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let batch: serde_json::Value = client
+            .post(format!("http://{}/rpc", addr))
+            .json(&json!([
+                {"jsonrpc": "2.0", "method": "eth_getBalance", "params": ["0xabc"], "id": 2},
+                {"jsonrpc": "2.0", "method": "eth_blockNumber", "id": 3},
+                {"jsonrpc": "2.0", "method": "eth_chainId", "id": 4},
+            ]))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        (single, batch)
+    });
+
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            JsonRpcEndpoint::new("/rpc")
+                .with_method("eth_blockNumber", Handler::from_json(&json!("0x10")))
+                .with_method(
+                    "eth_getBalance",
+                    Handler::dynamic(|req| {
+                        let params = req.json_body().unwrap_or_default();
+                        Response::ok().with_json(&json!({"address": params[0], "balance": "0x1"}))
+                    }),
+                )
+                .into_endpoint()
+                .expect_calls(2),
+        )
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    let (single, batch) = requests_task.await.unwrap();
+    assert_eq!(single, json!({"jsonrpc": "2.0", "result": "0x10", "id": 1}));
+    assert_eq!(
+        batch,
+        json!([
+            {"jsonrpc": "2.0", "result": {"address": "0xabc", "balance": "0x1"}, "id": 2},
+            {"jsonrpc": "2.0", "result": "0x10", "id": 3},
+            {"jsonrpc": "2.0", "error": {"code": -32601, "message": "Method not found"}, "id": 4},
+        ])
+    );
+
+    assert_eq!(collected.len(), 2);
+    let calls = collected[1].json_rpc_calls();
+    assert_eq!(calls.len(), 3);
+    assert_eq!(calls[0].method, "eth_getBalance");
+    assert_eq!(calls[0].params, Some(json!(["0xabc"])));
+    assert_eq!(collected[0].json_rpc_calls()[0].method, "eth_blockNumber");
+}
+
+#[tokio::test]
+async fn test_duplicate_endpoints_rejected_before_binding() {
+    let users = || {
+        Endpoint::new("/api/users", Method::Get).with_handler(Handler::from_json(&json!([])))
+    };
+    let duplicated = || {
+        ScenarioBuilder::new()
+            .server(Axum::bind(get_test_addr()))
+            .collector(DefaultCollector::new())
+            .endpoint(users())
+            .endpoint(users())
+    };
+
+    let error = duplicated().try_build().err().unwrap();
+    assert!(matches!(error, HarnessError::ConfigurationError(_)));
+    assert!(error.to_string().contains("GET /api/users is registered twice"));
+
+    // The address is taken, so only a check before binding reports the conflict
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let result = ScenarioBuilder::new()
+        .server(Axum::bind(listener.local_addr().unwrap()))
+        .collector(DefaultCollector::new())
+        .endpoint(Endpoint::new("/users/{id}", Method::Get))
+        .endpoint(Endpoint::new("/users/{name}", Method::Delete))
+        .build()
+        .execute()
+        .await;
+    assert!(matches!(result, Err(HarnessError::ConfigurationError(_))));
+}
+
+#[tokio::test]
+async fn test_random_handler_is_deterministic_with_seed() {
+    async fn statuses(seed: u64) -> Vec<u16> {
+        let addr = get_test_addr();
+
+        let requests_task = tokio::spawn(async move {
+            wait_for_server(addr).await;
+
+            let client = reqwest::Client::new();
+            let mut statuses = Vec::new();
+            for _ in 0..10 {
+                let response = client
+                    .get(format!("http://{}/api/flaky", addr))
+                    .send()
+                    .await
+                    .unwrap();
+                statuses.push(response.status().as_u16());
+            }
+            statuses
+        });
+
+        let collected = ScenarioBuilder::new()
+            .server(Axum::bind(addr))
+            .collector(DefaultCollector::new())
+            .endpoint(Endpoint::new("/api/flaky", Method::Get).random(vec![
+                (3, Handler::new(Response::ok())),
+                (1, Handler::new(Response::new(503))),
+            ]))
+            .seed(seed)
+            .expected_requests(10)
+            .build()
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(collected.len(), 10);
+
+        requests_task.await.unwrap()
+    }
+
+    let expected = [200, 200, 200, 200, 503, 200, 200, 200, 503, 200];
+    assert_eq!(statuses(7).await, expected);
+    assert_eq!(statuses(7).await, expected);
+}
+
+#[tokio::test]
+async fn test_on_request_called_once_per_collected_request() {
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let client = reqwest::Client::new();
+        for path in ["/api/users", "/api/posts", "/api/users"] {
+            client
+                .get(format!("http://{}{}", addr, path))
+                .send()
+                .await
+                .unwrap();
+        }
+    });
+
+    let (paths_tx, mut paths_rx) = tokio::sync::mpsc::unbounded_channel();
+    let collected = ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(Endpoint::new("/api/users", Method::Get).expect_calls(2))
+        .endpoint(Endpoint::new("/api/posts", Method::Get))
+        .on_request(move |request| {
+            let _ = paths_tx.send((request.sequence, request.path.clone()));
+        })
+        .build()
+        .execute()
+        .await
+        .unwrap();
+    requests_task.await.unwrap();
+
+    let mut paths = Vec::new();
+    while let Ok(path) = paths_rx.try_recv() {
+        paths.push(path);
+    }
+    paths.sort();
+    assert_eq!(
+        paths,
+        [
+            (0, "/api/users".to_string()),
+            (1, "/api/posts".to_string()),
+            (2, "/api/users".to_string()),
+        ]
+    );
+    assert_eq!(collected.len(), 3);
+}
+
+#[tokio::test]
+async fn test_bind_failure_preserves_io_error() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+
+    let error = ScenarioBuilder::new()
+        .server(Axum::bind(listener.local_addr().unwrap()))
+        .collector(DefaultCollector::new())
+        .endpoint(Endpoint::new("/api/test", Method::Get))
+        .build()
+        .execute()
+        .await
+        .unwrap_err();
+
+    assert!(
+        matches!(error, HarnessError::Bind(ref e) if e.kind() == std::io::ErrorKind::AddrInUse)
+    );
+    let source = std::error::Error::source(&error).unwrap();
+    let io_error = source.downcast_ref::<std::io::Error>().unwrap();
+    assert_eq!(io_error.kind(), std::io::ErrorKind::AddrInUse);
+}
+
+#[tokio::test]
+async fn test_response_trailers_reach_trailer_aware_client() {
+    use http_body_util::{BodyExt, Empty};
+    use hyper::body::Bytes;
+    use hyper_util::rt::TokioIo;
+
+    let addr = get_test_addr();
+
+    let requests_task = tokio::spawn(async move {
+        wait_for_server(addr).await;
+
+        let mut responses = Vec::new();
+        for path in ["/api/status", "/api/plain"] {
+            let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+            let (mut sender, connection) =
+                hyper::client::conn::http1::handshake(TokioIo::new(stream))
+                    .await
+                    .unwrap();
+            tokio::spawn(connection);
+
+            let request = hyper::Request::get(path)
+                .header("host", "localhost")
+                .header("te", "trailers")
+                .body(Empty::<Bytes>::new())
+                .unwrap();
+            let response = sender.send_request(request).await.unwrap();
+            let headers = response.headers().clone();
+            let collected = response.into_body().collect().await.unwrap();
+            let trailers = collected.trailers().cloned();
+            responses.push((headers, trailers, collected.to_bytes()));
+        }
+        responses
+    });
+
+    ScenarioBuilder::new()
+        .server(Axum::bind(addr))
+        .collector(DefaultCollector::new())
+        .endpoint(
+            Endpoint::new("/api/status", Method::Get).with_handler(Handler::new(
+                Response::ok()
+                    .with_body("done")
+                    .with_trailer("x-status", "0")
+                    .with_trailer("x-checksum", "abc"),
+            )),
+        )
+        .endpoint(
+            Endpoint::new("/api/plain", Method::Get)
+                .with_handler(Handler::new(Response::ok().with_body("plain"))),
+        )
+        .build()
+        .execute()
+        .await
+        .unwrap();
+
+    let responses = requests_task.await.unwrap();
+
+    let (headers, trailers, body) = &responses[0];
+    assert_eq!(headers["trailer"], "x-checksum, x-status");
+    assert_eq!(headers["transfer-encoding"], "chunked");
+    let trailers = trailers.as_ref().expect("trailers should be received");
+    assert_eq!(trailers["x-status"], "0");
+    assert_eq!(trailers["x-checksum"], "abc");
+    assert_eq!(body.as_ref(), b"done");
+
+    let (headers, trailers, body) = &responses[1];
+    assert!(!headers.contains_key("trailer"));
+    assert_eq!(headers["content-length"], "5");
+    assert!(trailers.is_none());
+    assert_eq!(body.as_ref(), b"plain");
+}
+
+#[tokio::test]
+async fn test_serves_over_ipv6() {
+    let (server, addr) = Axum::bind((std::net::Ipv6Addr::LOCALHOST, 0))
+        .prepare()
+        .await
+        .unwrap();
+    assert!(addr.is_ipv6());
+    assert_ne!(addr.port(), 0);
+
+    let serve = tokio::spawn(server.serve(
+        vec![Endpoint::new("/api/test", Method::Get)
+            .with_handler(Handler::from_json(&json!({"status": "ok"})))],
+        DefaultCollector::new(),
+    ));
+
+    let response = reqwest::get(format!("http://{}/api/test", addr))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(serve.await.unwrap().unwrap().len(), 1);
+
+    let handle = ScenarioBuilder::new()
+        .server(Axum::bind_any_v6())
+        .collector(DefaultCollector::new())
+        .endpoint(Endpoint::new("/api/test", Method::Get))
+        .start()
+        .await
+        .unwrap();
+    assert!(handle.addr().ip().is_unspecified() && handle.addr().is_ipv6());
+
+    let url = format!("http://[::1]:{}/api/test", handle.addr().port());
+    reqwest::get(url).await.unwrap();
+    assert_eq!(handle.output().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_serves_on_pre_bound_listener() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = Axum::from_listener(listener);
+
+    for _ in 0..2 {
+        let (prepared, addr) = server.prepare().await.unwrap();
+        assert_eq!(addr.port(), port);
+
+        let serve = tokio::spawn(prepared.serve(
+            vec![Endpoint::new("/api/test", Method::Get)
+                .with_handler(Handler::from_json(&json!({"status": "ok"})))],
+            DefaultCollector::new(),
+        ));
+        let response = reqwest::get(format!("http://127.0.0.1:{}/api/test", port))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(serve.await.unwrap().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,339 @@
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+
+/// Trait for collecting items, such as received requests, during scenario execution
+///
+/// The `Output` type is the final result returned when the scenario completes.
+/// This allows users to define their own collection strategy and return type.
+pub trait Collector<Item>: Send + Sync {
+    /// The type returned when the scenario completes
+    type Output: Send;
+
+    /// Called when an item is received
+    fn collect(&self, item: Item);
+
+    /// Consume the collector and return the final output
+    fn into_output(self) -> Self::Output;
+
+    /// Whether the server should also collect requests matching no configured
+    /// handler, which it otherwise answers without collecting.
+    fn collects_unmatched(&self) -> bool {
+        false
+    }
+}
+
+/// Collectors whose output so far can be read without consuming them
+///
+/// The server owns its collector, so share it as an [`Arc`] to call
+/// [`snapshot`](Snapshot::snapshot) while the scenario is still running.
+pub trait Snapshot<Item>: Collector<Item> {
+    /// Get the output so far, as `into_output` would return it now
+    fn snapshot(&self) -> Self::Output;
+}
+
+/// Collected items that record whether a configured handler served them
+pub trait Matched {
+    /// `false` for requests answered by a default handler or the "not found" fallback
+    fn matched(&self) -> bool;
+}
+
+/// A shared collector, so tests can keep a handle and [`snapshot`](Snapshot::snapshot)
+/// it mid-scenario
+///
+/// `into_output` returns the full output when the server holds the last handle,
+/// and a snapshot otherwise.
+impl<Item, C: Snapshot<Item>> Collector<Item> for Arc<C> {
+    type Output = C::Output;
+
+    fn collect(&self, item: Item) {
+        C::collect(self, item)
+    }
+
+    fn into_output(self) -> Self::Output {
+        match Arc::try_unwrap(self) {
+            Ok(collector) => collector.into_output(),
+            Err(shared) => shared.snapshot(),
+        }
+    }
+
+    fn collects_unmatched(&self) -> bool {
+        C::collects_unmatched(self)
+    }
+}
+
+impl<Item, C: Snapshot<Item>> Snapshot<Item> for Arc<C> {
+    fn snapshot(&self) -> Self::Output {
+        C::snapshot(self)
+    }
+}
+
+/// Default collector implementation that collects items into a Vec
+pub struct DefaultCollector<Item> {
+    items: Mutex<Vec<Item>>,
+}
+
+impl<Item> DefaultCollector<Item> {
+    pub fn new() -> Self {
+        Self {
+            items: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl<Item> Default for DefaultCollector<Item> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Item: Clone + Send> Collector<Item> for DefaultCollector<Item> {
+    type Output = Vec<Item>;
+
+    fn collect(&self, item: Item) {
+        if let Ok(mut items) = self.items.lock() {
+            items.push(item);
+        }
+    }
+
+    fn into_output(self) -> Self::Output {
+        self.items.into_inner().unwrap_or_default()
+    }
+}
+
+impl<Item: Clone + Send> Snapshot<Item> for DefaultCollector<Item> {
+    fn snapshot(&self) -> Self::Output {
+        self.items
+            .lock()
+            .map(|items| items.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Collector that records items as JSON, e.g. for snapshot tests
+///
+/// `into_output` returns an array with one serialized value per item.
+pub struct RecordingCollector<Item> {
+    items: Mutex<Vec<Item>>,
+}
+
+impl<Item> RecordingCollector<Item> {
+    pub fn new() -> Self {
+        Self {
+            items: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl<Item> Default for RecordingCollector<Item> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Item: Serialize + Send> Collector<Item> for RecordingCollector<Item> {
+    type Output = serde_json::Value;
+
+    fn collect(&self, item: Item) {
+        if let Ok(mut items) = self.items.lock() {
+            items.push(item);
+        }
+    }
+
+    fn into_output(self) -> Self::Output {
+        let items = self.items.into_inner().unwrap_or_default();
+        serde_json::to_value(items).unwrap_or_default()
+    }
+}
+
+impl<Item: Serialize + Send> Snapshot<Item> for RecordingCollector<Item> {
+    fn snapshot(&self) -> Self::Output {
+        self.items
+            .lock()
+            .map(|items| serde_json::to_value(&*items).unwrap_or_default())
+            .unwrap_or_default()
+    }
+}
+
+/// Collector that fails when a request matched no configured handler
+///
+/// Unmatched requests, including those answered by a default handler, are
+/// collected too. `into_output` returns every request as `Ok` when all of them
+/// matched, and only the unmatched ones as `Err` otherwise.
+pub struct StrictCollector<Item> {
+    items: Mutex<Vec<Item>>,
+}
+
+impl<Item> StrictCollector<Item> {
+    pub fn new() -> Self {
+        Self {
+            items: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl<Item> Default for StrictCollector<Item> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Item: Matched + Clone + Send> Collector<Item> for StrictCollector<Item> {
+    type Output = Result<Vec<Item>, Vec<Item>>;
+
+    fn collect(&self, item: Item) {
+        if let Ok(mut items) = self.items.lock() {
+            items.push(item);
+        }
+    }
+
+    fn into_output(self) -> Self::Output {
+        strict_output(self.items.into_inner().unwrap_or_default())
+    }
+
+    fn collects_unmatched(&self) -> bool {
+        true
+    }
+}
+
+impl<Item: Matched + Clone + Send> Snapshot<Item> for StrictCollector<Item> {
+    fn snapshot(&self) -> Self::Output {
+        let items = self.items.lock().map(|items| items.clone()).unwrap_or_default();
+        strict_output(items)
+    }
+}
+
+fn strict_output<Item: Matched>(items: Vec<Item>) -> Result<Vec<Item>, Vec<Item>> {
+    if items.iter().all(Matched::matched) {
+        Ok(items)
+    } else {
+        Err(items.into_iter().filter(|item| !item.matched()).collect())
+    }
+}
+
+/// Collector storing only the items a predicate accepts, delegating them to an
+/// inner collector
+///
+/// The predicate runs when an item is collected, not when requests are routed,
+/// so filtered requests are still answered normally.
+pub struct FilteringCollector<C, F> {
+    inner: C,
+    predicate: F,
+}
+
+impl<C, F> FilteringCollector<C, F> {
+    /// Pass items for which `predicate` returns `true` on to `inner`
+    pub fn new(inner: C, predicate: F) -> Self {
+        Self { inner, predicate }
+    }
+}
+
+impl<Item, C, F> Collector<Item> for FilteringCollector<C, F>
+where
+    C: Collector<Item>,
+    F: Fn(&Item) -> bool + Send + Sync,
+{
+    type Output = C::Output;
+
+    fn collect(&self, item: Item) {
+        if (self.predicate)(&item) {
+            self.inner.collect(item);
+        }
+    }
+
+    fn into_output(self) -> Self::Output {
+        self.inner.into_output()
+    }
+
+    fn collects_unmatched(&self) -> bool {
+        self.inner.collects_unmatched()
+    }
+}
+
+impl<Item, C, F> Snapshot<Item> for FilteringCollector<C, F>
+where
+    C: Snapshot<Item>,
+    F: Fn(&Item) -> bool + Send + Sync,
+{
+    fn snapshot(&self) -> Self::Output {
+        self.inner.snapshot()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_collector_keeps_order() {
+        let collector = DefaultCollector::new();
+        collector.collect("a");
+        collector.collect("b");
+        assert_eq!(collector.into_output(), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_default_collector_snapshot() {
+        let collector = DefaultCollector::new();
+        collector.collect(1);
+        assert_eq!(collector.snapshot(), vec![1]);
+
+        collector.collect(2);
+        assert_eq!(collector.snapshot(), vec![1, 2]);
+        assert_eq!(collector.into_output(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_shared_collector_output() {
+        let collector = Arc::new(DefaultCollector::new());
+        let handle = collector.clone();
+        collector.collect("a");
+        assert_eq!(handle.snapshot(), vec!["a"]);
+
+        // Still shared: the output is a snapshot
+        assert_eq!(collector.into_output(), vec!["a"]);
+        assert_eq!(handle.into_output(), vec!["a"]);
+    }
+
+    impl Matched for (&str, bool) {
+        fn matched(&self) -> bool {
+            self.1
+        }
+    }
+
+    #[test]
+    fn test_strict_collector_output() {
+        let collector = StrictCollector::new();
+        collector.collect(("a", true));
+        assert!(collector.collects_unmatched());
+        assert_eq!(collector.snapshot(), Ok(vec![("a", true)]));
+
+        collector.collect(("b", false));
+        assert_eq!(collector.into_output(), Err(vec![("b", false)]));
+    }
+
+    #[test]
+    fn test_filtering_collector_delegates_accepted_items() {
+        let collector =
+            FilteringCollector::new(DefaultCollector::new(), |item: &&str| *item != "ping");
+        collector.collect("a");
+        collector.collect("ping");
+        assert_eq!(collector.snapshot(), vec!["a"]);
+
+        collector.collect("b");
+        assert_eq!(collector.into_output(), vec!["a", "b"]);
+
+        let strict = FilteringCollector::new(StrictCollector::<(&str, bool)>::new(), |_: &_| true);
+        assert!(strict.collects_unmatched());
+    }
+
+    #[test]
+    fn test_recording_collector_output() {
+        let collector = RecordingCollector::new();
+        collector.collect(serde_json::json!({"id": 1}));
+        collector.collect(serde_json::json!({"id": 2}));
+        assert_eq!(
+            collector.into_output(),
+            serde_json::json!([{"id": 1}, {"id": 2}])
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::{oneshot, Mutex};
+
+/// Shared state tracking when a scenario is complete
+///
+/// Sends on the shutdown channel once every handler has been called, or
+/// once the expected number of requests has been handled.
+#[derive(Clone)]
+pub struct CompletionTracker {
+    /// Number of completions that trigger shutdown
+    total_handlers: usize,
+    /// Whether every request completes, rather than only first handler calls
+    counts_requests: bool,
+    /// Number of completions so far
+    handlers_called: Arc<AtomicUsize>,
+    /// Shutdown signal sender (wrapped in Mutex for Clone)
+    shutdown_tx: Arc<Mutex<Option<oneshot::Sender<()>>>>,
+}
+
+impl CompletionTracker {
+    pub fn new(total_handlers: usize, shutdown_tx: oneshot::Sender<()>) -> Self {
+        Self {
+            total_handlers,
+            counts_requests: false,
+            handlers_called: Arc::new(AtomicUsize::new(0)),
+            shutdown_tx: Arc::new(Mutex::new(Some(shutdown_tx))),
+        }
+    }
+
+    /// Number of first calls a route with `handler_count` handlers contributes
+    /// to the shutdown total.
+    ///
+    /// A route without handlers still expects one request (answered with the
+    /// harness' "no handler" response), so declaring it keeps the scenario running
+    /// until it is hit.
+    pub fn expected_calls(handler_count: usize) -> usize {
+        handler_count.max(1)
+    }
+
+    /// Whether the `call_index`-th request to a route is one of its
+    /// [`expected_calls`](Self::expected_calls)
+    pub fn is_first_call(call_index: usize, handler_count: usize) -> bool {
+        call_index < Self::expected_calls(handler_count)
+    }
+
+    /// Track `expected` requests instead of distinct handler calls
+    pub fn counting_requests(mut self, expected: usize) -> Self {
+        self.total_handlers = expected;
+        self.counts_requests = true;
+        self
+    }
+
+    /// Number of completions so far, shared with the tracker
+    pub fn handlers_called(&self) -> Arc<AtomicUsize> {
+        self.handlers_called.clone()
+    }
+
+    /// Called for every handled request; `first_call` is whether its handler
+    /// was used for the first time
+    pub async fn request_handled(&self, first_call: bool) {
+        if first_call || self.counts_requests {
+            self.handler_called().await;
+        }
+    }
+
+    /// Called once for a handled request that used `first_calls` handlers for
+    /// the first time, e.g. a GraphQL operation selecting several fields.
+    ///
+    /// Counts each first call, or the request once when counting requests.
+    pub async fn operation_handled(&self, first_calls: usize) {
+        match self.counts_requests {
+            true => self.completed(1).await,
+            false if first_calls > 0 => self.completed(first_calls).await,
+            false => {}
+        }
+    }
+
+    /// Called when a handler is used for the first time
+    pub async fn handler_called(&self) {
+        self.completed(1).await;
+    }
+
+    async fn completed(&self, count: usize) {
+        let called = self.handlers_called.fetch_add(count, Ordering::SeqCst) + count;
+        if called >= self.total_handlers {
+            // All handlers have been called, trigger shutdown
+            if let Some(tx) = self.shutdown_tx.lock().await.take() {
+                let _ = tx.send(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_shutdown_after_all_handlers_called() {
+        let (tx, mut rx) = oneshot::channel();
+        let tracker = CompletionTracker::new(2, tx);
+
+        tracker.request_handled(true).await;
+        tracker.request_handled(false).await;
+        assert!(rx.try_recv().is_err());
+
+        tracker.request_handled(true).await;
+        assert!(rx.try_recv().is_ok());
+    }
+
+    #[test]
+    fn test_route_without_handlers_expects_one_call() {
+        assert_eq!(CompletionTracker::expected_calls(0), 1);
+        assert_eq!(CompletionTracker::expected_calls(3), 3);
+
+        assert!(CompletionTracker::is_first_call(0, 0));
+        assert!(!CompletionTracker::is_first_call(1, 0));
+        assert!(CompletionTracker::is_first_call(1, 2));
+        assert!(!CompletionTracker::is_first_call(2, 2));
+    }
+
+    #[tokio::test]
+    async fn test_counting_requests() {
+        let (tx, mut rx) = oneshot::channel();
+        let tracker = CompletionTracker::new(1, tx).counting_requests(3);
+
+        tracker.request_handled(true).await;
+        tracker.request_handled(false).await;
+        assert!(rx.try_recv().is_err());
+
+        tracker.request_handled(false).await;
+        assert!(rx.try_recv().is_ok());
+        assert_eq!(tracker.handlers_called().load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_operation_handled() {
+        let (tx, mut rx) = oneshot::channel();
+        let tracker = CompletionTracker::new(3, tx);
+
+        tracker.operation_handled(2).await;
+        tracker.operation_handled(0).await;
+        assert!(rx.try_recv().is_err());
+
+        tracker.operation_handled(1).await;
+        assert!(rx.try_recv().is_ok());
+
+        // Counting requests, an operation counts once however many first calls it made
+        let (tx, mut rx) = oneshot::channel();
+        let tracker = CompletionTracker::new(3, tx).counting_requests(2);
+
+        tracker.operation_handled(3).await;
+        assert!(rx.try_recv().is_err());
+
+        tracker.operation_handled(0).await;
+        assert!(rx.try_recv().is_ok());
+        assert_eq!(tracker.handlers_called().load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,52 @@
+use thiserror::Error;
+
+/// Errors that can occur during harness execution
+#[derive(Error, Debug)]
+pub enum HarnessError {
+    #[error("Server error: {0}")]
+    ServerError(String),
+
+    #[error("Failed to bind: {0}")]
+    Bind(#[source] std::io::Error),
+
+    #[error("Server failed: {0}")]
+    Serve(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("Configuration error: {0}")]
+    ConfigurationError(String),
+
+    #[error("Scenario incomplete, handlers never called: {}", .0.join(", "))]
+    IncompleteScenario(Vec<String>),
+
+    #[error("Expected {expected} calls to {path}, got {actual}")]
+    ExpectationFailed {
+        path: String,
+        expected: usize,
+        actual: usize,
+    },
+
+    #[error("Handler panicked: {message}")]
+    HandlerPanic { message: String },
+
+    #[error("Requests failed validation: {}", .0.join("; "))]
+    InvalidMessages(Vec<String>),
+
+    #[error(
+        "Requests out of order, expected [{}], got [{}]",
+        .expected.join(", "),
+        .actual.join(", ")
+    )]
+    OrderViolation {
+        expected: Vec<String>,
+        actual: Vec<String>,
+    },
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Transport error: {0}")]
+    TransportError(String),
+
+    #[error("Parse error: {0}")]
+    ParseError(String),
+}
@@ -0,0 +1,19 @@
+//! Server Harness Core
+//!
+//! Abstractions shared by the HTTP, gRPC and GraphQL server harnesses.
+//! Each harness re-exports these with its own collected request type.
+
+mod collector;
+mod completion;
+mod error;
+mod request_log;
+#[cfg(feature = "tls")]
+pub mod tls;
+
+pub use collector::{
+    Collector, DefaultCollector, FilteringCollector, Matched, RecordingCollector, Snapshot,
+    StrictCollector,
+};
+pub use completion::CompletionTracker;
+pub use error::HarnessError;
+pub use request_log::{LogEntry, RequestLog};
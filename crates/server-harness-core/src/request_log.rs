@@ -0,0 +1,122 @@
+use std::ops::Deref;
+
+/// A collected request that can describe itself in one line of an assertion message
+pub trait LogEntry {
+    /// One-line description, e.g. the method and path of an HTTP request
+    fn summary(&self) -> String;
+}
+
+/// Collected requests with assertion helpers that panic with readable messages
+///
+/// Each harness adds assertions specific to its request type on top of these
+/// through an extension trait.
+#[derive(Debug, Clone)]
+pub struct RequestLog<Item>(Vec<Item>);
+
+impl<Item> RequestLog<Item> {
+    /// Get the wrapped requests back
+    pub fn into_inner(self) -> Vec<Item> {
+        self.0
+    }
+
+    /// The request at `index`, panicking with the number collected when out of range
+    #[track_caller]
+    pub fn request(&self, index: usize) -> &Item {
+        match self.0.get(index) {
+            Some(request) => request,
+            None => panic!(
+                "no request at index {}, only {} collected",
+                index,
+                self.0.len()
+            ),
+        }
+    }
+}
+
+impl<Item: LogEntry> RequestLog<Item> {
+    /// Assert that exactly `expected` requests were collected
+    #[track_caller]
+    pub fn assert_count(&self, expected: usize) -> &Self {
+        if self.0.len() != expected {
+            let received: Vec<String> = self
+                .0
+                .iter()
+                .map(|r| format!("  {}", r.summary()))
+                .collect();
+            panic!(
+                "expected {} requests, got {}:\n{}",
+                expected,
+                self.0.len(),
+                received.join("\n")
+            );
+        }
+        self
+    }
+}
+
+impl<Item> Default for RequestLog<Item> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<Item> From<Vec<Item>> for RequestLog<Item> {
+    fn from(requests: Vec<Item>) -> Self {
+        Self(requests)
+    }
+}
+
+impl<Item> Deref for RequestLog<Item> {
+    type Target = [Item];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    struct Entry(&'static str);
+
+    impl LogEntry for Entry {
+        fn summary(&self) -> String {
+            self.0.to_string()
+        }
+    }
+
+    fn panic_message(f: impl FnOnce()) -> String {
+        let error = catch_unwind(AssertUnwindSafe(f)).expect_err("assertion should fail");
+        error.downcast_ref::<String>().cloned().unwrap_or_default()
+    }
+
+    #[test]
+    fn test_request_log() {
+        let log = RequestLog::from(vec![Entry("first"), Entry("second")]);
+        log.assert_count(2);
+        assert_eq!(log.len(), 2);
+        assert_eq!(log.request(1).0, "second");
+        assert_eq!(log.into_inner()[0].0, "first");
+        assert!(RequestLog::<Entry>::default().is_empty());
+    }
+
+    #[test]
+    fn test_assert_count_lists_received_requests() {
+        let log = RequestLog::from(vec![Entry("first"), Entry("second")]);
+        let message = panic_message(|| {
+            log.assert_count(1);
+        });
+        assert_eq!(message, "expected 1 requests, got 2:\n  first\n  second");
+    }
+
+    #[test]
+    fn test_request_out_of_range() {
+        let log = RequestLog::from(vec![Entry("first")]);
+        let message = panic_message(|| {
+            log.request(3);
+        });
+        assert_eq!(message, "no request at index 3, only 1 collected");
+    }
+}
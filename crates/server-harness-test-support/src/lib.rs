@@ -0,0 +1,57 @@
+//! Helpers shared by the integration tests of the server harness crates
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use tracing::subscriber::DefaultGuard;
+use tracing_subscriber::util::SubscriberInitExt;
+
+/// Formatted `tracing` output of the current thread, captured in memory at
+/// `DEBUG` level until dropped
+///
+/// ```
+/// let logs = server_harness_test_support::CapturedLogs::start();
+/// tracing::debug!(index = 0, "handler selected");
+/// assert!(logs.contents().contains("handler selected index=0"));
+/// ```
+pub struct CapturedLogs {
+    buffer: Arc<Mutex<Vec<u8>>>,
+    _guard: DefaultGuard,
+}
+
+impl CapturedLogs {
+    /// Install a subscriber writing to a fresh buffer for the current thread
+    pub fn start() -> Self {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let writer = Buffer(buffer.clone());
+        let guard = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish()
+            .set_default();
+        Self {
+            buffer,
+            _guard: guard,
+        }
+    }
+
+    /// Everything logged so far
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.buffer.lock().unwrap()).into_owned()
+    }
+}
+
+/// Writer appending to the shared buffer
+#[derive(Clone)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}